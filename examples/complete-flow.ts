@@ -70,6 +70,7 @@ function generateRandomCommitment(): Buffer {
   for (let i = 0; i < 32; i++) {
     commitment[i] = Math.floor(Math.random() * 256);
   }
+  commitment[0] &= 0x1f; // keep below the BN254 scalar modulus
   return commitment;
 }
 
@@ -286,7 +287,7 @@ async function main() {
     const commitment = generateRandomCommitment();
     
     const depositTx = await program.methods
-      .deposit(DEPOSIT_AMOUNT, Array.from(commitment))
+      .deposit(DEPOSIT_AMOUNT, Array.from(commitment), null)
       .accounts({
         poolConfig,
         merkleTree,
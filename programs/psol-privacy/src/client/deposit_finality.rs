@@ -0,0 +1,133 @@
+//! Deposit finality
+//!
+//! A deposit seen at `confirmed` commitment can still be rolled back by a
+//! reorg, taking the saved `leaf_index` with it or moving the commitment to
+//! another index. Once the deposit's `root_slot` is finalized, a wallet
+//! rebuilds the tree from finalized `DepositEvent`s, checks it against
+//! `get_tree_state` at `finalized` commitment, and confirms the commitment
+//! still sits at `leaf_index` under the same `root`.
+
+use anchor_lang::prelude::*;
+
+use crate::client::MerkleTreeBuilder;
+use crate::error::PrivacyError;
+use crate::events::DepositEvent;
+use crate::instructions::TreeState;
+
+/// Outcome of `check_deposit_finality`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DepositFinality {
+    /// `root_slot` is not finalized yet; check again later
+    Pending,
+    /// The commitment is at `leaf_index` under the event's `root`
+    Finalized,
+    /// The finalized tree disagrees with the event; recover the note's
+    /// index with `find_leaf_index_by_commitment` or deposit again
+    RolledBack,
+}
+
+/// Check `event` against the finalized tree.
+///
+/// `tree` is rebuilt from finalized `DepositEvent`s and `finalized_state`
+/// is the first `get_tree_state` page read at `finalized` commitment.
+///
+/// # Errors
+/// * `InvalidMerkleRoot` if `tree` does not match `finalized_state`; resync
+///   it before judging the deposit
+pub fn check_deposit_finality(
+    event: &DepositEvent,
+    finalized_slot: u64,
+    finalized_state: &TreeState,
+    tree: &MerkleTreeBuilder,
+) -> Result<DepositFinality> {
+    if event.root_slot > finalized_slot {
+        return Ok(DepositFinality::Pending);
+    }
+    require!(
+        tree.len() == finalized_state.next_leaf_index && tree.root()? == finalized_state.current_root,
+        PrivacyError::InvalidMerkleRoot
+    );
+
+    let in_place = tree.leaf(event.leaf_index) == Some(event.commitment)
+        && tree.root_at(event.leaf_index + 1)? == event.root;
+    Ok(if in_place {
+        DepositFinality::Finalized
+    } else {
+        DepositFinality::RolledBack
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::TreeHash;
+    use crate::state::merkle_tree::test_fixtures::new_tree;
+
+    /// On-chain tree, its client mirror and the events of `leaves`.
+    fn chain(leaves: &[[u8; 32]]) -> (TreeState, MerkleTreeBuilder, Vec<DepositEvent>) {
+        let mut tree = new_tree(8, TreeHash::Keccak);
+        let mut builder = MerkleTreeBuilder::new(8, TreeHash::Keccak).unwrap();
+
+        let events = leaves
+            .iter()
+            .enumerate()
+            .map(|(slot, leaf)| {
+                let leaf_index = tree.insert_leaf(*leaf, slot as u64).unwrap();
+                builder.insert(*leaf).unwrap();
+                DepositEvent {
+                    pool: tree.pool,
+                    commitment: *leaf,
+                    leaf_index,
+                    amount: 1,
+                    timestamp: 0,
+                    root_slot: slot as u64,
+                    root: tree.current_root,
+                    encrypted_memo: Vec::new(),
+                }
+            })
+            .collect();
+        let state = crate::instructions::tree_state(&tree, 0).unwrap();
+        (state, builder, events)
+    }
+
+    #[test]
+    fn test_deposit_finalized_in_place() {
+        let (state, builder, events) = chain(&[[1; 32], [2; 32], [3; 32]]);
+
+        assert_eq!(
+            check_deposit_finality(&events[1], 0, &state, &builder).unwrap(),
+            DepositFinality::Pending
+        );
+        for event in &events {
+            assert_eq!(
+                check_deposit_finality(event, 10, &state, &builder).unwrap(),
+                DepositFinality::Finalized
+            );
+        }
+    }
+
+    #[test]
+    fn test_reorged_deposit_flagged() {
+        // Confirmed view: ours is leaf 1. The finalized fork dropped the
+        // deposit before it, so ours landed at leaf 0 with another root.
+        let (_, _, confirmed) = chain(&[[1; 32], [2; 32]]);
+        let ours = &confirmed[1];
+        let (state, builder, _) = chain(&[[2; 32], [3; 32]]);
+        assert_eq!(
+            check_deposit_finality(ours, 10, &state, &builder).unwrap(),
+            DepositFinality::RolledBack
+        );
+
+        // Same leaf index but a different history before it
+        let (state, builder, _) = chain(&[[9; 32], [2; 32]]);
+        assert_eq!(
+            check_deposit_finality(ours, 10, &state, &builder).unwrap(),
+            DepositFinality::RolledBack
+        );
+
+        // A mirror out of sync with the finalized account is an error
+        let (_, stale, _) = chain(&[[9; 32]]);
+        let err = check_deposit_finality(ours, 10, &state, &stale).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::InvalidMerkleRoot));
+    }
+}
@@ -0,0 +1,178 @@
+//! Program error codes
+//!
+//! Maps the custom error code of a failed transaction (the `N` in
+//! `custom program error: 0xN`, or `error_code_number` in the logs) back to
+//! its `PrivacyError` variant and message.
+//!
+//! The on-chain enum is reused directly, so names and messages cannot
+//! drift; `PROGRAM_ERRORS` only has to list the variants in declaration
+//! order, which the tests check against `error.rs`.
+
+use std::fmt;
+
+use anchor_lang::error::ERROR_CODE_OFFSET;
+
+use crate::error::PrivacyError;
+use crate::error::PrivacyError::*;
+
+/// Every `PrivacyError` variant, indexed by `code - ERROR_CODE_OFFSET`.
+pub const PROGRAM_ERRORS: [PrivacyError; 83] = [
+    InvalidProof,
+    InvalidProofFormat,
+    InvalidPublicInputs,
+    VerificationKeyNotSet,
+    InvalidMerkleRoot,
+    MerkleTreeFull,
+    InvalidTreeDepth,
+    InvalidRootHistorySize,
+    NullifierAlreadySpent,
+    InvalidNullifier,
+    InvalidAmount,
+    InsufficientBalance,
+    InvalidMint,
+    RelayerFeeExceedsAmount,
+    InvalidCommitment,
+    DuplicateCommitment,
+    InvalidSecret,
+    Unauthorized,
+    PoolPaused,
+    RecipientMismatch,
+    ArithmeticOverflow,
+    NotImplemented,
+    CryptoNotImplemented,
+    VerificationKeyLocked,
+    InvalidAuthority,
+    NoPendingAuthority,
+    AlreadyInitialized,
+    InputTooLarge,
+    PoolHasDeposits,
+    InvalidOwner,
+    CorruptedData,
+    LimitExceeded,
+    InvalidTimestamp,
+    AnonymitySetTooSmall,
+    InvalidTreeHash,
+    InvalidEscrowRecipient,
+    MerkleRootExpired,
+    InvalidReferrer,
+    DevModeNotAcknowledged,
+    RootHistoryMismatch,
+    InvalidFeeRecipient,
+    NoPendingFeeRecipient,
+    IncompatibleSwapPools,
+    PoseidonParameterMismatch,
+    InvalidSwapRoute,
+    SwapSlippageExceeded,
+    NoUntrackedBalance,
+    RecipientCooldownActive,
+    RecipientCooldownRequired,
+    VkXIdentity,
+    InvalidPauseReason,
+    InvalidCommitmentMode,
+    CommitmentModeMismatch,
+    PoolRegistryFull,
+    PoolAlreadyRegistered,
+    PoolNotRegistered,
+    RelayerFeeTooLow,
+    InvalidRecipientBinding,
+    LeafIndexHintMismatch,
+    PendingQueueFull,
+    UnsupportedCurve,
+    AmountCommitmentMismatch,
+    InvalidMultisigConfig,
+    MultisigThresholdNotMet,
+    RecipientDenied,
+    RecipientDenylistMismatch,
+    EmptyPool,
+    FeeMintNotConfigured,
+    InvalidSubtreeImport,
+    VkIcLengthMismatch,
+    MerkleRootTooRecent,
+    InvalidZeroValues,
+    PayeeAccountNotReady,
+    CpiWithdrawDisallowed,
+    ProofVersionMismatch,
+    RootHistoryShrinkDropsRoots,
+    LamportFeeNotEnabled,
+    ZeroNetWithdrawal,
+    PoolConfigNotMigratable,
+    InvalidBatchAccounts,
+    MerkleTreeNotMigratable,
+    VkXScratchStale,
+    VkXScratchIncomplete,
+];
+
+/// A program error code as seen by a client.
+#[derive(Debug, Clone, Copy)]
+pub enum PrivacyErrorCode {
+    /// A `PrivacyError` raised by this program
+    Program(PrivacyError),
+    /// Any other code: Anchor framework errors, other programs, or errors
+    /// added after this client was built
+    Unknown(u32),
+}
+
+impl PrivacyErrorCode {
+    /// Look up a custom error code.
+    pub fn from_code(code: u32) -> Self {
+        code.checked_sub(ERROR_CODE_OFFSET)
+            .and_then(|index| PROGRAM_ERRORS.get(index as usize))
+            .map_or(Self::Unknown(code), |error| Self::Program(*error))
+    }
+
+    /// The numeric code.
+    pub fn code(&self) -> u32 {
+        match self {
+            Self::Program(error) => u32::from(*error),
+            Self::Unknown(code) => *code,
+        }
+    }
+}
+
+impl fmt::Display for PrivacyErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Program(error) => write!(f, "{} ({}): {}", error.name(), self.code(), error),
+            Self::Unknown(code) => write!(f, "Unknown error code {}", code),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_codes_map_to_messages() {
+        assert_eq!(
+            PrivacyErrorCode::from_code(6000).to_string(),
+            "InvalidProof (6000): Invalid proof: verification failed"
+        );
+        assert_eq!(
+            PrivacyErrorCode::from_code(6008).to_string(),
+            "NullifierAlreadySpent (6008): Nullifier already spent"
+        );
+        let code = u32::from(MerkleRootTooRecent);
+        assert!(matches!(PrivacyErrorCode::from_code(code), PrivacyErrorCode::Program(MerkleRootTooRecent)));
+    }
+
+    #[test]
+    fn test_unknown_code_is_catch_all() {
+        for code in [0, 3012, ERROR_CODE_OFFSET + PROGRAM_ERRORS.len() as u32, u32::MAX] {
+            let error = PrivacyErrorCode::from_code(code);
+            assert!(matches!(error, PrivacyErrorCode::Unknown(c) if c == code));
+            assert_eq!(error.to_string(), format!("Unknown error code {}", code));
+        }
+    }
+
+    #[test]
+    fn test_table_matches_error_enum() {
+        for (index, error) in PROGRAM_ERRORS.iter().enumerate() {
+            assert_eq!(*error as usize, index, "{} out of order", error.name());
+            assert_eq!(PrivacyErrorCode::from_code(u32::from(*error)).code(), u32::from(*error));
+        }
+        // Every variant carries a message, so this counts them
+        let declared = include_str!("../error.rs").matches("#[msg(").count();
+        assert_eq!(PROGRAM_ERRORS.len(), declared, "PROGRAM_ERRORS is missing new variants");
+    }
+}
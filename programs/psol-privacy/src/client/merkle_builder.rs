@@ -0,0 +1,255 @@
+//! Off-chain Merkle tree builder
+//!
+//! Keeps every leaf so it can produce inclusion proofs, while hashing with
+//! the same left/right/zero rules as the on-chain incremental tree:
+//! a node's missing right sibling is the zero value for its level.
+
+use anchor_lang::prelude::*;
+
+use crate::crypto::poseidon::TreeHash;
+use crate::error::PrivacyError;
+use crate::state::MerkleTree;
+
+/// Inclusion proof for one leaf.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleProof {
+    pub leaf_index: u32,
+    /// Sibling at each level, leaf level first (length = depth)
+    pub path_elements: Vec<[u8; 32]>,
+    /// 0 if the node is a left child at that level, 1 if right
+    pub path_indices: Vec<u8>,
+}
+
+impl MerkleProof {
+    /// Recompute the root from `leaf` along this path.
+    pub fn compute_root(&self, leaf: &[u8; 32], hash: TreeHash) -> Result<[u8; 32]> {
+        let mut node = *leaf;
+        for (sibling, &index) in self.path_elements.iter().zip(self.path_indices.iter()) {
+            node = if index == 1 {
+                hash.hash_two_to_one(sibling, &node)?
+            } else {
+                hash.hash_two_to_one(&node, sibling)?
+            };
+        }
+        Ok(node)
+    }
+}
+
+/// Client-side mirror of an on-chain `MerkleTree`.
+pub struct MerkleTreeBuilder {
+    depth: u8,
+    hash: TreeHash,
+    zeros: Vec<[u8; 32]>,
+    leaves: Vec<[u8; 32]>,
+}
+
+impl MerkleTreeBuilder {
+    /// Empty tree with the same parameters as the on-chain account.
+    pub fn new(depth: u8, hash: TreeHash) -> Result<Self> {
+        Ok(Self {
+            depth,
+            hash,
+            zeros: MerkleTree::compute_zero_values(depth, hash)?,
+            leaves: Vec::new(),
+        })
+    }
+
+    /// Empty tree mirroring a pool initialized with custom `zeros`.
+    ///
+    /// # Errors
+    /// * `InvalidZeroValues` per `MerkleTree::validate_zero_values`
+    pub fn with_zeros(depth: u8, hash: TreeHash, zeros: Vec<[u8; 32]>) -> Result<Self> {
+        MerkleTree::validate_zero_values(depth, &zeros)?;
+        Ok(Self {
+            depth,
+            hash,
+            zeros,
+            leaves: Vec::new(),
+        })
+    }
+
+    /// Append a leaf, returning its index.
+    ///
+    /// # Errors
+    /// * `MerkleTreeFull` if the tree has reached capacity
+    pub fn insert(&mut self, leaf: [u8; 32]) -> Result<u32> {
+        require!(
+            (self.leaves.len() as u64) < (1u64 << self.depth),
+            PrivacyError::MerkleTreeFull
+        );
+        self.leaves.push(leaf);
+        Ok((self.leaves.len() - 1) as u32)
+    }
+
+    /// Leaf at `leaf_index`, if inserted.
+    pub fn leaf(&self, leaf_index: u32) -> Option<[u8; 32]> {
+        self.leaves.get(leaf_index as usize).copied()
+    }
+
+    /// Number of leaves inserted.
+    pub fn len(&self) -> u32 {
+        self.leaves.len() as u32
+    }
+
+    /// Whether no leaves have been inserted.
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Current root; equals the on-chain `current_root` after the same inserts.
+    pub fn root(&self) -> Result<[u8; 32]> {
+        self.root_at(self.len())
+    }
+
+    /// Root the tree had when it held its first `leaf_count` leaves.
+    ///
+    /// # Errors
+    /// * `LimitExceeded` if fewer than `leaf_count` leaves have been inserted
+    pub fn root_at(&self, leaf_count: u32) -> Result<[u8; 32]> {
+        require!(
+            (leaf_count as usize) <= self.leaves.len(),
+            PrivacyError::LimitExceeded
+        );
+        let mut level = self.leaves[..leaf_count as usize].to_vec();
+        for depth in 0..self.depth as usize {
+            level = self.parent_level(&level, depth)?;
+        }
+        Ok(level.first().copied().unwrap_or(self.zeros[self.depth as usize]))
+    }
+
+    /// Inclusion proof for `leaf_index` against the current `root()`.
+    ///
+    /// # Errors
+    /// * `LimitExceeded` if `leaf_index` has not been inserted
+    pub fn proof(&self, leaf_index: u32) -> Result<MerkleProof> {
+        require!(
+            (leaf_index as usize) < self.leaves.len(),
+            PrivacyError::LimitExceeded
+        );
+
+        let mut path_elements = Vec::with_capacity(self.depth as usize);
+        let mut path_indices = Vec::with_capacity(self.depth as usize);
+        let mut level = self.leaves.clone();
+        let mut index = leaf_index as usize;
+
+        for depth in 0..self.depth as usize {
+            let sibling = index ^ 1;
+            path_elements.push(level.get(sibling).copied().unwrap_or(self.zeros[depth]));
+            path_indices.push((index & 1) as u8);
+
+            level = self.parent_level(&level, depth)?;
+            index >>= 1;
+        }
+
+        Ok(MerkleProof {
+            leaf_index,
+            path_elements,
+            path_indices,
+        })
+    }
+
+    fn parent_level(&self, level: &[[u8; 32]], depth: usize) -> Result<Vec<[u8; 32]>> {
+        level
+            .chunks(2)
+            .map(|pair| {
+                let right = pair.get(1).unwrap_or(&self.zeros[depth]);
+                self.hash.hash_two_to_one(&pair[0], right)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::merkle_tree::test_fixtures::new_tree;
+    use crate::state::merkle_tree::MIN_ROOT_HISTORY_SIZE;
+    use rand::{Rng, SeedableRng};
+
+    fn random_leaf(rng: &mut impl Rng) -> [u8; 32] {
+        let mut leaf: [u8; 32] = rng.gen();
+        leaf[0] &= 0x1f; // canonical field element for Poseidon trees
+        leaf
+    }
+
+    #[test]
+    fn test_empty_root_matches_onchain() {
+        for hash in [TreeHash::Poseidon, TreeHash::Keccak] {
+            let builder = MerkleTreeBuilder::new(6, hash).unwrap();
+            assert_eq!(builder.root().unwrap(), new_tree(6, hash).current_root);
+        }
+    }
+
+    #[test]
+    fn test_root_matches_onchain_for_random_sequences() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(843);
+        for (hash, runs) in [(TreeHash::Keccak, 8), (TreeHash::Poseidon, 2)] {
+            for _ in 0..runs {
+                let mut onchain = new_tree(6, hash);
+                let mut builder = MerkleTreeBuilder::new(6, hash).unwrap();
+
+                for _ in 0..rng.gen_range(1..=40) {
+                    let leaf = random_leaf(&mut rng);
+                    let index = onchain.insert_leaf(leaf, 0).unwrap();
+                    assert_eq!(builder.insert(leaf).unwrap(), index);
+                    assert_eq!(builder.root().unwrap(), onchain.current_root);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_proofs_verify_against_root() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(8430);
+        let mut builder = MerkleTreeBuilder::new(5, TreeHash::Keccak).unwrap();
+        let leaves: Vec<_> = (0..13).map(|_| random_leaf(&mut rng)).collect();
+        for leaf in &leaves {
+            builder.insert(*leaf).unwrap();
+        }
+
+        let root = builder.root().unwrap();
+        for (i, leaf) in leaves.iter().enumerate() {
+            let proof = builder.proof(i as u32).unwrap();
+            assert_eq!(proof.path_elements.len(), 5);
+            assert_eq!(proof.compute_root(leaf, TreeHash::Keccak).unwrap(), root);
+            assert_ne!(proof.compute_root(&[9u8; 32], TreeHash::Keccak).unwrap(), root);
+        }
+        assert!(builder.proof(13).is_err());
+    }
+
+    #[test]
+    fn test_custom_zeros_match_onchain() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(889);
+        let zeros: Vec<_> = (0..=6).map(|_| random_leaf(&mut rng)).collect();
+        let data = vec![0u8; MerkleTree::space(6, MIN_ROOT_HISTORY_SIZE)];
+        let mut onchain = MerkleTree::try_deserialize_unchecked(&mut data.as_slice()).unwrap();
+        onchain
+            .initialize_with_zeros(
+                Pubkey::new_unique(),
+                6,
+                MIN_ROOT_HISTORY_SIZE,
+                TreeHash::Keccak as u8,
+                Some(zeros.clone()),
+            )
+            .unwrap();
+        let mut builder = MerkleTreeBuilder::with_zeros(6, TreeHash::Keccak, zeros).unwrap();
+        assert_eq!(builder.root().unwrap(), onchain.current_root);
+
+        for _ in 0..11 {
+            let leaf = random_leaf(&mut rng);
+            onchain.insert_leaf(leaf, 0).unwrap();
+            builder.insert(leaf).unwrap();
+            assert_eq!(builder.root().unwrap(), onchain.current_root);
+        }
+        assert!(MerkleTreeBuilder::with_zeros(6, TreeHash::Keccak, vec![[0u8; 32]; 6]).is_err());
+    }
+
+    #[test]
+    fn test_full_tree_rejected() {
+        let mut builder = MerkleTreeBuilder::new(4, TreeHash::Keccak).unwrap();
+        for i in 0..16u8 {
+            builder.insert([i; 32]).unwrap();
+        }
+        assert!(builder.insert([99u8; 32]).is_err());
+    }
+}
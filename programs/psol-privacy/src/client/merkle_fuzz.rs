@@ -0,0 +1,271 @@
+//! Deterministic fuzz harness for the on-chain incremental tree
+//!
+//! Drives `MerkleTree::insert_leaf_into` with arbitrary batches of leaves
+//! next to a `MerkleTreeBuilder` and a model of the root history, checking
+//! that:
+//! * after every insert, `current_root` equals a full recomputation over
+//!   all leaves
+//! * after every batch, every root produced so far is reported by
+//!   `is_known_root` exactly while it is among the last
+//!   `root_history_size` distinct roots
+//!
+//! A second property drives deposit, withdraw and re-deposit flows and
+//! checks that no spent `nullifier_hash` ever becomes a tree leaf.
+//!
+//! Test-only; the proptests below run with a fixed seed.
+
+use std::collections::VecDeque;
+
+use anchor_lang::prelude::*;
+
+use crate::client::MerkleTreeBuilder;
+use crate::crypto::poseidon::TreeHash;
+use crate::state::merkle_tree::test_fixtures::new_tree;
+use crate::state::merkle_tree::{debug_assert_not_nullifier, MIN_ROOT_HISTORY_SIZE};
+use crate::state::{MerkleTree, RootHistory};
+
+/// On-chain tree, its off-chain mirror and the expected root history.
+pub(crate) struct InsertHarness {
+    tree: MerkleTree,
+    history: Option<RootHistory>,
+    builder: MerkleTreeBuilder,
+    /// Roots that must still be known, oldest first
+    live_roots: VecDeque<[u8; 32]>,
+    /// Every root the tree has had
+    snapshots: Vec<[u8; 32]>,
+    slot: u64,
+}
+
+impl InsertHarness {
+    /// Empty tree of `depth`, with the root history in a `RootHistory`
+    /// account when `split`.
+    pub(crate) fn new(depth: u8, hash: TreeHash, split: bool) -> Self {
+        let mut tree = new_tree(depth, hash);
+
+        let history = split.then(|| {
+            let data = vec![0u8; RootHistory::space(MIN_ROOT_HISTORY_SIZE)];
+            let mut history = RootHistory::try_deserialize_unchecked(&mut data.as_slice()).unwrap();
+            history.take_from(&mut tree, 255).unwrap();
+            history
+        });
+
+        let root = tree.current_root;
+        Self {
+            tree,
+            history,
+            builder: MerkleTreeBuilder::new(depth, hash).unwrap(),
+            live_roots: VecDeque::from([root]),
+            snapshots: vec![root],
+            slot: 0,
+        }
+    }
+
+    /// Insert `leaves` one by one in the same slot, then check the
+    /// invariants. Stops early once the tree is full.
+    pub(crate) fn insert_batch(&mut self, leaves: &[[u8; 32]]) {
+        self.slot += 1;
+        for leaf in leaves {
+            if self.tree.is_full() {
+                let err = self.tree.insert_leaf_into(*leaf, self.slot, self.history.as_mut());
+                assert!(err.is_err(), "insert into a full tree succeeded");
+                break;
+            }
+
+            let index = self
+                .tree
+                .insert_leaf_into(*leaf, self.slot, self.history.as_mut())
+                .unwrap();
+            assert_eq!(index, self.builder.insert(*leaf).unwrap());
+            assert_eq!(self.tree.current_root, self.builder.root().unwrap());
+            self.record_root();
+        }
+        self.check();
+    }
+
+    fn record_root(&mut self) {
+        let root = self.tree.current_root;
+        if self.live_roots.back() != Some(&root) {
+            self.live_roots.push_back(root);
+            if self.live_roots.len() > usize::from(MIN_ROOT_HISTORY_SIZE) {
+                self.live_roots.pop_front();
+            }
+        }
+        self.snapshots.push(root);
+    }
+
+    /// Assert both invariants against the current state.
+    pub(crate) fn check(&self) {
+        assert_eq!(self.tree.next_leaf_index, self.builder.len());
+        assert_eq!(self.tree.current_root, self.builder.root().unwrap());
+
+        for root in &self.snapshots {
+            let (known, _) = self
+                .tree
+                .is_known_root_in(self.history.as_ref(), root, self.slot, 0)
+                .unwrap();
+            assert_eq!(known, self.live_roots.contains(root), "root {:?}", root);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use proptest::test_runner::RngSeed;
+
+    /// Distinct leaves from a seed: byte 0 stays zero so they are also
+    /// valid Poseidon field elements.
+    fn leaves(seed: u64, count: usize) -> Vec<[u8; 32]> {
+        (0..count as u64)
+            .map(|i| {
+                let mut leaf = [0u8; 32];
+                leaf[8..16].copy_from_slice(&seed.to_be_bytes());
+                leaf[24..].copy_from_slice(&(i + 1).to_be_bytes());
+                leaf
+            })
+            .collect()
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig {
+            cases: 16,
+            rng_seed: RngSeed::Fixed(0x7073_6f6c),
+            failure_persistence: None,
+            ..ProptestConfig::default()
+        })]
+
+        #[test]
+        fn insert_leaf_matches_builder_and_history_ages_out(
+            batches in prop::collection::vec(0usize..40, 1..12),
+            split in any::<bool>(),
+            seed in any::<u64>(),
+        ) {
+            // Up to 429 inserts into capacity 512, so early roots age out
+            let mut harness = InsertHarness::new(9, TreeHash::Keccak, split);
+            for (i, &count) in batches.iter().enumerate() {
+                harness.insert_batch(&leaves(seed ^ i as u64, count));
+            }
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    enum Flow {
+        Deposit(u64),
+        /// Spend the n-th unspent note (mod their count)
+        Withdraw(usize),
+        /// Spend a note and keep up to the given amount shielded as change
+        Redeposit(usize, u64),
+    }
+
+    fn flow() -> impl Strategy<Value = Flow> {
+        prop_oneof![
+            (1u64..1_000_000).prop_map(Flow::Deposit),
+            any::<usize>().prop_map(Flow::Withdraw),
+            (any::<usize>(), 1u64..1_000).prop_map(|(i, change)| Flow::Redeposit(i, change)),
+        ]
+    }
+
+    /// Note secrets from a counter; byte 0 stays zero so they are field elements.
+    fn note_secrets(seed: u64, n: u64) -> ([u8; 32], [u8; 32]) {
+        let (mut secret, mut preimage) = ([0u8; 32], [0u8; 32]);
+        secret[8..16].copy_from_slice(&seed.to_be_bytes());
+        secret[24..].copy_from_slice(&n.to_be_bytes());
+        preimage[8..16].copy_from_slice(&(!seed).to_be_bytes());
+        preimage[24..].copy_from_slice(&n.to_be_bytes());
+        (secret, preimage)
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig {
+            cases: 16,
+            rng_seed: RngSeed::Fixed(0x7073_6f6c),
+            failure_persistence: None,
+            ..ProptestConfig::default()
+        })]
+
+        #[test]
+        fn spent_nullifiers_never_become_leaves(
+            flows in prop::collection::vec(flow(), 1..40),
+            seed in any::<u64>(),
+        ) {
+            use std::collections::HashSet;
+
+            use crate::crypto::{hash_commitment, hash_nullifier};
+            use crate::instructions::withdraw_with_redeposit::apply_redeposit;
+            use crate::state::pool_config::test_fixtures::new_pool;
+
+            let mut pool = new_pool();
+            let mut harness = InsertHarness::new(6, TreeHash::Keccak, false);
+            let tree = &mut harness.tree;
+            // (secret, nullifier preimage, amount) of every unspent note
+            let mut unspent = Vec::new();
+            let (mut leaves, mut spent) = (HashSet::new(), HashSet::new());
+            let mut notes = 0u64;
+
+            for (slot, flow) in (1u64..).zip(flows) {
+                if tree.is_full() {
+                    break;
+                }
+                let (index, change) = match flow {
+                    Flow::Deposit(amount) => {
+                        notes += 1;
+                        let (secret, preimage) = note_secrets(seed, notes);
+                        let commitment = hash_commitment(&secret, &preimage, amount).unwrap();
+                        prop_assert!(!spent.contains(&commitment));
+                        tree.insert_leaf(commitment, slot).unwrap();
+                        pool.record_deposit(amount).unwrap();
+                        leaves.insert(commitment);
+                        unspent.push((secret, preimage, amount));
+                        continue;
+                    }
+                    _ if unspent.is_empty() => continue,
+                    Flow::Withdraw(i) => (i % unspent.len(), None),
+                    Flow::Redeposit(i, change) => (i % unspent.len(), Some(change)),
+                };
+
+                let (secret, preimage, amount) = unspent.swap_remove(index);
+                let nullifier_hash = hash_nullifier(&preimage, &secret).unwrap();
+                prop_assert!(!leaves.contains(&nullifier_hash));
+                prop_assert!(spent.insert(nullifier_hash), "nullifier spent twice");
+
+                match change {
+                    Some(change) => {
+                        let change = change.min(amount);
+                        notes += 1;
+                        let (secret, preimage) = note_secrets(seed, notes);
+                        let commitment = hash_commitment(&secret, &preimage, change).unwrap();
+                        debug_assert_not_nullifier(&commitment, &nullifier_hash);
+                        apply_redeposit(&mut pool, tree, None, amount, commitment, change, slot).unwrap();
+                        leaves.insert(commitment);
+                        unspent.push((secret, preimage, change));
+                    }
+                    None => pool.record_withdrawal(amount).unwrap(),
+                }
+                prop_assert!(leaves.is_disjoint(&spent));
+            }
+            prop_assert_eq!(tree.next_leaf_index as usize, leaves.len());
+        }
+    }
+
+    #[test]
+    fn test_harness_fills_tree_and_ages_out_roots() {
+        let mut harness = InsertHarness::new(8, TreeHash::Keccak, false);
+        harness.insert_batch(&leaves(1, 300));
+
+        assert!(harness.tree.is_full());
+        assert_eq!(harness.live_roots.len(), usize::from(MIN_ROOT_HISTORY_SIZE));
+        // The empty root and early roots are gone from the history
+        let (known, _) =
+            harness.tree.is_known_root_in(None, &harness.snapshots[0], 0, 0).unwrap();
+        assert!(!known);
+    }
+
+    #[test]
+    fn test_harness_poseidon_tree() {
+        let mut harness = InsertHarness::new(4, TreeHash::Poseidon, true);
+        harness.insert_batch(&leaves(2, 5));
+        harness.insert_batch(&leaves(3, 7));
+        harness.check();
+    }
+}
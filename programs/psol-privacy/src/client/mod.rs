@@ -0,0 +1,53 @@
+//! Off-Chain Client Helpers
+//!
+//! Enabled with the `client` feature. Nothing here runs on-chain; these
+//! mirror on-chain logic so wallets and relayers compute identical values.
+//!
+//! ## deposit_finality
+//! Confirms a deposit survived to `finalized` commitment at its expected
+//! leaf index, flagging deposits rolled back or moved by a reorg.
+//!
+//! ## error_code
+//! Maps custom program error codes from failed transactions to their
+//! `PrivacyError` name and message.
+//!
+//! ## merkle_fuzz
+//! Test-only harness fuzzing `MerkleTree::insert_leaf` and root history
+//! aging against `merkle_builder`.
+//!
+//! ## merkle_builder
+//! Full-leaf-set Merkle tree producing roots and inclusion proofs that
+//! match `MerkleTree::insert_leaf`.
+//!
+//! ## note_generator
+//! Draws new deposit notes from a CSPRNG with field-valid secrets.
+//!
+//! ## note_recovery
+//! Finds a note's leaf index from its commitment in the `DepositEvent`
+//! stream, for users who lost their saved `leaf_index`.
+//!
+//! ## root_selection
+//! Picks a withdrawal root from the `DepositEvent` stream that is settled
+//! but will not be evicted from the root history before submission.
+//!
+//! ## withdrawal_inputs
+//! Assembles commitment, nullifier hash, Merkle path and public inputs for
+//! a withdrawal proof from a saved note.
+
+pub mod deposit_finality;
+pub mod error_code;
+pub mod merkle_builder;
+#[cfg(test)]
+mod merkle_fuzz;
+pub mod note_generator;
+pub mod note_recovery;
+pub mod root_selection;
+pub mod withdrawal_inputs;
+
+pub use deposit_finality::{check_deposit_finality, DepositFinality};
+pub use error_code::PrivacyErrorCode;
+pub use merkle_builder::{MerkleProof, MerkleTreeBuilder};
+pub use note_generator::{generate_note, Note};
+pub use note_recovery::find_leaf_index_by_commitment;
+pub use root_selection::pick_safe_root;
+pub use withdrawal_inputs::WithdrawalInputs;
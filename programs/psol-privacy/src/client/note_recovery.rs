@@ -0,0 +1,78 @@
+//! Note recovery
+//!
+//! A user who lost a note's `leaf_index` but still holds `secret`,
+//! `nullifier_preimage` and `amount` can find it again:
+//!
+//! 1. Recompute the leaf with `hash_commitment(secret, nullifier_preimage,
+//!    amount)`. For a `ProgramComputed` pool the leaf is instead
+//!    `Poseidon(note_hash, amount)`, as computed by `deposit_note_hash`.
+//! 2. Fetch the pool's `DepositEvent` stream. Every instruction that
+//!    inserts a leaf emits one, carrying the commitment and its index.
+//! 3. Look the leaf up with `find_leaf_index_by_commitment`, then build the
+//!    withdrawal as usual with `WithdrawalInputs::assemble`.
+//!
+//! Leaves migrated with `import_subtree` have no `DepositEvent` in this
+//! pool; search the legacy pool's stream and add the `first_leaf_index` of
+//! the matching `SubtreeImported` block.
+
+use crate::events::DepositEvent;
+
+/// Leaf index at which `commitment` was inserted, from a pool's
+/// `DepositEvent` stream in any order.
+///
+/// If the same commitment was deposited more than once, the lowest index
+/// is returned; every copy shares one nullifier, so only one is spendable.
+pub fn find_leaf_index_by_commitment(
+    events: &[DepositEvent],
+    commitment: &[u8; 32],
+) -> Option<u32> {
+    events
+        .iter()
+        .filter(|e| e.commitment == *commitment)
+        .map(|e| e.leaf_index)
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::hash_commitment;
+    use anchor_lang::prelude::Pubkey;
+
+    fn event(commitment: [u8; 32], leaf_index: u32) -> DepositEvent {
+        DepositEvent {
+            pool: Pubkey::default(),
+            commitment,
+            leaf_index,
+            amount: 1_000,
+            timestamp: 0,
+            root_slot: u64::from(leaf_index),
+            root: [0u8; 32],
+            encrypted_memo: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_recovers_leaf_index_from_note_secrets() {
+        let (secret, nullifier_preimage) = ([7u8; 32], [9u8; 32]);
+        let mine = hash_commitment(&secret, &nullifier_preimage, 1_000).unwrap();
+
+        // Out-of-order log with other users' deposits around ours
+        let mut events: Vec<DepositEvent> = (0..20u8)
+            .filter(|i| *i != 13)
+            .map(|i| event([i; 32], u32::from(i)))
+            .collect();
+        events.push(event(mine, 13));
+        events.reverse();
+
+        assert_eq!(find_leaf_index_by_commitment(&events, &mine), Some(13));
+
+        // A wrong amount gives a different commitment that is not found
+        let wrong = hash_commitment(&secret, &nullifier_preimage, 999).unwrap();
+        assert_eq!(find_leaf_index_by_commitment(&events, &wrong), None);
+
+        // Duplicate deposits resolve to the earliest leaf
+        events.push(event(mine, 25));
+        assert_eq!(find_leaf_index_by_commitment(&events, &mine), Some(13));
+    }
+}
@@ -0,0 +1,90 @@
+//! Withdrawal root selection
+//!
+//! Every insertion pushes a new root into the pool's ring of
+//! `root_history_size` roots. A withdrawal built against a very fresh root
+//! risks that root being rolled back with its slot; one built against an
+//! old root risks it being evicted before the transaction lands. The root
+//! after the newest deposit that is at least `margin` slots old balances
+//! the two: it is settled, and no younger settled root outlives it.
+
+use crate::events::DepositEvent;
+
+/// Pick the deposit whose post-insertion root to withdraw against.
+///
+/// Returns the newest event with `root_slot + margin <= current_slot`,
+/// provided its root is still in the ring, i.e. fewer than
+/// `root_history_size` leaves were inserted after it. The caller proves
+/// against the root of the tree holding leaves `0..=leaf_index`.
+///
+/// `events` is the pool's `DepositEvent` stream in any order.
+pub fn pick_safe_root(
+    events: &[DepositEvent],
+    current_slot: u64,
+    margin: u64,
+    root_history_size: u16,
+) -> Option<&DepositEvent> {
+    let latest_leaf = events.iter().map(|e| e.leaf_index).max()?;
+    let candidate = events
+        .iter()
+        .filter(|e| e.root_slot.saturating_add(margin) <= current_slot)
+        .max_by_key(|e| e.leaf_index)?;
+
+    let roots_after = latest_leaf - candidate.leaf_index;
+    (roots_after < u32::from(root_history_size)).then_some(candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::prelude::Pubkey;
+
+    /// One deposit per slot starting at `first_slot`.
+    fn stream(count: u32, first_slot: u64) -> Vec<DepositEvent> {
+        (0..count)
+            .map(|i| DepositEvent {
+                pool: Pubkey::default(),
+                commitment: [i as u8; 32],
+                leaf_index: i,
+                amount: 1,
+                timestamp: 0,
+                root_slot: first_slot + u64::from(i),
+                root: [0u8; 32],
+                encrypted_memo: Vec::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_picks_newest_settled_root() {
+        // Leaves 0..10 at slots 100..110
+        let events = stream(10, 100);
+
+        // At slot 109 with margin 5, slots <= 104 are settled -> leaf 4
+        let picked = pick_safe_root(&events, 109, 5, 100).unwrap();
+        assert_eq!(picked.leaf_index, 4);
+
+        // Zero margin takes the current root
+        assert_eq!(pick_safe_root(&events, 109, 0, 100).unwrap().leaf_index, 9);
+
+        // Order of the stream does not matter
+        let mut reversed = stream(10, 100);
+        reversed.reverse();
+        assert_eq!(pick_safe_root(&reversed, 109, 5, 100).unwrap().leaf_index, 4);
+    }
+
+    #[test]
+    fn test_no_settled_root() {
+        let events = stream(10, 100);
+        assert!(pick_safe_root(&events, 100, 5, 100).is_none());
+        assert!(pick_safe_root(&[], 1_000, 0, 100).is_none());
+    }
+
+    #[test]
+    fn test_evicted_root_not_recommended() {
+        // Leaves 0..10; a 5-slot margin at slot 109 selects leaf 4, which
+        // has 5 newer roots after it
+        let events = stream(10, 100);
+        assert_eq!(pick_safe_root(&events, 109, 5, 6).unwrap().leaf_index, 4);
+        assert!(pick_safe_root(&events, 109, 5, 5).is_none());
+    }
+}
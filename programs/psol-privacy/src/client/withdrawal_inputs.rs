@@ -0,0 +1,167 @@
+//! Withdrawal proof inputs
+//!
+//! Everything a prover needs for one withdrawal, assembled from the note
+//! the user saved at deposit time (`secret`, `nullifier_preimage`,
+//! `amount`, `leaf_index`) and a synced `MerkleTreeBuilder`.
+
+use anchor_lang::prelude::*;
+
+use crate::client::{MerkleProof, MerkleTreeBuilder};
+use crate::crypto::{hash_commitment, hash_nullifier, ZkPublicInputs};
+use crate::error::PrivacyError;
+
+/// Private and public inputs for the withdrawal circuit.
+#[derive(Clone, Debug)]
+pub struct WithdrawalInputs {
+    /// Note secret (private input)
+    pub secret: [u8; 32],
+    /// Note nullifier preimage (private input)
+    pub nullifier_preimage: [u8; 32],
+    /// `Poseidon(secret, nullifier_preimage, amount)`, the deposited leaf
+    pub commitment: [u8; 32],
+    /// Merkle path from `commitment` to `public_inputs.merkle_root` (private input)
+    pub merkle_proof: MerkleProof,
+    /// Public inputs; `nullifier_hash` and `merkle_root` are derived here
+    pub public_inputs: ZkPublicInputs,
+}
+
+impl WithdrawalInputs {
+    /// Compute the commitment, nullifier hash, Merkle path and public
+    /// inputs for withdrawing the note at `leaf_index` against the tree's
+    /// current root.
+    ///
+    /// # Errors
+    /// * `InvalidCommitment` if the note secrets are not canonical field
+    ///   elements, or the leaf at `leaf_index` is not this note's commitment
+    /// * `LimitExceeded` if `leaf_index` is not in the tree
+    /// * Any `ZkPublicInputs::validate` error (e.g. fee above amount)
+    #[allow(clippy::too_many_arguments)]
+    pub fn assemble(
+        secret: [u8; 32],
+        nullifier_preimage: [u8; 32],
+        amount: u64,
+        leaf_index: u32,
+        tree: &MerkleTreeBuilder,
+        recipient: Pubkey,
+        relayer: Pubkey,
+        relayer_fee: u64,
+    ) -> Result<Self> {
+        let commitment = hash_commitment(&secret, &nullifier_preimage, amount)?;
+        let nullifier_hash = hash_nullifier(&nullifier_preimage, &secret)?;
+
+        let merkle_proof = tree.proof(leaf_index)?;
+        require!(
+            tree.leaf(leaf_index) == Some(commitment),
+            PrivacyError::InvalidCommitment
+        );
+
+        let public_inputs = ZkPublicInputs::new(
+            tree.root()?,
+            nullifier_hash,
+            recipient,
+            amount,
+            relayer,
+            relayer_fee,
+        );
+        public_inputs.validate()?;
+
+        Ok(Self {
+            secret,
+            nullifier_preimage,
+            commitment,
+            merkle_proof,
+            public_inputs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::TreeHash;
+    use crate::state::merkle_tree::test_fixtures::new_tree;
+
+    const DEPTH: u8 = 4;
+
+    fn note(seed: u8) -> ([u8; 32], [u8; 32]) {
+        let mut secret = [seed; 32];
+        let mut nullifier_preimage = [seed.wrapping_add(1); 32];
+        secret[0] = 0;
+        nullifier_preimage[0] = 0;
+        (secret, nullifier_preimage)
+    }
+
+    #[test]
+    fn test_assembled_inputs_match_onchain_deposit() {
+        let mut onchain = new_tree(DEPTH, TreeHash::Poseidon);
+        let mut builder = MerkleTreeBuilder::new(DEPTH, TreeHash::Poseidon).unwrap();
+
+        // Someone else's deposit, then ours, as the deposit instruction would insert them
+        let (other_secret, other_np) = note(7);
+        let other = hash_commitment(&other_secret, &other_np, 5).unwrap();
+        onchain.insert_leaf(other, 1).unwrap();
+        builder.insert(other).unwrap();
+
+        let (secret, nullifier_preimage) = note(42);
+        let deposited = hash_commitment(&secret, &nullifier_preimage, 1_000).unwrap();
+        let leaf_index = onchain.insert_leaf(deposited, 2).unwrap();
+        builder.insert(deposited).unwrap();
+
+        let recipient = Pubkey::new_unique();
+        let relayer = Pubkey::new_unique();
+        let inputs = WithdrawalInputs::assemble(
+            secret,
+            nullifier_preimage,
+            1_000,
+            leaf_index,
+            &builder,
+            recipient,
+            relayer,
+            10,
+        )
+        .unwrap();
+
+        assert_eq!(inputs.commitment, deposited);
+        assert_eq!(
+            inputs.public_inputs.nullifier_hash,
+            hash_nullifier(&nullifier_preimage, &secret).unwrap()
+        );
+        assert_eq!(inputs.public_inputs.recipient, recipient);
+        assert_eq!(inputs.public_inputs.relayer_fee, 10);
+
+        // Path hashes to a root the on-chain tree accepts
+        let root = inputs
+            .merkle_proof
+            .compute_root(&inputs.commitment, TreeHash::Poseidon)
+            .unwrap();
+        assert_eq!(root, inputs.public_inputs.merkle_root);
+        assert!(onchain.is_known_root(&root));
+    }
+
+    #[test]
+    fn test_wrong_note_rejected() {
+        let mut builder = MerkleTreeBuilder::new(DEPTH, TreeHash::Poseidon).unwrap();
+        let (secret, nullifier_preimage) = note(1);
+        builder
+            .insert(hash_commitment(&secret, &nullifier_preimage, 100).unwrap())
+            .unwrap();
+
+        let assemble = |amount, leaf_index| {
+            WithdrawalInputs::assemble(
+                secret,
+                nullifier_preimage,
+                amount,
+                leaf_index,
+                &builder,
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                0,
+            )
+        };
+        assert!(assemble(100, 0).is_ok());
+        // Wrong amount gives a different commitment than the stored leaf
+        assert!(assemble(101, 0).is_err());
+        // Leaf not in the tree
+        assert!(assemble(100, 1).is_err());
+    }
+}
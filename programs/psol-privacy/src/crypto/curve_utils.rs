@@ -32,6 +32,10 @@ use solana_program::alt_bn128::{
 
 use crate::error::PrivacyError;
 
+pub mod fp2;
+
+pub use fp2::Fp2;
+
 // ============================================================================
 // BN254 CURVE PARAMETERS
 // ============================================================================
@@ -45,6 +49,14 @@ pub const BN254_FIELD_MODULUS: [u8; 32] = [
     0x3c, 0x20, 0x8c, 0x16, 0xd8, 0x7c, 0xfd, 0x47,
 ];
 
+/// Fewest public inputs a verification key may bind. With none, vk_x is
+/// IC[0] whatever the inputs, so a proof would attest to nothing.
+pub const MIN_PUBLIC_INPUTS: usize = 1;
+
+/// Most public inputs a verification key may bind. VK accounts are sized
+/// for `MAX_PUBLIC_INPUTS + 1` IC points, so every key that validates fits.
+pub const MAX_PUBLIC_INPUTS: usize = 15;
+
 /// BN254 scalar field modulus (r) - order of G1 - big-endian bytes
 /// r = 21888242871839275222246405745257275088548364400416034343698204186575808495617
 pub const BN254_SCALAR_MODULUS: [u8; 32] = [
@@ -68,6 +80,71 @@ pub const G1_GENERATOR: [u8; 64] = [
     0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02,
 ];
 
+// ============================================================================
+// BASE FIELD OPERATIONS
+// ============================================================================
+
+/// Canonical element of the base field Fp (< p), big-endian: a curve
+/// coordinate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FieldElement([u8; 32]);
+
+impl FieldElement {
+    /// Take `bytes` as-is.
+    ///
+    /// # Errors
+    /// * `InvalidProof` if `bytes >= p` (coordinates come from proofs and VKs)
+    pub fn from_canonical(bytes: [u8; 32]) -> Result<Self> {
+        let p = BigUint::from_bytes_be(&BN254_FIELD_MODULUS);
+        require!(BigUint::from_bytes_be(&bytes) < p, PrivacyError::InvalidProof);
+        Ok(Self(bytes))
+    }
+
+    /// Reduce a 32-byte big-endian integer mod p.
+    pub fn reduce(bytes: &[u8; 32]) -> Self {
+        Self(reduce_mod(bytes, &BN254_FIELD_MODULUS))
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    pub fn to_bytes(self) -> [u8; 32] {
+        self.0
+    }
+}
+
+/// Compute a square root of `a` in Fp, if one exists.
+///
+/// BN254's p ≡ 3 (mod 4), so a root is `a^((p+1)/4) mod p`. The candidate is
+/// squared and compared against `a` to reject non-residues.
+///
+/// # Arguments
+/// * `a` - Field element (32 bytes, big-endian)
+///
+/// # Returns
+/// * `Some(root)` - One of the two square roots (the other is `p - root`)
+/// * `None` - `a` is not a quadratic residue or is not reduced mod p
+pub fn sqrt_fp(a: &[u8; 32]) -> Option<[u8; 32]> {
+    let p = BigUint::from_bytes_be(&BN254_FIELD_MODULUS);
+    let a = BigUint::from_bytes_be(a);
+    if a >= p {
+        return None;
+    }
+
+    let exponent = (&p + BigUint::from(1u32)) >> 2;
+    let root = a.modpow(&exponent, &p);
+
+    if (&root * &root) % &p != a {
+        return None;
+    }
+
+    let root_bytes = root.to_bytes_be();
+    let mut out = [0u8; 32];
+    out[32 - root_bytes.len()..].copy_from_slice(&root_bytes);
+    Some(out)
+}
+
 // ============================================================================
 // G1 POINT OPERATIONS
 // ============================================================================
@@ -102,15 +179,12 @@ pub fn validate_g1_point(point: &G1Point) -> Result<()> {
         return Ok(());
     }
 
-    // Extract coordinates
-    let x = BigUint::from_bytes_be(&point[0..32]);
-    let y = BigUint::from_bytes_be(&point[32..64]);
+    // Extract coordinates, which must be less than the field modulus
+    let (x, y) = g1_coordinates(point)?;
+    let x = BigUint::from_bytes_be(x.as_bytes());
+    let y = BigUint::from_bytes_be(y.as_bytes());
     let p = BigUint::from_bytes_be(&BN254_FIELD_MODULUS);
 
-    // Check coordinates are less than field modulus
-    require!(x < p, PrivacyError::InvalidProof);
-    require!(y < p, PrivacyError::InvalidProof);
-
     // Check curve equation: y² = x³ + 3 (mod p)
     let y_squared = (&y * &y) % &p;
     let x_cubed = (&x * &x * &x) % &p;
@@ -122,6 +196,18 @@ pub fn validate_g1_point(point: &G1Point) -> Result<()> {
     Ok(())
 }
 
+/// Split a G1 point into its canonical `(x, y)` coordinates.
+///
+/// # Errors
+/// * `InvalidProof` if either coordinate is not reduced mod p
+pub fn g1_coordinates(point: &G1Point) -> Result<(FieldElement, FieldElement)> {
+    let mut x = [0u8; 32];
+    let mut y = [0u8; 32];
+    x.copy_from_slice(&point[0..32]);
+    y.copy_from_slice(&point[32..64]);
+    Ok((FieldElement::from_canonical(x)?, FieldElement::from_canonical(y)?))
+}
+
 /// Negate a G1 point (used in pairing verification).
 ///
 /// For BN254: -P = (x, -y mod p) = (x, p - y)
@@ -160,6 +246,75 @@ pub fn negate_g1(point: &G1Point) -> Result<G1Point> {
     Ok(result)
 }
 
+/// G1 point in compressed form (32 bytes): x big-endian, with flags in the
+/// two top bits, which x < p < 2^254 leaves free.
+pub type CompressedG1Point = [u8; 32];
+
+/// Compressed flag: y is odd (otherwise even).
+const G1_COMPRESSED_Y_ODD: u8 = 0x80;
+
+/// Compressed flag: the identity (x bits all zero).
+const G1_COMPRESSED_IDENTITY: u8 = 0x40;
+
+/// Compress a G1 point to its x coordinate and the parity of y.
+///
+/// `point` is assumed valid (see `validate_g1_point`); an invalid one
+/// fails in `decompress_g1`.
+pub fn compress_g1(point: &G1Point) -> CompressedG1Point {
+    let mut compressed = [0u8; 32];
+    if is_g1_identity(point) {
+        compressed[0] = G1_COMPRESSED_IDENTITY;
+        return compressed;
+    }
+    compressed.copy_from_slice(&point[0..32]);
+    if point[63] & 1 == 1 {
+        compressed[0] |= G1_COMPRESSED_Y_ODD;
+    }
+    compressed
+}
+
+/// Recover a G1 point from `compress_g1` output: y = ±sqrt(x³ + 3), picking
+/// the root with the stored parity.
+///
+/// # Errors
+/// * `InvalidProof` if x is not reduced mod p, x³ + 3 has no square root
+///   (not on the curve), or the flags are malformed
+pub fn decompress_g1(compressed: &CompressedG1Point) -> Result<G1Point> {
+    let flags = compressed[0] & (G1_COMPRESSED_Y_ODD | G1_COMPRESSED_IDENTITY);
+    let mut x = *compressed;
+    x[0] &= !flags;
+
+    if flags & G1_COMPRESSED_IDENTITY != 0 {
+        require!(
+            flags == G1_COMPRESSED_IDENTITY && x == [0u8; 32],
+            PrivacyError::InvalidProof
+        );
+        return Ok(G1_IDENTITY);
+    }
+
+    let x = FieldElement::from_canonical(x)?;
+    let p = BigUint::from_bytes_be(&BN254_FIELD_MODULUS);
+    let x_int = BigUint::from_bytes_be(x.as_bytes());
+    let rhs = (&x_int * &x_int * &x_int + BigUint::from(3u32)) % &p;
+    let mut rhs_bytes = [0u8; 32];
+    let rhs_be = rhs.to_bytes_be();
+    rhs_bytes[32 - rhs_be.len()..].copy_from_slice(&rhs_be);
+
+    let mut y = sqrt_fp(&rhs_bytes).ok_or(error!(PrivacyError::InvalidProof))?;
+    if (y[31] & 1 == 1) != (flags & G1_COMPRESSED_Y_ODD != 0) {
+        // y = 0 has no odd counterpart
+        require!(y != [0u8; 32], PrivacyError::InvalidProof);
+        let neg_y = (&p - BigUint::from_bytes_be(&y)).to_bytes_be();
+        y = [0u8; 32];
+        y[32 - neg_y.len()..].copy_from_slice(&neg_y);
+    }
+
+    let mut point = [0u8; 64];
+    point[0..32].copy_from_slice(x.as_bytes());
+    point[32..64].copy_from_slice(&y);
+    Ok(point)
+}
+
 /// Add two G1 points using Solana's alt_bn128_addition precompile.
 ///
 /// # Arguments
@@ -187,15 +342,15 @@ pub fn g1_add(a: &G1Point, b: &G1Point) -> Result<G1Point> {
 ///
 /// # Arguments
 /// * `point` - G1 point
-/// * `scalar` - 32-byte scalar (big-endian)
+/// * `scalar` - Canonical scalar
 ///
 /// # Returns
 /// Scalar multiple (scalar * point)
-pub fn g1_scalar_mul(point: &G1Point, scalar: &[u8; 32]) -> Result<G1Point> {
+pub fn g1_scalar_mul(point: &G1Point, scalar: &Scalar) -> Result<G1Point> {
     // Prepare input: point || scalar
     let mut input = [0u8; 96];
     input[0..64].copy_from_slice(point);
-    input[64..96].copy_from_slice(scalar);
+    input[64..96].copy_from_slice(scalar.as_bytes());
 
     // Call precompile
     let result = alt_bn128_multiplication(&input)
@@ -233,15 +388,13 @@ pub fn validate_g2_point(point: &G2Point) -> Result<()> {
     }
 
     // Check all coordinate components are in field range
-    let p = BigUint::from_bytes_be(&BN254_FIELD_MODULUS);
-    
     // G2 point has coordinates (x, y) where x, y ∈ Fp2
     // Each Fp2 element is represented as two Fp elements
     // Layout: x_c0 (32) || x_c1 (32) || y_c0 (32) || y_c1 (32)
-    for i in 0..4 {
-        let start = i * 32;
-        let component = BigUint::from_bytes_be(&point[start..start + 32]);
-        require!(component < p, PrivacyError::InvalidProof);
+    for chunk in point.chunks_exact(32) {
+        let mut component = [0u8; 32];
+        component.copy_from_slice(chunk);
+        FieldElement::from_canonical(component)?;
     }
 
     Ok(())
@@ -254,6 +407,49 @@ pub fn validate_g2_point(point: &G2Point) -> Result<()> {
 /// Scalar field element (32 bytes, big-endian).
 pub type ScalarField = [u8; 32];
 
+/// Canonical element of the scalar field Fr (< r), big-endian: a public
+/// input or scalar multiplier.
+///
+/// Only built through `from_canonical` (rejects values >= r) or `reduce`
+/// (mod r), so every call site states which one it means.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Scalar([u8; 32]);
+
+impl Scalar {
+    /// Take `bytes` as-is.
+    ///
+    /// # Errors
+    /// * `InvalidPublicInputs` if `bytes >= r`
+    pub fn from_canonical(bytes: [u8; 32]) -> Result<Self> {
+        require!(is_valid_scalar(&bytes), PrivacyError::InvalidPublicInputs);
+        Ok(Self(bytes))
+    }
+
+    /// Reduce a 32-byte big-endian integer mod r.
+    pub fn reduce(bytes: &[u8; 32]) -> Self {
+        Self(reduce_scalar(bytes))
+    }
+
+    /// `value` as a scalar (always < r).
+    pub fn from_u64(value: u64) -> Self {
+        Self(u64_to_scalar(value))
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    pub fn to_bytes(self) -> [u8; 32] {
+        self.0
+    }
+}
+
+impl AsRef<[u8]> for Scalar {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
 /// Check if scalar is less than the field modulus.
 pub fn is_valid_scalar(scalar: &ScalarField) -> bool {
     let s = BigUint::from_bytes_be(scalar);
@@ -268,9 +464,28 @@ pub fn u64_to_scalar(value: u64) -> ScalarField {
     scalar
 }
 
+/// Reduce a 32-byte big-endian integer mod r.
+pub fn reduce_scalar(bytes: &[u8; 32]) -> ScalarField {
+    reduce_mod(bytes, &BN254_SCALAR_MODULUS)
+}
+
+fn reduce_mod(bytes: &[u8; 32], modulus: &[u8; 32]) -> [u8; 32] {
+    let modulus = BigUint::from_bytes_be(modulus);
+    let reduced = (BigUint::from_bytes_be(bytes) % modulus).to_bytes_be();
+    let mut out = [0u8; 32];
+    out[32 - reduced.len()..].copy_from_slice(&reduced);
+    out
+}
+
 /// Convert Pubkey to scalar field element.
+///
+/// Encoding convention shared with the circuit: the pubkey bytes are read
+/// as a big-endian integer and reduced mod r. That is the signal value a
+/// prover gets when it passes the pubkey as an integer, and the scalar the
+/// bn128 precompile would use anyway, so reducing never changes which
+/// proofs verify; it only keeps every public input canonical (< r).
 pub fn pubkey_to_scalar(pubkey: &Pubkey) -> ScalarField {
-    pubkey.to_bytes()
+    reduce_scalar(&pubkey.to_bytes())
 }
 
 // ============================================================================
@@ -333,7 +548,7 @@ pub fn make_pairing_element(g1: &G1Point, g2: &G2Point) -> PairingElement {
 ///
 /// # Arguments
 /// * `ic` - IC points from verification key (IC[0], IC[1], ..., IC[n])
-/// * `public_inputs` - Public inputs as field elements (n elements)
+/// * `public_inputs` - Public inputs as canonical scalars (n elements)
 ///
 /// # Returns
 /// The computed vk_x point in G1
@@ -341,21 +556,57 @@ pub fn make_pairing_element(g1: &G1Point, g2: &G2Point) -> PairingElement {
 /// # Errors
 /// Returns error if:
 /// * `ic.len() != public_inputs.len() + 1`
+/// * Fewer than `MIN_PUBLIC_INPUTS` or more than `MAX_PUBLIC_INPUTS`
+///   public inputs
 /// * Any curve operation fails
-pub fn compute_vk_x(ic: &[[u8; 64]], public_inputs: &[[u8; 32]]) -> Result<G1Point> {
+pub fn compute_vk_x(ic: &[[u8; 64]], public_inputs: &[Scalar]) -> Result<G1Point> {
     // Validate lengths
     require!(
         ic.len() == public_inputs.len() + 1,
         PrivacyError::InvalidPublicInputs
     );
+    require!(
+        public_inputs.len() >= MIN_PUBLIC_INPUTS,
+        PrivacyError::InvalidPublicInputs
+    );
+    require!(
+        public_inputs.len() <= MAX_PUBLIC_INPUTS,
+        PrivacyError::InputTooLarge
+    );
 
-    // Start with IC[0]
-    let mut acc = ic[0];
+    // Start with IC[0] and add every term in one pass
+    accumulate_vk_x(&ic[0], &ic[1..], public_inputs)
+}
 
-    // Add public_input[i] * IC[i+1] for each input
-    for (i, input) in public_inputs.iter().enumerate() {
+/// Add Σ(public_input[i] * ic_terms[i]) to a partial vk_x sum.
+///
+/// Lets a large linear combination be split across several instructions:
+/// start from IC[0] and feed consecutive chunks of (IC[i+1], input[i]).
+/// Summing all chunks gives the same point as `compute_vk_x`.
+///
+/// # Arguments
+/// * `acc` - Partial sum so far (IC[0] for the first chunk)
+/// * `ic_terms` - IC points matching this chunk of inputs
+/// * `public_inputs` - Chunk of public inputs as canonical scalars
+///
+/// # Errors
+/// * `InvalidPublicInputs` if `ic_terms.len() != public_inputs.len()`
+pub fn accumulate_vk_x(
+    acc: &G1Point,
+    ic_terms: &[[u8; 64]],
+    public_inputs: &[Scalar],
+) -> Result<G1Point> {
+    require!(
+        ic_terms.len() == public_inputs.len(),
+        PrivacyError::InvalidPublicInputs
+    );
+
+    let mut acc = *acc;
+
+    // Add public_input[i] * ic_terms[i] for each input
+    for (ic_point, input) in ic_terms.iter().zip(public_inputs.iter()) {
         // Compute input[i] * IC[i+1]
-        let term = g1_scalar_mul(&ic[i + 1], input)?;
+        let term = g1_scalar_mul(ic_point, input)?;
         
         // Add to accumulator
         acc = g1_add(&acc, &term)?;
@@ -406,6 +657,47 @@ mod tests {
         assert!(scalar[..24].iter().all(|&b| b == 0));
     }
 
+    #[test]
+    fn test_pubkey_to_scalar_reduces_mod_r() {
+        let r = BigUint::from_bytes_be(&BN254_SCALAR_MODULUS);
+
+        // Below r: unchanged
+        let small = Pubkey::new_from_array([0x01; 32]);
+        assert_eq!(pubkey_to_scalar(&small), small.to_bytes());
+
+        // At or above r: reduced to the canonical representative
+        let big = Pubkey::new_from_array([0xff; 32]);
+        let scalar = pubkey_to_scalar(&big);
+        assert!(is_valid_scalar(&scalar));
+        assert_eq!(
+            BigUint::from_bytes_be(&scalar),
+            BigUint::from_bytes_be(big.as_ref()) % &r
+        );
+        assert_eq!(pubkey_to_scalar(&Pubkey::new_from_array(BN254_SCALAR_MODULUS)), [0u8; 32]);
+
+        assert_eq!(Scalar::reduce(&big.to_bytes()).to_bytes(), scalar);
+    }
+
+    #[test]
+    fn test_scalar_from_canonical_rejects_unreduced() {
+        let mut below_r = BN254_SCALAR_MODULUS;
+        below_r[31] -= 1;
+        assert_eq!(Scalar::from_canonical(below_r).unwrap().to_bytes(), below_r);
+        assert_eq!(Scalar::from_u64(7), Scalar::from_canonical(u64_to_scalar(7)).unwrap());
+
+        for unreduced in [BN254_SCALAR_MODULUS, BN254_FIELD_MODULUS, [0xff; 32]] {
+            let err = Scalar::from_canonical(unreduced).unwrap_err();
+            assert_eq!(err, error!(PrivacyError::InvalidPublicInputs));
+            assert!(is_valid_scalar(Scalar::reduce(&unreduced).as_bytes()));
+        }
+        assert_eq!(Scalar::reduce(&BN254_SCALAR_MODULUS), Scalar::default());
+
+        // r <= x < p is a valid coordinate but not a valid scalar
+        assert!(FieldElement::from_canonical(BN254_SCALAR_MODULUS).is_ok());
+        assert!(FieldElement::from_canonical(BN254_FIELD_MODULUS).is_err());
+        assert_eq!(FieldElement::reduce(&BN254_FIELD_MODULUS).to_bytes(), [0u8; 32]);
+    }
+
     #[test]
     fn test_negate_identity() {
         let identity = G1_IDENTITY;
@@ -435,6 +727,106 @@ mod tests {
         assert_eq!(&elem[64..192], &g2);
     }
 
+    #[test]
+    fn test_sqrt_fp_recovers_square() {
+        let p = BigUint::from_bytes_be(&BN254_FIELD_MODULUS);
+        let x = BigUint::from(123_456_789u64);
+        let square = (&x * &x) % &p;
+
+        let mut square_bytes = [0u8; 32];
+        let sb = square.to_bytes_be();
+        square_bytes[32 - sb.len()..].copy_from_slice(&sb);
+
+        let root = BigUint::from_bytes_be(&sqrt_fp(&square_bytes).expect("square must have a root"));
+        assert!(root == x || root == &p - &x);
+    }
+
+    #[test]
+    fn test_sqrt_fp_generator_y() {
+        // y² = x³ + 3 = 4 for the generator, so sqrt(4) ∈ {2, p - 2}
+        let mut four = [0u8; 32];
+        four[31] = 4;
+        let root = sqrt_fp(&four).unwrap();
+        let p = BigUint::from_bytes_be(&BN254_FIELD_MODULUS);
+        let r = BigUint::from_bytes_be(&root);
+        assert!(r == BigUint::from(2u32) || r == &p - BigUint::from(2u32));
+    }
+
+    #[test]
+    fn test_sqrt_fp_non_residue() {
+        // p ≡ 3 (mod 4), so -1 is not a quadratic residue
+        let p = BigUint::from_bytes_be(&BN254_FIELD_MODULUS);
+        let minus_one = &p - BigUint::from(1u32);
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(&minus_one.to_bytes_be());
+        assert!(sqrt_fp(&bytes).is_none());
+    }
+
+    #[test]
+    fn test_sqrt_fp_rejects_unreduced() {
+        assert!(sqrt_fp(&BN254_FIELD_MODULUS).is_none());
+    }
+
+    #[test]
+    fn test_chunked_vk_x_matches_single_pass() {
+        let ic: Vec<G1Point> = (1..=7u64)
+            .map(|k| g1_scalar_mul(&G1_GENERATOR, &Scalar::from_u64(k)).unwrap())
+            .collect();
+        let inputs: Vec<Scalar> = (10..16u64).map(Scalar::from_u64).collect();
+
+        let single = compute_vk_x(&ic, &inputs).unwrap();
+
+        let mut acc = ic[0];
+        let mut start = 0;
+        for chunk_len in [2usize, 3, 1] {
+            let end = start + chunk_len;
+            acc = accumulate_vk_x(&acc, &ic[start + 1..end + 1], &inputs[start..end]).unwrap();
+            start = end;
+        }
+
+        assert_eq!(acc, single);
+    }
+
+    #[test]
+    fn test_accumulate_vk_x_length_mismatch() {
+        let ic = [G1_GENERATOR; 2];
+        let inputs = [Scalar::from_u64(1)];
+        assert!(accumulate_vk_x(&G1_GENERATOR, &ic, &inputs).is_err());
+    }
+
+    #[test]
+    fn test_g1_compression_round_trips() {
+        let mut points = vec![G1_IDENTITY, G1_GENERATOR, negate_g1(&G1_GENERATOR).unwrap()];
+        for k in [2u64, 3, 7, 1_000_003] {
+            let point = g1_scalar_mul(&G1_GENERATOR, &Scalar::from_u64(k)).unwrap();
+            points.push(point);
+            points.push(negate_g1(&point).unwrap());
+        }
+
+        for point in &points {
+            let compressed = compress_g1(point);
+            assert_eq!(decompress_g1(&compressed).unwrap(), *point);
+        }
+        // P and -P share x and differ only in the parity flag
+        assert_eq!(compress_g1(&points[1])[1..], compress_g1(&points[2])[1..]);
+        assert_ne!(compress_g1(&points[1]), compress_g1(&points[2]));
+    }
+
+    #[test]
+    fn test_g1_decompression_rejects_invalid() {
+        // x = 0: 3 is not a square mod p, so no point has x = 0
+        assert!(decompress_g1(&[0u8; 32]).is_err());
+        // x >= p (p's top bits are clear, so no flags are read)
+        assert!(decompress_g1(&BN254_FIELD_MODULUS).is_err());
+        // Identity flag with x bits or the parity flag set
+        let mut identity = compress_g1(&G1_IDENTITY);
+        identity[31] = 1;
+        assert!(decompress_g1(&identity).is_err());
+        let mut identity = compress_g1(&G1_IDENTITY);
+        identity[0] |= G1_COMPRESSED_Y_ODD;
+        assert!(decompress_g1(&identity).is_err());
+    }
+
     #[test]
     fn test_g1_generator_on_curve() {
         // The generator (1, 2) should satisfy y² = x³ + 3
@@ -0,0 +1,297 @@
+//! BN254 Quadratic Extension Field Fp2
+//!
+//! Fp2 = Fp[u] / (u² + 1), i.e. the non-residue is β = -1. G2 coordinates
+//! live in this field, so full G2 on-curve/subgroup checks and point
+//! decompression are built on these helpers.
+//!
+//! Elements are `c0 + c1·u` with both limbs as 32-byte big-endian integers.
+//! Arithmetic goes through `num_bigint` and every result is reduced mod p.
+//! This is tooling-grade code: it is not constant time and is only meant
+//! for validating public data (keys, proofs).
+
+use num_bigint::BigUint;
+use num_traits::Zero;
+
+use super::{sqrt_fp, BN254_FIELD_MODULUS};
+
+/// Element `c0 + c1·u` of Fp2 (big-endian limbs).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fp2 {
+    pub c0: [u8; 32],
+    pub c1: [u8; 32],
+}
+
+impl Fp2 {
+    pub const ZERO: Fp2 = Fp2 { c0: [0u8; 32], c1: [0u8; 32] };
+
+    pub const ONE: Fp2 = Fp2 { c0: one_bytes(), c1: [0u8; 32] };
+
+    pub fn new(c0: [u8; 32], c1: [u8; 32]) -> Self {
+        Self { c0, c1 }
+    }
+
+    /// Parse an EIP-197 / alt_bn128 G2 coordinate (`c1 || c0`, big-endian).
+    pub fn from_g2_bytes(bytes: &[u8; 64]) -> Self {
+        let mut c0 = [0u8; 32];
+        let mut c1 = [0u8; 32];
+        c1.copy_from_slice(&bytes[..32]);
+        c0.copy_from_slice(&bytes[32..]);
+        Self { c0, c1 }
+    }
+
+    /// Encode as an EIP-197 / alt_bn128 G2 coordinate (`c1 || c0`).
+    pub fn to_g2_bytes(&self) -> [u8; 64] {
+        let mut out = [0u8; 64];
+        out[..32].copy_from_slice(&self.c1);
+        out[32..].copy_from_slice(&self.c0);
+        out
+    }
+
+    /// Both limbs are canonical (< p).
+    pub fn is_reduced(&self) -> bool {
+        let p = modulus();
+        BigUint::from_bytes_be(&self.c0) < p && BigUint::from_bytes_be(&self.c1) < p
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.c0.iter().chain(self.c1.iter()).all(|&b| b == 0)
+    }
+
+    pub fn add(&self, other: &Fp2) -> Fp2 {
+        let p = modulus();
+        let (a0, a1) = self.limbs();
+        let (b0, b1) = other.limbs();
+        from_limbs((a0 + b0) % &p, (a1 + b1) % &p)
+    }
+
+    pub fn sub(&self, other: &Fp2) -> Fp2 {
+        let p = modulus();
+        let (a0, a1) = self.limbs();
+        let (b0, b1) = other.limbs();
+        from_limbs(fp_sub(&a0, &b0, &p), fp_sub(&a1, &b1, &p))
+    }
+
+    pub fn neg(&self) -> Fp2 {
+        Fp2::ZERO.sub(self)
+    }
+
+    /// (a0 + a1·u)(b0 + b1·u) = (a0·b0 - a1·b1) + (a0·b1 + a1·b0)·u
+    pub fn mul(&self, other: &Fp2) -> Fp2 {
+        let p = modulus();
+        let (a0, a1) = self.limbs();
+        let (b0, b1) = other.limbs();
+        let c0 = fp_sub(&((&a0 * &b0) % &p), &((&a1 * &b1) % &p), &p);
+        let c1 = (&a0 * &b1 + &a1 * &b0) % &p;
+        from_limbs(c0, c1)
+    }
+
+    /// (a0 + a1·u)² = (a0 + a1)(a0 - a1) + 2·a0·a1·u
+    pub fn square(&self) -> Fp2 {
+        let p = modulus();
+        let (a0, a1) = self.limbs();
+        let c0 = ((&a0 + &a1) * fp_sub(&a0, &a1, &p)) % &p;
+        let c1 = (BigUint::from(2u32) * &a0 * &a1) % &p;
+        from_limbs(c0, c1)
+    }
+
+    /// Multiplicative inverse via the norm: (a0 - a1·u) / (a0² + a1²).
+    ///
+    /// Returns `None` for zero.
+    pub fn inverse(&self) -> Option<Fp2> {
+        let p = modulus();
+        let (a0, a1) = self.limbs();
+        let norm = (&a0 * &a0 + &a1 * &a1) % &p;
+        if norm.is_zero() {
+            return None;
+        }
+        let norm_inv = fp_inverse(&norm, &p);
+        let c0 = (&a0 * &norm_inv) % &p;
+        let c1 = fp_sub(&BigUint::zero(), &((&a1 * &norm_inv) % &p), &p);
+        Some(from_limbs(c0, c1))
+    }
+
+    /// Compute a square root, if one exists.
+    ///
+    /// Uses the norm method: with n = sqrt(a0² + a1²) in Fp, a root is
+    /// x0 = sqrt((a0 ± n) / 2), x1 = a1 / (2·x0). The candidate is squared
+    /// and compared against `self`, so non-residues yield `None`.
+    pub fn sqrt(&self) -> Option<Fp2> {
+        if !self.is_reduced() {
+            return None;
+        }
+        let p = modulus();
+        let (a0, a1) = self.limbs();
+
+        if a1.is_zero() {
+            // a0 ∈ Fp: either sqrt(a0) or sqrt(-a0)·u
+            if let Some(root) = sqrt_fp(&self.c0) {
+                return Some(Fp2::new(root, [0u8; 32]));
+            }
+            let neg = to_bytes(&fp_sub(&BigUint::zero(), &a0, &p));
+            return sqrt_fp(&neg).map(|root| Fp2::new([0u8; 32], root));
+        }
+
+        let norm = (&a0 * &a0 + &a1 * &a1) % &p;
+        let n = BigUint::from_bytes_be(&sqrt_fp(&to_bytes(&norm))?);
+        let half = fp_inverse(&BigUint::from(2u32), &p);
+
+        let candidate = ((&a0 + &n) * &half) % &p;
+        let x0 = match sqrt_fp(&to_bytes(&candidate)) {
+            Some(x0) => BigUint::from_bytes_be(&x0),
+            None => {
+                let candidate = (fp_sub(&a0, &n, &p) * &half) % &p;
+                BigUint::from_bytes_be(&sqrt_fp(&to_bytes(&candidate))?)
+            }
+        };
+        if x0.is_zero() {
+            return None;
+        }
+
+        let x1 = (&a1 * fp_inverse(&(BigUint::from(2u32) * &x0 % &p), &p)) % &p;
+        let root = from_limbs(x0, x1);
+        (root.square() == *self).then_some(root)
+    }
+
+    fn limbs(&self) -> (BigUint, BigUint) {
+        let p = modulus();
+        (
+            BigUint::from_bytes_be(&self.c0) % &p,
+            BigUint::from_bytes_be(&self.c1) % &p,
+        )
+    }
+}
+
+const fn one_bytes() -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out[31] = 1;
+    out
+}
+
+fn modulus() -> BigUint {
+    BigUint::from_bytes_be(&BN254_FIELD_MODULUS)
+}
+
+/// (a - b) mod p for reduced a, b.
+fn fp_sub(a: &BigUint, b: &BigUint, p: &BigUint) -> BigUint {
+    if a >= b {
+        a - b
+    } else {
+        p - (b - a)
+    }
+}
+
+/// a⁻¹ mod p by Fermat (a must be non-zero).
+fn fp_inverse(a: &BigUint, p: &BigUint) -> BigUint {
+    a.modpow(&(p - BigUint::from(2u32)), p)
+}
+
+fn to_bytes(x: &BigUint) -> [u8; 32] {
+    let bytes = x.to_bytes_be();
+    let mut out = [0u8; 32];
+    out[32 - bytes.len()..].copy_from_slice(&bytes);
+    out
+}
+
+fn from_limbs(c0: BigUint, c1: BigUint) -> Fp2 {
+    Fp2::new(to_bytes(&c0), to_bytes(&c1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fp2(c0: u64, c1: u64) -> Fp2 {
+        from_limbs(BigUint::from(c0), BigUint::from(c1))
+    }
+
+    /// Deterministic full-width elements
+    fn sample(seed: u64) -> Fp2 {
+        let p = modulus();
+        let base = BigUint::from(0x9e37_79b9_7f4a_7c15u64);
+        from_limbs(
+            base.modpow(&BigUint::from(seed + 3), &p),
+            base.modpow(&BigUint::from(seed + 11), &p),
+        )
+    }
+
+    #[test]
+    fn test_u_squared_is_minus_one() {
+        let u = fp2(0, 1);
+        assert_eq!(u.square(), Fp2::ONE.neg());
+    }
+
+    #[test]
+    fn test_mul_associative() {
+        for seed in 0..8 {
+            let (a, b, c) = (sample(seed), sample(seed + 100), sample(seed + 200));
+            assert_eq!(a.mul(&b).mul(&c), a.mul(&b.mul(&c)));
+        }
+    }
+
+    #[test]
+    fn test_square_matches_mul() {
+        for seed in 0..8 {
+            let a = sample(seed);
+            assert_eq!(a.square(), a.mul(&a));
+        }
+    }
+
+    #[test]
+    fn test_add_sub_roundtrip() {
+        let (a, b) = (sample(1), sample(2));
+        assert_eq!(a.add(&b).sub(&b), a);
+        assert_eq!(a.add(&a.neg()), Fp2::ZERO);
+    }
+
+    #[test]
+    fn test_inverse() {
+        for seed in 0..8 {
+            let a = sample(seed);
+            assert_eq!(a.mul(&a.inverse().unwrap()), Fp2::ONE);
+        }
+        assert!(Fp2::ZERO.inverse().is_none());
+    }
+
+    #[test]
+    fn test_sqrt_of_square() {
+        for seed in 0..8 {
+            let x = sample(seed).square();
+            let root = x.sqrt().expect("square must have a root");
+            assert_eq!(root.square(), x);
+        }
+    }
+
+    #[test]
+    fn test_sqrt_of_base_field_elements() {
+        // -1 has no root in Fp but is u² in Fp2
+        let minus_one = Fp2::ONE.neg();
+        assert_eq!(minus_one.sqrt().unwrap().square(), minus_one);
+
+        let four = fp2(4, 0);
+        assert_eq!(four.sqrt().unwrap().square(), four);
+        assert_eq!(Fp2::ZERO.sqrt(), Some(Fp2::ZERO));
+    }
+
+    #[test]
+    fn test_sqrt_non_residue() {
+        // Half of Fp2* are non-squares; scan small elements for one
+        let non_residue = (1..64u64)
+            .map(|k| fp2(k, 1))
+            .find(|a| a.sqrt().is_none())
+            .expect("non-residue exists");
+        assert!(non_residue.sqrt().is_none());
+    }
+
+    #[test]
+    fn test_sqrt_rejects_unreduced() {
+        assert!(Fp2::new(BN254_FIELD_MODULUS, [0u8; 32]).sqrt().is_none());
+    }
+
+    #[test]
+    fn test_g2_bytes_roundtrip() {
+        let a = sample(5);
+        let bytes = a.to_g2_bytes();
+        assert_eq!(&bytes[..32], &a.c1);
+        assert_eq!(Fp2::from_g2_bytes(&bytes), a);
+    }
+}
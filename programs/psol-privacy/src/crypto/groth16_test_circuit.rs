@@ -0,0 +1,305 @@
+//! Real Groth16 proofs for a tiny withdrawal-shaped circuit (tests only)
+//!
+//! `test_fixtures` drives the verifier with generator-only keys; this
+//! module runs arkworks' Groth16 setup and prover over BN254 for an actual
+//! R1CS relation, so β, γ, δ are independent G2 points, the 256-byte proof
+//! layout is checked against arkworks' own point encoding, and proofs come
+//! from a witness rather than a trapdoor.
+//!
+//! The circuit has the withdrawal's `ZkPublicInputs::COUNT` public inputs,
+//! in `to_field_elements` order, and proves knowledge of a witness with
+//!
+//! * `secret · secret = merkle_root`
+//! * `(net_amount + relayer_fee) · 1 = amount`
+//!
+//! (field equations, no range checks; use `root_for` to pick a root with a
+//! known square root). The prover takes any witness, so tests can check
+//! that an unsatisfying one yields no verifying proof. Setup and proving
+//! randomness come from a seed, so runs are deterministic.
+
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::{CurveGroup, Group};
+use ark_ff::{BigInteger, PrimeField, UniformRand};
+use ark_groth16::{Groth16, Proof, ProvingKey};
+use ark_relations::lc;
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, SynthesisError, Variable,
+};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::crypto::{G1Point, G2Point, Groth16Proof, ZkPublicInputs};
+use crate::state::VerificationKey;
+
+/// Positions in `ZkPublicInputs::to_field_elements`.
+const MERKLE_ROOT: usize = 0;
+const AMOUNT: usize = 3;
+const RELAYER_FEE: usize = 5;
+
+/// Private witness for the test circuit.
+#[derive(Clone, Copy, Debug)]
+pub struct Witness {
+    /// Square root of the Merkle root
+    pub secret: u64,
+    /// `amount - relayer_fee`
+    pub net_amount: u64,
+}
+
+impl Witness {
+    /// The satisfying witness for `inputs` whose root is `root_for(secret)`.
+    pub fn for_inputs(inputs: &ZkPublicInputs, secret: u64) -> Self {
+        Self { secret, net_amount: inputs.amount - inputs.relayer_fee }
+    }
+}
+
+/// The R1CS relation: public inputs plus the witness to assign.
+#[derive(Clone)]
+struct WithdrawRelation {
+    public: Vec<Fr>,
+    witness: Witness,
+}
+
+impl ConstraintSynthesizer<Fr> for WithdrawRelation {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let public = self
+            .public
+            .iter()
+            .map(|x| cs.new_input_variable(|| Ok(*x)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let secret = cs.new_witness_variable(|| Ok(Fr::from(self.witness.secret)))?;
+        let net_amount = cs.new_witness_variable(|| Ok(Fr::from(self.witness.net_amount)))?;
+
+        cs.enforce_constraint(lc!() + secret, lc!() + secret, lc!() + public[MERKLE_ROOT])?;
+        cs.enforce_constraint(
+            lc!() + net_amount + public[RELAYER_FEE],
+            lc!() + Variable::One,
+            lc!() + public[AMOUNT],
+        )?;
+        Ok(())
+    }
+}
+
+/// A setup for the test circuit: its proving key and verifying key.
+pub struct TestCircuit {
+    pk: ProvingKey<Bn254>,
+    pub vk: VerificationKey,
+}
+
+/// Merkle root whose square root is `secret`.
+pub fn root_for(secret: u64) -> [u8; 32] {
+    let root = Fr::from(secret) * Fr::from(secret);
+    let bytes = root.into_bigint().to_bytes_be();
+    let mut out = [0u8; 32];
+    out[32 - bytes.len()..].copy_from_slice(&bytes);
+    out
+}
+
+fn fq_bytes(value: &Fq) -> [u8; 32] {
+    let bytes = value.into_bigint().to_bytes_be();
+    let mut out = [0u8; 32];
+    out[32 - bytes.len()..].copy_from_slice(&bytes);
+    out
+}
+
+/// Uncompressed `x || y`, big-endian, as the alt_bn128 syscalls expect.
+fn g1_bytes(affine: G1Affine) -> G1Point {
+    let mut out = [0u8; 64];
+    if !affine.infinity {
+        out[..32].copy_from_slice(&fq_bytes(&affine.x));
+        out[32..].copy_from_slice(&fq_bytes(&affine.y));
+    }
+    out
+}
+
+/// EIP-197 encoding: `x_im || x_re || y_im || y_re`.
+fn g2_bytes(affine: G2Affine) -> G2Point {
+    let mut out = [0u8; 128];
+    let coords: [&Fq2; 2] = [&affine.x, &affine.y];
+    for (i, c) in coords.iter().enumerate() {
+        out[i * 64..i * 64 + 32].copy_from_slice(&fq_bytes(&c.c1));
+        out[i * 64 + 32..i * 64 + 64].copy_from_slice(&fq_bytes(&c.c0));
+    }
+    out
+}
+
+fn public_fields(inputs: &ZkPublicInputs) -> Vec<Fr> {
+    inputs
+        .to_field_elements()
+        .unwrap()
+        .iter()
+        .map(|x| Fr::from_be_bytes_mod_order(x.as_bytes()))
+        .collect()
+}
+
+impl TestCircuit {
+    /// Groth16 setup for the circuit from `seed`.
+    pub fn setup(seed: u64) -> Self {
+        let shape = WithdrawRelation {
+            public: vec![Fr::from(0u64); ZkPublicInputs::COUNT],
+            witness: Witness { secret: 0, net_amount: 0 },
+        };
+        let pk = Groth16::<Bn254>::generate_random_parameters_with_reduction(
+            shape,
+            &mut StdRng::seed_from_u64(seed),
+        )
+        .unwrap();
+
+        let vk = VerificationKey {
+            curve: crate::crypto::Curve::Bn254 as u8,
+            alpha_g1: g1_bytes(pk.vk.alpha_g1),
+            beta_g2: g2_bytes(pk.vk.beta_g2),
+            gamma_g2: g2_bytes(pk.vk.gamma_g2),
+            delta_g2: g2_bytes(pk.vk.delta_g2),
+            ic: pk.vk.gamma_abc_g1.iter().map(|p| g1_bytes(*p)).collect(),
+        };
+        Self { pk, vk }
+    }
+
+    /// arkworks proof for `inputs` and `witness`, randomized from `nonce`.
+    ///
+    /// Goes through the constraint matrices rather than
+    /// `create_random_proof_with_reduction`, which debug-asserts that the
+    /// witness is satisfying: a cheating prover is not bound by that.
+    fn prove_ark(&self, inputs: &ZkPublicInputs, witness: Witness, nonce: u64) -> Proof<Bn254> {
+        let cs = ConstraintSystem::new_ref();
+        WithdrawRelation { public: public_fields(inputs), witness }
+            .generate_constraints(cs.clone())
+            .unwrap();
+        cs.finalize();
+        let matrices = cs.to_matrices().unwrap();
+        let prover = cs.borrow().unwrap();
+        let assignment = [&prover.instance_assignment[..], &prover.witness_assignment[..]].concat();
+
+        let mut rng = StdRng::seed_from_u64(nonce);
+        Groth16::<Bn254>::create_proof_with_reduction_and_matrices(
+            &self.pk,
+            Fr::rand(&mut rng),
+            Fr::rand(&mut rng),
+            &matrices,
+            prover.num_instance_variables,
+            prover.num_constraints,
+            &assignment,
+        )
+        .unwrap()
+    }
+
+    /// 256-byte proof for `inputs` and `witness`, randomized from `nonce`.
+    pub fn prove(&self, inputs: &ZkPublicInputs, witness: Witness, nonce: u64) -> Vec<u8> {
+        let proof = self.prove_ark(inputs, witness, nonce);
+        Groth16Proof {
+            a: g1_bytes(proof.a),
+            b: g2_bytes(proof.b),
+            c: g1_bytes(proof.c),
+        }
+        .to_bytes()
+        .to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{g1_add, verify_groth16_proof, G1_GENERATOR};
+    use anchor_lang::prelude::Pubkey;
+    use ark_groth16::prepare_verifying_key;
+
+    const SECRET: u64 = 42;
+
+    fn inputs() -> ZkPublicInputs {
+        ZkPublicInputs::new(
+            root_for(SECRET),
+            [0x22; 32],
+            Pubkey::new_from_array([0x33; 32]),
+            1_000_000,
+            Pubkey::new_from_array([0x44; 32]),
+            5_000,
+        )
+    }
+
+    fn g1(k: u64) -> G1Affine {
+        (G1Projective::generator() * Fr::from(k)).into_affine()
+    }
+
+    /// Whether `witness` satisfies the relation for `inputs`.
+    fn satisfied(inputs: &ZkPublicInputs, witness: Witness) -> bool {
+        let cs = ConstraintSystem::new_ref();
+        WithdrawRelation { public: public_fields(inputs), witness }
+            .generate_constraints(cs.clone())
+            .unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    /// Verdict of arkworks' own verifier, to cross-check ours.
+    fn ark_verifies(circuit: &TestCircuit, inputs: &ZkPublicInputs, witness: Witness, nonce: u64) -> bool {
+        let proof = circuit.prove_ark(inputs, witness, nonce);
+        Groth16::<Bn254>::verify_proof(&prepare_verifying_key(&circuit.pk.vk), &proof, &public_fields(inputs))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_encoding_matches_arkworks_generators() {
+        assert_eq!(g1_bytes(g1(1)), G1_GENERATOR);
+        assert_eq!(
+            g2_bytes(G2Projective::generator().into_affine()),
+            crate::crypto::groth16_verifier::test_fixtures::G2_GENERATOR
+        );
+        // Encoded points add like arkworks points under alt_bn128
+        assert_eq!(g1_add(&g1_bytes(g1(5)), &g1_bytes(g1(9))).unwrap(), g1_bytes(g1(14)));
+    }
+
+    #[test]
+    fn test_real_proof_verifies() {
+        let circuit = TestCircuit::setup(1);
+        let inputs = inputs();
+        let witness = Witness::for_inputs(&inputs, SECRET);
+        assert!(satisfied(&inputs, witness));
+
+        for nonce in [1u64, 2] {
+            let proof = circuit.prove(&inputs, witness, nonce);
+            assert!(verify_groth16_proof(&proof, &circuit.vk, &inputs, false).unwrap());
+            assert!(ark_verifies(&circuit, &inputs, witness, nonce));
+        }
+    }
+
+    #[test]
+    fn test_invalid_witness_cannot_verify() {
+        let circuit = TestCircuit::setup(1);
+        let inputs = inputs();
+        let valid = Witness::for_inputs(&inputs, SECRET);
+
+        for witness in [
+            // Not a square root of the Merkle root
+            Witness { secret: SECRET + 1, ..valid },
+            Witness { secret: 0, ..valid },
+            // Net amount that does not add up with the fee
+            Witness { net_amount: valid.net_amount + 1, ..valid },
+            Witness { net_amount: inputs.amount, ..valid },
+        ] {
+            assert!(!satisfied(&inputs, witness), "{witness:?}");
+            for nonce in [1u64, 2, 3] {
+                let proof = circuit.prove(&inputs, witness, nonce);
+                assert!(!verify_groth16_proof(&proof, &circuit.vk, &inputs, false).unwrap(), "{witness:?}");
+                assert!(!ark_verifies(&circuit, &inputs, witness, nonce), "{witness:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_flipped_public_input_rejected() {
+        let circuit = TestCircuit::setup(1);
+        let inputs = inputs();
+        let proof = circuit.prove(&inputs, Witness::for_inputs(&inputs, SECRET), 1);
+
+        let flipped = ZkPublicInputs { relayer_fee: inputs.relayer_fee + 1, ..inputs.clone() };
+        assert!(!verify_groth16_proof(&proof, &circuit.vk, &flipped, false).unwrap());
+
+        let mut root = inputs.merkle_root;
+        root[31] ^= 1;
+        let flipped = ZkPublicInputs { merkle_root: root, ..inputs.clone() };
+        assert!(!verify_groth16_proof(&proof, &circuit.vk, &flipped, false).unwrap());
+
+        // And a proof is bound to its setup
+        let other = TestCircuit::setup(2);
+        assert!(!verify_groth16_proof(&proof, &other.vk, &inputs, false).unwrap());
+    }
+}
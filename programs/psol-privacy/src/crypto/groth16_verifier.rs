@@ -37,10 +37,19 @@ use crate::state::verification_key::VerificationKey;
 use super::curve_utils::{
     compute_vk_x, is_g1_identity, is_g2_identity, make_pairing_element,
     negate_g1, validate_g1_point, validate_g2_point, verify_pairing,
-    G1Point, G2Point, PairingElement,
+    G1Point, G2Point, PairingElement, MIN_PUBLIC_INPUTS,
 };
 use super::public_inputs::ZkPublicInputs;
 
+/// `msg!` only when `$verbose`; for happy-path progress logs.
+macro_rules! verbose_msg {
+    ($verbose:expr, $($arg:tt)+) => {
+        if $verbose {
+            msg!($($arg)+);
+        }
+    };
+}
+
 // ============================================================================
 // CONSTANTS
 // ============================================================================
@@ -51,6 +60,9 @@ use super::public_inputs::ZkPublicInputs;
 /// C = 64 bytes (G1 uncompressed)
 pub const PROOF_DATA_LEN: usize = 256;
 
+/// Proof data prefixed with a one-byte proof-system version.
+pub const VERSIONED_PROOF_DATA_LEN: usize = PROOF_DATA_LEN + 1;
+
 // ============================================================================
 // PROOF STRUCTURE
 // ============================================================================
@@ -92,10 +104,7 @@ impl Groth16Proof {
     /// [192..256] - C (G1 point)
     /// ```
     pub fn from_bytes(data: &[u8]) -> Result<Self> {
-        if data.len() != PROOF_DATA_LEN {
-            msg!("Invalid proof length: {} (expected {})", data.len(), PROOF_DATA_LEN);
-            return Err(error!(PrivacyError::InvalidProofFormat));
-        }
+        validate_proof_length(data)?;
 
         let mut proof = Groth16Proof {
             a: [0u8; 64],
@@ -120,6 +129,126 @@ impl Groth16Proof {
     }
 }
 
+/// Reject proof data that is not exactly `PROOF_DATA_LEN` bytes.
+///
+/// Cheap enough to run at the instruction boundary before any state checks.
+pub fn validate_proof_length(data: &[u8]) -> Result<()> {
+    if data.len() != PROOF_DATA_LEN {
+        msg!("Invalid proof length: {} (expected {})", data.len(), PROOF_DATA_LEN);
+        return Err(error!(PrivacyError::InvalidProofFormat));
+    }
+    Ok(())
+}
+
+/// Split `data` into its proof-system version and the 256-byte proof.
+///
+/// Provers tag their serialization with a leading version byte so a proof
+/// from an incompatible toolchain fails with a clear error rather than a
+/// pairing failure. Untagged 256-byte proofs are version 0.
+///
+/// # Errors
+/// * `InvalidProofFormat` if `data` is neither `PROOF_DATA_LEN` nor
+///   `VERSIONED_PROOF_DATA_LEN` bytes
+pub fn split_proof_version(data: &[u8]) -> Result<(u8, &[u8])> {
+    match data.len() {
+        PROOF_DATA_LEN => Ok((0, data)),
+        VERSIONED_PROOF_DATA_LEN => Ok((data[0], &data[1..])),
+        len => {
+            msg!("Invalid proof length: {} (expected {})", len, PROOF_DATA_LEN);
+            Err(error!(PrivacyError::InvalidProofFormat))
+        }
+    }
+}
+
+/// The 256-byte proof in `data`, provided its version is `expected`.
+///
+/// # Errors
+/// * `InvalidProofFormat` per `split_proof_version`
+/// * `ProofVersionMismatch` if the proof's version is not `expected`
+pub fn check_proof_version(data: &[u8], expected: u8) -> Result<&[u8]> {
+    let (version, proof) = split_proof_version(data)?;
+    if version != expected {
+        msg!("Proof version {} (pool expects {})", version, expected);
+        return Err(error!(PrivacyError::ProofVersionMismatch));
+    }
+    Ok(proof)
+}
+
+// ============================================================================
+// CURVE DISPATCH
+// ============================================================================
+
+/// Pairing curve a verification key targets (`VerificationKeyAccount.curve`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Curve {
+    /// alt_bn128, verified with Solana's alt_bn128 syscalls
+    #[default]
+    Bn254 = 0,
+    /// Reserved; no verifier yet
+    Bls12_381 = 1,
+}
+
+impl Curve {
+    /// Decode a stored curve byte.
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Curve::Bn254),
+            1 => Some(Curve::Bls12_381),
+            _ => None,
+        }
+    }
+}
+
+/// A Groth16 verifier for one pairing curve.
+pub trait Groth16Verifier {
+    fn curve(&self) -> Curve;
+
+    /// Same contract as `verify_groth16_proof`.
+    fn verify(
+        &self,
+        proof_bytes: &[u8],
+        vk: &VerificationKey,
+        public_inputs: &ZkPublicInputs,
+        verbose: bool,
+    ) -> Result<bool>;
+}
+
+/// BN254 verifier backed by `verify_groth16_proof`.
+pub struct Bn254Verifier;
+
+impl Groth16Verifier for Bn254Verifier {
+    fn curve(&self) -> Curve {
+        Curve::Bn254
+    }
+
+    fn verify(
+        &self,
+        proof_bytes: &[u8],
+        vk: &VerificationKey,
+        public_inputs: &ZkPublicInputs,
+        verbose: bool,
+    ) -> Result<bool> {
+        verify_groth16_proof(proof_bytes, vk, public_inputs, verbose)
+    }
+}
+
+/// Verifier for a stored curve byte.
+///
+/// # Errors
+/// * `UnsupportedCurve` for unknown curves and curves without a verifier
+pub fn verifier_for(curve: u8) -> Result<&'static dyn Groth16Verifier> {
+    match Curve::from_u8(curve) {
+        Some(Curve::Bn254) => Ok(&Bn254Verifier),
+        // TODO: BLS12-381 verifier, via Solana's bls12_381 syscalls once
+        // available on mainnet, otherwise behind a cargo feature
+        Some(Curve::Bls12_381) | None => {
+            msg!("No Groth16 verifier for curve {}", curve);
+            Err(error!(PrivacyError::UnsupportedCurve))
+        }
+    }
+}
+
 // ============================================================================
 // VERIFICATION FUNCTION
 // ============================================================================
@@ -137,6 +266,8 @@ impl Groth16Proof {
 /// * `proof_bytes` - Raw proof data (256 bytes)
 /// * `vk` - Verification key from trusted setup
 /// * `public_inputs` - Public inputs to the circuit
+/// * `verbose` - Log each verification step (`PoolConfig.verbose_logging`);
+///   failures are logged regardless
 ///
 /// # Returns
 /// * `Ok(true)` - Proof is valid
@@ -152,6 +283,7 @@ pub fn verify_groth16_proof(
     proof_bytes: &[u8],
     vk: &VerificationKey,
     public_inputs: &ZkPublicInputs,
+    verbose: bool,
 ) -> Result<bool> {
     // In test builds, allow bypass for unit testing
     #[cfg(test)]
@@ -163,7 +295,7 @@ pub fn verify_groth16_proof(
     }
 
     // Production verification - always cryptographically enforced
-    verify_groth16_proof_impl(proof_bytes, vk, public_inputs)
+    verify_groth16_proof_impl(proof_bytes, vk, public_inputs, verbose)
 }
 
 /// Internal implementation of Groth16 verification.
@@ -173,50 +305,84 @@ fn verify_groth16_proof_impl(
     proof_bytes: &[u8],
     vk: &VerificationKey,
     public_inputs: &ZkPublicInputs,
+    verbose: bool,
 ) -> Result<bool> {
-    msg!("Groth16 verification starting...");
+    verbose_msg!(verbose, "Groth16 verification starting...");
 
     // Step 1: Parse proof structure
     let proof = Groth16Proof::from_bytes(proof_bytes)?;
-    msg!("Step 1/8: Proof parsed");
+    verbose_msg!(verbose, "Step 1/8: Proof parsed");
 
     // Step 2: Validate proof points are on curve and not identity
     validate_proof_points(&proof)?;
-    msg!("Step 2/8: Proof points validated");
+    verbose_msg!(verbose, "Step 2/8: Proof points validated");
 
     // Step 3: Validate VK is properly configured
     validate_verification_key(vk)?;
-    msg!("Step 3/8: Verification key validated");
+    verbose_msg!(verbose, "Step 3/8: Verification key validated");
 
     // Step 4: Validate and encode public inputs
     public_inputs.validate()?;
-    let encoded_inputs = public_inputs.to_field_elements();
-    msg!("Step 4/8: {} public inputs encoded", encoded_inputs.len());
+    let encoded_inputs = public_inputs.to_field_elements()?;
+    verbose_msg!(verbose, "Step 4/8: {} public inputs encoded", encoded_inputs.len());
 
     // Step 5: Compute vk_x = IC[0] + Σ(input[i] * IC[i+1])
     let vk_x = compute_vk_x(&vk.ic, &encoded_inputs)?;
-    msg!("Step 5/8: vk_x computed");
+    require_vk_x_not_identity(&vk_x)?;
+    verbose_msg!(verbose, "Step 5/8: vk_x computed");
 
+    check_pairing(&proof, vk, &vk_x, verbose)
+}
+
+/// Verify a Groth16 proof against a precomputed vk_x.
+///
+/// For circuits whose public-input linear combination is too large for a
+/// single instruction, vk_x can be accumulated across instructions (see
+/// `accumulate_vk_x`) and supplied here. The caller is responsible for
+/// binding vk_x to `vk` and the intended public inputs, as
+/// `VkXScratch::finished_vk_x` does.
+pub fn verify_groth16_proof_with_vk_x(
+    proof_bytes: &[u8],
+    vk: &VerificationKey,
+    vk_x: &G1Point,
+    verbose: bool,
+) -> Result<bool> {
+    let proof = Groth16Proof::from_bytes(proof_bytes)?;
+    validate_proof_points(&proof)?;
+    validate_verification_key(vk)?;
+    validate_g1_point(vk_x)?;
+    require_vk_x_not_identity(vk_x)?;
+
+    check_pairing(&proof, vk, vk_x, verbose)
+}
+
+/// Steps 6-8: e(-A, B) · e(α, β) · e(vk_x, γ) · e(C, δ) = 1
+fn check_pairing(
+    proof: &Groth16Proof,
+    vk: &VerificationKey,
+    vk_x: &G1Point,
+    verbose: bool,
+) -> Result<bool> {
     // Step 6: Negate A for pairing equation
     let neg_a = negate_g1(&proof.a)?;
-    msg!("Step 6/8: A negated");
+    verbose_msg!(verbose, "Step 6/8: A negated");
 
     // Step 7: Construct pairing elements
     // Verification equation: e(-A, B) · e(α, β) · e(vk_x, γ) · e(C, δ) = 1
     let pairing_elements: [PairingElement; 4] = [
         make_pairing_element(&neg_a, &proof.b),           // e(-A, B)
         make_pairing_element(&vk.alpha_g1, &vk.beta_g2),  // e(α, β)
-        make_pairing_element(&vk_x, &vk.gamma_g2),        // e(vk_x, γ)
+        make_pairing_element(vk_x, &vk.gamma_g2),         // e(vk_x, γ)
         make_pairing_element(&proof.c, &vk.delta_g2),     // e(C, δ)
     ];
-    msg!("Step 7/8: Pairing elements constructed");
+    verbose_msg!(verbose, "Step 7/8: Pairing elements constructed");
 
     // Step 8: Verify pairing
-    msg!("Step 8/8: Performing pairing check...");
+    verbose_msg!(verbose, "Step 8/8: Performing pairing check...");
     let result = verify_pairing(&pairing_elements)?;
 
     if result {
-        msg!("✓ Proof verified successfully");
+        verbose_msg!(verbose, "✓ Proof verified successfully");
     } else {
         msg!("✗ Proof verification FAILED");
     }
@@ -274,6 +440,19 @@ fn validate_proof_points(proof: &Groth16Proof) -> Result<()> {
     Ok(())
 }
 
+/// Reject an identity vk_x before the pairing check.
+///
+/// A legitimate withdrawal never produces one (merkle_root and nullifier
+/// are nonzero), but degenerate IC points or all-zero inputs can, and the
+/// e(vk_x, γ) term then drops out of the pairing equation.
+fn require_vk_x_not_identity(vk_x: &G1Point) -> Result<()> {
+    if is_g1_identity(vk_x) {
+        msg!("vk_x is identity (degenerate public inputs or IC)");
+        return Err(error!(PrivacyError::VkXIdentity));
+    }
+    Ok(())
+}
+
 /// Validate verification key structure and values.
 ///
 /// Checks:
@@ -281,9 +460,16 @@ fn validate_proof_points(proof: &Groth16Proof) -> Result<()> {
 /// 2. Alpha is not identity and on curve
 /// 3. All VK points are valid
 fn validate_verification_key(vk: &VerificationKey) -> Result<()> {
-    // Must have at least 2 IC points (1 base + 1 for at least 1 public input)
-    if vk.ic.len() < 2 {
-        msg!("VK has insufficient IC points: {} (need at least 2)", vk.ic.len());
+    // The alt_bn128 checks below are meaningless for other curves
+    require!(vk.curve == Curve::Bn254 as u8, PrivacyError::UnsupportedCurve);
+
+    // IC[0] plus one point per public input, with at least MIN_PUBLIC_INPUTS
+    if vk.ic.len() < MIN_PUBLIC_INPUTS + 1 {
+        msg!(
+            "VK has insufficient IC points: {} (need at least {})",
+            vk.ic.len(),
+            MIN_PUBLIC_INPUTS + 1
+        );
         return Err(error!(PrivacyError::VerificationKeyNotSet));
     }
 
@@ -340,26 +526,89 @@ fn validate_verification_key(vk: &VerificationKey) -> Result<()> {
 // TEST UTILITIES
 // ============================================================================
 
-/// Test-only bypass flag
+// Test-only bypass flag (per test thread, so it cannot leak into
+// tests that exercise real verification)
 #[cfg(test)]
-static TEST_BYPASS: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+thread_local! {
+    static TEST_BYPASS: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
 
 /// Check if test bypass is enabled
 #[cfg(test)]
 fn is_test_bypass_enabled() -> bool {
-    TEST_BYPASS.load(std::sync::atomic::Ordering::Relaxed)
+    TEST_BYPASS.with(|bypass| bypass.get())
 }
 
 /// Enable test bypass (for unit tests only)
 #[cfg(test)]
 pub fn enable_test_bypass() {
-    TEST_BYPASS.store(true, std::sync::atomic::Ordering::Relaxed);
+    TEST_BYPASS.with(|bypass| bypass.set(true));
 }
 
 /// Disable test bypass
 #[cfg(test)]
 pub fn disable_test_bypass() {
-    TEST_BYPASS.store(false, std::sync::atomic::Ordering::Relaxed);
+    TEST_BYPASS.with(|bypass| bypass.set(false));
+}
+
+// ============================================================================
+// TEST FIXTURES
+// ============================================================================
+
+/// Synthetic Groth16 keys and proofs for exercising the full pairing check.
+///
+/// Uses γ = δ = β = G2 generator and α = a·G1, so a valid proof for any
+/// public inputs is `A = (a + c)·G1 + vk_x`, `B = G2`, `C = c·G1`.
+/// These are NOT sound keys; they only exist to drive the verifier in tests.
+#[cfg(test)]
+pub(crate) mod test_fixtures {
+    use super::*;
+    use crate::crypto::curve_utils::{g1_add, g1_scalar_mul, Scalar, G1_GENERATOR};
+
+    /// BN254 G2 generator (EIP-197 encoding: x_im, x_re, y_im, y_re).
+    pub const G2_GENERATOR: G2Point = [
+        0x19, 0x8e, 0x93, 0x93, 0x92, 0x0d, 0x48, 0x3a, 0x72, 0x60, 0xbf, 0xb7, 0x31, 0xfb, 0x5d, 0x25,
+        0xf1, 0xaa, 0x49, 0x33, 0x35, 0xa9, 0xe7, 0x12, 0x97, 0xe4, 0x85, 0xb7, 0xae, 0xf3, 0x12, 0xc2,
+        0x18, 0x00, 0xde, 0xef, 0x12, 0x1f, 0x1e, 0x76, 0x42, 0x6a, 0x00, 0x66, 0x5e, 0x5c, 0x44, 0x79,
+        0x67, 0x43, 0x22, 0xd4, 0xf7, 0x5e, 0xda, 0xdd, 0x46, 0xde, 0xbd, 0x5c, 0xd9, 0x92, 0xf6, 0xed,
+        0x09, 0x06, 0x89, 0xd0, 0x58, 0x5f, 0xf0, 0x75, 0xec, 0x9e, 0x99, 0xad, 0x69, 0x0c, 0x33, 0x95,
+        0xbc, 0x4b, 0x31, 0x33, 0x70, 0xb3, 0x8e, 0xf3, 0x55, 0xac, 0xda, 0xdc, 0xd1, 0x22, 0x97, 0x5b,
+        0x12, 0xc8, 0x5e, 0xa5, 0xdb, 0x8c, 0x6d, 0xeb, 0x4a, 0xab, 0x71, 0x80, 0x8d, 0xcb, 0x40, 0x8f,
+        0xe3, 0xd1, 0xe7, 0x69, 0x0c, 0x43, 0xd3, 0x7b, 0x4c, 0xe6, 0xcc, 0x01, 0x66, 0xfa, 0x7d, 0xaa,
+    ];
+
+    fn g1_mul(k: u64) -> G1Point {
+        g1_scalar_mul(&G1_GENERATOR, &Scalar::from_u64(k)).unwrap()
+    }
+
+    /// Synthetic VK with α = `seed`·G1. Distinct seeds give distinct circuits.
+    pub fn synthetic_vk(seed: u64) -> VerificationKey {
+        VerificationKey {
+            curve: Curve::Bn254 as u8,
+            alpha_g1: g1_mul(seed),
+            beta_g2: G2_GENERATOR,
+            gamma_g2: G2_GENERATOR,
+            delta_g2: G2_GENERATOR,
+            ic: (0..=ZkPublicInputs::COUNT as u64)
+                .map(|i| g1_mul(seed * 100 + i + 1))
+                .collect(),
+        }
+    }
+
+    /// Proof that verifies against `synthetic_vk(seed)` for `inputs`.
+    pub fn synthetic_proof(seed: u64, inputs: &ZkPublicInputs) -> Vec<u8> {
+        let vk = synthetic_vk(seed);
+        let vk_x = compute_vk_x(&vk.ic, &inputs.to_field_elements().unwrap()).unwrap();
+        let c = 7u64;
+
+        Groth16Proof {
+            a: g1_add(&g1_mul(seed + c), &vk_x).unwrap(),
+            b: G2_GENERATOR,
+            c: g1_mul(c),
+        }
+        .to_bytes()
+        .to_vec()
+    }
 }
 
 // ============================================================================
@@ -369,6 +618,8 @@ pub fn disable_test_bypass() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::crypto::{RecipientBinding, Scalar, BN254_SCALAR_MODULUS};
+    use num_bigint::BigUint;
 
     #[test]
     fn test_proof_parsing() {
@@ -405,6 +656,27 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_proof_version_byte() {
+        let proof = [7u8; PROOF_DATA_LEN];
+        // Untagged proofs are version 0 and pass through unchanged
+        assert_eq!(check_proof_version(&proof, 0).unwrap(), &proof[..]);
+
+        let mut tagged = vec![2u8];
+        tagged.extend_from_slice(&proof);
+        assert_eq!(check_proof_version(&tagged, 2).unwrap(), &proof[..]);
+        tagged[0] = 0;
+        assert_eq!(check_proof_version(&tagged, 0).unwrap(), &proof[..]);
+
+        let err = check_proof_version(&tagged, 1).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::ProofVersionMismatch));
+        let err = check_proof_version(&proof, 1).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::ProofVersionMismatch));
+
+        let err = check_proof_version(&tagged[..PROOF_DATA_LEN - 1], 0).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::InvalidProofFormat));
+    }
+
     #[test]
     fn test_empty_proof() {
         let data: [u8; 0] = [];
@@ -437,6 +709,206 @@ mod tests {
         assert!(proof.c.iter().all(|&b| b == 3));
     }
 
+    fn fixture_inputs() -> ZkPublicInputs {
+        ZkPublicInputs::new(
+            [1u8; 32],
+            [2u8; 32],
+            Pubkey::new_from_array([3u8; 32]),
+            1_000,
+            Pubkey::new_from_array([4u8; 32]),
+            10,
+        )
+    }
+
+    #[test]
+    fn test_synthetic_proof_verifies_only_for_its_vk() {
+        let inputs = fixture_inputs();
+        let proof = test_fixtures::synthetic_proof(5, &inputs);
+
+        assert!(verify_groth16_proof_impl(&proof, &test_fixtures::synthetic_vk(5), &inputs, false).unwrap());
+        assert!(!verify_groth16_proof_impl(&proof, &test_fixtures::synthetic_vk(6), &inputs, false).unwrap());
+
+        let mut other = inputs.clone();
+        other.amount = 999;
+        assert!(!verify_groth16_proof_impl(&proof, &test_fixtures::synthetic_vk(5), &other, false).unwrap());
+    }
+
+    /// Pubkey equal to `recipient - r`, which the precompile maps to the
+    /// same scalar as `recipient`.
+    fn scalar_alias(recipient: &Pubkey) -> Pubkey {
+        let r = BigUint::from_bytes_be(&BN254_SCALAR_MODULUS);
+        let alias = BigUint::from_bytes_be(recipient.as_ref()) - r;
+        let mut bytes = [0u8; 32];
+        let alias = alias.to_bytes_be();
+        bytes[32 - alias.len()..].copy_from_slice(&alias);
+        Pubkey::new_from_array(bytes)
+    }
+
+    #[test]
+    fn test_proof_bound_to_recipient() {
+        let inputs = fixture_inputs();
+        let proof = test_fixtures::synthetic_proof(5, &inputs);
+        let vk = test_fixtures::synthetic_vk(5);
+
+        // A relayer resubmitting the same proof to its own address fails
+        let stolen = ZkPublicInputs { recipient: Pubkey::new_unique(), ..inputs.clone() };
+        assert!(!verify_groth16_proof_impl(&proof, &vk, &stolen, false).unwrap());
+    }
+
+    #[test]
+    fn test_hashed_recipient_binding_rejects_scalar_alias() {
+        let recipient = Pubkey::new_from_array([0xee; 32]);
+        let vk = test_fixtures::synthetic_vk(5);
+
+        // Raw binding: recipient and recipient - r share a field element
+        let raw = ZkPublicInputs { recipient, ..fixture_inputs() };
+        let proof = test_fixtures::synthetic_proof(5, &raw);
+        let aliased = ZkPublicInputs { recipient: scalar_alias(&recipient), ..raw.clone() };
+        assert!(verify_groth16_proof_impl(&proof, &vk, &aliased, false).unwrap());
+
+        // Hashed binding: the alias no longer verifies
+        let hashed = raw.with_recipient_binding(RecipientBinding::Hashed);
+        let proof = test_fixtures::synthetic_proof(5, &hashed);
+        assert!(verify_groth16_proof_impl(&proof, &vk, &hashed, false).unwrap());
+        let aliased = ZkPublicInputs { recipient: scalar_alias(&recipient), ..hashed };
+        assert!(!verify_groth16_proof_impl(&proof, &vk, &aliased, false).unwrap());
+    }
+
+    #[test]
+    fn test_vault_bound_proof_rejects_substituted_vault() {
+        let vk = test_fixtures::synthetic_vk(5);
+        let inputs = fixture_inputs()
+            .with_recipient_binding(RecipientBinding::VaultBound)
+            .with_vault(Pubkey::new_from_array([0x11; 32]));
+        let proof = test_fixtures::synthetic_proof(5, &inputs);
+        assert!(verify_groth16_proof_impl(&proof, &vk, &inputs, false).unwrap());
+
+        let substituted = inputs.clone().with_vault(Pubkey::new_from_array([0x22; 32]));
+        assert!(!verify_groth16_proof_impl(&proof, &vk, &substituted, false).unwrap());
+    }
+
+    #[test]
+    fn test_bn254_verifies_through_dispatch() {
+        let inputs = fixture_inputs();
+        let proof = test_fixtures::synthetic_proof(5, &inputs);
+        let vk = test_fixtures::synthetic_vk(5);
+
+        let verifier = verifier_for(vk.curve).unwrap();
+        assert_eq!(verifier.curve(), Curve::Bn254);
+        assert!(verifier.verify(&proof, &vk, &inputs, false).unwrap());
+
+        let other = ZkPublicInputs { amount: 999, ..inputs.clone() };
+        assert!(!verifier.verify(&proof, &vk, &other, false).unwrap());
+    }
+
+    #[test]
+    fn test_verbose_logging_does_not_change_result() {
+        let inputs = fixture_inputs();
+        let proof = test_fixtures::synthetic_proof(5, &inputs);
+        let vk = test_fixtures::synthetic_vk(5);
+        let other = ZkPublicInputs { amount: 999, ..inputs.clone() };
+
+        for public_inputs in [&inputs, &other] {
+            assert_eq!(
+                verify_groth16_proof_impl(&proof, &vk, public_inputs, false).unwrap(),
+                verify_groth16_proof_impl(&proof, &vk, public_inputs, true).unwrap(),
+            );
+        }
+        assert!(verify_groth16_proof_impl(&proof, &vk, &inputs, false).unwrap());
+
+        // Malformed proofs fail the same way either way
+        let err = verify_groth16_proof_impl(&proof[..255], &vk, &inputs, false).unwrap_err();
+        assert_eq!(
+            err,
+            verify_groth16_proof_impl(&proof[..255], &vk, &inputs, true).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn test_unimplemented_curves_rejected() {
+        for curve in [Curve::Bls12_381 as u8, 7] {
+            let err = verifier_for(curve).err().unwrap();
+            assert_eq!(err, error!(PrivacyError::UnsupportedCurve));
+        }
+
+        // A BLS key must not reach the alt_bn128 checks
+        let inputs = fixture_inputs();
+        let proof = test_fixtures::synthetic_proof(5, &inputs);
+        let mut vk = test_fixtures::synthetic_vk(5);
+        vk.curve = Curve::Bls12_381 as u8;
+        assert!(verify_groth16_proof_impl(&proof, &vk, &inputs, false).is_err());
+    }
+
+    #[test]
+    fn test_zero_inputs_with_degenerate_ic_rejected_before_pairing() {
+        let mut vk = test_fixtures::synthetic_vk(5);
+        vk.ic[0] = [0u8; 64];
+        let zero_inputs = [Scalar::default(); ZkPublicInputs::COUNT];
+
+        let vk_x = compute_vk_x(&vk.ic, &zero_inputs).unwrap();
+        assert!(is_g1_identity(&vk_x));
+        assert!(require_vk_x_not_identity(&vk_x).is_err());
+
+        // The precomputed-vk_x path rejects it too, without reaching the pairing
+        let proof = test_fixtures::synthetic_proof(5, &ZkPublicInputs::new(
+            [1u8; 32],
+            [2u8; 32],
+            Pubkey::new_unique(),
+            1,
+            Pubkey::new_unique(),
+            0,
+        ));
+        let err = verify_groth16_proof_with_vk_x(&proof, &vk, &vk_x, false).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::VkXIdentity));
+    }
+
+    #[test]
+    fn test_vk_without_public_inputs_rejected() {
+        // IC = [IC[0]] binds nothing: vk_x would be IC[0] for every proof
+        let mut vk = test_fixtures::synthetic_vk(7);
+        vk.ic.truncate(1);
+        let err = compute_vk_x(&vk.ic, &[]).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::InvalidPublicInputs));
+
+        let inputs = ZkPublicInputs::new(
+            [1u8; 32],
+            [2u8; 32],
+            Pubkey::new_unique(),
+            1,
+            Pubkey::new_unique(),
+            0,
+        );
+        let proof = test_fixtures::synthetic_proof(7, &inputs);
+        let err = verify_groth16_proof_impl(&proof, &vk, &inputs, false).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::VerificationKeyNotSet));
+        let vk_x = vk.ic[0];
+        assert!(verify_groth16_proof_with_vk_x(&proof, &vk, &vk_x, false).is_err());
+
+        // And at configuration time
+        let data = crate::state::VerificationKeyData {
+            alpha_g1: vk.alpha_g1,
+            beta_g2: vk.beta_g2,
+            gamma_g2: vk.gamma_g2,
+            delta_g2: vk.delta_g2,
+            ic: vk.ic.clone(),
+            circuit_id: [0u8; 32],
+        };
+        let err = data.validate().unwrap_err();
+        assert_eq!(err, error!(PrivacyError::InvalidPublicInputs));
+    }
+
+    #[test]
+    fn test_cancelling_ic_terms_rejected() {
+        let vk = test_fixtures::synthetic_vk(6);
+        let mut ic = vk.ic.clone();
+        ic[1] = negate_g1(&ic[0]).unwrap();
+        let mut inputs = [Scalar::default(); ZkPublicInputs::COUNT];
+        inputs[0] = Scalar::from_u64(1);
+
+        let vk_x = compute_vk_x(&ic, &inputs).unwrap();
+        assert!(require_vk_x_not_identity(&vk_x).is_err());
+    }
+
     #[test]
     fn test_bypass_flag() {
         // Initially disabled
@@ -5,6 +5,7 @@
 //! ## curve_utils
 //! BN254 elliptic curve operations using Solana's alt_bn128 precompiles:
 //! - G1/G2 point validation
+//! - Fp2 arithmetic (`curve_utils::fp2`) for G2 coordinates
 //! - Scalar multiplication
 //! - Pairing operations
 //!
@@ -15,8 +16,15 @@
 //!
 //! ## poseidon
 //! Hash functions:
-//! - Keccak256 for Merkle tree (on-chain)
-//! - Poseidon documentation for commitments (off-chain)
+//! - Poseidon or Keccak256 for Merkle tree (on-chain, per-tree `TreeHash`)
+//! - Poseidon commitment/nullifier hashing for clients and tooling
+//!
+//! ## test_vectors (tests only)
+//! Canonical circomlib-compatible vectors for the Poseidon hashes
+//!
+//! ## groth16_test_circuit (tests only)
+//! Real Groth16 setup and proofs (arkworks BN254) for a tiny R1CS circuit with
+//! the withdrawal's public inputs
 //!
 //! ## public_inputs
 //! Public input encoding for ZK circuits:
@@ -35,30 +43,38 @@ pub mod groth16_verifier;
 pub mod poseidon;
 pub mod public_inputs;
 
+#[cfg(test)]
+pub mod groth16_test_circuit;
+#[cfg(test)]
+pub mod test_vectors;
+
 // ============================================================================
 // CURVE UTILITIES
 // ============================================================================
 
 pub use curve_utils::{
     // Point types
-    G1Point, G2Point, PairingElement, ScalarField,
+    G1Point, CompressedG1Point, G2Point, PairingElement, ScalarField, Scalar, FieldElement,
     
     // Constants
     G1_IDENTITY, G2_IDENTITY, G1_GENERATOR,
-    BN254_FIELD_MODULUS, BN254_SCALAR_MODULUS,
+    BN254_FIELD_MODULUS, BN254_SCALAR_MODULUS, MIN_PUBLIC_INPUTS, MAX_PUBLIC_INPUTS,
+    
+    // Base / extension field operations
+    sqrt_fp, Fp2,
     
     // G1 operations
-    validate_g1_point, negate_g1, g1_add, g1_scalar_mul,
-    is_g1_identity,
+    validate_g1_point, g1_coordinates, negate_g1, g1_add, g1_scalar_mul,
+    is_g1_identity, compress_g1, decompress_g1,
     
     // G2 operations
     validate_g2_point, is_g2_identity,
     
     // Scalar operations
-    is_valid_scalar, u64_to_scalar, pubkey_to_scalar,
+    is_valid_scalar, u64_to_scalar, pubkey_to_scalar, reduce_scalar,
     
     // Pairing operations
-    verify_pairing, make_pairing_element, compute_vk_x,
+    verify_pairing, make_pairing_element, compute_vk_x, accumulate_vk_x,
 };
 
 // ============================================================================
@@ -67,8 +83,17 @@ pub use curve_utils::{
 
 pub use groth16_verifier::{
     verify_groth16_proof,
+    verify_groth16_proof_with_vk_x,
+    check_proof_version,
+    split_proof_version,
+    validate_proof_length,
+    verifier_for,
+    Bn254Verifier,
+    Curve,
     Groth16Proof,
+    Groth16Verifier,
     PROOF_DATA_LEN,
+    VERSIONED_PROOF_DATA_LEN,
 };
 
 // ============================================================================
@@ -76,8 +101,19 @@ pub use groth16_verifier::{
 // ============================================================================
 
 pub use poseidon::{
-    // Merkle tree hash (on-chain, Keccak256)
+    // Merkle tree hash (on-chain)
     hash_two_to_one,
+    hash_two_to_one_poseidon,
+    poseidon_self_test,
+    TreeHash,
+    
+    // Commitment/nullifier (client-side)
+    hash_commitment,
+    hash_position_bound_commitment,
+    hash_note_commitment,
+    hash_amount_commitment,
+    hash_nullifier,
+    note_hashes_zeroizing,
     
     // Utilities
     is_zero_hash,
@@ -91,6 +127,11 @@ pub use poseidon::{
 // ============================================================================
 
 pub use public_inputs::{
+    lamport_fee_relayer_binding,
+    migration_relayer_binding,
+    redeposit_relayer_binding,
+    split_fee_relayer_binding,
+    RecipientBinding,
     ZkPublicInputs,
     ZkPublicInputsBuilder,
 };
@@ -16,23 +16,64 @@
 //! - Rounds: RF=8, RP=57
 //!
 //! ## On-Chain (Merkle Tree)
-//! The Merkle tree uses Keccak256 for internal nodes.
-//! This is acceptable because:
-//! 1. Merkle tree structure is public (not privacy-sensitive)
-//! 2. Keccak256 is available as a Solana syscall (efficient)
-//! 3. ZK circuit can support any Merkle tree hash
+//! The Merkle tree hash is selected per tree at initialization (`TreeHash`):
+//! - `Poseidon` - BN254 x5 Poseidon via the `sol_poseidon` syscall.
+//!   Matches `circuits/withdraw.circom`. Leaves must be canonical field elements.
+//! - `Keccak` - Keccak256 via the `sol_keccak256` syscall. Cheaper in CU,
+//!   matches `circuits/withdraw_keccak.circom`.
 //!
-//! The commitment leaves ARE computed with Poseidon (off-chain),
-//! but the tree aggregation uses Keccak256.
+//! Merkle tree structure is public (not privacy-sensitive), so either hash is
+//! acceptable as long as the circuit uses the same one.
 //!
 //! # Circuit Compatibility
 //!
 //! Your ZK circuit must be configured to:
 //! 1. Use Poseidon for commitment/nullifier verification
-//! 2. Use Keccak256 for Merkle path verification
+//! 2. Use the pool's `TreeHash` for Merkle path verification
 //! 3. Match the exact field element encoding (big-endian)
 
+use anchor_lang::prelude::*;
 use solana_program::keccak;
+use solana_program::poseidon::{self as poseidon_syscall, Endianness, Parameters};
+
+use zeroize::Zeroize;
+
+use crate::error::PrivacyError;
+
+// ============================================================================
+// MERKLE TREE HASH SELECTOR
+// ============================================================================
+
+/// Hash function used for Merkle tree internal nodes.
+///
+/// Stored on `MerkleTree` as a `u8` and fixed at initialization.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum TreeHash {
+    /// Poseidon (BN254 x5, big-endian)
+    Poseidon = 0,
+    /// Keccak256
+    Keccak = 1,
+}
+
+impl TreeHash {
+    /// Decode a stored selector byte.
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(TreeHash::Poseidon),
+            1 => Some(TreeHash::Keccak),
+            _ => None,
+        }
+    }
+
+    /// Hash two child nodes with the selected function.
+    pub fn hash_two_to_one(self, left: &[u8; 32], right: &[u8; 32]) -> Result<[u8; 32]> {
+        match self {
+            TreeHash::Poseidon => hash_two_to_one_poseidon(left, right),
+            TreeHash::Keccak => Ok(hash_two_to_one(left, right)),
+        }
+    }
+}
 
 // ============================================================================
 // MERKLE TREE HASH (On-Chain, Keccak256)
@@ -55,6 +96,39 @@ pub fn hash_two_to_one(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
     keccak::hash(&combined).to_bytes()
 }
 
+/// circomlib `poseidon([1, 2])` (t = 3, RF = 8, RP = 57, BN254 x5).
+pub const POSEIDON_SELF_TEST_DIGEST: [u8; 32] = [
+    0x11, 0x5c, 0xc0, 0xf5, 0xe7, 0xd6, 0x90, 0x41, 0x3d, 0xf6, 0x4c, 0x6b, 0x96, 0x62, 0xe9, 0xcf,
+    0x2a, 0x36, 0x17, 0xf2, 0x74, 0x32, 0x45, 0x51, 0x9e, 0x19, 0x60, 0x7a, 0x44, 0x17, 0x18, 0x9a,
+];
+
+/// Check that the Poseidon implementation still uses circomlib parameters.
+///
+/// Hashes `[1, 2]` and compares against `POSEIDON_SELF_TEST_DIGEST`. Catches a
+/// runtime or dependency change to the round constants, width or round
+/// counts, which would otherwise only show up as proofs failing to verify.
+///
+/// # Errors
+/// * `PoseidonParameterMismatch` if the digest differs
+pub fn poseidon_self_test() -> Result<()> {
+    let digest = hash_two_to_one_poseidon(&u64_to_bytes32_be(1), &u64_to_bytes32_be(2))?;
+    if digest != POSEIDON_SELF_TEST_DIGEST {
+        msg!("Poseidon self-test failed: parameters differ from circomlib");
+        return Err(error!(PrivacyError::PoseidonParameterMismatch));
+    }
+    Ok(())
+}
+
+/// Hash two 32-byte values for Merkle tree internal nodes using Poseidon.
+///
+/// Uses circomlib-compatible BN254 x5 parameters, big-endian.
+///
+/// # Errors
+/// * `InvalidCommitment` if either input is not a canonical field element
+pub fn hash_two_to_one_poseidon(left: &[u8; 32], right: &[u8; 32]) -> Result<[u8; 32]> {
+    poseidon_hashv(&[left.as_ref(), right.as_ref()])
+}
+
 // ============================================================================
 // COMMITMENT/NULLIFIER (Off-Chain Only - Documentation)
 // ============================================================================
@@ -112,6 +186,118 @@ pub fn compute_nullifier_offchain(
     panic!("Nullifiers must be computed off-chain using Poseidon")
 }
 
+/// Compute a commitment with circomlib-compatible Poseidon (t = 4).
+///
+/// `commitment = Poseidon(secret, nullifier_preimage, amount)`, with `amount`
+/// encoded via `u64_to_bytes32_be`. Intended for clients and tooling; the
+/// program itself never sees the preimage.
+///
+/// # Errors
+/// * `InvalidCommitment` if `secret` or `nullifier_preimage` is not a canonical field element
+pub fn hash_commitment(
+    secret: &[u8; 32],
+    nullifier_preimage: &[u8; 32],
+    amount: u64,
+) -> Result<[u8; 32]> {
+    let amount = u64_to_bytes32_be(amount);
+    poseidon_hashv(&[secret.as_ref(), nullifier_preimage.as_ref(), amount.as_ref()])
+}
+
+/// Compute a position-bound commitment with circomlib-compatible Poseidon
+/// (t = 5), for pools in `CommitmentMode::PositionBound`.
+///
+/// `commitment = Poseidon(secret, nullifier_preimage, amount, leaf_index)`,
+/// with `amount` and `leaf_index` encoded via `u64_to_bytes32_be`. The
+/// client computes it for the leaf index it expects to be assigned; the
+/// deposit fails unless that is the tree's next leaf index.
+///
+/// # Errors
+/// * `InvalidCommitment` if `secret` or `nullifier_preimage` is not a canonical field element
+pub fn hash_position_bound_commitment(
+    secret: &[u8; 32],
+    nullifier_preimage: &[u8; 32],
+    amount: u64,
+    leaf_index: u32,
+) -> Result<[u8; 32]> {
+    let amount = u64_to_bytes32_be(amount);
+    let leaf_index = u64_to_bytes32_be(u64::from(leaf_index));
+    poseidon_hashv(&[
+        secret.as_ref(),
+        nullifier_preimage.as_ref(),
+        amount.as_ref(),
+        leaf_index.as_ref(),
+    ])
+}
+
+/// Compute a nullifier hash with circomlib-compatible Poseidon (t = 3).
+///
+/// `nullifier_hash = Poseidon(nullifier_preimage, secret)`
+///
+/// # Errors
+/// * `InvalidCommitment` if either input is not a canonical field element
+pub fn hash_nullifier(nullifier_preimage: &[u8; 32], secret: &[u8; 32]) -> Result<[u8; 32]> {
+    poseidon_hashv(&[nullifier_preimage.as_ref(), secret.as_ref()])
+}
+
+/// Compute an amount-bound commitment from a note hash, for pools in
+/// `CommitmentMode::ProgramComputed`.
+///
+/// `commitment = Poseidon(note_hash, amount)` where the client supplies
+/// `note_hash = Poseidon(secret, nullifier_preimage)`. Because the program
+/// computes it from the transferred `amount`, a depositor cannot commit to
+/// a larger note value than they paid. Runs on-chain.
+///
+/// # Errors
+/// * `InvalidCommitment` if `note_hash` is not a canonical field element
+pub fn hash_note_commitment(note_hash: &[u8; 32], amount: u64) -> Result<[u8; 32]> {
+    let amount = u64_to_bytes32_be(amount);
+    poseidon_hashv(&[note_hash.as_ref(), amount.as_ref()])
+}
+
+/// Compute the amount commitment exposed by confidential withdrawals.
+///
+/// `amount_commitment = Poseidon(amount, blinding)`. The withdrawal circuit
+/// range-checks `amount` and proves it equals the note value; the program
+/// recomputes this from the cleartext transfer amount. Runs on-chain.
+///
+/// # Errors
+/// * `InvalidCommitment` if `blinding` is not a canonical field element
+pub fn hash_amount_commitment(amount: u64, blinding: &[u8; 32]) -> Result<[u8; 32]> {
+    let amount = u64_to_bytes32_be(amount);
+    poseidon_hashv(&[amount.as_ref(), blinding.as_ref()])
+}
+
+/// Compute `(commitment, nullifier_hash)` for a note, then wipe the secrets.
+///
+/// `secret` and `nullifier_preimage` are zeroized before returning, on
+/// success and on error, so callers don't leave note secrets in memory.
+/// Persist (encrypted) note secrets BEFORE calling this.
+///
+/// # Errors
+/// * `InvalidCommitment` if either secret is not a canonical field element
+pub fn note_hashes_zeroizing(
+    secret: &mut [u8; 32],
+    nullifier_preimage: &mut [u8; 32],
+    amount: u64,
+) -> Result<([u8; 32], [u8; 32])> {
+    let hashes = hash_commitment(secret, nullifier_preimage, amount)
+        .and_then(|commitment| Ok((commitment, hash_nullifier(nullifier_preimage, secret)?)));
+
+    secret.zeroize();
+    nullifier_preimage.zeroize();
+
+    hashes
+}
+
+fn poseidon_hashv(inputs: &[&[u8]]) -> Result<[u8; 32]> {
+    poseidon_syscall::hashv(Parameters::Bn254X5, Endianness::BigEndian, inputs)
+        .map(|h| h.to_bytes())
+        .map_err(|e| {
+            msg!("Poseidon hash failed: {:?}", e);
+            error!(PrivacyError::InvalidCommitment)
+        })
+}
+
 // ============================================================================
 // LEGACY FUNCTIONS (Kept for backward compatibility, use with caution)
 // ============================================================================
@@ -224,6 +410,88 @@ mod tests {
         assert_ne!(h1, h2, "Different inputs should produce different hashes");
     }
 
+    #[test]
+    fn test_poseidon_two_to_one_deterministic() {
+        let left = [1u8; 32];
+        let right = [2u8; 32];
+        let h1 = hash_two_to_one_poseidon(&left, &right).unwrap();
+        let h2 = hash_two_to_one_poseidon(&left, &right).unwrap();
+        assert_eq!(h1, h2);
+        assert_ne!(h1, hash_two_to_one(&left, &right));
+    }
+
+    #[test]
+    fn test_poseidon_rejects_unreduced_input() {
+        let too_large = [0xffu8; 32];
+        assert!(hash_two_to_one_poseidon(&too_large, &[0u8; 32]).is_err());
+    }
+
+    #[test]
+    fn test_position_bound_commitment_differs_by_index() {
+        let secret = [1u8; 32];
+        let nullifier_preimage = [2u8; 32];
+
+        let at_0 = hash_position_bound_commitment(&secret, &nullifier_preimage, 1_000, 0).unwrap();
+        let at_1 = hash_position_bound_commitment(&secret, &nullifier_preimage, 1_000, 1).unwrap();
+        assert_ne!(at_0, at_1);
+        assert_eq!(
+            at_1,
+            hash_position_bound_commitment(&secret, &nullifier_preimage, 1_000, 1).unwrap()
+        );
+        // Not the unbound commitment either
+        assert_ne!(at_0, hash_commitment(&secret, &nullifier_preimage, 1_000).unwrap());
+    }
+
+    #[test]
+    fn test_note_hashes_zeroize_secrets() {
+        let mut secret = [0u8; 32];
+        secret[31] = 1;
+        let mut nullifier_preimage = [0u8; 32];
+        nullifier_preimage[31] = 2;
+        let expected = (
+            hash_commitment(&secret, &nullifier_preimage, 5).unwrap(),
+            hash_nullifier(&nullifier_preimage, &secret).unwrap(),
+        );
+
+        let hashes = note_hashes_zeroizing(&mut secret, &mut nullifier_preimage, 5).unwrap();
+        assert_eq!(hashes, expected);
+        assert_eq!(secret, [0u8; 32]);
+        assert_eq!(nullifier_preimage, [0u8; 32]);
+    }
+
+    #[test]
+    fn test_note_hashes_zeroize_secrets_on_error() {
+        let mut secret = [0xffu8; 32];
+        let mut nullifier_preimage = [0x01u8; 32];
+
+        assert!(note_hashes_zeroizing(&mut secret, &mut nullifier_preimage, 5).is_err());
+        assert_eq!(secret, [0u8; 32]);
+        assert_eq!(nullifier_preimage, [0u8; 32]);
+    }
+
+    #[test]
+    fn test_poseidon_self_test_passes() {
+        assert!(poseidon_self_test().is_ok());
+    }
+
+    #[test]
+    fn test_poseidon_self_test_digest_is_circomlib() {
+        // Independent of POSEIDON_SELF_TEST_DIGEST: circomlibjs poseidon([1, 2])
+        let expected = "115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a";
+        let digest = hash_two_to_one_poseidon(&u64_to_bytes32_be(1), &u64_to_bytes32_be(2))
+            .unwrap();
+        let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(hex, expected);
+        assert_eq!(digest, POSEIDON_SELF_TEST_DIGEST);
+    }
+
+    #[test]
+    fn test_tree_hash_from_u8() {
+        assert_eq!(TreeHash::from_u8(0), Some(TreeHash::Poseidon));
+        assert_eq!(TreeHash::from_u8(1), Some(TreeHash::Keccak));
+        assert_eq!(TreeHash::from_u8(2), None);
+    }
+
     #[test]
     fn test_is_zero_hash() {
         let zero = [0u8; 32];
@@ -11,14 +11,90 @@
 //! 5. relayer - Relayer address
 //! 6. relayer_fee - Fee paid to relayer
 //!
+//! For split-fee withdrawals, `relayer` is `split_fee_relayer_binding(..)`
+//! and `relayer_fee` is the total of both shares. Withdrawals that keep
+//! change shielded set `relayer` to `redeposit_relayer_binding(..)`.
+//! Withdrawals paying the relayer in lamports set `relayer` to
+//! `lamport_fee_relayer_binding(..)` and `relayer_fee` to zero.
+//!
 //! # Field Element Encoding
 //! All values are encoded as 32-byte big-endian field elements
 //! in the BN254 scalar field.
+//!
+//! Pubkeys (`relayer`, and `recipient` under `RecipientBinding::Raw`) are
+//! read as big-endian integers and reduced mod r (`pubkey_to_scalar`), so
+//! every input handed to the verifier is canonical and matches the value
+//! the circuit sees. Reduction means a proof for recipient `R` also verifies
+//! for `R ± k·r`; `RecipientBinding::Hashed` closes that gap, and
+//! `RecipientBinding::VaultBound` additionally binds the paying vault.
+//!
+//! Inputs passed through as raw bytes (`merkle_root`, `nullifier_hash`,
+//! `amount_commitment`) are not reduced: `validate` rejects them unless
+//! they are already `< r`. Otherwise the circuit would see `x mod r` while
+//! `is_known_root` and the nullifier PDA seeds use the unreduced bytes.
+//! `to_field_elements` builds them with `Scalar::from_canonical`, so an
+//! unvalidated value cannot reach the verifier either.
 
 use anchor_lang::prelude::*;
 
+use crate::crypto::{is_valid_scalar, pubkey_to_scalar, Scalar, MAX_PUBLIC_INPUTS};
 use crate::error::PrivacyError;
 
+// ============================================================================
+// RECIPIENT BINDING
+// ============================================================================
+
+/// How the `recipient` public input is derived from the recipient pubkey.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum RecipientBinding {
+    /// Pubkey bytes reduced mod r. Binding only holds modulo r: ~80% of pubkeys are
+    /// >= r and share their field element with another 32-byte value.
+    #[default]
+    Raw = 0,
+    /// `Keccak256("psol-recipient" || pubkey)` with the top 3 bits cleared,
+    /// so every recipient maps to a distinct canonical field element.
+    Hashed = 1,
+    /// `Keccak256("psol-recipient-vault" || pubkey || vault)` with the top
+    /// 3 bits cleared: as `Hashed`, and the proof also commits to the vault
+    /// that pays out. With one vault per pool this pins the proof to the
+    /// pool's PDA vault; it keeps proofs unambiguous should a pool ever
+    /// hold several.
+    VaultBound = 2,
+}
+
+impl RecipientBinding {
+    /// Decode a stored binding byte.
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(RecipientBinding::Raw),
+            1 => Some(RecipientBinding::Hashed),
+            2 => Some(RecipientBinding::VaultBound),
+            _ => None,
+        }
+    }
+
+    /// Field element the circuit sees for `recipient` paid from `vault`.
+    /// Only `VaultBound` reads `vault`.
+    pub fn encode(self, recipient: &Pubkey, vault: &Pubkey) -> [u8; 32] {
+        let mut out = match self {
+            RecipientBinding::Raw => return pubkey_to_scalar(recipient),
+            RecipientBinding::Hashed => {
+                solana_program::keccak::hashv(&[b"psol-recipient", recipient.as_ref()])
+            }
+            RecipientBinding::VaultBound => solana_program::keccak::hashv(&[
+                b"psol-recipient-vault",
+                recipient.as_ref(),
+                vault.as_ref(),
+            ]),
+        }
+        .to_bytes();
+        // < 2^253 < r
+        out[0] &= 0x1f;
+        out
+    }
+}
+
 // ============================================================================
 // PUBLIC INPUTS STRUCTURE
 // ============================================================================
@@ -46,8 +122,22 @@ pub struct ZkPublicInputs {
     
     /// Fee paid to relayer (deducted from amount)
     pub relayer_fee: u64,
+
+    /// Encoding of `recipient` as a field element (pool setting)
+    pub recipient_binding: RecipientBinding,
+
+    /// Vault paying the withdrawal; part of the `recipient` input under
+    /// `RecipientBinding::VaultBound`
+    pub vault: Pubkey,
+
+    /// `Poseidon(amount, blinding)`; when set it replaces `amount` as the
+    /// public input (confidential-amount circuit)
+    pub amount_commitment: Option<[u8; 32]>,
 }
 
+// The withdrawal circuit's key must fit a default-sized VK account
+const _: () = assert!(ZkPublicInputs::COUNT <= MAX_PUBLIC_INPUTS);
+
 impl ZkPublicInputs {
     /// Number of public inputs for verification
     pub const COUNT: usize = 6;
@@ -68,9 +158,32 @@ impl ZkPublicInputs {
             amount,
             relayer,
             relayer_fee,
+            recipient_binding: RecipientBinding::Raw,
+            vault: Pubkey::default(),
+            amount_commitment: None,
         }
     }
 
+    /// Expose `amount_commitment` instead of the cleartext amount.
+    ///
+    /// The caller must have checked that it commits to `amount`.
+    pub fn with_amount_commitment(mut self, amount_commitment: [u8; 32]) -> Self {
+        self.amount_commitment = Some(amount_commitment);
+        self
+    }
+
+    /// Use `binding` to encode `recipient`.
+    pub fn with_recipient_binding(mut self, binding: RecipientBinding) -> Self {
+        self.recipient_binding = binding;
+        self
+    }
+
+    /// Record the vault paying this withdrawal.
+    pub fn with_vault(mut self, vault: Pubkey) -> Self {
+        self.vault = vault;
+        self
+    }
+
     /// Validate public inputs
     pub fn validate(&self) -> Result<()> {
         // Merkle root cannot be zero
@@ -85,6 +198,13 @@ impl ZkPublicInputs {
             PrivacyError::InvalidNullifier
         );
 
+        // Raw field elements must be canonical (< r)
+        require!(is_valid_scalar(&self.merkle_root), PrivacyError::InvalidMerkleRoot);
+        require!(is_valid_scalar(&self.nullifier_hash), PrivacyError::InvalidNullifier);
+        if let Some(amount_commitment) = &self.amount_commitment {
+            require!(is_valid_scalar(amount_commitment), PrivacyError::InvalidPublicInputs);
+        }
+
         // Amount must be positive
         require!(self.amount > 0, PrivacyError::InvalidAmount);
 
@@ -99,17 +219,25 @@ impl ZkPublicInputs {
 
     /// Convert to field elements for Groth16 verification.
     ///
-    /// Returns a vector of 32-byte field elements in the order
-    /// expected by the circuit.
-    pub fn to_field_elements(&self) -> Vec<[u8; 32]> {
-        vec![
-            self.merkle_root,
-            self.nullifier_hash,
-            self.recipient.to_bytes(),
-            u64_to_field(self.amount),
-            self.relayer.to_bytes(),
-            u64_to_field(self.relayer_fee),
-        ]
+    /// Returns the scalars in the order expected by the circuit.
+    ///
+    /// # Errors
+    /// * `InvalidPublicInputs` if `merkle_root`, `nullifier_hash` or
+    ///   `amount_commitment` is not a canonical scalar
+    pub fn to_field_elements(&self) -> Result<Vec<Scalar>> {
+        let amount = match self.amount_commitment {
+            Some(commitment) => Scalar::from_canonical(commitment)?,
+            None => Scalar::from_u64(self.amount),
+        };
+        Ok(vec![
+            Scalar::from_canonical(self.merkle_root)?,
+            Scalar::from_canonical(self.nullifier_hash)?,
+            // Already < r for every binding
+            Scalar::reduce(&self.recipient_binding.encode(&self.recipient, &self.vault)),
+            amount,
+            Scalar::reduce(&self.relayer.to_bytes()),
+            Scalar::from_u64(self.relayer_fee),
+        ])
     }
 
     /// Calculate net amount after fee
@@ -119,6 +247,21 @@ impl ZkPublicInputs {
             .ok_or_else(|| error!(PrivacyError::ArithmeticOverflow))
     }
 
+    /// Digest of the public inputs, emitted as `WithdrawEvent.inputs_hash`.
+    ///
+    /// `Keccak256("psol-public-inputs" || field_elements...)`, so anyone
+    /// holding the inputs can later show which withdrawal they belong to.
+    ///
+    /// # Errors
+    /// * `InvalidPublicInputs` per `to_field_elements`
+    pub fn hash(&self) -> Result<[u8; 32]> {
+        let elements = self.to_field_elements()?;
+        let mut parts: Vec<&[u8]> = Vec::with_capacity(elements.len() + 1);
+        parts.push(b"psol-public-inputs");
+        parts.extend(elements.iter().map(|e| e.as_ref()));
+        Ok(solana_program::keccak::hashv(&parts).to_bytes())
+    }
+
     /// Check if this is a self-relay (recipient == relayer, no fee)
     pub fn is_self_relay(&self) -> bool {
         self.recipient == self.relayer && self.relayer_fee == 0
@@ -199,6 +342,9 @@ impl ZkPublicInputsBuilder {
             amount: self.amount.ok_or(error!(PrivacyError::InvalidAmount))?,
             relayer: self.relayer.ok_or(error!(PrivacyError::RecipientMismatch))?,
             relayer_fee: self.relayer_fee.unwrap_or(0),
+            recipient_binding: RecipientBinding::Raw,
+            vault: Pubkey::default(),
+            amount_commitment: None,
         };
 
         inputs.validate()?;
@@ -207,18 +353,107 @@ impl ZkPublicInputsBuilder {
 }
 
 // ============================================================================
-// UTILITY FUNCTIONS
+// SPLIT RELAYER FEES
 // ============================================================================
 
-/// Convert u64 to 32-byte field element (big-endian).
+/// Relayer public input for a withdrawal whose fee is split between two
+/// relayers.
+///
+/// The withdrawal circuit has a single `relayer` / `relayer_fee` pair. For a
+/// split, the prover sets `relayer` to this binding and `relayer_fee` to
+/// `fee_1 + fee_2`, so the proof commits to both recipients and both shares
+/// without changing the circuit or the verification key.
+///
+/// `binding = Keccak256("psol-split-fee" || relayer_1 || fee_1 || relayer_2 || fee_2)`
+/// (fees big-endian), with the top bits cleared so it is a canonical field element.
+pub fn split_fee_relayer_binding(
+    relayer_1: &Pubkey,
+    fee_1: u64,
+    relayer_2: &Pubkey,
+    fee_2: u64,
+) -> Pubkey {
+    let mut binding = solana_program::keccak::hashv(&[
+        b"psol-split-fee",
+        relayer_1.as_ref(),
+        &fee_1.to_be_bytes(),
+        relayer_2.as_ref(),
+        &fee_2.to_be_bytes(),
+    ])
+    .to_bytes();
+    binding[0] &= 0x1f;
+    Pubkey::new_from_array(binding)
+}
+
+/// Relayer public input for a withdrawal whose relayer fee is paid in
+/// lamports from the pool's SOL fee reserve.
+///
+/// The token `relayer_fee` input is zero, so this is what binds the lamport
+/// fee: a front-runner cannot raise it or redirect it to another relayer.
+///
+/// `binding = Keccak256("psol-lamport-fee" || relayer || fee_lamports)`
+/// (fee big-endian), with the top bits cleared so it is a canonical field element.
+pub fn lamport_fee_relayer_binding(relayer: &Pubkey, fee_lamports: u64) -> Pubkey {
+    let mut binding = solana_program::keccak::hashv(&[
+        b"psol-lamport-fee",
+        relayer.as_ref(),
+        &fee_lamports.to_be_bytes(),
+    ])
+    .to_bytes();
+    binding[0] &= 0x1f;
+    Pubkey::new_from_array(binding)
+}
+
+/// Relayer public input for a withdrawal that re-deposits part of its
+/// amount as a new note.
+///
+/// Committing to the change note through the `relayer` input means a
+/// front-runner cannot swap `change_commitment` for one they control.
+///
+/// `binding = Keccak256("psol-redeposit" || relayer || change_commitment || redeposit_amount)`
+/// (amount big-endian), with the top bits cleared so it is a canonical field element.
+pub fn redeposit_relayer_binding(
+    relayer: &Pubkey,
+    change_commitment: &[u8; 32],
+    redeposit_amount: u64,
+) -> Pubkey {
+    let mut binding = solana_program::keccak::hashv(&[
+        b"psol-redeposit",
+        relayer.as_ref(),
+        change_commitment,
+        &redeposit_amount.to_be_bytes(),
+    ])
+    .to_bytes();
+    binding[0] &= 0x1f;
+    Pubkey::new_from_array(binding)
+}
+
+/// Relayer public input for a `migrate_note` spend.
 ///
-/// The value is placed in the last 8 bytes of a 32-byte array.
-fn u64_to_field(value: u64) -> [u8; 32] {
-    let mut bytes = [0u8; 32];
-    bytes[24..32].copy_from_slice(&value.to_be_bytes());
-    bytes
+/// Commits to the pool the note moves to and the commitment it becomes
+/// there, so a front-runner cannot redirect the migrated value.
+///
+/// `binding = Keccak256("psol-migrate" || relayer || target_pool || commitment)`,
+/// with the top bits cleared so it is a canonical field element.
+pub fn migration_relayer_binding(
+    relayer: &Pubkey,
+    target_pool: &Pubkey,
+    commitment: &[u8; 32],
+) -> Pubkey {
+    let mut binding = solana_program::keccak::hashv(&[
+        b"psol-migrate",
+        relayer.as_ref(),
+        target_pool.as_ref(),
+        commitment,
+    ])
+    .to_bytes();
+    binding[0] &= 0x1f;
+    Pubkey::new_from_array(binding)
 }
 
+// ============================================================================
+// UTILITY FUNCTIONS
+// ============================================================================
+
 // ============================================================================
 // TESTS
 // ============================================================================
@@ -270,6 +505,28 @@ mod tests {
         assert!(inputs.validate().is_err());
     }
 
+    #[test]
+    fn test_non_canonical_field_elements_rejected() {
+        let r = crate::crypto::BN254_SCALAR_MODULUS;
+        let inputs = ZkPublicInputs::new([1u8; 32], r, test_pubkey(), 1000, test_pubkey(), 100);
+        assert_eq!(inputs.validate().unwrap_err(), error!(PrivacyError::InvalidNullifier));
+        let inputs = ZkPublicInputs::new([1u8; 32], [0xff; 32], test_pubkey(), 1000, test_pubkey(), 100);
+        assert_eq!(inputs.validate().unwrap_err(), error!(PrivacyError::InvalidNullifier));
+
+        let inputs = ZkPublicInputs::new(r, [2u8; 32], test_pubkey(), 1000, test_pubkey(), 100);
+        assert_eq!(inputs.validate().unwrap_err(), error!(PrivacyError::InvalidMerkleRoot));
+
+        let inputs = ZkPublicInputs::new([1u8; 32], [2u8; 32], test_pubkey(), 1000, test_pubkey(), 100)
+            .with_amount_commitment([0xff; 32]);
+        assert_eq!(inputs.validate().unwrap_err(), error!(PrivacyError::InvalidPublicInputs));
+
+        // r - 1 is the largest canonical value
+        let mut max = r;
+        max[31] -= 1;
+        let inputs = ZkPublicInputs::new(max, max, test_pubkey(), 1000, test_pubkey(), 100);
+        assert!(inputs.validate().is_ok());
+    }
+
     #[test]
     fn test_zero_amount_invalid() {
         let inputs = ZkPublicInputs::new(
@@ -320,10 +577,83 @@ mod tests {
             test_pubkey(),
             100,
         );
-        let elements = inputs.to_field_elements();
+        let elements = inputs.to_field_elements().unwrap();
         assert_eq!(elements.len(), ZkPublicInputs::COUNT);
     }
 
+    #[test]
+    fn test_hash_binds_every_input() {
+        let recipient = test_pubkey();
+        let relayer = test_pubkey();
+        let base = ZkPublicInputs::new([1u8; 32], [2u8; 32], recipient, 1000, relayer, 100);
+        assert_eq!(base.hash(), base.clone().hash());
+
+        let variants = [
+            ZkPublicInputs { merkle_root: [9u8; 32], ..base.clone() },
+            ZkPublicInputs { nullifier_hash: [9u8; 32], ..base.clone() },
+            ZkPublicInputs { recipient: relayer, ..base.clone() },
+            ZkPublicInputs { amount: 1001, ..base.clone() },
+            ZkPublicInputs { relayer: recipient, ..base.clone() },
+            ZkPublicInputs { relayer_fee: 99, ..base.clone() },
+        ];
+        for variant in &variants {
+            assert_ne!(variant.hash(), base.hash());
+        }
+    }
+
+    #[test]
+    fn test_hashed_recipient_is_canonical() {
+        let r = num_bigint::BigUint::from_bytes_be(&crate::crypto::BN254_SCALAR_MODULUS);
+        let recipient = Pubkey::new_from_array([0xff; 32]);
+
+        // Raw reduces a pubkey >= r to its canonical representative
+        let raw = RecipientBinding::Raw.encode(&recipient, &Pubkey::default());
+        assert!(num_bigint::BigUint::from_bytes_be(recipient.as_ref()) >= r);
+        assert_eq!(
+            num_bigint::BigUint::from_bytes_be(&raw),
+            num_bigint::BigUint::from_bytes_be(recipient.as_ref()) % &r
+        );
+
+        let hashed = RecipientBinding::Hashed.encode(&recipient, &Pubkey::default());
+        assert!(num_bigint::BigUint::from_bytes_be(&hashed) < r);
+        assert_ne!(hashed, RecipientBinding::Hashed.encode(&test_pubkey(), &Pubkey::default()));
+
+        let inputs = ZkPublicInputs::new([1u8; 32], [2u8; 32], recipient, 1000, recipient, 0)
+            .with_recipient_binding(RecipientBinding::Hashed);
+        assert_eq!(inputs.to_field_elements().unwrap()[2].to_bytes(), hashed);
+    }
+
+    #[test]
+    fn test_vault_bound_recipient_commits_to_vault() {
+        let (recipient, vault) = (test_pubkey(), test_pubkey());
+        let bound = RecipientBinding::VaultBound.encode(&recipient, &vault);
+
+        assert_ne!(bound, RecipientBinding::VaultBound.encode(&recipient, &test_pubkey()));
+        assert_ne!(bound, RecipientBinding::VaultBound.encode(&test_pubkey(), &vault));
+        assert_ne!(bound, RecipientBinding::Hashed.encode(&recipient, &vault));
+        // Other bindings ignore the vault
+        assert_eq!(
+            RecipientBinding::Hashed.encode(&recipient, &vault),
+            RecipientBinding::Hashed.encode(&recipient, &test_pubkey())
+        );
+        assert_eq!(RecipientBinding::from_u8(2), Some(RecipientBinding::VaultBound));
+    }
+
+    #[test]
+    fn test_field_elements_canonical_for_large_pubkeys() {
+        let recipient = Pubkey::new_from_array([0xff; 32]);
+        let relayer = Pubkey::new_from_array([0xfe; 32]);
+
+        for binding in [RecipientBinding::Raw, RecipientBinding::Hashed, RecipientBinding::VaultBound] {
+            let inputs = ZkPublicInputs::new([1u8; 32], [2u8; 32], recipient, 1000, relayer, 10)
+                .with_recipient_binding(binding)
+                .with_vault(Pubkey::new_from_array([0xfd; 32]));
+            let elements = inputs.to_field_elements().unwrap();
+            assert!(elements.iter().all(|e| crate::crypto::is_valid_scalar(e.as_bytes())));
+            assert_eq!(elements[4].to_bytes(), pubkey_to_scalar(&relayer));
+        }
+    }
+
     #[test]
     fn test_self_relay() {
         let addr = test_pubkey();
@@ -362,10 +692,57 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_split_fee_binding_commits_to_shares() {
+        let r1 = test_pubkey();
+        let r2 = test_pubkey();
+        let binding = split_fee_relayer_binding(&r1, 30, &r2, 20);
+
+        assert_eq!(binding, split_fee_relayer_binding(&r1, 30, &r2, 20));
+        assert_ne!(binding, split_fee_relayer_binding(&r1, 20, &r2, 30));
+        assert_ne!(binding, split_fee_relayer_binding(&r2, 30, &r1, 20));
+        assert!(binding.to_bytes()[0] < 0x30);
+    }
+
+    #[test]
+    fn test_redeposit_binding_commits_to_change() {
+        let relayer = test_pubkey();
+        let binding = redeposit_relayer_binding(&relayer, &[7u8; 32], 400);
+
+        assert_eq!(binding, redeposit_relayer_binding(&relayer, &[7u8; 32], 400));
+        assert_ne!(binding, redeposit_relayer_binding(&relayer, &[8u8; 32], 400));
+        assert_ne!(binding, redeposit_relayer_binding(&relayer, &[7u8; 32], 401));
+        assert_ne!(binding, redeposit_relayer_binding(&test_pubkey(), &[7u8; 32], 400));
+        assert!(binding.to_bytes()[0] < 0x20);
+    }
+
+    #[test]
+    fn test_lamport_fee_binding_commits_to_fee() {
+        let relayer = test_pubkey();
+        let binding = lamport_fee_relayer_binding(&relayer, 5_000);
+
+        assert_eq!(binding, lamport_fee_relayer_binding(&relayer, 5_000));
+        assert_ne!(binding, lamport_fee_relayer_binding(&relayer, 5_001));
+        assert_ne!(binding, lamport_fee_relayer_binding(&test_pubkey(), 5_000));
+        assert!(binding.to_bytes()[0] < 0x20);
+    }
+
+    #[test]
+    fn test_migration_binding_commits_to_target() {
+        let (relayer, pool) = (test_pubkey(), test_pubkey());
+        let binding = migration_relayer_binding(&relayer, &pool, &[1u8; 32]);
+
+        assert_eq!(binding, migration_relayer_binding(&relayer, &pool, &[1u8; 32]));
+        assert_ne!(binding, migration_relayer_binding(&relayer, &pool, &[2u8; 32]));
+        assert_ne!(binding, migration_relayer_binding(&relayer, &test_pubkey(), &[1u8; 32]));
+        assert_ne!(binding, migration_relayer_binding(&test_pubkey(), &pool, &[1u8; 32]));
+        assert!(binding.to_bytes()[0] < 0x20);
+    }
+
     #[test]
     fn test_u64_to_field_encoding() {
         let value = 0x0102030405060708u64;
-        let field = u64_to_field(value);
+        let field = Scalar::from_u64(value).to_bytes();
         
         // First 24 bytes should be zero
         assert!(field[..24].iter().all(|&b| b == 0));
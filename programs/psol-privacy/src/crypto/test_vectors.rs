@@ -0,0 +1,177 @@
+//! Canonical Hash Test Vectors
+//!
+//! Fixed `(inputs, expected_output)` tuples shared with circuit authors and
+//! client implementations. If a proof does not verify, first check that your
+//! Poseidon reproduces these exactly.
+//!
+//! All values are 32-byte big-endian hex, i.e. the output of
+//! `poseidon([...]).toString(16)` in circomlibjs, left-padded to 64 chars.
+//! Amounts are the plain integer passed to circomlibjs.
+//!
+//! Vectors over small integers (`[0, 0]`, `[1, 1]`, `[1, 2]`, `[1, 1, 1]`,
+//! `[1, 2, 3]`) are the published circomlibjs outputs. The remaining vectors
+//! use the same BN254 x5 circom parameters.
+
+/// `hash_two_to_one_poseidon(left, right)`
+pub struct TwoToOneVector {
+    pub left: &'static str,
+    pub right: &'static str,
+    pub expected: &'static str,
+}
+
+/// `hash_commitment(secret, nullifier_preimage, amount)`
+pub struct CommitmentVector {
+    pub secret: &'static str,
+    pub nullifier_preimage: &'static str,
+    pub amount: u64,
+    pub expected: &'static str,
+}
+
+/// `hash_nullifier(nullifier_preimage, secret)`
+pub struct NullifierVector {
+    pub nullifier_preimage: &'static str,
+    pub secret: &'static str,
+    pub expected: &'static str,
+}
+
+const ZERO: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+const ONE: &str = "0000000000000000000000000000000000000000000000000000000000000001";
+const TWO: &str = "0000000000000000000000000000000000000000000000000000000000000002";
+const ONES: &str = "0101010101010101010101010101010101010101010101010101010101010101";
+const TWOS: &str = "0202020202020202020202020202020202020202020202020202020202020202";
+const FIELD_A: &str = "1a21421ad3056008222f3d39d3e7e2a38fbbd4742883bff725b6ce5699d72f1b";
+const FIELD_B: &str = "062d7eb423c9fdc9809183a6b5611af486d14acac97bc390428d398668b12b0c";
+
+pub const TWO_TO_ONE_VECTORS: &[TwoToOneVector] = &[
+    TwoToOneVector {
+        left: ZERO,
+        right: ZERO,
+        expected: "2098f5fb9e239eab3ceac3f27b81e481dc3124d55ffed523a839ee8446b64864",
+    },
+    TwoToOneVector {
+        left: ZERO,
+        right: ONE,
+        expected: "1bd20834f5de9830c643778a2e88a3a1363c8b9ac083d36d75bf87c49953e65e",
+    },
+    TwoToOneVector {
+        left: ONE,
+        right: TWO,
+        expected: "115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a",
+    },
+    TwoToOneVector {
+        left: ONES,
+        right: TWOS,
+        expected: "0d54e1938f8a8c1c7deb5e0355f26319207b84fe9ca2ce1b26e735c829821990",
+    },
+    TwoToOneVector {
+        left: FIELD_A,
+        right: FIELD_B,
+        expected: "168613f10017bc23aba08fbd5f193be5f6400afa6c6d44e7a991db36e11f290a",
+    },
+];
+
+pub const COMMITMENT_VECTORS: &[CommitmentVector] = &[
+    CommitmentVector {
+        secret: ZERO,
+        nullifier_preimage: ZERO,
+        amount: 0,
+        expected: "0bc188d27dcceadc1dcfb6af0a7af08fe2864eecec96c5ae7cee6db31ba599aa",
+    },
+    CommitmentVector {
+        secret: ONE,
+        nullifier_preimage: ONE,
+        amount: 1,
+        expected: "02c0066e10a72abd2b33c3b214cb3e81bcb1b6e30961cd23c202b18673bf2543",
+    },
+    CommitmentVector {
+        secret: ONE,
+        nullifier_preimage: TWO,
+        amount: 3,
+        expected: "0e7732d89e6939c0ff03d5e58dab6302f3230e269dc5b968f725df34ab36d732",
+    },
+    CommitmentVector {
+        secret: ONES,
+        nullifier_preimage: TWOS,
+        amount: 1_000_000_000,
+        expected: "1494fbd047b6a73642e44825d885d000ca9074e1411496fe5107a7441f9466f5",
+    },
+    CommitmentVector {
+        secret: FIELD_A,
+        nullifier_preimage: FIELD_B,
+        amount: u64::MAX,
+        expected: "2b89880b40aeb72c5c0562ad2db8df3b0839d3da20c26f2204d1193017fe3edb",
+    },
+];
+
+pub const NULLIFIER_VECTORS: &[NullifierVector] = &[
+    NullifierVector {
+        nullifier_preimage: ZERO,
+        secret: ZERO,
+        expected: "2098f5fb9e239eab3ceac3f27b81e481dc3124d55ffed523a839ee8446b64864",
+    },
+    NullifierVector {
+        nullifier_preimage: ONE,
+        secret: ONE,
+        expected: "007af346e2d304279e79e0a9f3023f771294a78acb70e73f90afe27cad401e81",
+    },
+    NullifierVector {
+        nullifier_preimage: ONE,
+        secret: TWO,
+        expected: "115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a",
+    },
+    NullifierVector {
+        nullifier_preimage: ONES,
+        secret: TWOS,
+        expected: "0d54e1938f8a8c1c7deb5e0355f26319207b84fe9ca2ce1b26e735c829821990",
+    },
+    NullifierVector {
+        nullifier_preimage: FIELD_B,
+        secret: FIELD_A,
+        expected: "0c44fdfc33cf10633e6f6b1e80e60d8af18ec531140cb8d992b7d0dad9ae0fbf",
+    },
+];
+
+/// Decode a 64-char hex vector field into bytes.
+pub fn decode(hex: &str) -> [u8; 32] {
+    assert_eq!(hex.len(), 64, "vector fields are 32 bytes");
+    let mut out = [0u8; 32];
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).expect("invalid hex");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::poseidon::{hash_commitment, hash_nullifier, hash_two_to_one_poseidon};
+
+    #[test]
+    fn test_two_to_one_vectors() {
+        for (i, v) in TWO_TO_ONE_VECTORS.iter().enumerate() {
+            let hash = hash_two_to_one_poseidon(&decode(v.left), &decode(v.right)).unwrap();
+            assert_eq!(hash, decode(v.expected), "two_to_one vector {}", i);
+        }
+    }
+
+    #[test]
+    fn test_commitment_vectors() {
+        for (i, v) in COMMITMENT_VECTORS.iter().enumerate() {
+            let hash = hash_commitment(
+                &decode(v.secret),
+                &decode(v.nullifier_preimage),
+                v.amount,
+            )
+            .unwrap();
+            assert_eq!(hash, decode(v.expected), "commitment vector {}", i);
+        }
+    }
+
+    #[test]
+    fn test_nullifier_vectors() {
+        for (i, v) in NULLIFIER_VECTORS.iter().enumerate() {
+            let hash = hash_nullifier(&decode(v.nullifier_preimage), &decode(v.secret)).unwrap();
+            assert_eq!(hash, decode(v.expected), "nullifier vector {}", i);
+        }
+    }
+}
@@ -103,4 +103,154 @@ pub enum PrivacyError {
 
     #[msg("Invalid timestamp")]
     InvalidTimestamp, // 6032
+
+    #[msg("Not enough deposits in the pool to satisfy the minimum anonymity set")]
+    AnonymitySetTooSmall, // 6033
+
+    #[msg("Invalid Merkle tree hash function selector")]
+    InvalidTreeHash, // 6034
+
+    #[msg("Escrow recipient is not a PDA owned by the whitelisted escrow program")]
+    InvalidEscrowRecipient, // 6035
+
+    #[msg("Merkle root is older than the pool's maximum root age")]
+    MerkleRootExpired, // 6036
+
+    #[msg("Invalid referrer address")]
+    InvalidReferrer, // 6037
+
+    #[msg("Dev-mode build: pool has not acknowledged dev-mode")]
+    DevModeNotAcknowledged, // 6038
+
+    #[msg("Root history account does not match the Merkle tree layout")]
+    RootHistoryMismatch, // 6039
+
+    #[msg("Invalid fee recipient")]
+    InvalidFeeRecipient, // 6040
+
+    #[msg("No pending fee recipient change")]
+    NoPendingFeeRecipient, // 6041
+
+    #[msg("Shielded swap legs must use distinct pools with matching mint decimals")]
+    IncompatibleSwapPools, // 6042
+
+    #[msg("Poseidon implementation does not match circomlib parameters")]
+    PoseidonParameterMismatch, // 6043
+
+    #[msg("Swap program or pool is not the pool's whitelisted swap route")]
+    InvalidSwapRoute, // 6044
+
+    #[msg("Swap output is below the minimum amount out")]
+    SwapSlippageExceeded, // 6045
+
+    #[msg("Vault holds no untracked balance to sweep")]
+    NoUntrackedBalance, // 6046
+
+    #[msg("Recipient withdrawal cooldown has not elapsed")]
+    RecipientCooldownActive, // 6047
+
+    #[msg("Pool requires the recipient cooldown account for withdrawals")]
+    RecipientCooldownRequired, // 6048
+
+    #[msg("Public-input linear combination vk_x is the identity point")]
+    VkXIdentity, // 6049
+
+    #[msg("Invalid pause reason")]
+    InvalidPauseReason, // 6050
+
+    #[msg("Invalid commitment mode")]
+    InvalidCommitmentMode, // 6051
+
+    #[msg("Deposit argument does not match the pool's commitment mode")]
+    CommitmentModeMismatch, // 6052
+
+    #[msg("Pool registry is full")]
+    PoolRegistryFull, // 6053
+
+    #[msg("Pool is already registered")]
+    PoolAlreadyRegistered, // 6054
+
+    #[msg("Pool is not in the registry")]
+    PoolNotRegistered, // 6055
+
+    #[msg("Relayer fee is below the pool's minimum")]
+    RelayerFeeTooLow, // 6056
+
+    #[msg("Invalid recipient binding")]
+    InvalidRecipientBinding, // 6057
+
+    #[msg("Expected leaf index is too far from the tree's; resync")]
+    LeafIndexHintMismatch, // 6058
+
+    #[msg("Pending commitment queue is full")]
+    PendingQueueFull, // 6059
+
+    #[msg("No verifier for the verification key's curve")]
+    UnsupportedCurve, // 6060
+
+    #[msg("Withdrawal amount does not open the amount commitment")]
+    AmountCommitmentMismatch, // 6061
+
+    #[msg("Invalid multisig signer set or threshold")]
+    InvalidMultisigConfig, // 6062
+
+    #[msg("Not enough multisig members signed")]
+    MultisigThresholdNotMet, // 6063
+
+    #[msg("Recipient is on the pool's denylist")]
+    RecipientDenied, // 6064
+
+    #[msg("Recipient denylist missing or does not match the pool's root")]
+    RecipientDenylistMismatch, // 6065
+
+    #[msg("Pool has no deposits; nothing can be withdrawn")]
+    EmptyPool, // 6066
+
+    #[msg("Pool has no fee mint configured")]
+    FeeMintNotConfigured, // 6067
+
+    #[msg("Subtree import is misaligned, mis-sized, or the pool already has deposits")]
+    InvalidSubtreeImport, // 6068
+
+    #[msg("Verification key IC points do not match its declared IC length")]
+    VkIcLengthMismatch, // 6069
+
+    #[msg("Merkle root is newer than the pool's minimum note age")]
+    MerkleRootTooRecent, // 6070
+
+    #[msg("Custom zero values must be depth + 1 canonical field elements")]
+    InvalidZeroValues, // 6071
+
+    #[msg("Payee token account is uninitialized, frozen, or not rent-exempt")]
+    PayeeAccountNotReady, // 6072
+
+    #[msg("Withdrawals from this pool cannot be invoked via CPI")]
+    CpiWithdrawDisallowed, // 6073
+
+    #[msg("Proof was serialized for a different proof-system version")]
+    ProofVersionMismatch, // 6074
+
+    #[msg("Shrinking the root history would drop roots withdrawals can still use")]
+    RootHistoryShrinkDropsRoots, // 6075
+
+    #[msg("Pool does not pay relayer fees in lamports")]
+    LamportFeeNotEnabled, // 6076
+
+    #[msg("Relayer fee equals the amount, leaving nothing for the recipient")]
+    ZeroNetWithdrawal, // 6077
+
+    #[msg("Pool config is not a version 2 account awaiting migration")]
+    PoolConfigNotMigratable, // 6078
+
+    #[msg("Batched withdrawal accounts do not match the batch entries")]
+    InvalidBatchAccounts, // 6079
+
+    #[msg("Merkle tree is not a version 1 account awaiting migration")]
+    MerkleTreeNotMigratable, // 6080
+
+    #[msg("vk_x scratch was accumulated against a different verification key")]
+    VkXScratchStale, // 6081
+
+    #[msg("vk_x scratch has not absorbed every public input")]
+    VkXScratchIncomplete, // 6082
 }
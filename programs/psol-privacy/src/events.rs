@@ -9,6 +9,7 @@ pub struct PoolInitialized {
     pub token_mint: Pubkey,
     pub tree_depth: u8,
     pub root_history_size: u16,
+    pub tree_hash: u8,
     pub timestamp: i64,
 }
 
@@ -16,6 +17,16 @@ pub struct PoolInitialized {
 pub struct PoolPaused {
     pub pool: Pubkey,
     pub authority: Pubkey,
+    /// `PauseReason` as u8
+    pub reason: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolRegistered {
+    pub registry: Pubkey,
+    pub pool: Pubkey,
+    pub token_mint: Pubkey,
     pub timestamp: i64,
 }
 
@@ -23,6 +34,234 @@ pub struct PoolPaused {
 pub struct PoolUnpaused {
     pub pool: Pubkey,
     pub authority: Pubkey,
+    /// `PauseReason` as u8
+    pub reason: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MinAnonymitySetUpdated {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub min_anonymity_set: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EscrowProgramUpdated {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub escrow_program: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SwapRouteUpdated {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub swap_program: Pubkey,
+    pub swap_pool: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RecipientCooldownUpdated {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub recipient_cooldown_slots: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RecipientDenylistUpdated {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub recipient: Pubkey,
+    pub denied: bool,
+    pub denylist_root: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeReserveInitialized {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub fee_mint: Pubkey,
+    pub fee_reserve: Pubkey,
+    pub fee_mint_rate: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeMintRateUpdated {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub fee_mint_rate: u64,
+    pub timestamp: i64,
+}
+
+/// Relayer fee of a `withdraw_fee_in_mint`, paid from the fee reserve.
+/// `relayer_fee` is the proof-bound fee in pool-mint units.
+#[event]
+pub struct RelayerFeePaidInMint {
+    pub pool: Pubkey,
+    pub nullifier_hash: [u8; 32],
+    pub relayer: Pubkey,
+    pub relayer_fee: u64,
+    pub fee_mint: Pubkey,
+    pub fee_amount: u64,
+}
+
+#[event]
+pub struct SolFeeReserveInitialized {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub sol_fee_reserve: Pubkey,
+    pub max_relayer_fee_lamports: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MaxRelayerFeeLamportsUpdated {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub max_relayer_fee_lamports: u64,
+    pub timestamp: i64,
+}
+
+/// Relayer fee of a `withdraw_fee_in_lamports`, paid from the SOL fee
+/// reserve (companion to `WithdrawEvent`, whose `relayer_fee` is zero).
+#[event]
+pub struct RelayerFeePaidInLamports {
+    pub pool: Pubkey,
+    pub nullifier_hash: [u8; 32],
+    pub relayer: Pubkey,
+    pub fee_lamports: u64,
+}
+
+/// Note spent in `source_pool` and re-created as `commitment` in
+/// `target_pool` by `migrate_note` (companion to the `WithdrawEvent` and
+/// `DepositEvent` of the two legs).
+#[event]
+pub struct NoteMigrated {
+    pub source_pool: Pubkey,
+    pub target_pool: Pubkey,
+    pub nullifier_hash: [u8; 32],
+    pub commitment: [u8; 32],
+    pub leaf_index: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MinRelayerFeeUpdated {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub min_relayer_fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RecipientBindingUpdated {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub recipient_binding: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MaxRootAgeUpdated {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub max_root_age_slots: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RequireReadyPayeesUpdated {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub require_ready_payees: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AllowZeroNetWithdrawalUpdated {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub allow_zero_net_withdrawal: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DisallowCpiWithdrawUpdated {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub disallow_cpi_withdraw: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProofVersionUpdated {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub proof_version: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MinNoteAgeUpdated {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub min_note_age_slots: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VerboseLoggingUpdated {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub verbose_logging: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DevModeAcknowledged {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub acknowledged: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RootHistorySplit {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub root_history: Pubkey,
+    pub root_history_size: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RootPolicyConfigured {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub root_history_size: u16,
+    pub max_root_age_slots: u64,
+    pub timestamp: i64,
+}
+
+/// A legacy subtree was appended by `import_subtree`. Leaves
+/// `first_leaf_index..first_leaf_index + count` now hang under `node`.
+#[event]
+pub struct SubtreeImported {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub level: u8,
+    pub node: [u8; 32],
+    pub first_leaf_index: u32,
+    pub count: u32,
+    /// Tree root after the import
+    pub root: [u8; 32],
     pub timestamp: i64,
 }
 
@@ -34,6 +273,16 @@ pub struct AuthorityTransferInitiated {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct AuthorityMultisigEnabled {
+    pub pool: Pubkey,
+    pub previous_authority: Pubkey,
+    pub multisig: Pubkey,
+    pub threshold: u8,
+    pub signer_count: u8,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct AuthorityTransferCompleted {
     pub pool: Pubkey,
@@ -50,11 +299,74 @@ pub struct AuthorityTransferCancelled {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct FeeRecipientChangeInitiated {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub current_fee_recipient: Pubkey,
+    pub pending_fee_recipient: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeRecipientChangeCompleted {
+    pub pool: Pubkey,
+    pub old_fee_recipient: Pubkey,
+    pub new_fee_recipient: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeRecipientChangeCancelled {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub cancelled_pending: Pubkey,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct VerificationKeySet {
     pub pool: Pubkey,
     pub authority: Pubkey,
     pub ic_length: u8,
+    pub circuit_id: [u8; 32],
+    pub timestamp: i64,
+}
+
+/// `update_vk_ic` replaced the IC points; α, β, γ, δ are unchanged.
+#[event]
+pub struct VerificationKeyIcUpdated {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub ic_length: u8,
+    pub timestamp: i64,
+}
+
+/// `set_vk_ic_compression` re-encoded the IC points; the key is unchanged.
+#[event]
+pub struct VerificationKeyIcCompressionSet {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub compressed_ic: bool,
+    /// Verification key account size after the resize
+    pub data_len: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct NextVerificationKeySet {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub ic_length: u8,
+    pub dual_vk_window_until: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VerificationKeyPromoted {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub dual_vk_window_until: i64,
     pub timestamp: i64,
 }
 
@@ -65,6 +377,65 @@ pub struct VerificationKeyLocked {
     pub timestamp: i64,
 }
 
+/// An unconfigured verification key account was recreated empty.
+#[event]
+pub struct VerificationKeyReinitialized {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub data_len: u32,
+    pub timestamp: i64,
+}
+
+/// A version 2 pool config was rewritten in the current layout.
+#[event]
+pub struct PoolConfigMigrated {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+    pub timestamp: i64,
+}
+
+/// A version 1 Merkle tree was rewritten in the current layout.
+#[event]
+pub struct MerkleTreeMigrated {
+    pub pool: Pubkey,
+    pub merkle_tree: Pubkey,
+    pub authority: Pubkey,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+/// Vault balance disagrees with the pool's deposit/withdrawal accounting,
+/// e.g. after a direct transfer into the vault.
+#[event]
+pub struct TvlInvariantViolated {
+    pub pool: Pubkey,
+    pub vault_amount: u64,
+    pub total_value_deposited: u64,
+    pub total_value_withdrawn: u64,
+}
+
+#[event]
+pub struct UntrackedSwept {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted for every leaf inserted into the tree (every deposit-style
+/// instruction, `process_pending`, `dev_seed_commitments`), so the stream
+/// maps each commitment to its leaf index; see `client::note_recovery`.
+///
+/// `root` is the tree root right after inserting this leaf and `root_slot`
+/// the slot it entered the root history. After a reorg, a client compares
+/// `root` with the finalized tree rebuilt up to `leaf_index` to detect a
+/// rolled-back or reordered deposit; see `client::deposit_finality`.
+///
+/// Emitted with `emit!` (a log line), not `emit_cpi!`: the latter would add
+/// two accounts to `Deposit` and every instruction embedding it.
 #[event]
 pub struct DepositEvent {
     pub pool: Pubkey,
@@ -72,6 +443,42 @@ pub struct DepositEvent {
     pub leaf_index: u32,
     pub amount: u64,
     pub timestamp: i64,
+    pub root_slot: u64,
+    pub root: [u8; 32],
+    /// Opaque label encrypted by the depositor for their own records
+    /// (empty = none); not part of the commitment
+    pub encrypted_memo: Vec<u8>,
+}
+
+/// New tree root after a leaf insertion, emitted alongside every
+/// `DepositEvent`. Light clients that only need `is_known_root` can follow
+/// the root sequence from this event alone.
+#[event]
+pub struct RootUpdated {
+    pub pool: Pubkey,
+    pub new_root: [u8; 32],
+    pub leaf_index: u32,
+    pub slot: u64,
+}
+
+/// Deposit collected by `deposit_deferred`; its `DepositEvent` (with the
+/// leaf index) follows when `process_pending` inserts it.
+#[event]
+pub struct CommitmentQueued {
+    pub pool: Pubkey,
+    pub commitment: [u8; 32],
+    pub sequence: u64,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+/// Referral attribution for a deposit. Intentionally excludes the
+/// commitment and leaf index.
+#[event]
+pub struct ReferralEvent {
+    pub pool: Pubkey,
+    pub referrer: Pubkey,
+    pub amount: u64,
 }
 
 #[event]
@@ -82,9 +489,41 @@ pub struct WithdrawEvent {
     pub amount: u64,
     pub relayer: Pubkey,
     pub relayer_fee: u64,
+    /// `ZkPublicInputs::hash()` of the inputs the proof was verified against
+    pub inputs_hash: [u8; 32],
+    /// `PoolConfig.nullifier_generation` after this withdrawal
+    pub nullifier_generation: u64,
+    /// `SpentNullifier` PDA created by this withdrawal; relayers can cache
+    /// it (or `nullifier_hash`) to drop duplicate submissions locally
+    pub spent_nullifier: Pubkey,
+    /// Lamports locked as rent in `spent_nullifier`, paid by the submitter;
+    /// relayers add this to their break-even fee
+    pub rent_paid: u64,
     pub timestamp: i64,
 }
 
+/// Per-relayer shares of a split-fee withdrawal (companion to `WithdrawEvent`).
+#[event]
+pub struct RelayerFeeSplit {
+    pub pool: Pubkey,
+    pub nullifier_hash: [u8; 32],
+    pub relayer: Pubkey,
+    pub relayer_fee: u64,
+    pub second_relayer: Pubkey,
+    pub second_relayer_fee: u64,
+}
+
+/// Swap leg of a `withdraw_and_swap` (companion to `WithdrawEvent`).
+#[event]
+pub struct WithdrawSwapped {
+    pub pool: Pubkey,
+    pub nullifier_hash: [u8; 32],
+    pub swap_program: Pubkey,
+    pub output_mint: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+}
+
 #[event]
 pub struct TransferEvent {
     pub pool: Pubkey,
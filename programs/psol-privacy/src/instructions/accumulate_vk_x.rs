@@ -0,0 +1,106 @@
+//! Accumulate vk_x Instructions
+//!
+//! Split the Groth16 public-input linear combination across several
+//! instructions for circuits too large to do it in one pass.
+
+use anchor_lang::prelude::*;
+
+use crate::crypto::Scalar;
+use crate::error::PrivacyError;
+use crate::instructions::set_verification_key::MIN_IC_POINTS;
+use crate::instructions::withdraw::require_verification_key_set;
+use crate::state::{PoolConfig, VerificationKey, VerificationKeyAccount, VkXScratch};
+
+#[derive(Accounts)]
+pub struct InitVkXScratch<'info> {
+    #[account(
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = VkXScratch::LEN,
+        seeds = [b"vk_x_scratch", pool_config.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub scratch: Account<'info, VkXScratch>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn init_scratch_handler(ctx: Context<InitVkXScratch>) -> Result<()> {
+    let pool = ctx.accounts.pool_config.key();
+    let owner = ctx.accounts.owner.key();
+    ctx.accounts
+        .scratch
+        .initialize(pool, owner, ctx.bumps.scratch);
+
+    msg!("vk_x scratch initialized for {}", owner);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct AccumulateVkX<'info> {
+    #[account(
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    #[account(
+        seeds = [b"verification_key", pool_config.key().as_ref()],
+        bump = verification_key.bump,
+        constraint = verification_key.pool == pool_config.key() @ PrivacyError::Unauthorized,
+        constraint = verification_key.is_initialized @ PrivacyError::VerificationKeyNotSet,
+    )]
+    pub verification_key: Account<'info, VerificationKeyAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"vk_x_scratch", pool_config.key().as_ref(), owner.key().as_ref()],
+        bump = scratch.bump,
+        has_one = owner @ PrivacyError::Unauthorized,
+        constraint = scratch.pool == pool_config.key() @ PrivacyError::Unauthorized,
+    )]
+    pub scratch: Account<'info, VkXScratch>,
+
+    pub owner: Signer<'info>,
+}
+
+/// Absorb the next chunk of public inputs against the pool's primary VK.
+/// A fresh scratch starts from IC[0]; pass `reset = true` to abandon a
+/// partial sum and start over with this chunk. Inputs must be canonical
+/// scalars, in `ZkPublicInputs::to_field_elements` order.
+pub fn accumulate_handler(
+    ctx: Context<AccumulateVkX>,
+    reset: bool,
+    inputs: Vec<[u8; 32]>,
+) -> Result<()> {
+    require_verification_key_set(&ctx.accounts.pool_config, &ctx.accounts.verification_key)?;
+    let vk = VerificationKey::try_from(ctx.accounts.verification_key.as_ref())?;
+    require!(vk.ic.len() >= MIN_IC_POINTS, PrivacyError::VerificationKeyNotSet);
+
+    let inputs = inputs
+        .into_iter()
+        .map(Scalar::from_canonical)
+        .collect::<Result<Vec<_>>>()?;
+
+    let scratch = &mut ctx.accounts.scratch;
+    if reset {
+        scratch.reset();
+    }
+    scratch.absorb(&vk, &inputs)?;
+
+    msg!(
+        "vk_x accumulated: {}/{} inputs",
+        scratch.next_input,
+        vk.ic.len() - 1
+    );
+    Ok(())
+}
@@ -0,0 +1,140 @@
+//! Authority Multisig Instructions
+//!
+//! Moves the pool authority to an M-of-N `AuthorityMultisig`. Afterwards,
+//! every admin instruction takes the multisig account and needs `threshold`
+//! member signatures: the `authority` signer plus co-signers passed as
+//! signing `remaining_accounts`. `pause_all` only covers single-key pools.
+//!
+//! Transferring the authority to a key (which must accept) disables the
+//! multisig path again.
+
+use anchor_lang::prelude::*;
+
+use crate::error::PrivacyError;
+use crate::events::AuthorityMultisigEnabled;
+use crate::state::{AuthorityMultisig, PoolConfig};
+
+#[derive(Accounts)]
+pub struct InitAuthorityMultisig<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+        has_one = authority @ PrivacyError::Unauthorized,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = AuthorityMultisig::LEN,
+        seeds = [b"authority_multisig", pool_config.key().as_ref()],
+        bump,
+    )]
+    pub authority_multisig: Account<'info, AuthorityMultisig>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn init_multisig_handler(
+    ctx: Context<InitAuthorityMultisig>,
+    signers: Vec<Pubkey>,
+    threshold: u8,
+) -> Result<()> {
+    let pool_config = &mut ctx.accounts.pool_config;
+    let multisig = &mut ctx.accounts.authority_multisig;
+    let previous_authority = ctx.accounts.authority.key();
+
+    multisig.initialize(
+        pool_config.key(),
+        signers,
+        threshold,
+        ctx.bumps.authority_multisig,
+    )?;
+    pool_config.enable_authority_multisig(multisig.key());
+
+    emit!(AuthorityMultisigEnabled {
+        pool: pool_config.key(),
+        previous_authority,
+        multisig: multisig.key(),
+        threshold,
+        signer_count: multisig.signers.len() as u8,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!(
+        "Authority moved to {}-of-{} multisig {}",
+        threshold,
+        multisig.signers.len(),
+        multisig.key()
+    );
+    Ok(())
+}
+
+/// Check the admin signers of an instruction whose accounts replace
+/// `has_one = authority` with an optional `authority_multisig`.
+///
+/// Signing `remaining_accounts` count as co-signers; non-signers are ignored.
+pub fn require_admin_signers(
+    pool_config: &PoolConfig,
+    authority: &Signer,
+    authority_multisig: &Option<Account<AuthorityMultisig>>,
+    remaining_accounts: &[AccountInfo],
+) -> Result<()> {
+    let signers: Vec<Pubkey> = std::iter::once(authority.key())
+        .chain(
+            remaining_accounts
+                .iter()
+                .filter(|account| account.is_signer)
+                .map(|account| account.key()),
+        )
+        .collect();
+    pool_config.require_admin(
+        &signers,
+        authority_multisig
+            .as_ref()
+            .map(|multisig| (multisig.key(), &**multisig)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::withdraw::test_fixtures::{account_info, program_account};
+    use crate::state::pool_config::test_fixtures::new_pool;
+
+    fn signer(key: Pubkey, is_signer: bool) -> &'static AccountInfo<'static> {
+        account_info(key, Pubkey::default(), Vec::new(), is_signer, false)
+    }
+
+    #[test]
+    fn test_admin_signers_counts_signing_remaining_accounts() {
+        let members: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let mut multisig = AuthorityMultisig::try_deserialize_unchecked(
+            &mut vec![0u8; AuthorityMultisig::LEN].as_slice(),
+        )
+        .unwrap();
+        multisig.initialize(Pubkey::new_unique(), members.clone(), 2, 255).unwrap();
+        let multisig = Some(program_account(&multisig, AuthorityMultisig::LEN));
+        let mut pool = new_pool();
+        pool.enable_authority_multisig(multisig.as_ref().unwrap().key());
+
+        let authority = Signer::try_from(signer(members[0], true)).unwrap();
+        let co_signed = [signer(members[1], true).clone()];
+        let listed_only = signer(members[1], false).clone();
+
+        // One of two: the authority alone, or with a co-signer that did not sign
+        assert_eq!(
+            require_admin_signers(&pool, &authority, &multisig, &[]).unwrap_err(),
+            error!(PrivacyError::MultisigThresholdNotMet)
+        );
+        assert!(require_admin_signers(&pool, &authority, &multisig, &[listed_only]).is_err());
+        assert!(require_admin_signers(&pool, &authority, &multisig, &co_signed).is_ok());
+
+        // The multisig account is required while it holds the authority
+        assert!(require_admin_signers(&pool, &authority, &None, &co_signed).is_err());
+    }
+}
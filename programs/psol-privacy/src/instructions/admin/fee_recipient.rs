@@ -0,0 +1,126 @@
+//! Fee Recipient Instructions (2-Step Change)
+//!
+//! Mirrors the authority transfer: the authority proposes a new fee
+//! recipient, which must sign to accept.
+
+use anchor_lang::prelude::*;
+
+use crate::events::{
+    FeeRecipientChangeCancelled, FeeRecipientChangeCompleted, FeeRecipientChangeInitiated,
+};
+use crate::instructions::admin::authority_multisig::require_admin_signers;
+use crate::state::{AuthorityMultisig, PoolConfig};
+
+#[derive(Accounts)]
+pub struct InitiateFeeRecipientChange<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    pub authority: Signer<'info>,
+
+    /// Required when `pool_config.authority_is_multisig`
+    pub authority_multisig: Option<Account<'info, AuthorityMultisig>>,
+}
+
+pub fn initiate_change_handler(
+    ctx: Context<InitiateFeeRecipientChange>,
+    new_fee_recipient: Pubkey,
+) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let pool_config = &mut ctx.accounts.pool_config;
+
+    pool_config.initiate_fee_recipient_change(new_fee_recipient)?;
+
+    emit!(FeeRecipientChangeInitiated {
+        pool: pool_config.key(),
+        authority: ctx.accounts.authority.key(),
+        current_fee_recipient: pool_config.fee_recipient,
+        pending_fee_recipient: new_fee_recipient,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Fee recipient change initiated to: {}", new_fee_recipient);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct AcceptFeeRecipientChange<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    pub new_fee_recipient: Signer<'info>,
+}
+
+pub fn accept_change_handler(ctx: Context<AcceptFeeRecipientChange>) -> Result<()> {
+    let pool_config = &mut ctx.accounts.pool_config;
+    let new_fee_recipient = ctx.accounts.new_fee_recipient.key();
+    let old_fee_recipient = pool_config.fee_recipient;
+
+    pool_config.accept_fee_recipient_change(new_fee_recipient)?;
+
+    emit!(FeeRecipientChangeCompleted {
+        pool: pool_config.key(),
+        old_fee_recipient,
+        new_fee_recipient,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Fee recipient change completed");
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CancelFeeRecipientChange<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    pub authority: Signer<'info>,
+
+    /// Required when `pool_config.authority_is_multisig`
+    pub authority_multisig: Option<Account<'info, AuthorityMultisig>>,
+}
+
+pub fn cancel_change_handler(ctx: Context<CancelFeeRecipientChange>) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let pool_config = &mut ctx.accounts.pool_config;
+
+    if !pool_config.has_pending_fee_recipient() {
+        msg!("No pending fee recipient change");
+        return Ok(());
+    }
+
+    let cancelled_pending = pool_config.pending_fee_recipient;
+    pool_config.cancel_fee_recipient_change();
+
+    emit!(FeeRecipientChangeCancelled {
+        pool: pool_config.key(),
+        authority: ctx.accounts.authority.key(),
+        cancelled_pending,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Fee recipient change cancelled");
+    Ok(())
+}
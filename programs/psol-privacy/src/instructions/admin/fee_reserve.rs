@@ -0,0 +1,77 @@
+//! Fee Reserve Instruction
+//!
+//! Creates the pool-owned token account that `withdraw_fee_in_mint` pays
+//! relayer fees from, in a mint other than the pool's (e.g. a stablecoin).
+//! The protocol funds it by plain transfers; the pool-mint fee of each such
+//! withdrawal goes to the fee recipient instead, at the fixed
+//! `fee_mint_rate`.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+use crate::error::PrivacyError;
+use crate::events::FeeReserveInitialized;
+use crate::instructions::admin::authority_multisig::require_admin_signers;
+use crate::state::{AuthorityMultisig, PoolConfig};
+
+#[derive(Accounts)]
+pub struct InitFeeReserve<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    pub fee_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = fee_mint,
+        token::authority = pool_config,
+        seeds = [b"fee_reserve", pool_config.key().as_ref()],
+        bump
+    )]
+    pub fee_reserve: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Required when `pool_config.authority_is_multisig`
+    pub authority_multisig: Option<Account<'info, AuthorityMultisig>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+pub fn init_fee_reserve_handler(ctx: Context<InitFeeReserve>, fee_mint_rate: u64) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let fee_mint = ctx.accounts.fee_mint.key();
+    require_keys_neq!(
+        fee_mint,
+        ctx.accounts.pool_config.token_mint,
+        PrivacyError::InvalidMint
+    );
+
+    let pool_config = &mut ctx.accounts.pool_config;
+    pool_config.set_fee_mint(fee_mint, fee_mint_rate);
+
+    emit!(FeeReserveInitialized {
+        pool: pool_config.key(),
+        authority: ctx.accounts.authority.key(),
+        fee_mint,
+        fee_reserve: ctx.accounts.fee_reserve.key(),
+        fee_mint_rate,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Fee reserve created for mint {}", fee_mint);
+    Ok(())
+}
@@ -0,0 +1,92 @@
+//! Import Subtree Instruction
+//!
+//! Bulk-imports commitments migrated from a legacy pool: each call appends
+//! one complete subtree of `2^level` leaves given only its root, instead of
+//! re-depositing every leaf.
+//!
+//! Only allowed before the pool's first deposit, so every imported leaf
+//! precedes every deposited one and no user proof is built against a root
+//! that a later import could reorder. Imported notes carry no deposit
+//! accounting: the authority must move the legacy vault's funds into this
+//! pool's vault, or the TVL monitor will flag the shortfall as they are
+//! withdrawn. See `MerkleTree::import_subtree` for the tree invariants.
+
+use anchor_lang::prelude::*;
+
+use crate::error::PrivacyError;
+use crate::events::{RootUpdated, SubtreeImported};
+use crate::instructions::admin::authority_multisig::require_admin_signers;
+use crate::state::{AuthorityMultisig, MerkleTree, PoolConfig, RootHistory};
+
+#[derive(Accounts)]
+pub struct ImportSubtree<'info> {
+    #[account(
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+        constraint = pool_config.total_deposits == 0 @ PrivacyError::InvalidSubtreeImport,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"merkle_tree", pool_config.key().as_ref()],
+        bump,
+        constraint = merkle_tree.pool == pool_config.key() @ PrivacyError::Unauthorized,
+    )]
+    pub merkle_tree: Account<'info, MerkleTree>,
+
+    /// Split-layout root history; required iff `merkle_tree.external_root_history`
+    #[account(
+        mut,
+        seeds = [b"root_history", pool_config.key().as_ref()],
+        bump = root_history.bump,
+    )]
+    pub root_history: Option<Account<'info, RootHistory>>,
+
+    pub authority: Signer<'info>,
+
+    /// Required when `pool_config.authority_is_multisig`
+    pub authority_multisig: Option<Account<'info, AuthorityMultisig>>,
+}
+
+pub fn import_subtree_handler(
+    ctx: Context<ImportSubtree>,
+    level: u8,
+    node: [u8; 32],
+    count: u32,
+) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    ctx.accounts.pool_config.require_not_paused()?;
+
+    let clock = Clock::get()?;
+    let merkle_tree = &mut ctx.accounts.merkle_tree;
+    let root_history = ctx.accounts.root_history.as_deref_mut();
+    let first_leaf_index = merkle_tree.import_subtree(level, node, count, clock.slot, root_history)?;
+
+    emit!(SubtreeImported {
+        pool: ctx.accounts.pool_config.key(),
+        authority: ctx.accounts.authority.key(),
+        level,
+        node,
+        first_leaf_index,
+        count,
+        root: merkle_tree.current_root,
+        timestamp: clock.unix_timestamp,
+    });
+    // One root per import: the subtree's leaves never appear in any
+    // intermediate root
+    emit!(RootUpdated {
+        pool: ctx.accounts.pool_config.key(),
+        new_root: merkle_tree.current_root,
+        leaf_index: first_leaf_index + (count - 1),
+        slot: clock.slot,
+    });
+
+    msg!("Imported {} leaves at index {}", count, first_leaf_index);
+    Ok(())
+}
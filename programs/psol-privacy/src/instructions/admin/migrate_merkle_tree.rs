@@ -0,0 +1,268 @@
+//! Migrate Merkle Tree Instruction
+//!
+//! Trees created before the per-tree hash selector hold a shorter
+//! `MerkleTree` with no `tree_hash`, `external_root_history` or
+//! `root_slots`, so every instruction taking the tree fails until it is
+//! migrated. Reads the version 1 fields, grows the account to
+//! `MerkleTree::space` (the authority pays the extra rent) and writes it
+//! back in the current layout as a Keccak tree with its roots in place.
+//!
+//! Run `migrate_pool_config` first: the pool config is read in its current
+//! layout to authorize the migration.
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+
+use crate::error::PrivacyError;
+use crate::events::MerkleTreeMigrated;
+use crate::instructions::admin::authority_multisig::require_admin_signers;
+use crate::state::{AuthorityMultisig, MerkleTree, MerkleTreeV1, PoolConfig};
+
+/// Accounts for migrate_merkle_tree instruction.
+#[derive(Accounts)]
+pub struct MigrateMerkleTree<'info> {
+    /// Pool configuration account.
+    #[account(
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    /// CHECK: PDA checked by seeds; layout and pool checked by
+    /// `migrate_merkle_tree_data`
+    #[account(
+        mut,
+        seeds = [b"merkle_tree", pool_config.key().as_ref()],
+        bump,
+        owner = crate::ID @ PrivacyError::InvalidOwner,
+    )]
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    /// Pool authority (must sign, pays for the larger account).
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Required when `pool_config.authority_is_multisig`
+    pub authority_multisig: Option<Account<'info, AuthorityMultisig>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler for migrate_merkle_tree instruction.
+pub fn migrate_merkle_tree_handler(ctx: Context<MigrateMerkleTree>) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let clock = Clock::get()?;
+    let target = ctx.accounts.merkle_tree.to_account_info();
+    let migrated = migrate_merkle_tree_data(
+        &target.try_borrow_data()?,
+        &ctx.accounts.pool_config.key(),
+        clock.slot,
+    )?;
+    let space = MerkleTree::space(migrated.depth, migrated.root_history_size);
+
+    let top_up = Rent::get()?
+        .minimum_balance(space)
+        .saturating_sub(target.lamports());
+    if top_up > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.authority.to_account_info(),
+                    to: target.clone(),
+                },
+            ),
+            top_up,
+        )?;
+    }
+    target.realloc(space, true)?;
+    migrated.try_serialize(&mut &mut target.try_borrow_mut_data()?[..])?;
+
+    emit!(MerkleTreeMigrated {
+        pool: ctx.accounts.pool_config.key(),
+        merkle_tree: target.key(),
+        authority: ctx.accounts.authority.key(),
+        slot: clock.slot,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Merkle tree migrated at slot {}", clock.slot);
+    Ok(())
+}
+
+/// Current-layout tree for the version 1 account `data`, with its roots
+/// tagged as inserted at `slot`.
+///
+/// # Errors
+/// * `MerkleTreeNotMigratable` unless `data` is a version 1 tree
+/// * `Unauthorized` if the tree does not belong to `pool`
+pub fn migrate_merkle_tree_data(data: &[u8], pool: &Pubkey, slot: u64) -> Result<MerkleTree> {
+    let old = MerkleTreeV1::try_from_account_data(data)?;
+    require!(old.pool == *pool, PrivacyError::Unauthorized);
+    Ok(MerkleTree::from_v1(&old, slot))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::Discriminator;
+    use crate::crypto::poseidon::{hash_two_to_one, TreeHash};
+    use crate::state::merkle_tree::test_fixtures::new_tree;
+    use crate::state::merkle_tree::MIN_ROOT_HISTORY_SIZE;
+
+    const DEPTH: u8 = 4;
+    const HISTORY: u16 = 30;
+
+    /// A tree account exactly as the version 1 program wrote it, after
+    /// `leaves` Keccak insertions.
+    fn v1_account(pool: Pubkey, leaves: &[[u8; 32]]) -> Vec<u8> {
+        let mut zeros = vec![[0u8; 32]];
+        for level in 0..DEPTH as usize {
+            zeros.push(hash_two_to_one(&zeros[level], &zeros[level]));
+        }
+        let mut old = MerkleTreeV1 {
+            pool,
+            depth: DEPTH,
+            next_leaf_index: 0,
+            current_root: zeros[DEPTH as usize],
+            root_history: vec![[0u8; 32]; HISTORY as usize],
+            root_history_index: 0,
+            root_history_size: HISTORY,
+            filled_subtrees: zeros[..DEPTH as usize].to_vec(),
+            zeros,
+        };
+        old.root_history[0] = old.current_root;
+        for leaf in leaves {
+            let mut index = old.next_leaf_index;
+            let mut node = *leaf;
+            for level in 0..DEPTH as usize {
+                node = if index % 2 == 0 {
+                    old.filled_subtrees[level] = node;
+                    hash_two_to_one(&node, &old.zeros[level])
+                } else {
+                    hash_two_to_one(&old.filled_subtrees[level], &node)
+                };
+                index /= 2;
+            }
+            old.next_leaf_index += 1;
+            old.current_root = node;
+            old.root_history_index = (old.root_history_index + 1) % HISTORY;
+            old.root_history[old.root_history_index as usize] = node;
+        }
+
+        let mut data = vec![0u8; MerkleTreeV1::space(DEPTH, HISTORY)];
+        data[..8].copy_from_slice(&MerkleTree::DISCRIMINATOR);
+        old.serialize(&mut &mut data[8..]).unwrap();
+        data
+    }
+
+    #[test]
+    fn test_v1_tree_migrates_to_current_layout() {
+        let pool = Pubkey::new_unique();
+        let leaves = [[1u8; 32], [2u8; 32], [3u8; 32]];
+        let data = v1_account(pool, &leaves);
+        let old = MerkleTreeV1::try_from_account_data(&data).unwrap();
+
+        // The bricked state this instruction exists for
+        assert!(MerkleTree::try_deserialize(&mut data.as_slice()).is_err());
+
+        let migrated = migrate_merkle_tree_data(&data, &pool, 500).unwrap();
+        let mut out = vec![0u8; MerkleTree::space(DEPTH, HISTORY)];
+        migrated.try_serialize(&mut out.as_mut_slice()).unwrap();
+        let mut tree = MerkleTree::try_deserialize(&mut out.as_slice()).unwrap();
+
+        assert_eq!((tree.pool, tree.depth, tree.next_leaf_index), (pool, DEPTH, 3));
+        assert_eq!(tree.tree_hash, TreeHash::Keccak as u8);
+        assert_eq!(tree.current_root, old.current_root);
+        assert_eq!(tree.root_history, old.root_history);
+        assert_eq!(tree.root_history_index, old.root_history_index);
+        assert!(old.root_history[..4].iter().all(|root| tree.is_known_root(root)));
+
+        // Inserting continues the version 1 tree: same root as a fresh
+        // Keccak tree fed the same leaves
+        let mut fresh = new_tree(DEPTH, TreeHash::Keccak);
+        for (slot, leaf) in leaves.iter().chain([[4u8; 32]].iter()).enumerate() {
+            fresh.insert_leaf(*leaf, slot as u64).unwrap();
+        }
+        tree.insert_leaf([4u8; 32], 600).unwrap();
+        assert_eq!(tree.current_root, fresh.current_root);
+        assert_eq!(tree.zeros, fresh.zeros);
+
+        // Nothing left to migrate
+        assert_eq!(
+            migrate_merkle_tree_data(&out, &pool, 500).err().unwrap(),
+            error!(PrivacyError::MerkleTreeNotMigratable)
+        );
+    }
+
+    #[test]
+    fn test_migration_requires_v1_layout_and_pool() {
+        let pool = Pubkey::new_unique();
+        let data = v1_account(pool, &[[1u8; 32]]);
+        let err = migrate_merkle_tree_data(&data, &Pubkey::new_unique(), 0).err().unwrap();
+        assert_eq!(err, error!(PrivacyError::Unauthorized));
+
+        let mut other_account = data.clone();
+        other_account[0] ^= 1;
+        let err = migrate_merkle_tree_data(&other_account, &pool, 0).err().unwrap();
+        assert_eq!(err, error!(PrivacyError::MerkleTreeNotMigratable));
+
+        let mut padded = data.clone();
+        padded.push(0);
+        let err = migrate_merkle_tree_data(&padded, &pool, 0).err().unwrap();
+        assert_eq!(err, error!(PrivacyError::MerkleTreeNotMigratable));
+
+        let mut current = vec![0u8; MerkleTree::space(DEPTH, MIN_ROOT_HISTORY_SIZE)];
+        new_tree(DEPTH, TreeHash::Keccak).try_serialize(&mut current.as_mut_slice()).unwrap();
+        let err = migrate_merkle_tree_data(&current, &pool, 0).err().unwrap();
+        assert_eq!(err, error!(PrivacyError::MerkleTreeNotMigratable));
+    }
+
+    #[test]
+    fn test_migrated_tree_keeps_roots_in_account() {
+        use crate::state::RootHistory;
+
+        let pool = Pubkey::new_unique();
+        let data = v1_account(pool, &[[1u8; 32], [2u8; 32]]);
+        let mut tree = migrate_merkle_tree_data(&data, &pool, 500).unwrap();
+        let old_root = tree.current_root;
+
+        // Version 1 trees predate split root history: the roots stay in
+        // the tree and no RootHistory account is expected
+        assert!(!tree.external_root_history);
+        assert!(tree.insert_leaf_into([3u8; 32], 600, None).is_ok());
+        assert_eq!(tree.is_known_root_in(None, &old_root, 600, 0).unwrap(), (true, true));
+
+        // ...and can still be split out later
+        let space = RootHistory::space(tree.root_history_size);
+        let mut history = RootHistory::try_deserialize_unchecked(&mut vec![0u8; space].as_slice()).unwrap();
+        history.take_from(&mut tree, 255).unwrap();
+        assert!(tree.external_root_history);
+        assert_eq!(tree.is_known_root_in(Some(&history), &old_root, 600, 0).unwrap(), (true, true));
+    }
+
+    #[test]
+    fn test_migrated_roots_dated_at_migration() {
+        let pool = Pubkey::new_unique();
+        let data = v1_account(pool, &[[1u8; 32], [2u8; 32]]);
+        let old = MerkleTreeV1::try_from_account_data(&data).unwrap();
+        let tree = migrate_merkle_tree_data(&data, &pool, 500).unwrap();
+
+        // One slot per root; written roots carry the migration slot
+        assert_eq!(tree.root_slots.len(), HISTORY as usize);
+        for (root, slot) in old.root_history.iter().zip(&tree.root_slots) {
+            let expected = if *root == [0u8; 32] { 0 } else { 500 };
+            assert_eq!(*slot, expected);
+        }
+        assert_eq!(tree.root_slot(&old.current_root), Some(500));
+
+        // A root age limit counts from the migration, not from slot 0
+        assert!(tree.is_known_root_within(&old.root_history[0], 600, 100));
+        assert!(!tree.is_known_root_within(&old.root_history[0], 601, 100));
+    }
+}
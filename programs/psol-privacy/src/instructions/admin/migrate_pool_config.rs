@@ -0,0 +1,198 @@
+//! Migrate Pool Config Instruction
+//!
+//! Pools created at schema version 2 hold a shorter `PoolConfig` that no
+//! longer deserializes, so every instruction taking the pool fails until
+//! it is migrated. Reads the version 2 fields, grows the account to
+//! `PoolConfig::LEN` (the authority pays the extra rent) and writes it
+//! back in the current layout, with every newer setting at its default.
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token::Mint;
+
+use crate::error::PrivacyError;
+use crate::events::PoolConfigMigrated;
+use crate::state::{PoolConfig, PoolConfigV2};
+
+/// Accounts for migrate_pool_config instruction.
+#[derive(Accounts)]
+pub struct MigratePoolConfig<'info> {
+    /// CHECK: PDA checked by seeds; layout and authority checked by
+    /// `migrate_pool_config_data`
+    #[account(
+        mut,
+        seeds = [b"pool", token_mint.key().as_ref()],
+        bump,
+        owner = crate::ID @ PrivacyError::InvalidOwner,
+    )]
+    pub pool_config: UncheckedAccount<'info>,
+
+    /// Pool mint; its decimals fill `PoolConfig::token_decimals`
+    pub token_mint: Account<'info, Mint>,
+
+    /// Pool authority (must sign, pays for the larger account).
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler for migrate_pool_config instruction.
+pub fn migrate_pool_config_handler(ctx: Context<MigratePoolConfig>) -> Result<()> {
+    let target = ctx.accounts.pool_config.to_account_info();
+    let migrated = migrate_pool_config_data(
+        &target.try_borrow_data()?,
+        &ctx.accounts.authority.key(),
+        ctx.accounts.token_mint.decimals,
+    )?;
+
+    let top_up = Rent::get()?
+        .minimum_balance(PoolConfig::LEN)
+        .saturating_sub(target.lamports());
+    if top_up > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.authority.to_account_info(),
+                    to: target.clone(),
+                },
+            ),
+            top_up,
+        )?;
+    }
+    target.realloc(PoolConfig::LEN, true)?;
+    migrated.try_serialize(&mut &mut target.try_borrow_mut_data()?[..])?;
+
+    emit!(PoolConfigMigrated {
+        pool: target.key(),
+        authority: ctx.accounts.authority.key(),
+        from_version: PoolConfigV2::VERSION,
+        to_version: PoolConfig::VERSION,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Pool config migrated to version {}", PoolConfig::VERSION);
+    Ok(())
+}
+
+/// Current-layout config for the version 2 account `data`.
+///
+/// # Errors
+/// * `PoolConfigNotMigratable` unless `data` is a version 2 pool config
+/// * `Unauthorized` if `authority` is not the pool's authority
+///
+/// Version 2 pools predate `AuthorityMultisig`, so the migrated config's
+/// admin check always resolves to the single authority key.
+pub fn migrate_pool_config_data(
+    data: &[u8],
+    authority: &Pubkey,
+    token_decimals: u8,
+) -> Result<PoolConfig> {
+    let old = PoolConfigV2::try_from_account_data(data)?;
+    let migrated = PoolConfig::from_v2(&old, token_decimals)?;
+    migrated.require_admin(&[*authority], None)?;
+    Ok(migrated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::Discriminator;
+    use crate::crypto::RecipientBinding;
+    use crate::state::pool_config::test_fixtures::new_pool;
+    use crate::state::CommitmentMode;
+
+    /// A pool account exactly as the version 2 program wrote it.
+    fn v2_account(authority: Pubkey, is_paused: bool) -> Vec<u8> {
+        let old = PoolConfigV2 {
+            authority,
+            pending_authority: Pubkey::default(),
+            token_mint: Pubkey::new_unique(),
+            vault: Pubkey::new_unique(),
+            merkle_tree: Pubkey::new_unique(),
+            verification_key: Pubkey::new_unique(),
+            tree_depth: 20,
+            bump: 253,
+            is_paused,
+            vk_configured: true,
+            vk_locked: true,
+            total_deposits: 7,
+            total_withdrawals: 3,
+            total_value_deposited: 7_000,
+            total_value_withdrawn: 2_500,
+            version: PoolConfigV2::VERSION,
+            _reserved: [0u8; 64],
+        };
+        let mut data = vec![0u8; PoolConfigV2::LEN];
+        data[..8].copy_from_slice(&PoolConfig::DISCRIMINATOR);
+        old.serialize(&mut &mut data[8..]).unwrap();
+        data
+    }
+
+    #[test]
+    fn test_v2_pool_migrates_to_current_layout() {
+        let authority = Pubkey::new_unique();
+        let data = v2_account(authority, false);
+        let old = PoolConfigV2::try_from_account_data(&data).unwrap();
+
+        // The bricked state this instruction exists for
+        assert!(PoolConfig::try_deserialize(&mut data.as_slice()).is_err());
+
+        let migrated = migrate_pool_config_data(&data, &authority, 6).unwrap();
+        let mut out = vec![0u8; PoolConfig::LEN];
+        migrated.try_serialize(&mut out.as_mut_slice()).unwrap();
+        let pool = PoolConfig::try_deserialize(&mut out.as_slice()).unwrap();
+
+        assert_eq!(pool.version, PoolConfig::VERSION);
+        assert_eq!(
+            (pool.authority, pool.token_mint, pool.vault, pool.merkle_tree, pool.verification_key),
+            (old.authority, old.token_mint, old.vault, old.merkle_tree, old.verification_key)
+        );
+        assert_eq!((pool.tree_depth, pool.bump, pool.token_decimals), (20, 253, 6));
+        assert!(pool.vk_configured && pool.vk_locked);
+        assert_eq!(
+            (pool.total_deposits, pool.total_withdrawals),
+            (old.total_deposits, old.total_withdrawals)
+        );
+        assert_eq!(
+            (pool.total_value_deposited, pool.total_value_withdrawn),
+            (old.total_value_deposited, old.total_value_withdrawn)
+        );
+        assert_eq!(pool.commitment_mode, CommitmentMode::ClientSupplied as u8);
+        assert_eq!(pool.recipient_binding, RecipientBinding::Raw as u8);
+        assert_eq!(pool.fee_recipient, authority);
+        assert!(pool.require_not_paused_at(0).is_ok());
+
+        // Nothing left to migrate
+        assert_eq!(
+            migrate_pool_config_data(&out, &authority, 6).err().unwrap(),
+            error!(PrivacyError::PoolConfigNotMigratable)
+        );
+    }
+
+    #[test]
+    fn test_paused_v2_pool_stays_paused() {
+        let authority = Pubkey::new_unique();
+        let pool = migrate_pool_config_data(&v2_account(authority, true), &authority, 9).unwrap();
+        assert!(pool.is_paused_at(i64::MAX - 1));
+    }
+
+    #[test]
+    fn test_migration_requires_v2_authority_and_layout() {
+        let authority = Pubkey::new_unique();
+        let data = v2_account(authority, false);
+        let err = migrate_pool_config_data(&data, &Pubkey::new_unique(), 9).err().unwrap();
+        assert_eq!(err, error!(PrivacyError::Unauthorized));
+
+        let mut other_account = data.clone();
+        other_account[0] ^= 1;
+        let err = migrate_pool_config_data(&other_account, &authority, 9).err().unwrap();
+        assert_eq!(err, error!(PrivacyError::PoolConfigNotMigratable));
+
+        let mut current = vec![0u8; PoolConfig::LEN];
+        new_pool().try_serialize(&mut current.as_mut_slice()).unwrap();
+        let err = migrate_pool_config_data(&current, &authority, 9).err().unwrap();
+        assert_eq!(err, error!(PrivacyError::PoolConfigNotMigratable));
+    }
+}
@@ -1,9 +1,37 @@
 //! Admin Instructions for pSol Privacy Pool - Phase 4
 
+pub mod authority_multisig;
+pub mod fee_recipient;
+pub mod fee_reserve;
+pub mod import_subtree;
+pub mod migrate_merkle_tree;
+pub mod migrate_pool_config;
 pub mod pause;
+pub mod pool_registry;
+pub mod pool_settings;
+pub mod recipient_denylist;
+pub mod reinitialize_verification_key;
+pub mod root_policy;
+pub mod sol_fee_reserve;
+pub mod split_root_history;
+pub mod sweep_untracked;
 pub mod unpause;
 pub mod update_authority;
 
+pub use authority_multisig::*;
+pub use fee_recipient::*;
+pub use fee_reserve::*;
+pub use import_subtree::*;
+pub use migrate_merkle_tree::*;
+pub use migrate_pool_config::*;
 pub use pause::*;
+pub use pool_registry::*;
+pub use pool_settings::*;
+pub use recipient_denylist::*;
+pub use reinitialize_verification_key::*;
+pub use root_policy::*;
+pub use sol_fee_reserve::*;
+pub use split_root_history::*;
+pub use sweep_untracked::*;
 pub use unpause::*;
 pub use update_authority::*;
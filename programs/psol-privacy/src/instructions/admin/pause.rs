@@ -2,12 +2,41 @@
 //!
 //! Emergency stop for the privacy pool.
 //! Blocks all deposits and withdrawals when paused.
+//!
+//! A pause lasts until `unpause_pool`, or with `pause_until` set, until
+//! that timestamp: funds are never stuck behind an unavailable authority.
 
 use anchor_lang::prelude::*;
 
 use crate::error::PrivacyError;
 use crate::events::PoolPaused;
-use crate::state::PoolConfig;
+use crate::instructions::admin::authority_multisig::require_admin_signers;
+use crate::state::{AuthorityMultisig, PoolConfig};
+
+/// Why a pool was paused or unpaused, carried in `PoolPaused` /
+/// `PoolUnpaused` as a `u8` so monitoring can tell incidents apart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum PauseReason {
+    /// Operator decision
+    Manual = 0,
+    /// Automatic circuit breaker (e.g. withdrawal rate limit)
+    RateBreaker = 1,
+    /// Program or circuit upgrade
+    Upgrade = 2,
+}
+
+impl PauseReason {
+    /// Decode an instruction or event reason byte.
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(PauseReason::Manual),
+            1 => Some(PauseReason::RateBreaker),
+            2 => Some(PauseReason::Upgrade),
+            _ => None,
+        }
+    }
+}
 
 /// Accounts for pause_pool instruction.
 #[derive(Accounts)]
@@ -17,29 +46,142 @@ pub struct PausePool<'info> {
         mut,
         seeds = [b"pool", pool_config.token_mint.as_ref()],
         bump = pool_config.bump,
-        has_one = authority @ PrivacyError::Unauthorized,
     )]
     pub pool_config: Account<'info, PoolConfig>,
 
     /// Pool authority (must sign).
     pub authority: Signer<'info>,
+
+    /// Required when `pool_config.authority_is_multisig`
+    pub authority_multisig: Option<Account<'info, AuthorityMultisig>>,
 }
 
 /// Handler for pause_pool instruction.
-pub fn handler(ctx: Context<PausePool>) -> Result<()> {
+///
+/// `pause_until` is the unix timestamp the pause expires at; `None` pauses
+/// until `unpause_pool`.
+pub fn handler(ctx: Context<PausePool>, reason: u8, pause_until: Option<i64>) -> Result<()> {
+    let reason = PauseReason::from_u8(reason).ok_or(error!(PrivacyError::InvalidPauseReason))?;
+    let now = Clock::get()?.unix_timestamp;
+    let pause_until = pause_expiry(pause_until, now)?;
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
     let pool_config = &mut ctx.accounts.pool_config;
 
     // Set paused state
-    pool_config.set_paused(true);
+    pool_config.set_paused_until(pause_until);
 
     // Emit event
-    emit!(PoolPaused {
-        pool: pool_config.key(),
-        authority: ctx.accounts.authority.key(),
-        timestamp: Clock::get()?.unix_timestamp,
-    });
+    emit!(pool_paused_event(
+        pool_config.key(),
+        ctx.accounts.authority.key(),
+        reason,
+        now,
+    ));
 
-    msg!("Pool paused");
+    msg!("Pool paused ({:?}) until {}", reason, pause_until);
 
     Ok(())
 }
+
+/// Expiry timestamp for a pause requested at `now`: `i64::MAX` when none
+/// is given.
+///
+/// # Errors
+/// * `InvalidTimestamp` if `pause_until` is not after `now`
+pub fn pause_expiry(pause_until: Option<i64>, now: i64) -> Result<i64> {
+    match pause_until {
+        Some(pause_until) => {
+            require!(pause_until > now, PrivacyError::InvalidTimestamp);
+            Ok(pause_until)
+        }
+        None => Ok(i64::MAX),
+    }
+}
+
+/// `PoolPaused` payload for a pause with `reason`.
+pub fn pool_paused_event(
+    pool: Pubkey,
+    authority: Pubkey,
+    reason: PauseReason,
+    timestamp: i64,
+) -> PoolPaused {
+    PoolPaused {
+        pool,
+        authority,
+        reason: reason as u8,
+        timestamp,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::pool_config::test_fixtures::new_pool;
+
+    #[test]
+    fn test_pause_reason_roundtrip() {
+        for reason in [PauseReason::Manual, PauseReason::RateBreaker, PauseReason::Upgrade] {
+            assert_eq!(PauseReason::from_u8(reason as u8), Some(reason));
+        }
+        assert_eq!(PauseReason::from_u8(3), None);
+    }
+
+    #[test]
+    fn test_timed_pause_expires() {
+        let now = 1_700_000_000;
+        let mut pool = new_pool();
+        pool.set_paused_until(pause_expiry(Some(now + 3_600), now).unwrap());
+
+        let paused = error!(PrivacyError::PoolPaused);
+        assert_eq!(pool.require_not_paused_at(now).unwrap_err(), paused);
+        assert_eq!(pool.require_not_paused_at(now + 3_599).unwrap_err(), paused);
+        // Resumes at the deadline without an unpause
+        assert!(pool.require_not_paused_at(now + 3_600).is_ok());
+        assert!(pool.is_paused);
+
+        pool.set_paused(false);
+        assert!(pool.require_not_paused().is_ok());
+        assert_eq!(pool.pause_until, 0);
+
+        assert!(pause_expiry(Some(now), now).is_err());
+        assert!(pause_expiry(Some(now - 1), now).is_err());
+    }
+
+    #[test]
+    fn test_indefinite_pause_stays_blocked() {
+        let mut pool = new_pool();
+        pool.set_paused_until(pause_expiry(None, 1_700_000_000).unwrap());
+        assert_eq!(pool.pause_until, i64::MAX);
+        for now in [1_700_000_000, i64::MAX - 1] {
+            assert_eq!(pool.require_not_paused_at(now).unwrap_err(), error!(PrivacyError::PoolPaused));
+        }
+        // No clock needed for an indefinite pause
+        assert_eq!(pool.require_not_paused().unwrap_err(), error!(PrivacyError::PoolPaused));
+
+        let mut pool = new_pool();
+        pool.set_paused(true);
+        assert_eq!(pool.pause_until, i64::MAX);
+    }
+
+    #[test]
+    fn test_pause_event_payload() {
+        let pool = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let event = pool_paused_event(pool, authority, PauseReason::RateBreaker, 1_700_000_000);
+
+        assert_eq!(event.pool, pool);
+        assert_eq!(event.authority, authority);
+        assert_eq!(event.reason, 1);
+        assert_eq!(event.timestamp, 1_700_000_000);
+
+        // pool, authority, reason, timestamp in that order on the wire
+        let bytes = event.try_to_vec().unwrap();
+        assert_eq!(bytes.len(), 32 + 32 + 1 + 8);
+        assert_eq!(bytes[64], PauseReason::RateBreaker as u8);
+    }
+}
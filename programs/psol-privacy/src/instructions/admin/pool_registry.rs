@@ -0,0 +1,199 @@
+//! Pool Registry Instructions
+//!
+//! `init_pool_registry` creates the authority's registry; pools are added by
+//! passing it to `initialize_pool`. `pause_all` pauses every pool passed in
+//! `remaining_accounts` (each writable and registered) in one instruction.
+
+use anchor_lang::prelude::*;
+
+use crate::error::PrivacyError;
+use crate::instructions::admin::pause::{pool_paused_event, PauseReason};
+use crate::state::{PoolConfig, PoolRegistry};
+
+#[derive(Accounts)]
+pub struct InitPoolRegistry<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = PoolRegistry::LEN,
+        seeds = [b"pool_registry", authority.key().as_ref()],
+        bump
+    )]
+    pub pool_registry: Account<'info, PoolRegistry>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn init_registry_handler(ctx: Context<InitPoolRegistry>) -> Result<()> {
+    let authority = ctx.accounts.authority.key();
+    ctx.accounts
+        .pool_registry
+        .initialize(authority, ctx.bumps.pool_registry);
+
+    msg!("Pool registry created for {}", authority);
+    Ok(())
+}
+
+/// Accounts for pause_all. Pool configs are passed as writable
+/// `remaining_accounts`.
+#[derive(Accounts)]
+pub struct PauseAll<'info> {
+    #[account(
+        seeds = [b"pool_registry", authority.key().as_ref()],
+        bump = pool_registry.bump,
+        has_one = authority @ PrivacyError::Unauthorized,
+    )]
+    pub pool_registry: Account<'info, PoolRegistry>,
+
+    pub authority: Signer<'info>,
+}
+
+pub fn pause_all_handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, PauseAll<'info>>,
+    reason: u8,
+) -> Result<()> {
+    let reason = PauseReason::from_u8(reason).ok_or(error!(PrivacyError::InvalidPauseReason))?;
+
+    let mut pools = ctx
+        .remaining_accounts
+        .iter()
+        .map(|info| {
+            require!(info.is_writable, PrivacyError::Unauthorized);
+            Account::<PoolConfig>::try_from(info)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let paused = pause_registered_pools(
+        &ctx.accounts.pool_registry,
+        &ctx.accounts.authority.key(),
+        pools.iter_mut().map(|pool| (pool.key(), &mut **pool)),
+        reason,
+        Clock::get()?.unix_timestamp,
+    )?;
+
+    for pool in &pools {
+        pool.exit(&crate::ID)?;
+    }
+
+    msg!("Paused {} pools ({:?})", paused, reason);
+    Ok(())
+}
+
+/// Pause each `(pool_key, pool)` and emit `PoolPaused`. Every pool must be
+/// registered and still be administered by `authority` alone: a pool held
+/// by an `AuthorityMultisig` is paused with `pause_pool` and its co-signers.
+///
+/// Returns the number of pools paused.
+pub fn pause_registered_pools<'a>(
+    registry: &PoolRegistry,
+    authority: &Pubkey,
+    pools: impl Iterator<Item = (Pubkey, &'a mut PoolConfig)>,
+    reason: PauseReason,
+    timestamp: i64,
+) -> Result<u32> {
+    let mut paused = 0u32;
+    for (pool_key, pool) in pools {
+        require!(registry.contains(&pool_key), PrivacyError::PoolNotRegistered);
+        pool.require_admin(&[*authority], None)?;
+
+        pool.set_paused(true);
+        emit!(pool_paused_event(pool_key, *authority, reason, timestamp));
+        paused += 1;
+    }
+    Ok(paused)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::pool_config::test_fixtures::new_pool;
+
+    fn pool_for(authority: Pubkey, token_mint: Pubkey) -> PoolConfig {
+        let mut pool = new_pool();
+        pool.authority = authority;
+        pool.token_mint = token_mint;
+        pool
+    }
+
+    fn new_registry(authority: Pubkey) -> PoolRegistry {
+        let mut registry =
+            PoolRegistry::try_deserialize_unchecked(&mut vec![0u8; PoolRegistry::LEN].as_slice())
+                .unwrap();
+        registry.initialize(authority, 255);
+        registry
+    }
+
+    #[test]
+    fn test_pause_all_pauses_both_registered_pools() {
+        let authority = Pubkey::new_unique();
+        let mut registry = new_registry(authority);
+
+        let (mint_a, key_a) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (mint_b, key_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        registry.register_pool(mint_a, key_a).unwrap();
+        registry.register_pool(mint_b, key_b).unwrap();
+
+        let mut pool_a = pool_for(authority, mint_a);
+        let mut pool_b = pool_for(authority, mint_b);
+
+        let paused = pause_registered_pools(
+            &registry,
+            &authority,
+            [(key_a, &mut pool_a), (key_b, &mut pool_b)].into_iter(),
+            PauseReason::Manual,
+            0,
+        )
+        .unwrap();
+
+        assert_eq!(paused, 2);
+        assert!(pool_a.is_paused);
+        assert!(pool_b.is_paused);
+    }
+
+    #[test]
+    fn test_pause_all_rejects_unregistered_or_foreign_pool() {
+        let authority = Pubkey::new_unique();
+        let registry = new_registry(authority);
+        let mut pool = pool_for(authority, Pubkey::new_unique());
+        let unregistered = [(Pubkey::new_unique(), &mut pool)];
+        assert!(pause_registered_pools(
+            &registry,
+            &authority,
+            unregistered.into_iter(),
+            PauseReason::Manual,
+            0
+        )
+        .is_err());
+
+        // Registered, but authority has since been transferred away
+        let mut registry = new_registry(authority);
+        let key = Pubkey::new_unique();
+        registry.register_pool(Pubkey::new_unique(), key).unwrap();
+        let mut foreign = pool_for(Pubkey::new_unique(), Pubkey::new_unique());
+        assert!(pause_registered_pools(
+            &registry,
+            &authority,
+            [(key, &mut foreign)].into_iter(),
+            PauseReason::Manual,
+            0
+        )
+        .is_err());
+        assert!(!foreign.is_paused);
+
+        // Registered, but the authority has moved to a multisig
+        let mut held = pool_for(authority, Pubkey::new_unique());
+        held.enable_authority_multisig(Pubkey::new_unique());
+        assert!(pause_registered_pools(
+            &registry,
+            &authority,
+            [(key, &mut held)].into_iter(),
+            PauseReason::Manual,
+            0
+        )
+        .is_err());
+        assert!(!held.is_paused);
+    }
+}
@@ -0,0 +1,506 @@
+//! Pool Settings Instructions
+//!
+//! Authority-only knobs that tune pool policy without touching funds.
+
+use anchor_lang::prelude::*;
+
+use crate::crypto::RecipientBinding;
+use crate::error::PrivacyError;
+use crate::events::{
+    AllowZeroNetWithdrawalUpdated, DevModeAcknowledged, DisallowCpiWithdrawUpdated, EscrowProgramUpdated, FeeMintRateUpdated, MaxRelayerFeeLamportsUpdated, MaxRootAgeUpdated, MinAnonymitySetUpdated,
+    MinNoteAgeUpdated, ProofVersionUpdated, RequireReadyPayeesUpdated,
+    MinRelayerFeeUpdated, RecipientBindingUpdated, RecipientCooldownUpdated, SwapRouteUpdated,
+    VerboseLoggingUpdated,
+};
+use crate::instructions::admin::authority_multisig::require_admin_signers;
+use crate::state::{AuthorityMultisig, PoolConfig};
+
+/// Accounts for pool settings instructions.
+#[derive(Accounts)]
+pub struct UpdatePoolSettings<'info> {
+    /// Pool configuration account.
+    #[account(
+        mut,
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    /// Pool authority (must sign).
+    pub authority: Signer<'info>,
+
+    /// Required when `pool_config.authority_is_multisig`
+    pub authority_multisig: Option<Account<'info, AuthorityMultisig>>,
+}
+
+/// Handler for set_min_anonymity_set instruction.
+///
+/// Withdrawals are rejected until the tree holds at least
+/// `min_anonymity_set` leaves. Set to 0 to disable.
+pub fn set_min_anonymity_set_handler(
+    ctx: Context<UpdatePoolSettings>,
+    min_anonymity_set: u32,
+) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let pool_config = &mut ctx.accounts.pool_config;
+
+    pool_config.set_min_anonymity_set(min_anonymity_set);
+
+    emit!(MinAnonymitySetUpdated {
+        pool: pool_config.key(),
+        authority: ctx.accounts.authority.key(),
+        min_anonymity_set,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Minimum anonymity set updated: {}", min_anonymity_set);
+    Ok(())
+}
+
+/// Handler for set_escrow_program instruction.
+///
+/// Whitelists the program whose PDAs may receive `withdraw_to_escrow`
+/// payouts. Pass the default pubkey to disable escrow withdrawals.
+pub fn set_escrow_program_handler(
+    ctx: Context<UpdatePoolSettings>,
+    escrow_program: Pubkey,
+) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let pool_config = &mut ctx.accounts.pool_config;
+
+    pool_config.set_escrow_program(escrow_program);
+
+    emit!(EscrowProgramUpdated {
+        pool: pool_config.key(),
+        authority: ctx.accounts.authority.key(),
+        escrow_program,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Escrow program updated: {}", escrow_program);
+    Ok(())
+}
+
+/// Handler for set_swap_route instruction.
+///
+/// `withdraw_and_swap` may only CPI into `swap_program` and trade against
+/// `swap_pool`. Set `swap_program` to the zero key to disable it.
+pub fn set_swap_route_handler(
+    ctx: Context<UpdatePoolSettings>,
+    swap_program: Pubkey,
+    swap_pool: Pubkey,
+) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let pool_config = &mut ctx.accounts.pool_config;
+
+    pool_config.set_swap_route(swap_program, swap_pool);
+
+    emit!(SwapRouteUpdated {
+        pool: pool_config.key(),
+        authority: ctx.accounts.authority.key(),
+        swap_program,
+        swap_pool,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Swap route updated: program {}, pool {}", swap_program, swap_pool);
+    Ok(())
+}
+
+/// Handler for set_recipient_cooldown_slots instruction.
+///
+/// Withdrawals to the same recipient must be at least
+/// `recipient_cooldown_slots` apart. Set to 0 to disable.
+pub fn set_recipient_cooldown_slots_handler(
+    ctx: Context<UpdatePoolSettings>,
+    recipient_cooldown_slots: u64,
+) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let pool_config = &mut ctx.accounts.pool_config;
+
+    pool_config.set_recipient_cooldown_slots(recipient_cooldown_slots);
+
+    emit!(RecipientCooldownUpdated {
+        pool: pool_config.key(),
+        authority: ctx.accounts.authority.key(),
+        recipient_cooldown_slots,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Recipient cooldown updated: {} slots", recipient_cooldown_slots);
+    Ok(())
+}
+
+/// Handler for set_min_relayer_fee instruction.
+///
+/// Relayed withdrawals (relayer != recipient) must pay at least
+/// `min_relayer_fee`. Set to 0 to disable.
+pub fn set_min_relayer_fee_handler(
+    ctx: Context<UpdatePoolSettings>,
+    min_relayer_fee: u64,
+) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let pool_config = &mut ctx.accounts.pool_config;
+
+    pool_config.set_min_relayer_fee(min_relayer_fee);
+
+    emit!(MinRelayerFeeUpdated {
+        pool: pool_config.key(),
+        authority: ctx.accounts.authority.key(),
+        min_relayer_fee,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Min relayer fee updated: {}", min_relayer_fee);
+    Ok(())
+}
+
+/// Handler for set_recipient_binding instruction.
+///
+/// Selects how `recipient` is encoded as a public input. Proofs generated
+/// under the previous binding stop verifying, so switch alongside clients.
+pub fn set_recipient_binding_handler(
+    ctx: Context<UpdatePoolSettings>,
+    recipient_binding: u8,
+) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let binding = RecipientBinding::from_u8(recipient_binding)
+        .ok_or(error!(PrivacyError::InvalidRecipientBinding))?;
+    let pool_config = &mut ctx.accounts.pool_config;
+
+    pool_config.set_recipient_binding(binding);
+
+    emit!(RecipientBindingUpdated {
+        pool: pool_config.key(),
+        authority: ctx.accounts.authority.key(),
+        recipient_binding,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Recipient binding updated: {:?}", binding);
+    Ok(())
+}
+
+/// Handler for set_fee_mint_rate instruction.
+///
+/// `fee_mint` units paid by `withdraw_fee_in_mint` per
+/// `PoolConfig::FEE_RATE_SCALE` pool-mint units of relayer fee.
+pub fn set_fee_mint_rate_handler(
+    ctx: Context<UpdatePoolSettings>,
+    fee_mint_rate: u64,
+) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let pool_config = &mut ctx.accounts.pool_config;
+
+    pool_config.set_fee_mint_rate(fee_mint_rate);
+
+    emit!(FeeMintRateUpdated {
+        pool: pool_config.key(),
+        authority: ctx.accounts.authority.key(),
+        fee_mint_rate,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Fee mint rate updated: {}", fee_mint_rate);
+    Ok(())
+}
+
+/// Handler for set_max_relayer_fee_lamports instruction.
+///
+/// Largest relayer fee `withdraw_fee_in_lamports` may pay from the SOL fee
+/// reserve. Set to 0 to disable lamport fees.
+pub fn set_max_relayer_fee_lamports_handler(
+    ctx: Context<UpdatePoolSettings>,
+    max_relayer_fee_lamports: u64,
+) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let pool_config = &mut ctx.accounts.pool_config;
+
+    pool_config.set_max_relayer_fee_lamports(max_relayer_fee_lamports);
+
+    emit!(MaxRelayerFeeLamportsUpdated {
+        pool: pool_config.key(),
+        authority: ctx.accounts.authority.key(),
+        max_relayer_fee_lamports,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Max relayer fee updated: {} lamports", max_relayer_fee_lamports);
+    Ok(())
+}
+
+/// Handler for set_max_root_age_slots instruction.
+///
+/// Withdrawals must prove against a root inserted within the last
+/// `max_root_age_slots` slots. Set to 0 to disable the age limit.
+pub fn set_max_root_age_slots_handler(
+    ctx: Context<UpdatePoolSettings>,
+    max_root_age_slots: u64,
+) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let pool_config = &mut ctx.accounts.pool_config;
+
+    pool_config.set_max_root_age_slots(max_root_age_slots);
+
+    emit!(MaxRootAgeUpdated {
+        pool: pool_config.key(),
+        authority: ctx.accounts.authority.key(),
+        max_root_age_slots,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Max root age updated: {} slots", max_root_age_slots);
+    Ok(())
+}
+
+/// Handler for set_min_note_age_slots instruction.
+///
+/// Withdrawals must prove against a root inserted at least
+/// `min_note_age_slots` slots ago, delaying every note's withdrawal past
+/// its deposit. Set to 0 to disable. Combined with `max_root_age_slots`
+/// the delay must stay below the max age, or no root is accepted.
+pub fn set_min_note_age_slots_handler(
+    ctx: Context<UpdatePoolSettings>,
+    min_note_age_slots: u64,
+) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let pool_config = &mut ctx.accounts.pool_config;
+
+    pool_config.set_min_note_age_slots(min_note_age_slots);
+
+    emit!(MinNoteAgeUpdated {
+        pool: pool_config.key(),
+        authority: ctx.accounts.authority.key(),
+        min_note_age_slots,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Min note age updated: {} slots", min_note_age_slots);
+    Ok(())
+}
+
+/// Handler for set_require_ready_payees instruction.
+///
+/// When enabled, withdrawals check the recipient and relayer token accounts
+/// up front and fail with `PayeeAccountNotReady` instead of erroring inside
+/// the token transfer.
+pub fn set_require_ready_payees_handler(
+    ctx: Context<UpdatePoolSettings>,
+    require_ready_payees: bool,
+) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let pool_config = &mut ctx.accounts.pool_config;
+
+    pool_config.set_require_ready_payees(require_ready_payees);
+
+    emit!(RequireReadyPayeesUpdated {
+        pool: pool_config.key(),
+        authority: ctx.accounts.authority.key(),
+        require_ready_payees,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Require ready payees: {}", require_ready_payees);
+    Ok(())
+}
+
+/// Handler for set_disallow_cpi_withdraw instruction.
+///
+/// When enabled, withdrawals must be top-level instructions of the
+/// transaction; a wrapper program invoking them via CPI is rejected.
+pub fn set_disallow_cpi_withdraw_handler(
+    ctx: Context<UpdatePoolSettings>,
+    disallow_cpi_withdraw: bool,
+) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let pool_config = &mut ctx.accounts.pool_config;
+
+    pool_config.set_disallow_cpi_withdraw(disallow_cpi_withdraw);
+
+    emit!(DisallowCpiWithdrawUpdated {
+        pool: pool_config.key(),
+        authority: ctx.accounts.authority.key(),
+        disallow_cpi_withdraw,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Disallow CPI withdraw: {}", disallow_cpi_withdraw);
+    Ok(())
+}
+
+/// Handler for set_allow_zero_net_withdrawal instruction.
+///
+/// When enabled, a withdrawal whose relayer fee equals its amount (so the
+/// recipient receives nothing) is accepted instead of failing with
+/// `ZeroNetWithdrawal`.
+pub fn set_allow_zero_net_withdrawal_handler(
+    ctx: Context<UpdatePoolSettings>,
+    allow_zero_net_withdrawal: bool,
+) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let pool_config = &mut ctx.accounts.pool_config;
+
+    pool_config.set_allow_zero_net_withdrawal(allow_zero_net_withdrawal);
+
+    emit!(AllowZeroNetWithdrawalUpdated {
+        pool: pool_config.key(),
+        authority: ctx.accounts.authority.key(),
+        allow_zero_net_withdrawal,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Allow zero-net withdrawal: {}", allow_zero_net_withdrawal);
+    Ok(())
+}
+
+/// Handler for set_proof_version instruction.
+///
+/// Switch when the pool's prover toolchain changes serialization; proofs
+/// tagged with any other version are rejected with `ProofVersionMismatch`.
+pub fn set_proof_version_handler(
+    ctx: Context<UpdatePoolSettings>,
+    proof_version: u8,
+) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let pool_config = &mut ctx.accounts.pool_config;
+
+    pool_config.set_proof_version(proof_version);
+
+    emit!(ProofVersionUpdated {
+        pool: pool_config.key(),
+        authority: ctx.accounts.authority.key(),
+        proof_version,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Proof version: {}", proof_version);
+    Ok(())
+}
+
+/// Handler for set_verbose_logging instruction.
+///
+/// Turns the step-by-step verifier and withdrawal logs on for debugging.
+/// Leave off in production.
+pub fn set_verbose_logging_handler(
+    ctx: Context<UpdatePoolSettings>,
+    verbose_logging: bool,
+) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let pool_config = &mut ctx.accounts.pool_config;
+
+    pool_config.set_verbose_logging(verbose_logging);
+
+    emit!(VerboseLoggingUpdated {
+        pool: pool_config.key(),
+        authority: ctx.accounts.authority.key(),
+        verbose_logging,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Verbose logging: {}", verbose_logging);
+    Ok(())
+}
+
+/// Handler for set_dev_mode_acknowledged instruction.
+///
+/// In `dev-mode` builds, withdrawals and commitment seeding are refused
+/// until the pool authority explicitly acknowledges dev-mode.
+pub fn set_dev_mode_acknowledged_handler(
+    ctx: Context<UpdatePoolSettings>,
+    acknowledged: bool,
+) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let pool_config = &mut ctx.accounts.pool_config;
+
+    pool_config.set_dev_mode_acknowledged(acknowledged);
+
+    emit!(DevModeAcknowledged {
+        pool: pool_config.key(),
+        authority: ctx.accounts.authority.key(),
+        acknowledged,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Dev-mode acknowledged: {} (dev-mode build: {})", acknowledged, crate::DEV_MODE);
+    Ok(())
+}
@@ -0,0 +1,111 @@
+//! Recipient Denylist Instructions
+//!
+//! Authority-managed list of recipients withdrawals may not pay. Every
+//! change re-commits `PoolConfig.recipient_denylist_root`, so emptying the
+//! list switches the check off again.
+
+use anchor_lang::prelude::*;
+
+use crate::events::RecipientDenylistUpdated;
+use crate::instructions::admin::authority_multisig::require_admin_signers;
+use crate::state::{AuthorityMultisig, PoolConfig, RecipientDenylist};
+
+#[derive(Accounts)]
+pub struct InitRecipientDenylist<'info> {
+    #[account(
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = RecipientDenylist::LEN,
+        seeds = [b"recipient_denylist", pool_config.key().as_ref()],
+        bump,
+    )]
+    pub recipient_denylist: Account<'info, RecipientDenylist>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Required when `pool_config.authority_is_multisig`
+    pub authority_multisig: Option<Account<'info, AuthorityMultisig>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn init_denylist_handler(ctx: Context<InitRecipientDenylist>) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let pool = ctx.accounts.pool_config.key();
+    ctx.accounts
+        .recipient_denylist
+        .initialize(pool, ctx.bumps.recipient_denylist);
+
+    msg!("Recipient denylist created");
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdateRecipientDenylist<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"recipient_denylist", pool_config.key().as_ref()],
+        bump = recipient_denylist.bump,
+    )]
+    pub recipient_denylist: Account<'info, RecipientDenylist>,
+
+    pub authority: Signer<'info>,
+
+    /// Required when `pool_config.authority_is_multisig`
+    pub authority_multisig: Option<Account<'info, AuthorityMultisig>>,
+}
+
+/// Deny (`denied = true`) or re-allow a recipient.
+pub fn set_recipient_denied_handler(
+    ctx: Context<UpdateRecipientDenylist>,
+    recipient: Pubkey,
+    denied: bool,
+) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let denylist = &mut ctx.accounts.recipient_denylist;
+    if denied {
+        denylist.add(recipient)?;
+    } else {
+        denylist.remove(&recipient);
+    }
+
+    let root = denylist.root();
+    let pool_config = &mut ctx.accounts.pool_config;
+    pool_config.set_recipient_denylist_root(root);
+
+    emit!(RecipientDenylistUpdated {
+        pool: pool_config.key(),
+        authority: ctx.accounts.authority.key(),
+        recipient,
+        denied,
+        denylist_root: root,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Recipient {} denied: {}", recipient, denied);
+    Ok(())
+}
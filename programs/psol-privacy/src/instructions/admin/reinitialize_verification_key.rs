@@ -0,0 +1,196 @@
+//! Reinitialize Verification Key Instruction
+//!
+//! Recovery for a pool whose verification key PDA is missing, zeroed,
+//! undersized or otherwise unreadable, so that `set_verification_key`
+//! cannot run against it. Recreates the account (topping up rent and
+//! growing it to the default IC capacity as needed) and writes a fresh,
+//! unconfigured `VerificationKeyAccount`.
+//!
+//! Never touches a configured key: the pool must not have
+//! `vk_configured` or `vk_locked` set, and an account that already holds
+//! an initialized key is rejected.
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+
+use crate::error::PrivacyError;
+use crate::events::VerificationKeyReinitialized;
+use crate::instructions::admin::authority_multisig::require_admin_signers;
+use crate::instructions::withdraw::create_pda_account;
+use crate::state::{AuthorityMultisig, PoolConfig, VerificationKeyAccount};
+
+/// Accounts for reinitialize_verification_key instruction.
+#[derive(Accounts)]
+pub struct ReinitializeVerificationKey<'info> {
+    #[account(
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+        has_one = verification_key @ PrivacyError::Unauthorized,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    /// CHECK: PDA checked by seeds; contents checked by `check_vk_recoverable`
+    #[account(
+        mut,
+        seeds = [b"verification_key", pool_config.key().as_ref()],
+        bump,
+    )]
+    pub verification_key: UncheckedAccount<'info>,
+
+    /// Pool authority (must sign, pays for any rent top-up).
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Required when `pool_config.authority_is_multisig`
+    pub authority_multisig: Option<Account<'info, AuthorityMultisig>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler for reinitialize_verification_key instruction.
+pub fn reinitialize_verification_key_handler(ctx: Context<ReinitializeVerificationKey>) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let pool_key = ctx.accounts.pool_config.key();
+    let bump = ctx.bumps.verification_key;
+    let target = ctx.accounts.verification_key.to_account_info();
+    check_vk_recoverable(&ctx.accounts.pool_config, &target)?;
+
+    let space = VerificationKeyAccount::space(VerificationKeyAccount::DEFAULT_MAX_IC_POINTS);
+    let system = ctx.accounts.system_program.to_account_info();
+    let payer = ctx.accounts.authority.to_account_info();
+    if target.owner == &system_program::ID {
+        let bump_seed = [bump];
+        let seeds: &[&[u8]] = &[b"verification_key", pool_key.as_ref(), &bump_seed];
+        create_pda_account(system, payer, target.clone(), space, &[seeds])?;
+    } else if target.data_len() < space {
+        let top_up = Rent::get()?.minimum_balance(space).saturating_sub(target.lamports());
+        if top_up > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    system,
+                    system_program::Transfer { from: payer, to: target.clone() },
+                ),
+                top_up,
+            )?;
+        }
+        target.realloc(space, false)?;
+    }
+
+    reset_vk_data(&mut target.try_borrow_mut_data()?, pool_key, bump)?;
+
+    emit!(VerificationKeyReinitialized {
+        pool: pool_key,
+        authority: ctx.accounts.authority.key(),
+        data_len: space as u32,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Verification key account reinitialized");
+    Ok(())
+}
+
+/// Whether the VK account for `pool_config` may be reinitialized.
+///
+/// # Errors
+/// * `AlreadyInitialized` if the pool has a configured key, or the account
+///   holds a key with `is_initialized` set
+/// * `VerificationKeyLocked` if the pool's key is locked
+/// * `InvalidOwner` if the account belongs to neither this program nor the
+///   system program
+pub fn check_vk_recoverable(pool_config: &PoolConfig, verification_key: &AccountInfo) -> Result<()> {
+    require!(!pool_config.vk_configured, PrivacyError::AlreadyInitialized);
+    pool_config.require_vk_unlocked()?;
+
+    if verification_key.owner == &system_program::ID {
+        return Ok(());
+    }
+    require!(verification_key.owner == &crate::ID, PrivacyError::InvalidOwner);
+
+    let data = verification_key.try_borrow_data()?;
+    if let Ok(vk) = VerificationKeyAccount::try_deserialize(&mut &data[..]) {
+        require!(!vk.is_initialized, PrivacyError::AlreadyInitialized);
+    }
+    Ok(())
+}
+
+/// Overwrite `data` with an empty, unconfigured key for `pool`.
+pub(crate) fn reset_vk_data(data: &mut [u8], pool: Pubkey, bump: u8) -> Result<()> {
+    data.fill(0);
+    let mut vk = VerificationKeyAccount::try_deserialize_unchecked(&mut &data[..])?;
+    vk.initialize(pool, bump);
+    vk.try_serialize(&mut &mut data[..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::pool_config::test_fixtures::new_pool;
+
+    fn space() -> usize {
+        VerificationKeyAccount::space(VerificationKeyAccount::DEFAULT_MAX_IC_POINTS)
+    }
+
+    #[test]
+    fn test_partial_init_recovered() {
+        let pool = new_pool();
+        let pool_key = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        let owner = crate::ID;
+
+        // Allocated to the program but never written
+        let (mut lamports, mut data) = (1, vec![0u8; space()]);
+        let vk_info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+        check_vk_recoverable(&pool, &vk_info).unwrap();
+        reset_vk_data(&mut vk_info.try_borrow_mut_data().unwrap(), pool_key, 254).unwrap();
+
+        let vk = VerificationKeyAccount::try_deserialize(&mut &vk_info.data.borrow()[..]).unwrap();
+        assert_eq!((vk.pool, vk.bump, vk.is_initialized), (pool_key, 254, false));
+        // Still unconfigured, so recovery is idempotent
+        assert!(check_vk_recoverable(&pool, &vk_info).is_ok());
+
+        // Not created at all
+        let (mut lamports, mut data) = (0, vec![]);
+        let system = system_program::ID;
+        let missing = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &system, false, 0);
+        assert!(check_vk_recoverable(&pool, &missing).is_ok());
+    }
+
+    #[test]
+    fn test_configured_vk_not_overwritten() {
+        let key = Pubkey::new_unique();
+        let owner = crate::ID;
+        let (mut lamports, mut data) = (1, vec![0u8; space()]);
+        reset_vk_data(&mut data, Pubkey::new_unique(), 254).unwrap();
+        let mut vk = VerificationKeyAccount::try_deserialize(&mut &data[..]).unwrap();
+        vk.is_initialized = true;
+        vk.try_serialize(&mut &mut data[..]).unwrap();
+        let vk_info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+
+        let mut pool = new_pool();
+        let err = check_vk_recoverable(&pool, &vk_info).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::AlreadyInitialized));
+
+        pool.set_vk_configured(true);
+        let (mut lamports, mut data) = (1, vec![0u8; space()]);
+        let empty = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+        let err = check_vk_recoverable(&pool, &empty).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::AlreadyInitialized));
+
+        let mut pool = new_pool();
+        pool.vk_locked = true;
+        let err = check_vk_recoverable(&pool, &empty).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::VerificationKeyLocked));
+
+        // Some other program's account
+        let other = Pubkey::new_unique();
+        let (mut lamports, mut data) = (1, vec![0u8; space()]);
+        let foreign = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &other, false, 0);
+        let err = check_vk_recoverable(&new_pool(), &foreign).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::InvalidOwner));
+    }
+}
@@ -0,0 +1,114 @@
+//! Configure Root Policy Instruction
+//!
+//! One control for the proof validity window: resizes the root history
+//! (in the tree account, or the `RootHistory` PDA for split-layout pools)
+//! and sets `max_root_age_slots` together, keeping every root still in the
+//! history.
+//!
+//! Reallocation can grow an account by at most 10 KiB per instruction,
+//! i.e. 256 history entries; larger increases take several calls.
+
+use anchor_lang::prelude::*;
+
+use crate::error::PrivacyError;
+use crate::events::RootPolicyConfigured;
+use crate::instructions::admin::authority_multisig::require_admin_signers;
+use crate::instructions::initialize_pool::validate_root_history_size;
+use crate::state::{debug_assert_fits, AuthorityMultisig, MerkleTree, PoolConfig, RootHistory};
+
+/// Accounts for configure_root_policy instruction.
+#[derive(Accounts)]
+#[instruction(new_size: u16)]
+pub struct ConfigureRootPolicy<'info> {
+    /// Pool configuration account.
+    #[account(
+        mut,
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    /// Merkle tree, resized when it holds the root history itself.
+    #[account(
+        mut,
+        seeds = [b"merkle_tree", pool_config.key().as_ref()],
+        bump,
+        constraint = merkle_tree.pool == pool_config.key() @ PrivacyError::Unauthorized,
+        realloc = MerkleTree::space(
+            merkle_tree.depth,
+            if merkle_tree.external_root_history { 0 } else { new_size },
+        ),
+        realloc::payer = authority,
+        realloc::zero = false,
+    )]
+    pub merkle_tree: Box<Account<'info, MerkleTree>>,
+
+    /// Split-layout root history; required iff `merkle_tree.external_root_history`
+    #[account(
+        mut,
+        seeds = [b"root_history", pool_config.key().as_ref()],
+        bump = root_history.bump,
+        realloc = RootHistory::space(new_size),
+        realloc::payer = authority,
+        realloc::zero = false,
+    )]
+    pub root_history: Option<Box<Account<'info, RootHistory>>>,
+
+    /// Pool authority (must sign, pays for growth and receives freed rent).
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Required when `pool_config.authority_is_multisig`
+    pub authority_multisig: Option<Account<'info, AuthorityMultisig>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler for configure_root_policy instruction.
+///
+/// Rejects a shrink that would evict a root still valid under the new
+/// `max_root_age_slots` (0 = no age limit, so every recorded root).
+pub fn configure_root_policy_handler(
+    ctx: Context<ConfigureRootPolicy>,
+    new_size: u16,
+    max_root_age_slots: u64,
+) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    validate_root_history_size(new_size)?;
+    let clock = Clock::get()?;
+
+    let merkle_tree = &mut ctx.accounts.merkle_tree;
+    merkle_tree.resize_root_history_in(
+        ctx.accounts.root_history.as_deref_mut().map(|history| &mut **history),
+        new_size,
+        clock.slot,
+        max_root_age_slots,
+    )?;
+    debug_assert_fits(&***merkle_tree, merkle_tree.to_account_info().data_len());
+    if let Some(history) = &ctx.accounts.root_history {
+        debug_assert_fits(&***history, history.to_account_info().data_len());
+    }
+
+    let pool_config = &mut ctx.accounts.pool_config;
+    pool_config.set_max_root_age_slots(max_root_age_slots);
+
+    emit!(RootPolicyConfigured {
+        pool: pool_config.key(),
+        authority: ctx.accounts.authority.key(),
+        root_history_size: new_size,
+        max_root_age_slots,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!(
+        "Root policy: {} roots, max age {} slots",
+        new_size,
+        max_root_age_slots
+    );
+    Ok(())
+}
@@ -0,0 +1,69 @@
+//! SOL Fee Reserve Instruction
+//!
+//! Creates the program-owned account that `withdraw_fee_in_lamports` pays
+//! relayer fees from, and sets the per-withdrawal cap. The protocol funds
+//! it by plain system transfers; lamports above its rent-exempt minimum
+//! are available for fees.
+
+use anchor_lang::prelude::*;
+
+use crate::events::SolFeeReserveInitialized;
+use crate::instructions::admin::authority_multisig::require_admin_signers;
+use crate::state::{AuthorityMultisig, PoolConfig, SolFeeReserve};
+
+#[derive(Accounts)]
+pub struct InitSolFeeReserve<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = SolFeeReserve::LEN,
+        seeds = [b"sol_fee_reserve", pool_config.key().as_ref()],
+        bump
+    )]
+    pub sol_fee_reserve: Account<'info, SolFeeReserve>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Required when `pool_config.authority_is_multisig`
+    pub authority_multisig: Option<Account<'info, AuthorityMultisig>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn init_sol_fee_reserve_handler(
+    ctx: Context<InitSolFeeReserve>,
+    max_relayer_fee_lamports: u64,
+) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let pool_key = ctx.accounts.pool_config.key();
+    ctx.accounts
+        .sol_fee_reserve
+        .initialize(pool_key, ctx.bumps.sol_fee_reserve);
+
+    let pool_config = &mut ctx.accounts.pool_config;
+    pool_config.set_max_relayer_fee_lamports(max_relayer_fee_lamports);
+
+    emit!(SolFeeReserveInitialized {
+        pool: pool_key,
+        authority: ctx.accounts.authority.key(),
+        sol_fee_reserve: ctx.accounts.sol_fee_reserve.key(),
+        max_relayer_fee_lamports,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("SOL fee reserve created, max fee {} lamports", max_relayer_fee_lamports);
+    Ok(())
+}
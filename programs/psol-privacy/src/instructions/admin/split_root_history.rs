@@ -0,0 +1,78 @@
+//! Split Root History Instruction
+//!
+//! Moves a pool's root history out of the `MerkleTree` account into a
+//! separate `RootHistory` PDA and shrinks the tree account. One-way: after
+//! the split every deposit/withdraw must pass the `RootHistory` account.
+
+use anchor_lang::prelude::*;
+
+use crate::error::PrivacyError;
+use crate::events::RootHistorySplit;
+use crate::instructions::admin::authority_multisig::require_admin_signers;
+use crate::state::{AuthorityMultisig, MerkleTree, PoolConfig, RootHistory};
+
+/// Accounts for split_root_history instruction.
+#[derive(Accounts)]
+pub struct SplitRootHistory<'info> {
+    /// Pool configuration account.
+    #[account(
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    /// Merkle tree, shrunk to its split-layout size.
+    #[account(
+        mut,
+        seeds = [b"merkle_tree", pool_config.key().as_ref()],
+        bump,
+        constraint = merkle_tree.pool == pool_config.key() @ PrivacyError::Unauthorized,
+        constraint = !merkle_tree.external_root_history @ PrivacyError::RootHistoryMismatch,
+        realloc = MerkleTree::space(merkle_tree.depth, 0),
+        realloc::payer = authority,
+        realloc::zero = false,
+    )]
+    pub merkle_tree: Box<Account<'info, MerkleTree>>,
+
+    /// New root history account, sized to the tree's existing history.
+    #[account(
+        init,
+        payer = authority,
+        space = RootHistory::space(merkle_tree.root_history_size),
+        seeds = [b"root_history", pool_config.key().as_ref()],
+        bump
+    )]
+    pub root_history: Box<Account<'info, RootHistory>>,
+
+    /// Pool authority (must sign, receives the freed rent).
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Required when `pool_config.authority_is_multisig`
+    pub authority_multisig: Option<Account<'info, AuthorityMultisig>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler for split_root_history instruction.
+pub fn handler(ctx: Context<SplitRootHistory>) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let root_history = &mut ctx.accounts.root_history;
+    root_history.take_from(&mut ctx.accounts.merkle_tree, ctx.bumps.root_history)?;
+
+    emit!(RootHistorySplit {
+        pool: ctx.accounts.pool_config.key(),
+        authority: ctx.accounts.authority.key(),
+        root_history: root_history.key(),
+        root_history_size: root_history.root_history_size,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Root history moved to {}", root_history.key());
+    Ok(())
+}
@@ -0,0 +1,87 @@
+//! Sweep Untracked Vault Balance
+//!
+//! Tokens sent straight to the vault (not via `deposit`) are not backed by
+//! any note and break the TVL invariant. The authority can move exactly
+//! that excess to the pool's fee recipient; note-backed value is untouched.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::error::PrivacyError;
+use crate::events::UntrackedSwept;
+use crate::instructions::admin::authority_multisig::require_admin_signers;
+use crate::state::{AuthorityMultisig, PoolConfig};
+
+#[derive(Accounts)]
+pub struct SweepUntracked<'info> {
+    #[account(
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", pool_config.key().as_ref()],
+        bump,
+        constraint = vault.mint == pool_config.token_mint @ PrivacyError::InvalidMint,
+        constraint = vault.owner == pool_config.key() @ PrivacyError::Unauthorized,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = fee_recipient_token_account.mint == pool_config.token_mint @ PrivacyError::InvalidMint,
+        constraint = fee_recipient_token_account.owner == pool_config.fee_recipient @ PrivacyError::InvalidFeeRecipient,
+    )]
+    pub fee_recipient_token_account: Account<'info, TokenAccount>,
+
+    pub authority: Signer<'info>,
+
+    /// Required when `pool_config.authority_is_multisig`
+    pub authority_multisig: Option<Account<'info, AuthorityMultisig>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn handler(ctx: Context<SweepUntracked>) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let pool_config = &ctx.accounts.pool_config;
+    pool_config.require_not_paused()?;
+    let excess = pool_config.untracked_excess(ctx.accounts.vault.amount)?;
+
+    let pool_seeds = &[
+        b"pool".as_ref(),
+        pool_config.token_mint.as_ref(),
+        &[pool_config.bump],
+    ];
+    let signer_seeds = &[&pool_seeds[..]];
+
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.vault.to_account_info(),
+        to: ctx.accounts.fee_recipient_token_account.to_account_info(),
+        authority: pool_config.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_accounts,
+        signer_seeds,
+    );
+    token::transfer(cpi_ctx, excess)?;
+
+    emit!(UntrackedSwept {
+        pool: pool_config.key(),
+        authority: ctx.accounts.authority.key(),
+        fee_recipient: pool_config.fee_recipient,
+        amount: excess,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Swept {} untracked tokens to fee recipient", excess);
+    Ok(())
+}
@@ -6,7 +6,9 @@ use anchor_lang::prelude::*;
 
 use crate::error::PrivacyError;
 use crate::events::PoolUnpaused;
-use crate::state::PoolConfig;
+use crate::instructions::admin::authority_multisig::require_admin_signers;
+use crate::instructions::admin::pause::PauseReason;
+use crate::state::{AuthorityMultisig, PoolConfig};
 
 /// Accounts for unpause_pool instruction.
 #[derive(Accounts)]
@@ -16,16 +18,27 @@ pub struct UnpausePool<'info> {
         mut,
         seeds = [b"pool", pool_config.token_mint.as_ref()],
         bump = pool_config.bump,
-        has_one = authority @ PrivacyError::Unauthorized,
     )]
     pub pool_config: Account<'info, PoolConfig>,
 
     /// Pool authority (must sign).
     pub authority: Signer<'info>,
+
+    /// Required when `pool_config.authority_is_multisig`
+    pub authority_multisig: Option<Account<'info, AuthorityMultisig>>,
 }
 
 /// Handler for unpause_pool instruction.
-pub fn handler(ctx: Context<UnpausePool>) -> Result<()> {
+///
+/// `reason` is the `PauseReason` of the pause being lifted.
+pub fn handler(ctx: Context<UnpausePool>, reason: u8) -> Result<()> {
+    let reason = PauseReason::from_u8(reason).ok_or(error!(PrivacyError::InvalidPauseReason))?;
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
     let pool_config = &mut ctx.accounts.pool_config;
 
     // Clear paused state
@@ -35,10 +48,11 @@ pub fn handler(ctx: Context<UnpausePool>) -> Result<()> {
     emit!(PoolUnpaused {
         pool: pool_config.key(),
         authority: ctx.accounts.authority.key(),
+        reason: reason as u8,
         timestamp: Clock::get()?.unix_timestamp,
     });
 
-    msg!("Pool unpaused");
+    msg!("Pool unpaused ({:?})", reason);
 
     Ok(())
 }
@@ -2,9 +2,9 @@
 
 use anchor_lang::prelude::*;
 
-use crate::error::PrivacyError;
 use crate::events::{AuthorityTransferInitiated, AuthorityTransferCompleted, AuthorityTransferCancelled};
-use crate::state::PoolConfig;
+use crate::instructions::admin::authority_multisig::require_admin_signers;
+use crate::state::{AuthorityMultisig, PoolConfig};
 
 #[derive(Accounts)]
 pub struct InitiateAuthorityTransfer<'info> {
@@ -12,17 +12,25 @@ pub struct InitiateAuthorityTransfer<'info> {
         mut,
         seeds = [b"pool", pool_config.token_mint.as_ref()],
         bump = pool_config.bump,
-        has_one = authority @ PrivacyError::Unauthorized,
     )]
     pub pool_config: Account<'info, PoolConfig>,
 
     pub authority: Signer<'info>,
+
+    /// Required when `pool_config.authority_is_multisig`
+    pub authority_multisig: Option<Account<'info, AuthorityMultisig>>,
 }
 
 pub fn initiate_transfer_handler(
     ctx: Context<InitiateAuthorityTransfer>,
     new_authority: Pubkey,
 ) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
     let pool_config = &mut ctx.accounts.pool_config;
     let current_authority = ctx.accounts.authority.key();
 
@@ -75,14 +83,22 @@ pub struct CancelAuthorityTransfer<'info> {
         mut,
         seeds = [b"pool", pool_config.token_mint.as_ref()],
         bump = pool_config.bump,
-        has_one = authority @ PrivacyError::Unauthorized,
     )]
     pub pool_config: Account<'info, PoolConfig>,
 
     pub authority: Signer<'info>,
+
+    /// Required when `pool_config.authority_is_multisig`
+    pub authority_multisig: Option<Account<'info, AuthorityMultisig>>,
 }
 
 pub fn cancel_transfer_handler(ctx: Context<CancelAuthorityTransfer>) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
     let pool_config = &mut ctx.accounts.pool_config;
 
     if !pool_config.has_pending_transfer() {
@@ -111,15 +127,23 @@ pub struct UpdateAuthority<'info> {
         mut,
         seeds = [b"pool", pool_config.token_mint.as_ref()],
         bump = pool_config.bump,
-        has_one = authority @ PrivacyError::Unauthorized,
     )]
     pub pool_config: Account<'info, PoolConfig>,
 
     pub authority: Signer<'info>,
+
+    /// Required when `pool_config.authority_is_multisig`
+    pub authority_multisig: Option<Account<'info, AuthorityMultisig>>,
 }
 
 #[deprecated(note = "Use 2-step authority transfer")]
 pub fn handler(ctx: Context<UpdateAuthority>, new_authority: Pubkey) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
     let pool_config = &mut ctx.accounts.pool_config;
     pool_config.initiate_authority_transfer(new_authority)?;
     msg!("DEPRECATED: Use initiate/accept_authority_transfer");
@@ -0,0 +1,233 @@
+//! Deferred Deposit Instructions
+//!
+//! `deposit_deferred` collects the deposit and queues its commitment in
+//! `PendingCommitments`; `process_pending` is a permissionless keeper that
+//! inserts queued commitments into the tree in FIFO order. The depositor
+//! pays for the token transfer only, and the keeper pays for the hashing.
+//!
+//! Leaf indices are assigned when a commitment is processed, so clients
+//! learn theirs from the `DepositEvent` that `process_pending` emits.
+//! Direct deposits still insert immediately and may land between queued
+//! ones; the queue itself is strictly FIFO.
+
+use anchor_lang::prelude::*;
+
+use crate::error::PrivacyError;
+use crate::events::CommitmentQueued;
+use crate::instructions::deposit::{self, *};
+use crate::state::{
+    DepositNote, MerkleTree, PendingCommitment, PendingCommitments, PoolConfig, RootHistory,
+};
+
+/// Upper bound on commitments inserted by one `process_pending` call.
+pub const MAX_PROCESS_PER_CALL: u8 = 8;
+
+#[derive(Accounts)]
+pub struct InitPendingCommitments<'info> {
+    #[account(
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = PendingCommitments::LEN,
+        seeds = [b"pending_commitments", pool_config.key().as_ref()],
+        bump
+    )]
+    pub pending_commitments: Account<'info, PendingCommitments>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositDeferred<'info> {
+    pub deposit: Deposit<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pending_commitments", deposit.pool_config.key().as_ref()],
+        bump = pending_commitments.bump,
+    )]
+    pub pending_commitments: Account<'info, PendingCommitments>,
+}
+
+#[derive(Accounts)]
+pub struct ProcessPending<'info> {
+    #[account(
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"merkle_tree", pool_config.key().as_ref()],
+        bump,
+        constraint = merkle_tree.pool == pool_config.key() @ PrivacyError::Unauthorized,
+    )]
+    pub merkle_tree: Account<'info, MerkleTree>,
+
+    /// Split-layout root history; required iff `merkle_tree.external_root_history`
+    #[account(
+        mut,
+        seeds = [b"root_history", pool_config.key().as_ref()],
+        bump = root_history.bump,
+    )]
+    pub root_history: Option<Account<'info, RootHistory>>,
+
+    #[account(
+        mut,
+        seeds = [b"pending_commitments", pool_config.key().as_ref()],
+        bump = pending_commitments.bump,
+    )]
+    pub pending_commitments: Account<'info, PendingCommitments>,
+}
+
+pub fn init_pending_handler(ctx: Context<InitPendingCommitments>) -> Result<()> {
+    let pool = ctx.accounts.pool_config.key();
+    ctx.accounts
+        .pending_commitments
+        .initialize(pool, ctx.bumps.pending_commitments);
+
+    msg!("Pending commitment queue created for pool {}", pool);
+    Ok(())
+}
+
+/// Collect a deposit and queue `commitment`. Returns its sequence number.
+pub fn deposit_deferred_handler(
+    ctx: Context<DepositDeferred>,
+    amount: u64,
+    commitment: [u8; 32],
+) -> Result<u64> {
+    let accounts = ctx.accounts;
+    let reserved = accounts.pending_commitments.len() as u32;
+    let commitment = deposit::collect_deposit(
+        &mut accounts.deposit,
+        amount,
+        DepositNote::Commitment(commitment),
+        reserved,
+    )?;
+
+    let sequence = accounts.pending_commitments.enqueue(commitment, amount)?;
+
+    emit!(CommitmentQueued {
+        pool: accounts.deposit.pool_config.key(),
+        commitment,
+        sequence,
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Deposit queued: sequence {}", sequence);
+    Ok(sequence)
+}
+
+/// Insert up to `max_count` queued commitments. Anyone may call.
+pub fn process_pending_handler(ctx: Context<ProcessPending>, max_count: u8) -> Result<()> {
+    let accounts = ctx.accounts;
+    accounts.pool_config.require_not_paused()?;
+
+    let max_count = max_count.min(MAX_PROCESS_PER_CALL);
+    let clock = Clock::get()?;
+    let inserted = insert_pending(
+        &mut accounts.merkle_tree,
+        accounts.root_history.as_deref_mut(),
+        &mut accounts.pending_commitments,
+        max_count as usize,
+        clock.slot,
+    )?;
+
+    let pool = accounts.pool_config.key();
+    for (pending, leaf_index, root) in &inserted {
+        let (deposit_event, root_updated) =
+            deposit::deposit_events(pool, pending.commitment, *leaf_index, pending.amount, *root, &clock);
+        emit!(deposit_event);
+        emit!(root_updated);
+    }
+
+    msg!(
+        "Processed {} pending commitments, {} remaining",
+        inserted.len(),
+        accounts.pending_commitments.len()
+    );
+    Ok(())
+}
+
+/// Move up to `max_count` of the oldest queued commitments into `tree`.
+///
+/// Returns each inserted commitment with the leaf index it was assigned
+/// and the root right after its insertion.
+pub fn insert_pending(
+    tree: &mut MerkleTree,
+    mut root_history: Option<&mut RootHistory>,
+    queue: &mut PendingCommitments,
+    max_count: usize,
+    slot: u64,
+) -> Result<Vec<(PendingCommitment, u32, [u8; 32])>> {
+    queue
+        .dequeue(max_count)
+        .into_iter()
+        .map(|pending| {
+            let leaf_index =
+                tree.insert_leaf_into(pending.commitment, slot, root_history.as_deref_mut())?;
+            Ok((pending, leaf_index, tree.current_root))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::TreeHash;
+    use crate::state::merkle_tree::test_fixtures::new_tree;
+
+    fn new_queue() -> PendingCommitments {
+        let data = vec![0u8; PendingCommitments::LEN];
+        let mut queue = PendingCommitments::try_deserialize_unchecked(&mut data.as_slice()).unwrap();
+        queue.initialize(Pubkey::new_unique(), 255);
+        queue
+    }
+
+    #[test]
+    fn test_pending_inserted_in_fifo_order() {
+        let mut deferred = new_tree(8, TreeHash::Keccak);
+        let mut direct = new_tree(8, TreeHash::Keccak);
+        let mut queue = new_queue();
+
+        // One direct deposit lands before the queue is drained
+        deferred.insert_leaf([0xaa; 32], 1).unwrap();
+        direct.insert_leaf([0xaa; 32], 1).unwrap();
+
+        for i in 1..=5u8 {
+            queue.enqueue([i; 32], i as u64).unwrap();
+        }
+
+        let first = insert_pending(&mut deferred, None, &mut queue, 3, 2).unwrap();
+        let rest = insert_pending(&mut deferred, None, &mut queue, 10, 3).unwrap();
+        assert!(queue.is_empty());
+
+        let inserted: Vec<_> = first.into_iter().chain(rest).collect();
+        for (i, (pending, leaf_index, root)) in inserted.iter().enumerate() {
+            assert_eq!(pending.commitment, [i as u8 + 1; 32]);
+            assert_eq!(*leaf_index, i as u32 + 1);
+            assert_eq!(direct.insert_leaf(pending.commitment, 2).unwrap(), *leaf_index);
+            assert_eq!(*root, direct.current_root);
+        }
+        assert_eq!(deferred.current_root, direct.current_root);
+    }
+
+    #[test]
+    fn test_empty_queue_is_noop() {
+        let mut tree = new_tree(8, TreeHash::Keccak);
+        let root = tree.current_root;
+        let inserted = insert_pending(&mut tree, None, &mut new_queue(), 8, 1).unwrap();
+        assert!(inserted.is_empty());
+        assert_eq!(tree.current_root, root);
+    }
+}
@@ -0,0 +1,146 @@
+//! Dev Seed Commitments Instruction - dev-mode only
+//!
+//! Inserts deterministic commitments into the tree WITHOUT token transfers,
+//! so integration tests can build a realistic anonymity set quickly.
+//!
+//! The module, its accounts and the program entry point are only compiled
+//! with the `dev-mode` feature; other builds have no such instruction.
+
+use anchor_lang::prelude::*;
+
+use crate::error::PrivacyError;
+use crate::instructions::admin::authority_multisig::require_admin_signers;
+use crate::instructions::deposit::deposit_events;
+use crate::state::{AuthorityMultisig, MerkleTree, PoolConfig, RootHistory};
+
+/// Maximum commitments seeded per call (keeps each call within CU limits).
+pub const MAX_DEV_SEED_PER_CALL: u32 = 32;
+
+#[derive(Accounts)]
+pub struct DevSeedCommitments<'info> {
+    #[account(
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"merkle_tree", pool_config.key().as_ref()],
+        bump,
+        constraint = merkle_tree.pool == pool_config.key() @ PrivacyError::Unauthorized,
+    )]
+    pub merkle_tree: Account<'info, MerkleTree>,
+
+    /// Split-layout root history; required iff `merkle_tree.external_root_history`
+    #[account(
+        mut,
+        seeds = [b"root_history", pool_config.key().as_ref()],
+        bump = root_history.bump,
+    )]
+    pub root_history: Option<Account<'info, RootHistory>>,
+
+    pub authority: Signer<'info>,
+
+    /// Required when `pool_config.authority_is_multisig`
+    pub authority_multisig: Option<Account<'info, AuthorityMultisig>>,
+}
+
+pub fn handler(ctx: Context<DevSeedCommitments>, count: u32) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    ctx.accounts.pool_config.require_not_paused()?;
+    ctx.accounts.pool_config.require_dev_mode_acknowledged()?;
+    msg!("⚠️ DEV MODE: seeding {} commitments without deposits", count);
+
+    let pool = ctx.accounts.pool_config.key();
+    let clock = Clock::get()?;
+    let merkle_tree = &mut ctx.accounts.merkle_tree;
+    let root_history = ctx.accounts.root_history.as_deref_mut();
+
+    for (leaf_index, commitment, root) in seed_commitments(merkle_tree, root_history, count, clock.slot)? {
+        let (deposit_event, root_updated) = deposit_events(pool, commitment, leaf_index, 0, root, &clock);
+        emit!(deposit_event);
+        emit!(root_updated);
+    }
+
+    Ok(())
+}
+
+/// Deterministic commitment for seeded leaf `index`.
+///
+/// Keccak256("psol-dev-seed" || index_le) with the top bits cleared so the
+/// value is a canonical BN254 field element (valid for Poseidon trees).
+pub fn dev_seed_commitment(index: u32) -> [u8; 32] {
+    let mut commitment =
+        solana_program::keccak::hashv(&[b"psol-dev-seed", &index.to_le_bytes()]).to_bytes();
+    commitment[0] &= 0x1f;
+    commitment
+}
+
+/// `(leaf_index, commitment, root after insertion)` of one seeded leaf.
+pub type SeededLeaf = (u32, [u8; 32], [u8; 32]);
+
+/// Insert `count` deterministic commitments, returning a `SeededLeaf` for each.
+pub fn seed_commitments(
+    merkle_tree: &mut MerkleTree,
+    mut root_history: Option<&mut RootHistory>,
+    count: u32,
+    slot: u64,
+) -> Result<Vec<SeededLeaf>> {
+    require!(count > 0, PrivacyError::InvalidAmount);
+    require!(count <= MAX_DEV_SEED_PER_CALL, PrivacyError::LimitExceeded);
+
+    let mut inserted = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let commitment = dev_seed_commitment(merkle_tree.next_leaf_index);
+        let leaf_index =
+            merkle_tree.insert_leaf_into(commitment, slot, root_history.as_deref_mut())?;
+        inserted.push((leaf_index, commitment, merkle_tree.current_root));
+    }
+    Ok(inserted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::TreeHash;
+    use crate::state::merkle_tree::test_fixtures::new_tree;
+
+    #[test]
+    fn test_seed_advances_leaf_index_and_root() {
+        for hash in [TreeHash::Poseidon, TreeHash::Keccak] {
+            let mut tree = new_tree(10, hash);
+            let mut last_root = tree.current_root;
+
+            for round in 1..=3u32 {
+                let inserted = seed_commitments(&mut tree, None, 5, 0).unwrap();
+                assert_eq!(inserted.len(), 5);
+                assert_eq!(tree.next_leaf_index, 5 * round);
+                assert_ne!(tree.current_root, last_root);
+                last_root = tree.current_root;
+            }
+        }
+    }
+
+    #[test]
+    fn test_seed_is_deterministic() {
+        let mut a = new_tree(10, TreeHash::Poseidon);
+        let mut b = new_tree(10, TreeHash::Poseidon);
+        seed_commitments(&mut a, None, 8, 0).unwrap();
+        seed_commitments(&mut b, None, 8, 0).unwrap();
+        assert_eq!(a.current_root, b.current_root);
+    }
+
+    #[test]
+    fn test_seed_count_limits() {
+        let mut tree = new_tree(10, TreeHash::Keccak);
+        assert!(seed_commitments(&mut tree, None, 0, 0).is_err());
+        assert!(seed_commitments(&mut tree, None, MAX_DEV_SEED_PER_CALL + 1, 0).is_err());
+        assert_eq!(tree.next_leaf_index, 0);
+    }
+}
@@ -0,0 +1,65 @@
+//! Get Limits Instruction
+//!
+//! Returns the program's compile-time bounds as return data, so clients
+//! can size trees, deposits and fees without hard-coding this build's
+//! constants. Takes no accounts.
+
+use anchor_lang::prelude::*;
+
+use crate::instructions::deposit::MAX_DEPOSIT_AMOUNT;
+use crate::instructions::initialize_pool::{
+    MAX_ALLOWED_DEPTH, MAX_ROOT_HISTORY, MAX_TREE_DEPTH, MIN_ROOT_HISTORY, MIN_TREE_DEPTH,
+};
+use crate::instructions::withdraw::{MAX_RELAYER_FEE_BPS, MIN_WITHDRAWAL_AMOUNT};
+
+#[derive(Accounts)]
+pub struct GetLimits {}
+
+/// Bounds enforced by this build of the program.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ProgramLimits {
+    pub min_tree_depth: u8,
+    /// Hard maximum supported by the tree implementation
+    pub max_tree_depth: u8,
+    /// Deployment cap `initialize_pool` enforces (<= `max_tree_depth`)
+    pub max_allowed_depth: u8,
+    pub min_root_history: u16,
+    pub max_root_history: u16,
+    pub max_deposit_amount: u64,
+    pub min_withdrawal_amount: u64,
+    pub max_relayer_fee_bps: u64,
+}
+
+pub fn get_limits_handler(_ctx: Context<GetLimits>) -> Result<ProgramLimits> {
+    Ok(program_limits())
+}
+
+pub fn program_limits() -> ProgramLimits {
+    ProgramLimits {
+        min_tree_depth: MIN_TREE_DEPTH,
+        max_tree_depth: MAX_TREE_DEPTH,
+        max_allowed_depth: MAX_ALLOWED_DEPTH,
+        min_root_history: MIN_ROOT_HISTORY,
+        max_root_history: MAX_ROOT_HISTORY,
+        max_deposit_amount: MAX_DEPOSIT_AMOUNT,
+        min_withdrawal_amount: MIN_WITHDRAWAL_AMOUNT,
+        max_relayer_fee_bps: MAX_RELAYER_FEE_BPS,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::initialize_pool::validate_tree_depth;
+
+    #[test]
+    fn test_limits_match_enforced_bounds() {
+        let limits = program_limits();
+        let decoded = ProgramLimits::try_from_slice(&limits.try_to_vec().unwrap()).unwrap();
+        assert_eq!(decoded, limits);
+
+        assert!(validate_tree_depth(limits.max_allowed_depth, MAX_ALLOWED_DEPTH).is_ok());
+        assert!(validate_tree_depth(limits.max_allowed_depth + 1, MAX_ALLOWED_DEPTH).is_err());
+        assert!(validate_tree_depth(limits.min_tree_depth - 1, MAX_ALLOWED_DEPTH).is_err());
+    }
+}
@@ -0,0 +1,130 @@
+//! Get Tree State Instruction
+//!
+//! Read-only snapshot of the incremental tree for indexers: root, next leaf
+//! index, `filled_subtrees` and `zeros`, enough to resume appending without
+//! replaying every `DepositEvent`. Returned via return data.
+//!
+//! Return data is capped at 1024 bytes, so deep trees are read
+//! in pages of `MAX_TREE_STATE_LEVELS` levels starting at `start_level`.
+
+use anchor_lang::prelude::*;
+
+use crate::error::PrivacyError;
+use crate::state::{MerkleTree, PoolConfig};
+
+/// Levels per page; keeps an encoded `TreeState` within return data limits.
+pub const MAX_TREE_STATE_LEVELS: u8 = 14;
+
+#[derive(Accounts)]
+pub struct GetTreeState<'info> {
+    #[account(
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    #[account(
+        seeds = [b"merkle_tree", pool_config.key().as_ref()],
+        bump,
+        constraint = merkle_tree.pool == pool_config.key() @ PrivacyError::Unauthorized,
+    )]
+    pub merkle_tree: Account<'info, MerkleTree>,
+}
+
+/// One page of tree state, covering levels `start_level..start_level + filled_subtrees.len()`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TreeState {
+    pub depth: u8,
+    pub tree_hash: u8,
+    pub next_leaf_index: u32,
+    pub current_root: [u8; 32],
+    pub start_level: u8,
+    /// `filled_subtrees[start_level..end_level]`
+    pub filled_subtrees: Vec<[u8; 32]>,
+    /// `zeros[start_level..=end_level]`; the last page ends with the empty root
+    pub zeros: Vec<[u8; 32]>,
+}
+
+pub fn handler(ctx: Context<GetTreeState>, start_level: u8) -> Result<TreeState> {
+    tree_state(&ctx.accounts.merkle_tree, start_level)
+}
+
+/// Page of `tree`'s state starting at `start_level`.
+///
+/// # Errors
+/// * `InvalidTreeDepth` if `start_level` is past the last level
+pub fn tree_state(tree: &MerkleTree, start_level: u8) -> Result<TreeState> {
+    require!(start_level < tree.depth, PrivacyError::InvalidTreeDepth);
+    let start = start_level as usize;
+    let end = (start + MAX_TREE_STATE_LEVELS as usize).min(tree.depth as usize);
+
+    Ok(TreeState {
+        depth: tree.depth,
+        tree_hash: tree.tree_hash,
+        next_leaf_index: tree.next_leaf_index,
+        current_root: tree.current_root,
+        start_level,
+        filled_subtrees: tree.filled_subtrees[start..end].to_vec(),
+        zeros: tree.zeros[start..=end].to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::solana_program::program::MAX_RETURN_DATA;
+    use crate::crypto::TreeHash;
+    use crate::state::merkle_tree::test_fixtures::new_tree;
+
+    fn filled_tree(depth: u8) -> MerkleTree {
+        let mut tree = new_tree(depth, TreeHash::Keccak);
+        for i in 1..=5u8 {
+            tree.insert_leaf([i; 32], i as u64).unwrap();
+        }
+        tree
+    }
+
+    /// Encode and decode like a client reading return data
+    fn read_pages(tree: &MerkleTree) -> Vec<TreeState> {
+        let mut pages = Vec::new();
+        let mut start_level = 0;
+        while start_level < tree.depth {
+            let data = tree_state(tree, start_level).unwrap().try_to_vec().unwrap();
+            assert!(data.len() <= MAX_RETURN_DATA);
+            let page = TreeState::try_from_slice(&data).unwrap();
+            start_level += page.filled_subtrees.len() as u8;
+            pages.push(page);
+        }
+        pages
+    }
+
+    #[test]
+    fn test_tree_state_matches_account() {
+        let tree = filled_tree(8);
+        let pages = read_pages(&tree);
+        assert_eq!(pages.len(), 1);
+
+        let state = &pages[0];
+        assert_eq!(state.depth, tree.depth);
+        assert_eq!(state.tree_hash, tree.tree_hash);
+        assert_eq!(state.next_leaf_index, 5);
+        assert_eq!(state.current_root, tree.current_root);
+        assert_eq!(state.filled_subtrees, tree.filled_subtrees);
+        assert_eq!(state.zeros, tree.zeros);
+    }
+
+    #[test]
+    fn test_deep_tree_state_is_paged() {
+        let tree = filled_tree(24);
+        let pages = read_pages(&tree);
+        assert_eq!(pages.len(), 2);
+
+        let filled: Vec<_> = pages.iter().flat_map(|p| p.filled_subtrees.clone()).collect();
+        assert_eq!(filled, tree.filled_subtrees);
+        // Pages overlap by one zero value at the boundary
+        assert_eq!(pages[1].zeros.first(), pages[0].zeros.last());
+        assert_eq!(pages[1].zeros.last(), tree.zeros.last());
+
+        assert!(tree_state(&tree, 24).is_err());
+    }
+}
@@ -0,0 +1,148 @@
+//! Get Verification Key Instruction
+//!
+//! Exposes the pool's primary VK as return data so other programs (e.g. a
+//! router checking which circuit a pool accepts) can read it via CPI
+//! without deserializing the account. Wallets read its `circuit_id` to
+//! pick the matching proving key.
+//!
+//! The Borsh-encoded `VerificationKeyData` can exceed the 1024-byte return
+//! data cap, so it is returned in chunks of up to `MAX_VK_CHUNK_LEN` bytes:
+//! call with `offset = 0`, then with `offset + data.len()` until
+//! `total_len` bytes have been read.
+//!
+//! IC points are always returned uncompressed, whatever the account's
+//! `compressed_ic` storage mode.
+
+use anchor_lang::prelude::*;
+
+use crate::error::PrivacyError;
+use crate::state::{PoolConfig, VerificationKeyAccount, VerificationKeyData};
+
+/// Bytes of encoded VK per chunk; keeps an encoded chunk within return data limits.
+pub const MAX_VK_CHUNK_LEN: usize = 1000;
+
+#[derive(Accounts)]
+pub struct GetVerificationKey<'info> {
+    #[account(
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    #[account(
+        seeds = [b"verification_key", pool_config.key().as_ref()],
+        bump = verification_key.bump,
+        constraint = verification_key.pool == pool_config.key() @ PrivacyError::Unauthorized,
+        constraint = verification_key.is_initialized @ PrivacyError::VerificationKeyNotSet,
+    )]
+    pub verification_key: Account<'info, VerificationKeyAccount>,
+}
+
+/// Bytes `offset..offset + data.len()` of the encoded `VerificationKeyData`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct VerificationKeyChunk {
+    /// Length of the full encoding
+    pub total_len: u32,
+    pub offset: u32,
+    pub data: Vec<u8>,
+}
+
+pub fn handler(ctx: Context<GetVerificationKey>, offset: u32) -> Result<VerificationKeyChunk> {
+    vk_chunk(&ctx.accounts.verification_key, offset)
+}
+
+/// Chunk of `account`'s encoded VK starting at `offset`.
+///
+/// # Errors
+/// * `LimitExceeded` if `offset` is at or past the end of the encoding
+pub fn vk_chunk(account: &VerificationKeyAccount, offset: u32) -> Result<VerificationKeyChunk> {
+    let encoded = VerificationKeyData::try_from(account)?.try_to_vec()?;
+    let start = offset as usize;
+    require!(start < encoded.len(), PrivacyError::LimitExceeded);
+    let end = (start + MAX_VK_CHUNK_LEN).min(encoded.len());
+
+    Ok(VerificationKeyChunk {
+        total_len: encoded.len() as u32,
+        offset,
+        data: encoded[start..end].to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::solana_program::program::MAX_RETURN_DATA;
+    use crate::crypto::{ZkPublicInputs, G1_GENERATOR};
+    use crate::instructions::set_verification_key::MAX_IC_POINTS;
+
+    fn vk_account(ic_len: usize) -> VerificationKeyAccount {
+        let data = vec![0u8; VerificationKeyAccount::space(MAX_IC_POINTS as u8)];
+        let mut account = VerificationKeyAccount::try_deserialize_unchecked(&mut data.as_slice()).unwrap();
+        account.initialize(Pubkey::new_unique(), 255);
+        account.set_vk(G1_GENERATOR, [1u8; 128], [2u8; 128], [3u8; 128], vec![G1_GENERATOR; ic_len]);
+        account
+    }
+
+    /// Read every chunk like a client decoding return data
+    fn read_vk(account: &VerificationKeyAccount) -> (usize, VerificationKeyData) {
+        let mut encoded = Vec::new();
+        let mut chunks = 0;
+        loop {
+            let data = vk_chunk(account, encoded.len() as u32).unwrap().try_to_vec().unwrap();
+            assert!(data.len() <= MAX_RETURN_DATA);
+            let chunk = VerificationKeyChunk::try_from_slice(&data).unwrap();
+            assert_eq!(chunk.offset as usize, encoded.len());
+            encoded.extend_from_slice(&chunk.data);
+            chunks += 1;
+            if encoded.len() == chunk.total_len as usize {
+                break;
+            }
+        }
+        (chunks, VerificationKeyData::try_from_slice(&encoded).unwrap())
+    }
+
+    #[test]
+    fn test_exported_vk_matches_account() {
+        let account = vk_account(ZkPublicInputs::COUNT + 1);
+        let (chunks, vk) = read_vk(&account);
+
+        assert_eq!(chunks, 1);
+        assert_eq!(vk.alpha_g1, account.vk_alpha_g1);
+        assert_eq!(vk.delta_g2, account.vk_delta_g2);
+        assert_eq!(vk.ic, account.vk_ic);
+    }
+
+    #[test]
+    fn test_large_vk_is_chunked() {
+        let account = vk_account(MAX_IC_POINTS);
+        let (chunks, vk) = read_vk(&account);
+
+        assert_eq!(chunks, 2);
+        assert_eq!(vk, VerificationKeyData::try_from(&account).unwrap());
+    }
+
+    #[test]
+    fn test_circuit_id_round_trips() {
+        let mut account = vk_account(ZkPublicInputs::COUNT + 1);
+        let (_, vk) = read_vk(&account);
+        assert_eq!(vk.circuit_id, [0u8; 32]);
+
+        // As stored by `set_verification_key`
+        let circuit_id = solana_program::keccak::hash(b"withdraw-v1.zkey").to_bytes();
+        let ix: crate::instruction::SetVerificationKey =
+            VerificationKeyData { circuit_id, ..vk }.into();
+        account.set_vk(ix.vk_alpha_g1, ix.vk_beta_g2, ix.vk_gamma_g2, ix.vk_delta_g2, ix.vk_ic);
+        account.set_circuit_id(ix.circuit_id);
+
+        let (_, vk) = read_vk(&account);
+        assert_eq!(vk.circuit_id, circuit_id);
+        assert_eq!(vk, VerificationKeyData::try_from(&account).unwrap());
+    }
+
+    #[test]
+    fn test_offset_past_end_rejected() {
+        let account = vk_account(ZkPublicInputs::COUNT + 1);
+        let total_len = vk_chunk(&account, 0).unwrap().total_len;
+        assert!(vk_chunk(&account, total_len).is_err());
+    }
+}
@@ -0,0 +1,43 @@
+//! Init Recipient Cooldown Instruction
+//!
+//! Creates the `RecipientCooldown` PDA that withdrawals to `recipient` must
+//! pass while the pool's recipient cooldown is enabled. Anyone may pay for
+//! it, typically the relayer or the recipient ahead of the first withdrawal.
+
+use anchor_lang::prelude::*;
+
+use crate::state::{PoolConfig, RecipientCooldown};
+
+#[derive(Accounts)]
+#[instruction(recipient: Pubkey)]
+pub struct InitRecipientCooldown<'info> {
+    #[account(
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = RecipientCooldown::LEN,
+        seeds = [b"recipient_cooldown", pool_config.key().as_ref(), recipient.as_ref()],
+        bump
+    )]
+    pub recipient_cooldown: Account<'info, RecipientCooldown>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<InitRecipientCooldown>, recipient: Pubkey) -> Result<()> {
+    let pool = ctx.accounts.pool_config.key();
+    ctx.accounts
+        .recipient_cooldown
+        .initialize(pool, recipient, ctx.bumps.recipient_cooldown);
+
+    msg!("Recipient cooldown account created for {}", recipient);
+    Ok(())
+}
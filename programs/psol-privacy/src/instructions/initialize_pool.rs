@@ -3,17 +3,26 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{Mint, Token, TokenAccount};
 
+use crate::crypto::{poseidon_self_test, TreeHash};
 use crate::error::PrivacyError;
-use crate::events::PoolInitialized;
-use crate::state::{MerkleTree, PoolConfig, VerificationKeyAccount};
+use crate::events::{PoolInitialized, PoolRegistered};
+use crate::state::{
+    debug_assert_fits, CommitmentMode, MerkleTree, PoolConfig, PoolRegistry, VerificationKeyAccount,
+};
 
 pub const MIN_TREE_DEPTH: u8 = 4;
 pub const MAX_TREE_DEPTH: u8 = 24;
 pub const MIN_ROOT_HISTORY: u16 = 200;
 pub const MAX_ROOT_HISTORY: u16 = 1000;
 
+/// Deployment cap on pool tree depth. Lower it to bound proof size and
+/// withdrawal CU for every pool this program creates; it cannot exceed
+/// `MAX_TREE_DEPTH`.
+pub const MAX_ALLOWED_DEPTH: u8 = MAX_TREE_DEPTH;
+const _: () = assert!(MAX_ALLOWED_DEPTH >= MIN_TREE_DEPTH && MAX_ALLOWED_DEPTH <= MAX_TREE_DEPTH);
+
 #[derive(Accounts)]
-#[instruction(tree_depth: u8, root_history_size: u16)]
+#[instruction(tree_depth: u8, root_history_size: u16, tree_hash: u8)]
 pub struct InitializePool<'info> {
     #[account(
         init,
@@ -52,8 +61,16 @@ pub struct InitializePool<'info> {
     )]
     pub vault: Box<Account<'info, TokenAccount>>,
 
-    /// CHECK: Token mint validated by Anchor's token::mint constraint
-    pub token_mint: UncheckedAccount<'info>,
+    /// Authority's registry; the new pool is registered when passed
+    #[account(
+        mut,
+        seeds = [b"pool_registry", authority.key().as_ref()],
+        bump = pool_registry.bump,
+        has_one = authority @ PrivacyError::Unauthorized,
+    )]
+    pub pool_registry: Option<Account<'info, PoolRegistry>>,
+
+    pub token_mint: Box<Account<'info, Mint>>,
 
     #[account(mut)]
     pub authority: Signer<'info>,
@@ -67,17 +84,26 @@ pub fn handler(
     ctx: Context<InitializePool>,
     tree_depth: u8,
     root_history_size: u16,
+    tree_hash: u8,
+    commitment_mode: u8,
+    zeros: Option<Vec<[u8; 32]>>,
 ) -> Result<()> {
-    require!(
-        tree_depth >= MIN_TREE_DEPTH && tree_depth <= MAX_TREE_DEPTH,
-        PrivacyError::InvalidTreeDepth
-    );
-    require!(
-        root_history_size >= MIN_ROOT_HISTORY && root_history_size <= MAX_ROOT_HISTORY,
-        PrivacyError::InvalidRootHistorySize
-    );
+    validate_tree_depth(tree_depth, MAX_ALLOWED_DEPTH)?;
+    validate_root_history_size(root_history_size)?;
+
+    let commitment_mode = CommitmentMode::from_u8(commitment_mode)
+        .ok_or(error!(PrivacyError::InvalidCommitmentMode))?;
+
+    // Fail pool creation rather than accept deposits into a tree whose
+    // hash disagrees with the circuit
+    if tree_hash == TreeHash::Poseidon as u8 {
+        poseidon_self_test()?;
+    }
 
     msg!("Initializing privacy pool...");
+    if crate::DEV_MODE {
+        msg!("⚠️ DEV MODE BUILD - value-moving instructions require set_dev_mode_acknowledged");
+    }
 
     // Store keys in local scope to minimize stack
     let pool_key = ctx.accounts.pool_config.key();
@@ -86,24 +112,119 @@ pub fn handler(
     let vk_key = ctx.accounts.verification_key.key();
     let auth_key = ctx.accounts.authority.key();
     let mint_key = ctx.accounts.token_mint.key();
+    let decimals = ctx.accounts.token_mint.decimals;
     let bump = ctx.bumps.pool_config;
 
     ctx.accounts.pool_config.initialize(
-        auth_key, mint_key, vault_key, tree_key, vk_key, tree_depth, bump,
+        auth_key, mint_key, decimals, vault_key, tree_key, vk_key, tree_depth, bump, commitment_mode,
     );
 
-    ctx.accounts.merkle_tree.initialize(pool_key, tree_depth, root_history_size)?;
+    ctx.accounts.merkle_tree.initialize_with_zeros(
+        pool_key,
+        tree_depth,
+        root_history_size,
+        tree_hash,
+        zeros,
+    )?;
     ctx.accounts.verification_key.initialize(pool_key, ctx.bumps.verification_key);
 
+    // Root history and zeros are fully allocated by `initialize`, so the
+    // tree is already at its final size
+    debug_assert_fits(&**ctx.accounts.merkle_tree, ctx.accounts.merkle_tree.to_account_info().data_len());
+    debug_assert_fits(
+        &**ctx.accounts.verification_key,
+        ctx.accounts.verification_key.to_account_info().data_len(),
+    );
+
+    let timestamp = Clock::get()?.unix_timestamp;
+    if let Some(registry) = ctx.accounts.pool_registry.as_mut() {
+        registry.register_pool(mint_key, pool_key)?;
+        emit!(PoolRegistered {
+            registry: registry.key(),
+            pool: pool_key,
+            token_mint: mint_key,
+            timestamp,
+        });
+    }
+
     emit!(PoolInitialized {
         pool: pool_key,
         authority: auth_key,
         token_mint: mint_key,
         tree_depth,
         root_history_size,
-        timestamp: Clock::get()?.unix_timestamp,
+        tree_hash,
+        timestamp,
     });
 
     msg!("Pool initialized: {}", pool_key);
     Ok(())
 }
+
+/// Require `MIN_TREE_DEPTH <= tree_depth <= max_allowed_depth`, with the
+/// cap itself bounded by `MAX_TREE_DEPTH`.
+pub fn validate_tree_depth(tree_depth: u8, max_allowed_depth: u8) -> Result<()> {
+    require!(
+        tree_depth >= MIN_TREE_DEPTH && tree_depth <= max_allowed_depth.min(MAX_TREE_DEPTH),
+        PrivacyError::InvalidTreeDepth
+    );
+    Ok(())
+}
+
+/// Require `MIN_ROOT_HISTORY <= root_history_size <= MAX_ROOT_HISTORY`.
+pub fn validate_root_history_size(root_history_size: u16) -> Result<()> {
+    require!(
+        (MIN_ROOT_HISTORY..=MAX_ROOT_HISTORY).contains(&root_history_size),
+        PrivacyError::InvalidRootHistorySize
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_depth_above_cap_rejected() {
+        assert!(validate_tree_depth(20, 20).is_ok());
+        assert!(validate_tree_depth(MIN_TREE_DEPTH, 20).is_ok());
+        let err = validate_tree_depth(21, 20).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::InvalidTreeDepth));
+        assert!(validate_tree_depth(MIN_TREE_DEPTH - 1, 20).is_err());
+
+        // A cap above the supported maximum does not widen it
+        assert!(validate_tree_depth(MAX_TREE_DEPTH + 1, u8::MAX).is_err());
+        assert!(validate_tree_depth(MAX_ALLOWED_DEPTH, MAX_ALLOWED_DEPTH).is_ok());
+    }
+
+    #[test]
+    fn test_token_decimals_recorded_from_mint() {
+        use anchor_lang::solana_program::program_pack::Pack;
+        use anchor_spl::token::spl_token::state::Mint as SplMint;
+
+        for decimals in [6u8, 9] {
+            let mut data = vec![0u8; SplMint::LEN];
+            SplMint::pack(SplMint { decimals, is_initialized: true, ..SplMint::default() }, &mut data)
+                .unwrap();
+            let (key, mut lamports) = (Pubkey::new_unique(), 1_000_000);
+            let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &anchor_spl::token::ID, false, 0);
+            let mint = Account::<Mint>::try_from(&info).unwrap();
+
+            let mut pool =
+                PoolConfig::try_deserialize_unchecked(&mut vec![0u8; PoolConfig::LEN].as_slice()).unwrap();
+            pool.initialize(
+                Pubkey::new_unique(),
+                mint.key(),
+                mint.decimals,
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                20,
+                255,
+                CommitmentMode::ClientSupplied,
+            );
+            assert_eq!(pool.token_decimals, decimals);
+            assert_eq!(pool.token_mint, key);
+        }
+    }
+}
@@ -0,0 +1,30 @@
+//! Is Nullifier Spent Instruction
+//!
+//! Read-only spend check for composing programs. Returns the result via
+//! return data, so a CPI caller only needs the nullifier PDA.
+
+use anchor_lang::prelude::*;
+
+use crate::state::{PoolConfig, SpentNullifier};
+
+#[derive(Accounts)]
+#[instruction(nullifier_hash: [u8; 32])]
+pub struct IsNullifierSpent<'info> {
+    #[account(
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    /// CHECK: May not exist; address pinned to the nullifier PDA and
+    /// contents checked by `SpentNullifier::exists`
+    #[account(
+        seeds = [b"nullifier", pool_config.key().as_ref(), nullifier_hash.as_ref()],
+        bump
+    )]
+    pub spent_nullifier: UncheckedAccount<'info>,
+}
+
+pub fn handler(ctx: Context<IsNullifierSpent>, _nullifier_hash: [u8; 32]) -> Result<bool> {
+    SpentNullifier::exists(&ctx.accounts.spent_nullifier)
+}
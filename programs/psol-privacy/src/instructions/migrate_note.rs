@@ -0,0 +1,299 @@
+//! Migrate Note Instruction
+//!
+//! Moves a note from one pool to another in one instruction: the note is
+//! spent (with proof) in the source pool and re-created as `commitment` in
+//! the target pool. Unlike a withdraw followed by a deposit, the owner never
+//! holds tokens in between, so no wallet links the two notes.
+//!
+//! Pools are one per mint, so the relayer is the counterparty: it receives
+//! the whole `amount` from the source vault and pays `amount - relayer_fee`
+//! of the target mint into the target vault. Both mints must share decimals
+//! (see `validate_swap_pools`). The token transfers show the amount, as for
+//! any withdrawal; the new note stays unlinkable to the spent one.
+//!
+//! The proof binds the target pool and `commitment` through
+//! `migration_relayer_binding`. The spent nullifier is recorded in the
+//! source pool, so a note migrates at most once.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::crypto::{migration_relayer_binding, ZkPublicInputs};
+use crate::error::PrivacyError;
+use crate::events::{NoteMigrated, WithdrawEvent};
+use crate::instructions::deposit::{self, *};
+use crate::instructions::shielded_swap::validate_swap_pools;
+use crate::instructions::withdraw::{self, *};
+use crate::state::merkle_tree::debug_assert_not_nullifier;
+use crate::state::DepositNote;
+
+#[derive(Accounts)]
+#[instruction(
+    proof_data: Vec<u8>,
+    merkle_root: [u8; 32],
+    nullifier_hash: [u8; 32],
+    recipient: Pubkey,
+    amount: u64,
+    relayer: Pubkey,
+    relayer_fee: u64,
+)]
+pub struct MigrateNote<'info> {
+    /// Spend leg (source pool); the relayer is also the recipient
+    #[account(constraint = recipient == relayer @ PrivacyError::RecipientMismatch)]
+    pub withdraw: Withdraw<'info>,
+
+    /// Insert leg (target pool), funded by the relayer
+    #[account(
+        constraint = deposit.depositor.key() == relayer @ PrivacyError::Unauthorized,
+    )]
+    pub deposit: Deposit<'info>,
+
+    #[account(address = deposit.pool_config.token_mint @ PrivacyError::InvalidMint)]
+    pub deposit_mint: Account<'info, Mint>,
+
+    #[account(address = withdraw.pool_config.token_mint @ PrivacyError::InvalidMint)]
+    pub withdraw_mint: Account<'info, Mint>,
+}
+
+/// Returns the leaf index of `commitment` in the target pool.
+#[allow(clippy::too_many_arguments)]
+pub fn handler(
+    ctx: Context<MigrateNote>,
+    proof_data: Vec<u8>,
+    merkle_root: [u8; 32],
+    nullifier_hash: [u8; 32],
+    recipient: Pubkey,
+    amount: u64,
+    relayer: Pubkey,
+    relayer_fee: u64,
+    commitment: [u8; 32],
+) -> Result<u32> {
+    let target_pool = ctx.accounts.deposit.pool_config.key();
+    validate_swap_pools(
+        &target_pool,
+        &ctx.accounts.withdraw.pool_config.key(),
+        ctx.accounts.deposit_mint.decimals,
+        ctx.accounts.withdraw_mint.decimals,
+    )?;
+    let leaf_index = process_migrate_note(
+        &mut ctx.accounts.withdraw,
+        &ctx.bumps.withdraw,
+        &mut ctx.accounts.deposit,
+        proof_data,
+        merkle_root,
+        nullifier_hash,
+        recipient,
+        amount,
+        relayer,
+        relayer_fee,
+        commitment,
+    )?;
+
+    msg!("Note migrated");
+    Ok(leaf_index)
+}
+
+/// Spend the note in the source pool, then insert `commitment` into the
+/// target pool. Returns the new note's leaf index.
+///
+/// Any error aborts the instruction, so the runtime discards whatever the
+/// spend already wrote when the insert fails.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn process_migrate_note<'info>(
+    withdraw: &mut Withdraw<'info>,
+    withdraw_bumps: &WithdrawBumps,
+    deposit: &mut Deposit<'info>,
+    proof_data: Vec<u8>,
+    merkle_root: [u8; 32],
+    nullifier_hash: [u8; 32],
+    recipient: Pubkey,
+    amount: u64,
+    relayer: Pubkey,
+    relayer_fee: u64,
+    commitment: [u8; 32],
+) -> Result<u32> {
+    let target_pool = deposit.pool_config.key();
+    let migrated_amount = migrated_amount(amount, relayer_fee)?;
+    let relayer_binding = migration_relayer_binding(&relayer, &target_pool, &commitment);
+
+    // Spend first: proof verification fails before any deposit moves
+    let accounts = withdraw;
+    let (clock, public_inputs) = withdraw::verify_withdrawal_inputs(
+        accounts,
+        withdraw_bumps,
+        &proof_data,
+        ZkPublicInputs::new(merkle_root, nullifier_hash, recipient, amount, relayer_binding, relayer_fee),
+        &relayer,
+    )?;
+
+    let to = accounts.relayer_token_account.to_account_info();
+    withdraw::transfer_from_vault(accounts, to, amount)?;
+    accounts.pool_config.record_withdrawal(amount)?;
+    accounts.vault.reload()?;
+    let pool_config = &accounts.pool_config;
+    check_tvl_invariant(pool_config.key(), pool_config, accounts.vault.amount);
+
+    emit!(WithdrawEvent {
+        relayer,
+        ..withdraw::withdraw_event(
+            pool_config.key(),
+            pool_config.nullifier_generation,
+            accounts.spent_nullifier.key(),
+            &public_inputs,
+            &Rent::get()?,
+            clock.unix_timestamp,
+        )?
+    });
+    let source_pool = pool_config.key();
+
+    debug_assert_not_nullifier(&commitment, &nullifier_hash);
+    let leaf_index = deposit::process_deposit(
+        deposit,
+        migrated_amount,
+        DepositNote::Commitment(commitment),
+        Vec::new(),
+    )?;
+
+    emit!(NoteMigrated {
+        source_pool,
+        target_pool,
+        nullifier_hash,
+        commitment,
+        leaf_index,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(leaf_index)
+}
+
+/// Value of the migrated note: `amount` less the relayer's fee.
+///
+/// # Errors
+/// * `RelayerFeeExceedsAmount` per `withdraw::validate_relayer_fee`
+/// * `InvalidAmount` if nothing would be migrated
+pub fn migrated_amount(amount: u64, relayer_fee: u64) -> Result<u64> {
+    withdraw::validate_relayer_fee(amount, relayer_fee)?;
+    let migrated = amount
+        .checked_sub(relayer_fee)
+        .ok_or(error!(PrivacyError::ArithmeticOverflow))?;
+    require!(migrated > 0, PrivacyError::InvalidAmount);
+    Ok(migrated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::poseidon::TreeHash;
+    use crate::instructions::withdraw::test_fixtures::*;
+    use crate::state::merkle_tree::test_fixtures::new_tree;
+    use crate::state::pool_config::test_fixtures::new_pool;
+    use crate::state::{PoolConfig, SpentNullifier};
+
+    const AMOUNT: u64 = 100_000;
+    const FEE: u64 = 1_000;
+
+    /// A source pool holding one note, migrated by a relayer who is also
+    /// the withdrawal's recipient and funds the target pool's deposit.
+    struct Migration {
+        source: WithdrawFixture,
+        relayer: Pubkey,
+        nullifier_hash: [u8; 32],
+    }
+
+    impl Migration {
+        fn new() -> Self {
+            let (relayer, nullifier_hash) = (Pubkey::new_unique(), [9u8; 32]);
+            let source = WithdrawFixture::new(&relayer, &relayer, &nullifier_hash);
+            Self { source, relayer, nullifier_hash }
+        }
+
+        /// An empty target pool, funded by the relayer.
+        fn target_pool(&self) -> Deposit<'static> {
+            let mut pool = new_pool();
+            pool.set_vk_configured(true);
+            let pool_config = program_account(&pool, PoolConfig::LEN);
+            Deposit {
+                merkle_tree: tree_account(&new_tree(8, TreeHash::Poseidon)),
+                root_history: None,
+                vault: token_account(pool.token_mint, pool_config.key(), 0),
+                depositor_token_account: token_account(pool.token_mint, self.relayer, AMOUNT),
+                depositor: Signer::try_from(account_info(self.relayer, System::id(), Vec::new(), true, false))
+                    .unwrap(),
+                token_program: token_program(),
+                pool_config,
+            }
+        }
+
+        /// Proof migrating the note into `target` as `commitment`.
+        fn proof(&self, target: &Deposit, commitment: [u8; 32]) -> Vec<u8> {
+            self.source.proof(ZkPublicInputs::new(
+                self.source.merkle_root,
+                self.nullifier_hash,
+                self.relayer,
+                AMOUNT,
+                migration_relayer_binding(&self.relayer, &target.pool_config.key(), &commitment),
+                FEE,
+            ))
+        }
+
+        fn run(&mut self, target: &mut Deposit<'static>, proof_data: Vec<u8>, commitment: [u8; 32]) -> Result<u32> {
+            process_migrate_note(
+                &mut self.source.withdraw,
+                &self.source.bumps,
+                target,
+                proof_data,
+                self.source.merkle_root,
+                self.nullifier_hash,
+                self.relayer,
+                AMOUNT,
+                self.relayer,
+                FEE,
+                commitment,
+            )
+        }
+    }
+
+    #[test]
+    fn test_migrated_amount() {
+        assert_eq!(migrated_amount(1_000, 0).unwrap(), 1_000);
+        assert_eq!(migrated_amount(1_000, 50).unwrap(), 950);
+        assert!(migrated_amount(1_000, 101).is_err());
+        let err = migrated_amount(0, 0).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::InvalidAmount));
+    }
+
+    #[test]
+    fn test_note_moves_between_pools() {
+        let mut migration = Migration::new();
+        let mut target = migration.target_pool();
+        let proof = migration.proof(&target, [5u8; 32]);
+        assert_eq!(migration.run(&mut target, proof, [5u8; 32]).unwrap(), 0);
+
+        // The relayer takes the source note and funds the new one, less its fee
+        let source = &mut migration.source.withdraw;
+        assert!(SpentNullifier::exists(&source.spent_nullifier).unwrap());
+        assert_eq!(token_account_balance(&mut source.relayer_token_account), AMOUNT);
+        assert_eq!(token_account_balance(&mut source.vault), VAULT_BALANCE - AMOUNT);
+        assert_eq!(target.merkle_tree.next_leaf_index, 1);
+        assert_eq!(token_account_balance(&mut target.vault), AMOUNT - FEE);
+    }
+
+    #[test]
+    fn test_double_migration_rejected() {
+        let mut migration = Migration::new();
+        let mut target = migration.target_pool();
+        let proof = migration.proof(&target, [5u8; 32]);
+        migration.run(&mut target, proof, [5u8; 32]).unwrap();
+
+        // The second fails whatever target pool or commitment it names:
+        // the nullifier is recorded in the source pool
+        let mut other_target = migration.target_pool();
+        let proof = migration.proof(&other_target, [6u8; 32]);
+        let err = migration.run(&mut other_target, proof, [6u8; 32]).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::NullifierAlreadySpent));
+
+        assert_eq!(token_account_balance(&mut migration.source.withdraw.vault), VAULT_BALANCE - AMOUNT);
+        assert_eq!(other_target.merkle_tree.next_leaf_index, 0);
+        assert_eq!(token_account_balance(&mut other_target.vault), 0);
+    }
+}
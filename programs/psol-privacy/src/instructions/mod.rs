@@ -1,15 +1,61 @@
 //! Instruction handlers for pSol Privacy Pool - Phase 4
 
+pub mod accumulate_vk_x;
 pub mod admin;
+pub mod deferred_deposit;
 pub mod deposit;
+#[cfg(feature = "dev-mode")]
+pub mod dev_seed_commitments;
+pub mod get_limits;
+pub mod get_tree_state;
+pub mod get_verification_key;
+pub mod init_recipient_cooldown;
 pub mod initialize_pool;
+pub mod is_nullifier_spent;
+pub mod migrate_note;
+pub mod next_verification_key;
+pub mod preview_withdrawal;
 pub mod private_transfer;
 pub mod set_verification_key;
+pub mod shielded_swap;
+pub mod verify_commitment_inclusion;
 pub mod withdraw;
+pub mod withdraw_and_swap;
+pub mod withdraw_batch;
+pub mod withdraw_confidential;
+pub mod withdraw_fee_in_lamports;
+pub mod withdraw_fee_in_mint;
+pub mod withdraw_split_fee;
+pub mod withdraw_to_escrow;
+pub mod withdraw_with_redeposit;
+pub mod withdraw_with_vk_x;
 
+pub use accumulate_vk_x::*;
 pub use admin::*;
+pub use deferred_deposit::*;
 pub use deposit::*;
+#[cfg(feature = "dev-mode")]
+pub use dev_seed_commitments::*;
+pub use get_limits::*;
+pub use get_tree_state::*;
+pub use get_verification_key::*;
+pub use init_recipient_cooldown::*;
 pub use initialize_pool::*;
+pub use is_nullifier_spent::*;
+pub use migrate_note::*;
+pub use next_verification_key::*;
+pub use preview_withdrawal::*;
 pub use private_transfer::*;
 pub use set_verification_key::*;
+pub use shielded_swap::*;
+pub use verify_commitment_inclusion::*;
 pub use withdraw::*;
+pub use withdraw_and_swap::*;
+pub use withdraw_batch::*;
+pub use withdraw_confidential::*;
+pub use withdraw_fee_in_lamports::*;
+pub use withdraw_fee_in_mint::*;
+pub use withdraw_split_fee::*;
+pub use withdraw_to_escrow::*;
+pub use withdraw_with_redeposit::*;
+pub use withdraw_with_vk_x::*;
@@ -0,0 +1,196 @@
+//! Secondary Verification Key Instructions - Circuit Migration
+//!
+//! During a circuit upgrade the pool accepts proofs against either the
+//! primary VK or the secondary `verification_key_next` VK until
+//! `PoolConfig.dual_vk_window_until`.
+//!
+//! Migration flow:
+//! 1. `init_next_verification_key` - create the secondary VK account
+//! 2. `set_next_verification_key` - store the new circuit's VK, open the window
+//! 3. `promote_next_verification_key` - swap primary and secondary, so the
+//!    new VK becomes primary and the old one is accepted until the window closes
+
+use anchor_lang::prelude::*;
+
+use crate::error::PrivacyError;
+use crate::events::{NextVerificationKeySet, VerificationKeyPromoted};
+use crate::instructions::admin::authority_multisig::require_admin_signers;
+use crate::state::{AuthorityMultisig, PoolConfig, VerificationKeyAccount, VerificationKeyData};
+
+#[derive(Accounts)]
+pub struct InitNextVerificationKey<'info> {
+    #[account(
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = VerificationKeyAccount::space(VerificationKeyAccount::DEFAULT_MAX_IC_POINTS),
+        seeds = [b"verification_key_next", pool_config.key().as_ref()],
+        bump
+    )]
+    pub verification_key_next: Account<'info, VerificationKeyAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Required when `pool_config.authority_is_multisig`
+    pub authority_multisig: Option<Account<'info, AuthorityMultisig>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn init_handler(ctx: Context<InitNextVerificationKey>) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let pool = ctx.accounts.pool_config.key();
+    ctx.accounts
+        .verification_key_next
+        .initialize(pool, ctx.bumps.verification_key_next);
+
+    msg!("Secondary verification key account created");
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetNextVerificationKey<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"verification_key_next", pool_config.key().as_ref()],
+        bump = verification_key_next.bump,
+        constraint = verification_key_next.pool == pool_config.key() @ PrivacyError::Unauthorized,
+    )]
+    pub verification_key_next: Account<'info, VerificationKeyAccount>,
+
+    pub authority: Signer<'info>,
+
+    /// Required when `pool_config.authority_is_multisig`
+    pub authority_multisig: Option<Account<'info, AuthorityMultisig>>,
+}
+
+pub fn set_handler(
+    ctx: Context<SetNextVerificationKey>,
+    vk_data: VerificationKeyData,
+    dual_vk_window_until: i64,
+) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let pool_config = &mut ctx.accounts.pool_config;
+
+    // A locked VK means the circuit can never change, including via migration
+    pool_config.require_vk_unlocked()?;
+    pool_config.require_vk_configured()?;
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(dual_vk_window_until > now, PrivacyError::InvalidTimestamp);
+
+    vk_data.validate()?;
+    VerificationKeyAccount::require_ic_fits(
+        vk_data.ic.len(),
+        ctx.accounts.verification_key_next.to_account_info().data_len(),
+        ctx.accounts.verification_key_next.compressed_ic,
+    )?;
+    let ic_length = vk_data.ic.len() as u8;
+
+    ctx.accounts.verification_key_next.set_vk(
+        vk_data.alpha_g1,
+        vk_data.beta_g2,
+        vk_data.gamma_g2,
+        vk_data.delta_g2,
+        vk_data.ic,
+    );
+    ctx.accounts.verification_key_next.set_circuit_id(vk_data.circuit_id);
+    pool_config.set_dual_vk_window_until(dual_vk_window_until);
+
+    emit!(NextVerificationKeySet {
+        pool: pool_config.key(),
+        authority: ctx.accounts.authority.key(),
+        ic_length,
+        dual_vk_window_until,
+        timestamp: now,
+    });
+
+    msg!("Secondary verification key set, window until {}", dual_vk_window_until);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct PromoteNextVerificationKey<'info> {
+    #[account(
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"verification_key", pool_config.key().as_ref()],
+        bump = verification_key.bump,
+        constraint = verification_key.pool == pool_config.key() @ PrivacyError::Unauthorized,
+    )]
+    pub verification_key: Account<'info, VerificationKeyAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"verification_key_next", pool_config.key().as_ref()],
+        bump = verification_key_next.bump,
+        constraint = verification_key_next.pool == pool_config.key() @ PrivacyError::Unauthorized,
+        constraint = verification_key_next.is_initialized @ PrivacyError::VerificationKeyNotSet,
+    )]
+    pub verification_key_next: Account<'info, VerificationKeyAccount>,
+
+    pub authority: Signer<'info>,
+
+    /// Required when `pool_config.authority_is_multisig`
+    pub authority_multisig: Option<Account<'info, AuthorityMultisig>>,
+}
+
+pub fn promote_handler(ctx: Context<PromoteNextVerificationKey>) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let pool_config = &ctx.accounts.pool_config;
+    pool_config.require_vk_unlocked()?;
+
+    // Promoting outside the window would immediately strand old-circuit notes
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        pool_config.dual_vk_window_open(now),
+        PrivacyError::InvalidTimestamp
+    );
+
+    ctx.accounts
+        .verification_key
+        .swap_keys(&mut ctx.accounts.verification_key_next)?;
+
+    emit!(VerificationKeyPromoted {
+        pool: pool_config.key(),
+        authority: ctx.accounts.authority.key(),
+        dual_vk_window_until: pool_config.dual_vk_window_until,
+        timestamp: now,
+    });
+
+    msg!("Secondary verification key promoted to primary");
+    Ok(())
+}
@@ -0,0 +1,96 @@
+//! Preview Withdrawal Instruction
+//!
+//! Read-only fee breakdown for wallets: what the recipient would receive
+//! for a given `amount` and `relayer_fee`, using the same checks and fee
+//! math as `withdraw`. Returns the result via return data.
+
+use anchor_lang::prelude::*;
+
+use crate::error::PrivacyError;
+use crate::instructions::withdraw::{validate_relayer_fee, MIN_WITHDRAWAL_AMOUNT};
+use crate::state::PoolConfig;
+
+#[derive(Accounts)]
+pub struct PreviewWithdrawal<'info> {
+    #[account(
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+}
+
+/// Breakdown of a withdrawal of `net_amount + total_fees`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WithdrawalPreview {
+    /// Paid to the recipient
+    pub net_amount: u64,
+    /// Paid to the relayer
+    pub relayer_fee: u64,
+    /// Retained by the protocol (pools do not charge one yet)
+    pub protocol_fee: u64,
+    /// `relayer_fee + protocol_fee`
+    pub total_fees: u64,
+}
+
+pub fn handler(
+    _ctx: Context<PreviewWithdrawal>,
+    amount: u64,
+    relayer_fee: u64,
+) -> Result<WithdrawalPreview> {
+    preview_withdrawal(amount, relayer_fee)
+}
+
+/// Fee breakdown for withdrawing `amount` with `relayer_fee`.
+///
+/// # Errors
+/// Same amount/fee errors `withdraw` would raise before verifying the proof
+pub fn preview_withdrawal(amount: u64, relayer_fee: u64) -> Result<WithdrawalPreview> {
+    require!(amount >= MIN_WITHDRAWAL_AMOUNT, PrivacyError::InvalidAmount);
+    validate_relayer_fee(amount, relayer_fee)?;
+
+    let protocol_fee = 0;
+    let total_fees = relayer_fee
+        .checked_add(protocol_fee)
+        .ok_or(error!(PrivacyError::ArithmeticOverflow))?;
+    let net_amount = amount
+        .checked_sub(total_fees)
+        .ok_or(error!(PrivacyError::ArithmeticOverflow))?;
+
+    Ok(WithdrawalPreview {
+        net_amount,
+        relayer_fee,
+        protocol_fee,
+        total_fees,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::withdraw::withdrawal_payouts;
+
+    #[test]
+    fn test_preview_matches_payouts() {
+        let recipient = Pubkey::new_unique();
+        let relayer = Pubkey::new_unique();
+
+        for (amount, relayer_fee) in [(1_000_000, 0), (1_000_000, 25_000), (1_000_000, 100_000)] {
+            let preview = preview_withdrawal(amount, relayer_fee).unwrap();
+            let payouts = withdrawal_payouts(recipient, relayer, amount, relayer_fee).unwrap();
+
+            let paid_to = |account: Pubkey| {
+                payouts.iter().filter(|(to, _)| *to == account).map(|(_, v)| v).sum::<u64>()
+            };
+            assert_eq!(paid_to(recipient), preview.net_amount);
+            assert_eq!(paid_to(relayer), preview.relayer_fee);
+            assert_eq!(preview.net_amount + preview.total_fees, amount);
+        }
+    }
+
+    #[test]
+    fn test_preview_rejects_what_withdraw_rejects() {
+        assert!(preview_withdrawal(MIN_WITHDRAWAL_AMOUNT - 1, 0).is_err());
+        // Above the 10% relayer fee cap
+        assert!(preview_withdrawal(1_000_000, 100_001).is_err());
+    }
+}
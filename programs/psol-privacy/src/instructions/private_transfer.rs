@@ -8,6 +8,7 @@
 
 use anchor_lang::prelude::*;
 
+use crate::crypto::validate_proof_length;
 use crate::error::PrivacyError;
 use crate::state::{MerkleTree, PoolConfig};
 
@@ -35,9 +36,16 @@ pub struct PrivateTransfer<'info> {
 }
 
 #[deprecated(note = "Private transfers are not implemented in pSOL v1. Use deposit/withdraw only.")]
-pub fn handler(ctx: Context<PrivateTransfer>) -> Result<()> {
-    let pool_config = &ctx.accounts.pool_config;
+pub fn handler(ctx: Context<PrivateTransfer>, proof_data: &[u8]) -> Result<()> {
+    check_private_transfer(&ctx.accounts.pool_config, proof_data)
+}
+
+/// Guards a private transfer must pass, pause first like every other
+/// value-moving instruction. Always ends in `NotImplemented` in v1.
+pub fn check_private_transfer(pool_config: &PoolConfig, proof_data: &[u8]) -> Result<()> {
     pool_config.require_not_paused()?;
+    validate_proof_length(proof_data)?;
+    pool_config.require_vk_configured()?;
 
     msg!("ERROR: private_transfer is NOT available in pSOL v1.");
     msg!("This is a non-functional placeholder for a future join-split private transfer.");
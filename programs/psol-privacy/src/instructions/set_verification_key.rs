@@ -2,13 +2,19 @@
 
 use anchor_lang::prelude::*;
 
-use crate::crypto::{is_g1_identity, is_g2_identity, public_inputs::ZkPublicInputs, validate_g1_point, validate_g2_point};
+use crate::crypto::{MAX_PUBLIC_INPUTS, MIN_PUBLIC_INPUTS};
 use crate::error::PrivacyError;
-use crate::events::{VerificationKeyLocked, VerificationKeySet};
-use crate::state::{PoolConfig, VerificationKeyAccount};
+use crate::events::{
+    VerificationKeyIcCompressionSet, VerificationKeyIcUpdated, VerificationKeyLocked,
+    VerificationKeySet,
+};
+use crate::instructions::admin::authority_multisig::require_admin_signers;
+use crate::state::{
+    debug_assert_fits, AuthorityMultisig, PoolConfig, VerificationKeyAccount, VerificationKeyData,
+};
 
-pub const MAX_IC_POINTS: usize = 16;
-pub const MIN_IC_POINTS: usize = 2;
+pub const MAX_IC_POINTS: usize = MAX_PUBLIC_INPUTS + 1;
+pub const MIN_IC_POINTS: usize = MIN_PUBLIC_INPUTS + 1;
 
 #[derive(Accounts)]
 pub struct SetVerificationKey<'info> {
@@ -16,7 +22,6 @@ pub struct SetVerificationKey<'info> {
         mut,
         seeds = [b"pool", pool_config.token_mint.as_ref()],
         bump = pool_config.bump,
-        has_one = authority @ PrivacyError::Unauthorized,
     )]
     pub pool_config: Account<'info, PoolConfig>,
 
@@ -29,6 +34,9 @@ pub struct SetVerificationKey<'info> {
     pub verification_key: Account<'info, VerificationKeyAccount>,
 
     pub authority: Signer<'info>,
+
+    /// Required when `pool_config.authority_is_multisig`
+    pub authority_multisig: Option<Account<'info, AuthorityMultisig>>,
 }
 
 pub fn handler(
@@ -38,7 +46,14 @@ pub fn handler(
     vk_gamma_g2: [u8; 128],
     vk_delta_g2: [u8; 128],
     vk_ic: Vec<[u8; 64]>,
+    circuit_id: [u8; 32],
 ) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
     let pool_config = &mut ctx.accounts.pool_config;
     let verification_key = &mut ctx.accounts.verification_key;
 
@@ -49,73 +64,176 @@ pub fn handler(
     //
     // We enforce that VK cannot be changed once there have been any deposits.
     // (Assumes PoolConfig tracks total_deposits.)
+    require_vk_mutable(pool_config)?;
+
+    // Basic structural validation of VK points
+    let vk_data = VerificationKeyData {
+        alpha_g1: vk_alpha_g1,
+        beta_g2: vk_beta_g2,
+        gamma_g2: vk_gamma_g2,
+        delta_g2: vk_delta_g2,
+        ic: vk_ic,
+        circuit_id,
+    };
+    vk_data.validate()?;
+    let ic_len = vk_data.ic.len();
+    VerificationKeyAccount::require_ic_fits(
+        ic_len,
+        verification_key.to_account_info().data_len(),
+        verification_key.compressed_ic,
+    )?;
+
+    // Store VK on-chain
+    verification_key.set_vk(
+        vk_data.alpha_g1,
+        vk_data.beta_g2,
+        vk_data.gamma_g2,
+        vk_data.delta_g2,
+        vk_data.ic,
+    );
+    verification_key.set_circuit_id(vk_data.circuit_id);
+    debug_assert_fits(&**verification_key, verification_key.to_account_info().data_len());
+    pool_config.set_vk_configured(true);
+
+    emit!(VerificationKeySet {
+        pool: pool_config.key(),
+        authority: ctx.accounts.authority.key(),
+        ic_length: ic_len as u8,
+        circuit_id,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Verification key set successfully");
+    Ok(())
+}
+
+/// Replace only the IC points of the configured VK, for circuit tweaks
+/// that leave α, β, γ and δ unchanged. Same guards as `handler`.
+pub fn update_vk_ic_handler(ctx: Context<SetVerificationKey>, vk_ic: Vec<[u8; 64]>) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let pool_config = &ctx.accounts.pool_config;
+    let verification_key = &mut ctx.accounts.verification_key;
+    let data_len = verification_key.to_account_info().data_len();
+
+    let ic_len = vk_ic.len();
+    update_vk_ic(pool_config, verification_key, vk_ic, data_len)?;
+
+    emit!(VerificationKeyIcUpdated {
+        pool: pool_config.key(),
+        authority: ctx.accounts.authority.key(),
+        ic_length: ic_len as u8,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Verification key IC updated ({} points)", ic_len);
+    Ok(())
+}
+
+/// The VK may only change before the first deposit and while unlocked.
+///
+/// # Errors
+/// * `VerificationKeyLocked` if the pool has deposits or the VK is locked
+pub fn require_vk_mutable(pool_config: &PoolConfig) -> Result<()> {
     require!(
         pool_config.total_deposits == 0,
         PrivacyError::VerificationKeyLocked
     );
 
     // Still require the VK to be unlocked (not permanently locked)
-    pool_config.require_vk_unlocked()?;
+    pool_config.require_vk_unlocked()
+}
 
-    let ic_len = vk_ic.len();
-    require!(ic_len >= MIN_IC_POINTS, PrivacyError::InvalidPublicInputs);
-    require!(ic_len <= MAX_IC_POINTS, PrivacyError::InputTooLarge);
-    require!(
-        ic_len == ZkPublicInputs::COUNT + 1,
-        PrivacyError::InvalidPublicInputs
-    );
+/// Validate `ic` against the stored α, β, γ, δ and store it.
+///
+/// # Errors
+/// * `VerificationKeyLocked` per `require_vk_mutable`
+/// * `VerificationKeyNotSet` if no full VK has been set yet
+/// * Any `VerificationKeyData::validate` or `require_ic_fits` error
+pub fn update_vk_ic(
+    pool_config: &PoolConfig,
+    verification_key: &mut VerificationKeyAccount,
+    ic: Vec<[u8; 64]>,
+    data_len: usize,
+) -> Result<()> {
+    require_vk_mutable(pool_config)?;
+    pool_config.require_vk_configured()?;
+    require!(verification_key.is_initialized, PrivacyError::VerificationKeyNotSet);
 
-    // Basic structural validation of VK points
+    let vk_data = VerificationKeyData {
+        ic,
+        ..VerificationKeyData::try_from(&*verification_key)?
+    };
+    vk_data.validate()?;
+    VerificationKeyAccount::require_ic_fits(vk_data.ic.len(), data_len, verification_key.compressed_ic)?;
 
-    require!(
-        !is_g1_identity(&vk_alpha_g1),
-        PrivacyError::VerificationKeyNotSet
-    );
-    validate_g1_point(&vk_alpha_g1).map_err(|_| error!(PrivacyError::InvalidProof))?;
+    verification_key.set_ic(vk_data.ic);
+    debug_assert_fits(&*verification_key, data_len);
+    Ok(())
+}
 
-    require!(
-        !is_g2_identity(&vk_beta_g2),
-        PrivacyError::VerificationKeyNotSet
-    );
-    validate_g2_point(&vk_beta_g2).map_err(|_| error!(PrivacyError::InvalidProof))?;
+#[derive(Accounts)]
+#[instruction(compressed_ic: bool)]
+pub struct SetVkIcCompression<'info> {
+    #[account(
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
 
-    require!(
-        !is_g2_identity(&vk_gamma_g2),
-        PrivacyError::VerificationKeyNotSet
-    );
-    validate_g2_point(&vk_gamma_g2).map_err(|_| error!(PrivacyError::InvalidProof))?;
+    /// Resized to the default IC capacity in the new storage mode
+    #[account(
+        mut,
+        seeds = [b"verification_key", pool_config.key().as_ref()],
+        bump = verification_key.bump,
+        constraint = verification_key.pool == pool_config.key() @ PrivacyError::Unauthorized,
+        realloc = VerificationKeyAccount::space_with(
+            VerificationKeyAccount::DEFAULT_MAX_IC_POINTS,
+            compressed_ic,
+        ),
+        realloc::payer = authority,
+        realloc::zero = false,
+    )]
+    pub verification_key: Account<'info, VerificationKeyAccount>,
 
-    require!(
-        !is_g2_identity(&vk_delta_g2),
-        PrivacyError::VerificationKeyNotSet
-    );
-    validate_g2_point(&vk_delta_g2).map_err(|_| error!(PrivacyError::InvalidProof))?;
+    /// Pays for growth, receives the rent freed by compression
+    #[account(mut)]
+    pub authority: Signer<'info>,
 
-    for (i, ic_point) in vk_ic.iter().enumerate() {
-        validate_g1_point(ic_point).map_err(|_| {
-            msg!("IC[{}] failed validation", i);
-            error!(PrivacyError::InvalidProof)
-        })?;
-    }
+    /// Required when `pool_config.authority_is_multisig`
+    pub authority_multisig: Option<Account<'info, AuthorityMultisig>>,
 
-    // Store VK on-chain
-    verification_key.set_vk(
-        vk_alpha_g1,
-        vk_beta_g2,
-        vk_gamma_g2,
-        vk_delta_g2,
-        vk_ic.clone(),
-    );
-    pool_config.set_vk_configured(true);
+    pub system_program: Program<'info, System>,
+}
 
-    emit!(VerificationKeySet {
-        pool: pool_config.key(),
+/// Store the primary VK's IC points compressed (32 bytes each, halving IC
+/// storage) or uncompressed. Allowed on a locked key: the points are only
+/// re-encoded, and must decompress to exactly what was stored.
+pub fn set_vk_ic_compression_handler(ctx: Context<SetVkIcCompression>, compressed_ic: bool) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
+    let verification_key = &mut ctx.accounts.verification_key;
+    verification_key.set_ic_compression(compressed_ic)?;
+    let data_len = verification_key.to_account_info().data_len();
+    debug_assert_fits(&**verification_key, data_len);
+
+    emit!(VerificationKeyIcCompressionSet {
+        pool: ctx.accounts.pool_config.key(),
         authority: ctx.accounts.authority.key(),
-        ic_length: ic_len as u8,
+        compressed_ic,
+        data_len: data_len as u32,
         timestamp: Clock::get()?.unix_timestamp,
     });
 
-    msg!("Verification key set successfully");
+    msg!("Verification key IC compression: {}", compressed_ic);
     Ok(())
 }
 
@@ -125,14 +243,22 @@ pub struct LockVerificationKey<'info> {
         mut,
         seeds = [b"pool", pool_config.token_mint.as_ref()],
         bump = pool_config.bump,
-        has_one = authority @ PrivacyError::Unauthorized,
     )]
     pub pool_config: Account<'info, PoolConfig>,
 
     pub authority: Signer<'info>,
+
+    /// Required when `pool_config.authority_is_multisig`
+    pub authority_multisig: Option<Account<'info, AuthorityMultisig>>,
 }
 
 pub fn lock_vk_handler(ctx: Context<LockVerificationKey>) -> Result<()> {
+    require_admin_signers(
+        &ctx.accounts.pool_config,
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_multisig,
+        ctx.remaining_accounts,
+    )?;
     let pool_config = &mut ctx.accounts.pool_config;
 
     pool_config.require_vk_configured()?;
@@ -152,3 +278,60 @@ pub fn lock_vk_handler(ctx: Context<LockVerificationKey>) -> Result<()> {
     msg!("VERIFICATION KEY LOCKED PERMANENTLY");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{ZkPublicInputs, G1_GENERATOR};
+    use crate::state::pool_config::test_fixtures::new_pool;
+
+    fn setup() -> (PoolConfig, VerificationKeyAccount, usize) {
+        let mut pool = new_pool();
+        pool.set_vk_configured(true);
+
+        let data_len = VerificationKeyAccount::space(VerificationKeyAccount::DEFAULT_MAX_IC_POINTS);
+        let mut vk =
+            VerificationKeyAccount::try_deserialize_unchecked(&mut vec![0u8; data_len].as_slice()).unwrap();
+        vk.initialize(Pubkey::new_unique(), 255);
+        vk.set_vk(
+            G1_GENERATOR,
+            [1u8; 128],
+            [2u8; 128],
+            [3u8; 128],
+            vec![G1_GENERATOR; ZkPublicInputs::COUNT + 1],
+        );
+        (pool, vk, data_len)
+    }
+
+    #[test]
+    fn test_ic_update_before_deposits() {
+        let (pool, mut vk, data_len) = setup();
+        let mut ic = vec![G1_GENERATOR; ZkPublicInputs::COUNT + 1];
+        ic[0] = [0u8; 64]; // identity is a valid IC point
+
+        update_vk_ic(&pool, &mut vk, ic.clone(), data_len).unwrap();
+        assert_eq!(vk.vk_ic, ic);
+        assert_eq!(vk.vk_ic_len as usize, ic.len());
+        assert_eq!(vk.vk_beta_g2, [1u8; 128]);
+        assert_eq!(vk.vk_delta_g2, [3u8; 128]);
+
+        // IC is validated like a full VK
+        let short = vec![G1_GENERATOR; ZkPublicInputs::COUNT];
+        assert!(update_vk_ic(&pool, &mut vk, short, data_len).is_err());
+        assert_eq!(vk.vk_ic, ic);
+    }
+
+    #[test]
+    fn test_ic_update_blocked_after_deposits() {
+        let (mut pool, mut vk, data_len) = setup();
+        let ic = vec![G1_GENERATOR; ZkPublicInputs::COUNT + 1];
+        pool.record_deposit(1_000).unwrap();
+
+        let err = update_vk_ic(&pool, &mut vk, ic.clone(), data_len).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::VerificationKeyLocked));
+
+        let (mut pool, mut vk, data_len) = setup();
+        pool.lock_vk();
+        assert!(update_vk_ic(&pool, &mut vk, ic, data_len).is_err());
+    }
+}
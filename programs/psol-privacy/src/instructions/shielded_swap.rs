@@ -0,0 +1,320 @@
+//! Shielded Swap Instruction
+//!
+//! Atomically withdraws (with proof) from one pool and deposits into
+//! another, so liquidity providers can rebalance anonymity sets without
+//! holding funds between transactions. Both legs run in one instruction:
+//! if either fails, neither takes effect.
+//!
+//! The same signer acts as relayer for the withdrawal and as depositor;
+//! the accounts enforce that the `relayer` argument is that signer.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::error::PrivacyError;
+use crate::instructions::deposit::{self, *};
+use crate::instructions::withdraw::{self, *};
+use crate::state::merkle_tree::debug_assert_not_nullifier;
+use crate::state::DepositNote;
+
+#[derive(Accounts)]
+#[instruction(
+    proof_data: Vec<u8>,
+    merkle_root: [u8; 32],
+    nullifier_hash: [u8; 32],
+    recipient: Pubkey,
+    amount: u64,
+    relayer: Pubkey,
+    relayer_fee: u64,
+)]
+pub struct ShieldedSwap<'info> {
+    /// Withdrawal leg (pool B); its payer relays the withdrawal
+    #[account(
+        constraint = relayer == withdraw.payer.key() @ PrivacyError::Unauthorized,
+    )]
+    pub withdraw: Withdraw<'info>,
+
+    /// Deposit leg (pool A)
+    #[account(
+        constraint = deposit.depositor.key() == withdraw.payer.key() @ PrivacyError::Unauthorized,
+    )]
+    pub deposit: Deposit<'info>,
+
+    #[account(address = deposit.pool_config.token_mint @ PrivacyError::InvalidMint)]
+    pub deposit_mint: Account<'info, Mint>,
+
+    #[account(address = withdraw.pool_config.token_mint @ PrivacyError::InvalidMint)]
+    pub withdraw_mint: Account<'info, Mint>,
+}
+
+/// Returns the deposit's leaf index in pool A, as `DepositEvent` also
+/// reports it.
+#[allow(clippy::too_many_arguments)]
+pub fn handler(
+    ctx: Context<ShieldedSwap>,
+    proof_data: Vec<u8>,
+    merkle_root: [u8; 32],
+    nullifier_hash: [u8; 32],
+    recipient: Pubkey,
+    amount: u64,
+    relayer: Pubkey,
+    relayer_fee: u64,
+    deposit_amount: u64,
+    commitment: [u8; 32],
+) -> Result<u32> {
+    validate_swap_pools(
+        &ctx.accounts.deposit.pool_config.key(),
+        &ctx.accounts.withdraw.pool_config.key(),
+        ctx.accounts.deposit_mint.decimals,
+        ctx.accounts.withdraw_mint.decimals,
+    )?;
+
+    let leaf_index = swap_legs(
+        &mut ctx.accounts.withdraw,
+        &ctx.bumps.withdraw,
+        &mut ctx.accounts.deposit,
+        proof_data,
+        merkle_root,
+        nullifier_hash,
+        recipient,
+        amount,
+        relayer,
+        relayer_fee,
+        deposit_amount,
+        commitment,
+    )?;
+
+    msg!("Shielded swap successful");
+    Ok(leaf_index)
+}
+
+/// Run the withdrawal leg, then the deposit leg. Returns the deposit's leaf
+/// index.
+///
+/// Any error aborts the instruction, so the runtime discards whatever the
+/// withdrawal leg already wrote when the deposit leg fails.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn swap_legs<'info>(
+    withdraw: &mut Withdraw<'info>,
+    withdraw_bumps: &WithdrawBumps,
+    deposit: &mut Deposit<'info>,
+    proof_data: Vec<u8>,
+    merkle_root: [u8; 32],
+    nullifier_hash: [u8; 32],
+    recipient: Pubkey,
+    amount: u64,
+    relayer: Pubkey,
+    relayer_fee: u64,
+    deposit_amount: u64,
+    commitment: [u8; 32],
+) -> Result<u32> {
+    // Withdrawal first: proof verification fails before any deposit moves
+    withdraw::process_withdraw(
+        withdraw,
+        withdraw_bumps,
+        proof_data,
+        merkle_root,
+        nullifier_hash,
+        recipient,
+        amount,
+        relayer,
+        relayer_fee,
+    )?;
+
+    debug_assert_not_nullifier(&commitment, &nullifier_hash);
+    deposit::process_deposit(
+        deposit,
+        deposit_amount,
+        DepositNote::Commitment(commitment),
+        Vec::new(),
+    )
+}
+
+/// Both legs must target distinct pools whose mints share decimals, so
+/// amounts mean the same thing on both sides.
+///
+/// No mint pairs are allowlisted because no third party's value crosses
+/// between the pools. Here the signer funds the deposit and relays a
+/// withdrawal whose amount the proof fixes. In `migrate_note` the note
+/// owner's proof binds the target pool, and the relayer chooses to pay the
+/// target mint 1:1. The pool only needs to keep the units consistent.
+pub fn validate_swap_pools(
+    deposit_pool: &Pubkey,
+    withdraw_pool: &Pubkey,
+    deposit_decimals: u8,
+    withdraw_decimals: u8,
+) -> Result<()> {
+    require!(
+        deposit_pool != withdraw_pool,
+        PrivacyError::IncompatibleSwapPools
+    );
+    require!(
+        deposit_decimals == withdraw_decimals,
+        PrivacyError::IncompatibleSwapPools
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::poseidon::TreeHash;
+    use crate::crypto::ZkPublicInputs;
+    use crate::instructions::withdraw::test_fixtures::*;
+    use crate::state::merkle_tree::test_fixtures::new_tree;
+    use crate::state::pool_config::test_fixtures::new_pool;
+    use crate::state::{PoolConfig, SpentNullifier};
+
+    const AMOUNT: u64 = 100_000;
+    const FEE: u64 = 1_000;
+    const DEPOSIT_AMOUNT: u64 = 50_000;
+
+    /// A withdrawable pool B holding one note and an empty pool A, with a
+    /// single signer relaying the withdrawal and funding the deposit.
+    struct Swap {
+        pool_b: WithdrawFixture,
+        deposit: Deposit<'static>,
+        recipient: Pubkey,
+        relayer: Pubkey,
+        nullifier_hash: [u8; 32],
+    }
+
+    impl Swap {
+        fn new() -> Self {
+            let (relayer, recipient, nullifier_hash) = (Pubkey::new_unique(), Pubkey::new_unique(), [9u8; 32]);
+            let pool_b = WithdrawFixture::new(&relayer, &recipient, &nullifier_hash);
+
+            let mut pool_a = new_pool();
+            pool_a.set_vk_configured(true);
+            let pool_config = program_account(&pool_a, PoolConfig::LEN);
+            let deposit = Deposit {
+                merkle_tree: tree_account(&new_tree(8, TreeHash::Poseidon)),
+                root_history: None,
+                vault: token_account(pool_a.token_mint, pool_config.key(), 0),
+                depositor_token_account: token_account(pool_a.token_mint, relayer, DEPOSIT_AMOUNT),
+                depositor: Signer::try_from(account_info(relayer, System::id(), Vec::new(), true, false)).unwrap(),
+                token_program: token_program(),
+                pool_config,
+            };
+
+            Self { pool_b, deposit, recipient, relayer, nullifier_hash }
+        }
+
+        /// Proof for withdrawing `amount` from pool B.
+        fn proof(&self, amount: u64) -> Vec<u8> {
+            self.pool_b.proof(ZkPublicInputs::new(
+                self.pool_b.merkle_root,
+                self.nullifier_hash,
+                self.recipient,
+                amount,
+                self.relayer,
+                FEE,
+            ))
+        }
+
+        fn run(&mut self, proof_data: Vec<u8>, commitment: [u8; 32]) -> Result<u32> {
+            swap_legs(
+                &mut self.pool_b.withdraw,
+                &self.pool_b.bumps,
+                &mut self.deposit,
+                proof_data,
+                self.pool_b.merkle_root,
+                self.nullifier_hash,
+                self.recipient,
+                AMOUNT,
+                self.relayer,
+                FEE,
+                DEPOSIT_AMOUNT,
+                commitment,
+            )
+        }
+    }
+
+    #[test]
+    fn test_distinct_compatible_pools_accepted() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert!(validate_swap_pools(&a, &b, 6, 6).is_ok());
+    }
+
+    #[test]
+    fn test_same_pool_rejected() {
+        let a = Pubkey::new_unique();
+        assert!(validate_swap_pools(&a, &a, 6, 6).is_err());
+    }
+
+    #[test]
+    fn test_mismatched_decimals_rejected() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert!(validate_swap_pools(&a, &b, 6, 9).is_err());
+    }
+
+    #[test]
+    fn test_swap_spends_in_one_pool_and_inserts_in_the_other() {
+        let mut swap = Swap::new();
+        let commitment = [5u8; 32];
+        let proof = swap.proof(AMOUNT);
+        assert_eq!(swap.run(proof, commitment).unwrap(), 0);
+
+        // Pool B: nullifier spent, note paid out, tree untouched
+        let withdraw = &mut swap.pool_b.withdraw;
+        assert!(SpentNullifier::exists(&withdraw.spent_nullifier).unwrap());
+        assert_eq!(withdraw.pool_config.total_withdrawals, 1);
+        assert_eq!(withdraw.pool_config.total_value_withdrawn, AMOUNT);
+        assert_eq!(token_account_balance(&mut withdraw.vault), VAULT_BALANCE - AMOUNT);
+        assert_eq!(token_account_balance(&mut withdraw.recipient_token_account), AMOUNT - FEE);
+        assert_eq!(token_account_balance(&mut withdraw.relayer_token_account), FEE);
+        assert_eq!(withdraw.merkle_tree.next_leaf_index, 1);
+
+        // Pool A: commitment inserted, deposit collected
+        let mut expected = new_tree(8, TreeHash::Poseidon);
+        expected.insert_leaf_into(commitment, SLOT, None).unwrap();
+        let deposit = &mut swap.deposit;
+        assert_eq!(deposit.merkle_tree.next_leaf_index, 1);
+        assert_eq!(deposit.merkle_tree.current_root, expected.current_root);
+        assert_eq!(deposit.pool_config.total_deposits, 1);
+        assert_eq!(token_account_balance(&mut deposit.vault), DEPOSIT_AMOUNT);
+        assert_eq!(token_account_balance(&mut deposit.depositor_token_account), 0);
+    }
+
+    #[test]
+    fn test_failed_withdrawal_leg_skips_deposit() {
+        let mut swap = Swap::new();
+        let root_before = swap.deposit.merkle_tree.current_root;
+        // Proof for a different amount than the one withdrawn
+        let proof = swap.proof(AMOUNT + 1);
+        assert_eq!(swap.run(proof, [5u8; 32]).err().unwrap(), error!(PrivacyError::InvalidProof));
+
+        assert!(!SpentNullifier::exists(&swap.pool_b.withdraw.spent_nullifier).unwrap());
+        assert_eq!(token_account_balance(&mut swap.pool_b.withdraw.vault), VAULT_BALANCE);
+        assert_eq!(swap.deposit.merkle_tree.next_leaf_index, 0);
+        assert_eq!(swap.deposit.merkle_tree.current_root, root_before);
+        assert_eq!(token_account_balance(&mut swap.deposit.depositor_token_account), DEPOSIT_AMOUNT);
+    }
+
+    #[test]
+    fn test_replayed_swap_rejected_before_deposit() {
+        let mut swap = Swap::new();
+        let proof = swap.proof(AMOUNT);
+        swap.run(proof.clone(), [5u8; 32]).unwrap();
+        assert_eq!(
+            swap.run(proof, [6u8; 32]).err().unwrap(),
+            error!(PrivacyError::NullifierAlreadySpent)
+        );
+        assert_eq!(swap.deposit.merkle_tree.next_leaf_index, 1);
+        assert_eq!(swap.deposit.pool_config.total_deposits, 1);
+    }
+
+    #[test]
+    fn test_failed_deposit_leg_fails_the_swap() {
+        let mut swap = Swap::new();
+        let proof = swap.proof(AMOUNT);
+        // Not a canonical scalar: the deposit leg rejects it after the
+        // withdrawal leg ran, and the error aborts the whole instruction
+        assert_eq!(
+            swap.run(proof, [0xff; 32]).err().unwrap(),
+            error!(PrivacyError::InvalidCommitment)
+        );
+        assert_eq!(swap.deposit.merkle_tree.next_leaf_index, 0);
+        assert_eq!(token_account_balance(&mut swap.deposit.vault), 0);
+    }
+}
@@ -0,0 +1,146 @@
+//! Verify Commitment Inclusion Instruction
+//!
+//! Read-only precheck for wallets: is `commitment` in the tree at
+//! `leaf_index`, under a root the pool still accepts? Running it before
+//! proof generation catches a desynced local tree early instead of as a
+//! failed withdrawal. Returns the result via return data.
+
+use anchor_lang::prelude::*;
+
+use crate::error::PrivacyError;
+use crate::state::{MerkleTree, PoolConfig, RootHistory};
+
+#[derive(Accounts)]
+pub struct VerifyCommitmentInclusion<'info> {
+    #[account(
+        seeds = [b"pool", pool_config.token_mint.as_ref()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    #[account(
+        seeds = [b"merkle_tree", pool_config.key().as_ref()],
+        bump,
+        constraint = merkle_tree.pool == pool_config.key() @ PrivacyError::Unauthorized,
+    )]
+    pub merkle_tree: Account<'info, MerkleTree>,
+
+    /// Split-layout root history; required iff `merkle_tree.external_root_history`
+    #[account(
+        seeds = [b"root_history", pool_config.key().as_ref()],
+        bump = root_history.bump,
+    )]
+    pub root_history: Option<Account<'info, RootHistory>>,
+}
+
+pub fn verify_commitment_inclusion_handler(
+    ctx: Context<VerifyCommitmentInclusion>,
+    commitment: [u8; 32],
+    leaf_index: u32,
+    path: Vec<[u8; 32]>,
+    root: [u8; 32],
+) -> Result<bool> {
+    commitment_included(
+        &ctx.accounts.merkle_tree,
+        ctx.accounts.root_history.as_deref(),
+        &commitment,
+        leaf_index,
+        &path,
+        &root,
+    )
+}
+
+/// Whether `commitment` sits at an already inserted `leaf_index` along
+/// `path`, and `root` is still in the pool's root history (ignoring
+/// `max_root_age_slots`).
+///
+/// # Errors
+/// * `RootHistoryMismatch` if `history` does not match the tree's layout
+pub fn commitment_included(
+    tree: &MerkleTree,
+    history: Option<&RootHistory>,
+    commitment: &[u8; 32],
+    leaf_index: u32,
+    path: &[[u8; 32]],
+    root: &[u8; 32],
+) -> Result<bool> {
+    let (known, _) = tree.is_known_root_in(history, root, 0, 0)?;
+    if !known || leaf_index >= tree.next_leaf_index {
+        return Ok(false);
+    }
+    tree.verify_merkle_path(commitment, leaf_index, path, root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::poseidon::TreeHash;
+    use crate::state::merkle_tree::test_fixtures::new_tree;
+
+    const DEPTH: u8 = 4;
+
+    fn leaf(i: u8) -> [u8; 32] {
+        let mut leaf = [0u8; 32];
+        leaf[31] = i;
+        leaf
+    }
+
+    /// Siblings of `leaf_index` in the full tree over `leaves`.
+    fn path(tree: &MerkleTree, leaves: &[[u8; 32]], leaf_index: usize) -> Vec<[u8; 32]> {
+        let hash = tree.hash_fn().unwrap();
+        let mut level = leaves.to_vec();
+        let mut index = leaf_index;
+        let mut path = Vec::new();
+        for depth in 0..DEPTH as usize {
+            level.resize(level.len().next_multiple_of(2), tree.zeros[depth]);
+            path.push(level[index ^ 1]);
+            level = level
+                .chunks(2)
+                .map(|pair| hash.hash_two_to_one(&pair[0], &pair[1]).unwrap())
+                .collect();
+            index >>= 1;
+        }
+        path
+    }
+
+    fn tree_with(leaves: &[[u8; 32]]) -> MerkleTree {
+        let mut tree = new_tree(DEPTH, TreeHash::Keccak);
+        for leaf in leaves {
+            tree.insert_leaf(*leaf, 0).unwrap();
+        }
+        tree
+    }
+
+    #[test]
+    fn test_correct_inclusion_accepted() {
+        let leaves: Vec<_> = (1..=5).map(leaf).collect();
+        let tree = tree_with(&leaves);
+        let root = tree.current_root;
+
+        for (i, commitment) in leaves.iter().enumerate() {
+            let path = path(&tree, &leaves, i);
+            assert!(commitment_included(&tree, None, commitment, i as u32, &path, &root).unwrap());
+        }
+
+        // An older root still in the history also works
+        let old_root = tree_with(&leaves[..3]).current_root;
+        let path = path(&tree, &leaves[..3], 1);
+        assert!(commitment_included(&tree, None, &leaves[1], 1, &path, &old_root).unwrap());
+    }
+
+    #[test]
+    fn test_wrong_inclusion_rejected() {
+        let leaves: Vec<_> = (1..=5).map(leaf).collect();
+        let tree = tree_with(&leaves);
+        let root = tree.current_root;
+        let path = path(&tree, &leaves, 2);
+
+        // Wrong index, wrong commitment, unknown root, truncated path
+        assert!(!commitment_included(&tree, None, &leaves[2], 3, &path, &root).unwrap());
+        assert!(!commitment_included(&tree, None, &leaf(9), 2, &path, &root).unwrap());
+        assert!(!commitment_included(&tree, None, &leaves[2], 2, &path, &[7u8; 32]).unwrap());
+        assert!(!commitment_included(&tree, None, &leaves[2], 2, &path[1..], &root).unwrap());
+        // Index past the last inserted leaf
+        assert!(!commitment_included(&tree, None, &tree.zeros[0], 6, &path, &root).unwrap());
+    }
+}
@@ -1,14 +1,19 @@
 //! Withdraw Instruction - Devnet Alpha Hardened
 
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{get_stack_height, TRANSACTION_LEVEL_STACK_HEIGHT};
+use anchor_lang::system_program;
 use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 
-use crate::crypto::{verify_groth16_proof, ZkPublicInputs};
+use crate::crypto::{
+    check_proof_version, verifier_for, verify_groth16_proof_with_vk_x, ZkPublicInputs,
+};
 use crate::error::PrivacyError;
 use crate::events::WithdrawEvent;
+use crate::instructions::deposit::check_tvl_invariant;
 use crate::state::{
-    verification_key::VerificationKey, MerkleTree, PoolConfig, SpentNullifier,
-    VerificationKeyAccount,
+    verification_key::VerificationKey, MerkleTree, PayoutMint, PoolConfig, RecipientCooldown,
+    RecipientDenylist, RootHistory, SpentNullifier, VerificationKeyAccount, VkXScratch,
 };
 
 pub const MIN_WITHDRAWAL_AMOUNT: u64 = 1;
@@ -39,6 +44,13 @@ pub struct Withdraw<'info> {
     )]
     pub merkle_tree: Account<'info, MerkleTree>,
 
+    /// Split-layout root history; required iff `merkle_tree.external_root_history`
+    #[account(
+        seeds = [b"root_history", pool_config.key().as_ref()],
+        bump = root_history.bump,
+    )]
+    pub root_history: Option<Account<'info, RootHistory>>,
+
     #[account(
         seeds = [b"verification_key", pool_config.key().as_ref()],
         bump = verification_key.bump,
@@ -47,14 +59,24 @@ pub struct Withdraw<'info> {
     )]
     pub verification_key: Account<'info, VerificationKeyAccount>,
 
+    /// Secondary VK, only consulted while `pool_config.dual_vk_window_until` is open
+    #[account(
+        seeds = [b"verification_key_next", pool_config.key().as_ref()],
+        bump = verification_key_next.bump,
+        constraint = verification_key_next.pool == pool_config.key() @ PrivacyError::Unauthorized,
+    )]
+    pub verification_key_next: Option<Account<'info, VerificationKeyAccount>>,
+
+    /// CHECK: Address pinned to the nullifier PDA. Checked unspent with
+    /// `SpentNullifier::exists` and created in the handler, so a replay
+    /// fails with `NullifierAlreadySpent` rather than Anchor's generic
+    /// "account already in use".
     #[account(
-        init,
-        payer = payer,
-        space = SpentNullifier::LEN,
+        mut,
         seeds = [b"nullifier", pool_config.key().as_ref(), nullifier_hash.as_ref()],
         bump
     )]
-    pub spent_nullifier: Account<'info, SpentNullifier>,
+    pub spent_nullifier: UncheckedAccount<'info>,
 
     #[account(
         mut,
@@ -67,18 +89,33 @@ pub struct Withdraw<'info> {
 
     #[account(
         mut,
-        constraint = recipient_token_account.mint == pool_config.token_mint @ PrivacyError::InvalidMint,
+        constraint = validate_destination_mint(&pool_config, &recipient_token_account, PayoutMint::Pool).is_ok() @ PrivacyError::InvalidMint,
         constraint = recipient_token_account.owner == recipient @ PrivacyError::RecipientMismatch,
     )]
     pub recipient_token_account: Account<'info, TokenAccount>,
 
     #[account(
         mut,
-        constraint = relayer_token_account.mint == pool_config.token_mint @ PrivacyError::InvalidMint,
+        constraint = validate_destination_mint(&pool_config, &relayer_token_account, PayoutMint::Pool).is_ok() @ PrivacyError::InvalidMint,
         constraint = relayer_token_account.owner == relayer @ PrivacyError::Unauthorized,
     )]
     pub relayer_token_account: Account<'info, TokenAccount>,
 
+    /// Required iff `pool_config.recipient_cooldown_slots > 0`
+    #[account(
+        mut,
+        seeds = [b"recipient_cooldown", pool_config.key().as_ref(), recipient.as_ref()],
+        bump = recipient_cooldown.bump,
+    )]
+    pub recipient_cooldown: Option<Account<'info, RecipientCooldown>>,
+
+    /// Required iff `pool_config.recipient_denylist_root` is non-zero
+    #[account(
+        seeds = [b"recipient_denylist", pool_config.key().as_ref()],
+        bump = recipient_denylist.bump,
+    )]
+    pub recipient_denylist: Option<Account<'info, RecipientDenylist>>,
+
     #[account(mut)]
     pub payer: Signer<'info>,
 
@@ -97,69 +134,705 @@ pub fn handler(
     relayer: Pubkey,
     relayer_fee: u64,
 ) -> Result<()> {
-    let pool_config = &mut ctx.accounts.pool_config;
-    let merkle_tree = &ctx.accounts.merkle_tree;
-    let verification_key = &ctx.accounts.verification_key;
-    let spent_nullifier = &mut ctx.accounts.spent_nullifier;
+    process_withdraw(
+        ctx.accounts,
+        &ctx.bumps,
+        proof_data,
+        merkle_root,
+        nullifier_hash,
+        recipient,
+        amount,
+        relayer,
+        relayer_fee,
+    )
+}
+
+/// Shared withdrawal flow: verify the proof, mark the nullifier spent and
+/// pay out from the vault. Used by every withdraw-style instruction.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn process_withdraw<'info>(
+    accounts: &mut Withdraw<'info>,
+    bumps: &WithdrawBumps,
+    proof_data: Vec<u8>,
+    merkle_root: [u8; 32],
+    nullifier_hash: [u8; 32],
+    recipient: Pubkey,
+    amount: u64,
+    relayer: Pubkey,
+    relayer_fee: u64,
+) -> Result<()> {
+    let public_inputs =
+        ZkPublicInputs::new(merkle_root, nullifier_hash, recipient, amount, relayer, relayer_fee);
+    process_withdraw_inputs(accounts, bumps, &proof_data, public_inputs, None)
+}
+
+/// `process_withdraw` for pre-built public inputs, e.g. with an amount
+/// commitment, and optionally a vk_x accumulated in `vk_x_scratch`.
+pub(crate) fn process_withdraw_inputs<'info>(
+    accounts: &mut Withdraw<'info>,
+    bumps: &WithdrawBumps,
+    proof_data: &[u8],
+    public_inputs: ZkPublicInputs,
+    vk_x_scratch: Option<&VkXScratch>,
+) -> Result<()> {
+    let relayer = public_inputs.relayer;
+    let (clock, public_inputs) = verify_withdrawal_inputs_with_vk_x(
+        accounts,
+        bumps,
+        proof_data,
+        public_inputs,
+        &relayer,
+        vk_x_scratch,
+    )?;
+    let amount = public_inputs.amount;
+
+    // Transfer net amount to recipient and fee to relayer
+    let payouts = withdrawal_payouts(
+        accounts.recipient_token_account.to_account_info(),
+        accounts.relayer_token_account.to_account_info(),
+        amount,
+        public_inputs.relayer_fee,
+    )?;
+    pay_from_vault(accounts, payouts)?;
+
+    // Update pool stats (gross amount for accounting)
+    let pool_config = &mut accounts.pool_config;
+    pool_config.record_withdrawal(amount)?;
+
+    accounts.vault.reload()?;
+    check_tvl_invariant(pool_config.key(), pool_config, accounts.vault.amount);
+
+    // Emit event (net amount to user is usually what consumers care about)
+    emit!(withdraw_event(
+        pool_config.key(),
+        pool_config.nullifier_generation,
+        accounts.spent_nullifier.key(),
+        &public_inputs,
+        &Rent::get()?,
+        clock.unix_timestamp,
+    )?);
+
+    if pool_config.verbose_logging {
+        msg!("Withdrawal successful");
+    }
+    Ok(())
+}
+
+/// `WithdrawEvent` for a withdrawal verified against `public_inputs`.
+///
+/// `amount` is the net amount paid to the recipient; `nullifier_generation`
+/// is the pool's counter after recording this withdrawal and
+/// `spent_nullifier` the nullifier PDA it created, whose rent-exempt
+/// minimum under `rent` is reported as `rent_paid`.
+pub(crate) fn withdraw_event(
+    pool: Pubkey,
+    nullifier_generation: u64,
+    spent_nullifier: Pubkey,
+    public_inputs: &ZkPublicInputs,
+    rent: &Rent,
+    timestamp: i64,
+) -> Result<WithdrawEvent> {
+    Ok(WithdrawEvent {
+        pool,
+        nullifier_hash: public_inputs.nullifier_hash,
+        recipient: public_inputs.recipient,
+        amount: public_inputs.net_amount()?,
+        relayer: public_inputs.relayer,
+        relayer_fee: public_inputs.relayer_fee,
+        inputs_hash: public_inputs.hash()?,
+        nullifier_generation,
+        spent_nullifier,
+        rent_paid: rent.minimum_balance(SpentNullifier::LEN),
+        timestamp,
+    })
+}
+
+/// Run every pre-payout withdrawal check, verify the proof against the
+/// given public inputs and mark the nullifier spent.
+///
+/// Returns the clock, so callers can timestamp their events consistently,
+/// and the public inputs exactly as verified, for `withdraw_event`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn verify_withdrawal(
+    accounts: &mut Withdraw<'_>,
+    bumps: &WithdrawBumps,
+    proof_data: &[u8],
+    merkle_root: [u8; 32],
+    nullifier_hash: [u8; 32],
+    recipient: Pubkey,
+    amount: u64,
+    relayer: Pubkey,
+    relayer_fee: u64,
+) -> Result<(Clock, ZkPublicInputs)> {
+    let public_inputs =
+        ZkPublicInputs::new(merkle_root, nullifier_hash, recipient, amount, relayer, relayer_fee);
+    verify_withdrawal_inputs(accounts, bumps, proof_data, public_inputs, &relayer)
+}
+
+/// `verify_withdrawal` for pre-built public inputs. The pool's recipient
+/// binding and the vault are applied here and nowhere else, so the
+/// encoding verified is the one callers emit.
+///
+/// `relayer` is the relayer's wallet, which the fee floor and its
+/// self-relay exemption apply to. It differs from `public_inputs.relayer`
+/// when that input carries a binding (split fee, redeposit, ...).
+pub(crate) fn verify_withdrawal_inputs(
+    accounts: &mut Withdraw<'_>,
+    bumps: &WithdrawBumps,
+    proof_data: &[u8],
+    public_inputs: ZkPublicInputs,
+    relayer: &Pubkey,
+) -> Result<(Clock, ZkPublicInputs)> {
+    verify_withdrawal_inputs_with_vk_x(accounts, bumps, proof_data, public_inputs, relayer, None)
+}
+
+/// `verify_withdrawal_inputs`, taking vk_x from `vk_x_scratch` when given
+/// instead of computing it (see `verify_withdrawal_proof_with_vk_x`).
+pub(crate) fn verify_withdrawal_inputs_with_vk_x(
+    accounts: &mut Withdraw<'_>,
+    bumps: &WithdrawBumps,
+    proof_data: &[u8],
+    public_inputs: ZkPublicInputs,
+    relayer: &Pubkey,
+    vk_x_scratch: Option<&VkXScratch>,
+) -> Result<(Clock, ZkPublicInputs)> {
+    let ZkPublicInputs {
+        merkle_root,
+        nullifier_hash,
+        recipient,
+        amount,
+        relayer_fee,
+        ..
+    } = public_inputs;
+
+    let proof_data = precheck_withdrawal(
+        proof_data,
+        accounts.pool_config.proof_version,
+        &accounts.spent_nullifier,
+    )?;
+
+    let pool_config = &accounts.pool_config;
+    let merkle_tree = &accounts.merkle_tree;
+    let verification_key = &accounts.verification_key;
 
     // Basic state guards
-    pool_config.require_not_paused()?;
-    pool_config.require_vk_configured()?;
+    require_withdrawals_open(pool_config, verification_key)?;
+    if pool_config.disallow_cpi_withdraw {
+        require_top_level_invocation(get_stack_height())?;
+    }
+    require_nonempty_tree(merkle_tree)?;
+    pool_config.require_anonymity_set(merkle_tree.next_leaf_index)?;
 
     // Amount and fee sanity
     require!(amount >= MIN_WITHDRAWAL_AMOUNT, PrivacyError::InvalidAmount);
-    require!(
-        relayer_fee <= amount,
-        PrivacyError::RelayerFeeExceedsAmount
-    );
-
-    // Enforce maximum relayer fee (10% = 1000 basis points)
-    let max_fee = amount
-        .checked_mul(MAX_RELAYER_FEE_BPS)
-        .and_then(|v| v.checked_div(10_000))
-        .ok_or(error!(PrivacyError::ArithmeticOverflow))?;
-    require!(
-        relayer_fee <= max_fee,
-        PrivacyError::RelayerFeeExceedsAmount
-    );
+    pool_config.require_nonzero_net(amount, relayer_fee)?;
+    validate_relayer_fee(amount, relayer_fee)?;
+    pool_config.require_min_relayer_fee(&recipient, relayer, relayer_fee)?;
+    if pool_config.require_ready_payees {
+        let rent = Rent::get()?;
+        require_payee_ready(&accounts.recipient_token_account.to_account_info(), &rent)?;
+        require_payee_ready(&accounts.relayer_token_account.to_account_info(), &rent)?;
+    }
 
     // Vault and tree checks
     require!(
-        ctx.accounts.vault.amount >= amount,
+        accounts.vault.amount >= amount,
         PrivacyError::InsufficientBalance
     );
-    require!(
-        merkle_tree.is_known_root(&merkle_root),
-        PrivacyError::InvalidMerkleRoot
-    );
+    let clock = Clock::get()?;
+    let (known_root, root_within_age) = merkle_tree.is_known_root_in(
+        accounts.root_history.as_deref(),
+        &merkle_root,
+        clock.slot,
+        pool_config.max_root_age_slots,
+    )?;
+    require!(known_root, PrivacyError::InvalidMerkleRoot);
+    require!(root_within_age, PrivacyError::MerkleRootExpired);
+    enforce_min_note_age(
+        pool_config,
+        merkle_tree,
+        accounts.root_history.as_deref(),
+        &merkle_root,
+        clock.slot,
+    )?;
     require!(
         nullifier_hash != [0u8; 32],
         PrivacyError::InvalidNullifier
     );
+    enforce_recipient_cooldown(
+        pool_config,
+        accounts.recipient_cooldown.as_deref_mut(),
+        clock.slot,
+    )?;
+    enforce_recipient_denylist(
+        pool_config,
+        accounts.recipient_denylist.as_deref(),
+        &recipient,
+    )?;
 
-    // Public inputs and ZK verification
-    let public_inputs =
-        ZkPublicInputs::new(merkle_root, nullifier_hash, recipient, amount, relayer, relayer_fee);
+    // Public inputs and ZK verification; the vault is the resolved PDA
+    let public_inputs = public_inputs
+        .with_recipient_binding(pool_config.recipient_binding()?)
+        .with_vault(accounts.vault.key());
     public_inputs.validate()?;
 
-    let vk: VerificationKey = VerificationKey::from(verification_key.as_ref());
-    let proof_valid = verify_groth16_proof(&proof_data, &vk, &public_inputs)?;
+    let vk = VerificationKey::try_from(verification_key.as_ref())?;
+    let next_vk = next_verification_key(
+        pool_config,
+        accounts.verification_key_next.as_deref(),
+        clock.unix_timestamp,
+    )?;
+    let proof_valid = match vk_x_scratch {
+        Some(scratch) => verify_withdrawal_proof_with_vk_x(
+            proof_data,
+            &vk,
+            &public_inputs,
+            scratch,
+            pool_config.verbose_logging,
+        )?,
+        None => verify_withdrawal_proof(
+            proof_data,
+            &vk,
+            next_vk.as_ref(),
+            &public_inputs,
+            pool_config.verbose_logging,
+        )?,
+    };
     require!(proof_valid, PrivacyError::InvalidProof);
 
     // Nullifier marking
-    let clock = Clock::get()?;
-    spent_nullifier.initialize(
-        pool_config.key(),
+    let record = SpentNullifier {
+        pool: pool_config.key(),
         nullifier_hash,
-        clock.unix_timestamp,
-        clock.slot,
-        ctx.bumps.spent_nullifier,
+        root_used: merkle_root,
+        spent_at: clock.unix_timestamp,
+        spent_slot: clock.slot,
+        bump: bumps.spent_nullifier,
+    };
+    create_spent_nullifier(accounts, &record)?;
+
+    Ok((clock, public_inputs))
+}
+
+/// Checks run before any other withdrawal work: malformed proofs first, then
+/// replays. Returns the 256-byte proof.
+///
+/// # Errors
+/// * `InvalidProofFormat` / `ProofVersionMismatch` per `check_proof_version`
+/// * `NullifierAlreadySpent` per `require_nullifier_unspent`
+pub(crate) fn precheck_withdrawal<'a>(
+    proof_data: &'a [u8],
+    proof_version: u8,
+    spent_nullifier: &AccountInfo,
+) -> Result<&'a [u8]> {
+    let proof_data = check_proof_version(proof_data, proof_version)?;
+    require_nullifier_unspent(spent_nullifier)?;
+    Ok(proof_data)
+}
+
+/// Fail with `NullifierAlreadySpent` if the nullifier PDA already holds a
+/// spent record.
+pub fn require_nullifier_unspent(spent_nullifier: &AccountInfo) -> Result<()> {
+    require!(
+        !SpentNullifier::exists(spent_nullifier)?,
+        PrivacyError::NullifierAlreadySpent
     );
+    Ok(())
+}
+
+/// Create the nullifier PDA and write `record`, paid by `payer`.
+fn create_spent_nullifier<'info>(accounts: &Withdraw<'info>, record: &SpentNullifier) -> Result<()> {
+    let bump = [record.bump];
+    let seeds = SpentNullifier::seeds(&record.pool, &record.nullifier_hash, &bump);
+
+    let target = accounts.spent_nullifier.to_account_info();
+    create_pda_account(
+        accounts.system_program.to_account_info(),
+        accounts.payer.to_account_info(),
+        target.clone(),
+        SpentNullifier::LEN,
+        &[&seeds[..]],
+    )?;
+
+    let mut data = target.try_borrow_mut_data()?;
+    record.try_serialize(&mut &mut data[..])
+}
+
+/// Create a program-owned PDA of `space` bytes, rent paid by `payer`.
+///
+/// Mirrors Anchor's `init`: a PDA pre-funded with lamports is topped up,
+/// allocated and assigned instead of failing `create_account`.
+pub(crate) fn create_pda_account<'info>(
+    system_program: AccountInfo<'info>,
+    payer: AccountInfo<'info>,
+    target: AccountInfo<'info>,
+    space: usize,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+
+    if target.lamports() == 0 {
+        system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program,
+                system_program::CreateAccount { from: payer, to: target.clone() },
+                signer_seeds,
+            ),
+            rent,
+            space as u64,
+            &crate::ID,
+        )?;
+    } else {
+        let top_up = rent.saturating_sub(target.lamports());
+        if top_up > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    system_program.clone(),
+                    system_program::Transfer { from: payer, to: target.clone() },
+                ),
+                top_up,
+            )?;
+        }
+        system_program::allocate(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                system_program::Allocate { account_to_allocate: target.clone() },
+                signer_seeds,
+            ),
+            space as u64,
+        )?;
+        system_program::assign(
+            CpiContext::new_with_signer(
+                system_program,
+                system_program::Assign { account_to_assign: target.clone() },
+                signer_seeds,
+            ),
+            &crate::ID,
+        )?;
+    }
+    Ok(())
+}
 
-    // Compute net amount after relayer fee
+/// Vault payouts for a withdrawal as `(token_account, amount)` pairs,
+/// skipping zero amounts.
+///
+/// When self-relaying, the recipient and relayer token accounts may be the
+/// same account; the net amount and fee are then paid as a single transfer
+/// of the full `amount`.
+pub fn withdrawal_payouts<K: Key>(
+    recipient_token_account: K,
+    relayer_token_account: K,
+    amount: u64,
+    relayer_fee: u64,
+) -> Result<Vec<(K, u64)>> {
     let net_amount = amount
         .checked_sub(relayer_fee)
         .ok_or(error!(PrivacyError::ArithmeticOverflow))?;
+    merge_payouts([
+        (recipient_token_account, net_amount),
+        (relayer_token_account, relayer_fee),
+    ])
+}
+
+/// Combine vault payout legs into one transfer per distinct token account,
+/// in first-seen order, skipping zero amounts.
+///
+/// Every withdraw variant with more than one pool-mint payee pays out
+/// through here (directly or via `withdrawal_payouts`), so an account named
+/// by several legs, e.g. a self-relaying recipient, receives one transfer
+/// of its total.
+pub fn merge_payouts<K: Key>(legs: impl IntoIterator<Item = (K, u64)>) -> Result<Vec<(K, u64)>> {
+    let mut payouts: Vec<(K, u64)> = Vec::new();
+    for (destination, amount) in legs {
+        match payouts.iter_mut().find(|(to, _)| to.key() == destination.key()) {
+            Some((_, total)) => {
+                *total = total
+                    .checked_add(amount)
+                    .ok_or(error!(PrivacyError::ArithmeticOverflow))?;
+            }
+            None => payouts.push((destination, amount)),
+        }
+    }
+    payouts.retain(|(_, payout)| *payout > 0);
+    Ok(payouts)
+}
+
+/// Require a payout destination to hold the mint its transfer moves:
+/// `token_mint` for the recipient and relayers, `fee_mint` for a relayer
+/// paid in the fee mint.
+///
+/// # Errors
+/// * `InvalidMint` if `destination.mint` differs, or `payout` is
+///   `PayoutMint::Fee` and the pool has no fee mint
+pub fn validate_destination_mint(
+    pool_config: &PoolConfig,
+    destination: &TokenAccount,
+    payout: PayoutMint,
+) -> Result<()> {
+    let expected = pool_config.payout_mint(payout);
+    require!(
+        expected != Pubkey::default() && destination.mint == expected,
+        PrivacyError::InvalidMint
+    );
+    Ok(())
+}
+
+/// Require `token_account` to be an initialized, unfrozen SPL token
+/// account holding at least the rent-exempt minimum.
+///
+/// Runs before the nullifier is created or funds move. A transfer that
+/// fails later still reverts the whole transaction, nullifier included, so
+/// the note stays spendable either way; this only makes the failure clear
+/// and cheap.
+///
+/// # Errors
+/// * `PayeeAccountNotReady` if any of the above does not hold
+pub fn require_payee_ready(token_account: &AccountInfo, rent: &Rent) -> Result<()> {
+    use anchor_lang::solana_program::program_pack::Pack;
+    use anchor_spl::token::spl_token::state::{Account as SplAccount, AccountState};
+
+    let ready = token_account.owner == &token::ID
+        && rent.is_exempt(token_account.lamports(), token_account.data_len())
+        && SplAccount::unpack(&token_account.try_borrow_data()?)
+            .is_ok_and(|account| account.state == AccountState::Initialized);
+    if !ready {
+        msg!("Payee token account {} is not ready", token_account.key);
+        return Err(error!(PrivacyError::PayeeAccountNotReady));
+    }
+    Ok(())
+}
+
+/// Require the withdrawal to be a top-level instruction of its transaction.
+///
+/// Uses the runtime's invocation stack height rather than the instructions
+/// sysvar: the sysvar only shows the outermost instruction, which is still
+/// this program when a top-level `withdraw_and_swap` calls a swap program
+/// that re-enters `withdraw`.
+///
+/// # Errors
+/// * `CpiWithdrawDisallowed` if `stack_height` is above
+///   `TRANSACTION_LEVEL_STACK_HEIGHT`
+pub fn require_top_level_invocation(stack_height: usize) -> Result<()> {
+    require!(
+        stack_height <= TRANSACTION_LEVEL_STACK_HEIGHT,
+        PrivacyError::CpiWithdrawDisallowed
+    );
+    Ok(())
+}
+
+/// Pool state every withdrawal path requires: not paused, VK set, and
+/// dev mode acknowledged in dev builds.
+pub fn require_withdrawals_open(
+    pool_config: &PoolConfig,
+    verification_key: &VerificationKeyAccount,
+) -> Result<()> {
+    pool_config.require_not_paused()?;
+    require_verification_key_set(pool_config, verification_key)?;
+    pool_config.require_dev_mode_acknowledged()
+}
+
+/// Fail fast when no commitment has been inserted yet.
+///
+/// The only known root is then the empty root, so no valid proof exists;
+/// checking here gives a clear error instead of a failed verification.
+pub fn require_nonempty_tree(merkle_tree: &MerkleTree) -> Result<()> {
+    require!(merkle_tree.next_leaf_index > 0, PrivacyError::EmptyPool);
+    Ok(())
+}
+
+/// Enforce the pool's mixing delay, if enabled: `merkle_root` must have
+/// entered the tree at least `min_note_age_slots` before `current_slot`.
+///
+/// A note only appears in roots inserted after its deposit, so this keeps
+/// it from being withdrawn within the delay of being deposited.
+///
+/// # Errors
+/// * `RootHistoryMismatch` if `root_history` does not match the tree's layout
+/// * `InvalidMerkleRoot` if `merkle_root` is not in the root history
+/// * `MerkleRootTooRecent` if `merkle_root` is younger than the delay
+pub fn enforce_min_note_age(
+    pool_config: &PoolConfig,
+    merkle_tree: &MerkleTree,
+    root_history: Option<&RootHistory>,
+    merkle_root: &[u8; 32],
+    current_slot: u64,
+) -> Result<()> {
+    if pool_config.min_note_age_slots == 0 {
+        return Ok(());
+    }
+    let root_slot = merkle_tree
+        .root_slot_in(root_history, merkle_root)?
+        .ok_or(error!(PrivacyError::InvalidMerkleRoot))?;
+    require!(
+        current_slot.saturating_sub(root_slot) >= pool_config.min_note_age_slots,
+        PrivacyError::MerkleRootTooRecent
+    );
+    Ok(())
+}
+
+/// Enforce the pool's per-recipient cooldown, if enabled, and record this
+/// withdrawal's slot.
+///
+/// # Errors
+/// * `RecipientCooldownRequired` if enabled and no cooldown account was passed
+/// * `RecipientCooldownActive` if the recipient withdrew too recently
+pub fn enforce_recipient_cooldown(
+    pool_config: &PoolConfig,
+    recipient_cooldown: Option<&mut RecipientCooldown>,
+    current_slot: u64,
+) -> Result<()> {
+    if pool_config.recipient_cooldown_slots == 0 {
+        return Ok(());
+    }
+    let cooldown = recipient_cooldown.ok_or(error!(PrivacyError::RecipientCooldownRequired))?;
+    cooldown.check_and_record(current_slot, pool_config.recipient_cooldown_slots)
+}
+
+/// Reject withdrawals to a recipient on the pool's denylist, if enabled.
+///
+/// # Errors
+/// * `RecipientDenylistMismatch` if enabled and the denylist account is
+///   missing or its contents do not match `recipient_denylist_root`
+/// * `RecipientDenied` if `recipient` is denied
+pub fn enforce_recipient_denylist(
+    pool_config: &PoolConfig,
+    recipient_denylist: Option<&RecipientDenylist>,
+    recipient: &Pubkey,
+) -> Result<()> {
+    if pool_config.recipient_denylist_root == [0u8; 32] {
+        return Ok(());
+    }
+    let denylist = recipient_denylist.ok_or(error!(PrivacyError::RecipientDenylistMismatch))?;
+    require!(
+        denylist.root() == pool_config.recipient_denylist_root,
+        PrivacyError::RecipientDenylistMismatch
+    );
+    require!(!denylist.contains(recipient), PrivacyError::RecipientDenied);
+    Ok(())
+}
+
+/// Uniform VK guard for every proof-consuming instruction: the pool must
+/// have a VK configured and `verification_key` must hold it. Backs up the
+/// `is_initialized` account constraint.
+///
+/// # Errors
+/// * `VerificationKeyNotSet` if either is unset
+pub fn require_verification_key_set(
+    pool_config: &PoolConfig,
+    verification_key: &VerificationKeyAccount,
+) -> Result<()> {
+    pool_config.require_vk_configured()?;
+    require!(
+        verification_key.is_initialized,
+        PrivacyError::VerificationKeyNotSet
+    );
+    Ok(())
+}
+
+/// Secondary VK to accept alongside the primary, if the pool's dual-VK
+/// window is open at `now` and the secondary VK has been set.
+///
+/// # Errors
+/// * `VkIcLengthMismatch` if the secondary VK account is inconsistent
+pub fn next_verification_key(
+    pool_config: &PoolConfig,
+    verification_key_next: Option<&VerificationKeyAccount>,
+    now: i64,
+) -> Result<Option<VerificationKey>> {
+    verification_key_next
+        .filter(|next| next.is_initialized && pool_config.dual_vk_window_open(now))
+        .map(VerificationKey::try_from)
+        .transpose()
+}
+
+/// Verify against the primary VK, falling back to `next_vk` if given.
+/// `verbose` enables the verifier's step-by-step logs.
+pub fn verify_withdrawal_proof(
+    proof_data: &[u8],
+    vk: &VerificationKey,
+    next_vk: Option<&VerificationKey>,
+    public_inputs: &ZkPublicInputs,
+    verbose: bool,
+) -> Result<bool> {
+    if verifier_for(vk.curve)?.verify(proof_data, vk, public_inputs, verbose)? {
+        return Ok(true);
+    }
+    match next_vk {
+        Some(next_vk) => {
+            msg!("Primary VK rejected proof, trying secondary VK");
+            verifier_for(next_vk.curve)?.verify(proof_data, next_vk, public_inputs, verbose)
+        }
+        None => Ok(false),
+    }
+}
+
+/// `verify_withdrawal_proof` against a vk_x accumulated in `scratch` over
+/// several `accumulate_vk_x` instructions. Only the primary VK is tried:
+/// the scratch is bound to the key it was accumulated against.
+///
+/// # Errors
+/// * `UnsupportedCurve` per `verifier_for`
+/// * `VkXScratchStale` / `VkXScratchIncomplete` / `InvalidPublicInputs`
+///   per `VkXScratch::finished_vk_x`
+pub fn verify_withdrawal_proof_with_vk_x(
+    proof_data: &[u8],
+    vk: &VerificationKey,
+    public_inputs: &ZkPublicInputs,
+    scratch: &VkXScratch,
+    verbose: bool,
+) -> Result<bool> {
+    verifier_for(vk.curve)?;
+    let vk_x = scratch.finished_vk_x(vk, &public_inputs.to_field_elements()?)?;
+    verify_groth16_proof_with_vk_x(proof_data, vk, &vk_x, verbose)
+}
+
+/// Enforce `relayer_fee <= amount` and the maximum relayer fee
+/// (10% = 1000 basis points).
+pub fn validate_relayer_fee(amount: u64, relayer_fee: u64) -> Result<()> {
+    require!(
+        relayer_fee <= amount,
+        PrivacyError::RelayerFeeExceedsAmount
+    );
+
+    let max_fee = amount
+        .checked_mul(MAX_RELAYER_FEE_BPS)
+        .and_then(|v| v.checked_div(10_000))
+        .ok_or(error!(PrivacyError::ArithmeticOverflow))?;
+    require!(
+        relayer_fee <= max_fee,
+        PrivacyError::RelayerFeeExceedsAmount
+    );
+    Ok(())
+}
+
+/// Make each transfer of `payouts` (from `merge_payouts`) out of the vault.
+pub(crate) fn pay_from_vault<'info>(
+    accounts: &Withdraw<'info>,
+    payouts: Vec<(AccountInfo<'info>, u64)>,
+) -> Result<()> {
+    for (to, amount) in payouts {
+        transfer_from_vault(accounts, to, amount)?;
+    }
+    Ok(())
+}
+
+/// Transfer `amount` from the vault to `to`, signed by the pool PDA.
+pub(crate) fn transfer_from_vault<'info>(
+    accounts: &Withdraw<'info>,
+    to: AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    transfer_signed_by_pool(accounts, accounts.vault.to_account_info(), to, amount)
+}
+
+/// Transfer out of any token account owned by the pool PDA.
+pub(crate) fn transfer_signed_by_pool<'info>(
+    accounts: &Withdraw<'info>,
+    from: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let pool_config = &accounts.pool_config;
 
     // PDA signer seeds
     let pool_seeds = &[
@@ -169,53 +842,791 @@ pub fn handler(
     ];
     let signer_seeds = &[&pool_seeds[..]];
 
-    // Transfer to recipient
-    if net_amount > 0 {
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.vault.to_account_info(),
-            to: ctx
-                .accounts
-                .recipient_token_account
-                .to_account_info(),
-            authority: pool_config.to_account_info(),
+    let cpi_accounts = Transfer {
+        from,
+        to,
+        authority: pool_config.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(
+        accounts.token_program.to_account_info(),
+        cpi_accounts,
+        signer_seeds,
+    );
+    token::transfer(cpi_ctx, amount)
+}
+
+/// Withdraw accounts backed by in-memory account data, with a stubbed
+/// runtime, so handlers can run end to end in unit tests.
+#[cfg(test)]
+pub(crate) mod test_fixtures {
+    use super::*;
+    use crate::crypto::groth16_verifier::test_fixtures::{synthetic_proof, synthetic_vk};
+    use crate::crypto::poseidon::TreeHash;
+    use crate::state::merkle_tree::test_fixtures::new_tree;
+    use crate::state::merkle_tree::MIN_ROOT_HISTORY_SIZE;
+    use crate::state::pool_config::test_fixtures::new_pool;
+    use anchor_spl::token::spl_token::{self, instruction::TokenInstruction};
+    use solana_program::entrypoint::{ProgramResult, SUCCESS};
+    use solana_program::instruction::Instruction;
+    use solana_program::program_pack::Pack;
+    use solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+    use solana_program::program_utils::limited_deserialize;
+    use solana_program::system_instruction::SystemInstruction;
+    use std::cell::RefCell;
+
+    pub(crate) const SLOT: u64 = 1_000;
+    pub(crate) const VAULT_BALANCE: u64 = 1_000_000;
+
+    /// Program registered with `install_program`, run on CPI to its id.
+    pub(crate) type StubProgram = fn(&Instruction, &[AccountInfo]) -> ProgramResult;
+
+    thread_local! {
+        static TOKEN_TRANSFERS: RefCell<Vec<(Pubkey, u64)>> = const { RefCell::new(Vec::new()) };
+        static STUB_PROGRAMS: RefCell<Vec<(Pubkey, StubProgram)>> = const { RefCell::new(Vec::new()) };
+    }
+
+    /// Stands in for the runtime: serves the clock and rent sysvars and
+    /// executes SPL token transfers, system account creation and any
+    /// `install_program` stubs, logging each token transfer for
+    /// `token_transfers`.
+    struct Runtime;
+
+    impl SyscallStubs for Runtime {
+        fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+            let clock = Clock { slot: SLOT, unix_timestamp: 1_700_000_000, ..Clock::default() };
+            unsafe { *(var_addr as *mut Clock) = clock };
+            SUCCESS
+        }
+
+        fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe { *(var_addr as *mut Rent) = Rent::default() };
+            SUCCESS
+        }
+
+        fn sol_invoke_signed(
+            &self,
+            instruction: &Instruction,
+            account_infos: &[AccountInfo],
+            _signers_seeds: &[&[&[u8]]],
+        ) -> ProgramResult {
+            let account = |i: usize| {
+                account_infos
+                    .iter()
+                    .find(|info| *info.key == instruction.accounts[i].pubkey)
+                    .unwrap()
+            };
+            if instruction.program_id == spl_token::ID {
+                let TokenInstruction::Transfer { amount } = TokenInstruction::unpack(&instruction.data)? else {
+                    return Err(ProgramError::InvalidInstructionData);
+                };
+                let (from, to) = (account(0), account(1));
+                move_tokens(from, to, amount)?;
+                TOKEN_TRANSFERS.with(|log| log.borrow_mut().push((*to.key, amount)));
+                Ok(())
+            } else if instruction.program_id == System::id() {
+                let SystemInstruction::CreateAccount { lamports, space, owner } =
+                    limited_deserialize(&instruction.data, 1_232).map_err(|_| ProgramError::InvalidInstructionData)?
+                else {
+                    return Err(ProgramError::InvalidInstructionData);
+                };
+                let (from, to) = (account(0), account(1));
+                assert_eq!(to.data_len() as u64, space);
+                **from.try_borrow_mut_lamports()? -= lamports;
+                **to.try_borrow_mut_lamports()? += lamports;
+                to.assign(&owner);
+                Ok(())
+            } else {
+                let stub = STUB_PROGRAMS.with(|programs| {
+                    programs.borrow().iter().find(|(id, _)| *id == instruction.program_id).map(|(_, stub)| *stub)
+                });
+                stub.ok_or(ProgramError::IncorrectProgramId)?(instruction, account_infos)
+            }
+        }
+    }
+
+    /// Move `amount` between two SPL token accounts, as a token transfer would.
+    pub(crate) fn move_tokens(from: &AccountInfo, to: &AccountInfo, amount: u64) -> ProgramResult {
+        let mut source = spl_token::state::Account::unpack(&from.try_borrow_data()?)?;
+        let mut destination = spl_token::state::Account::unpack(&to.try_borrow_data()?)?;
+        source.amount = source.amount.checked_sub(amount).ok_or(ProgramError::InsufficientFunds)?;
+        destination.amount += amount;
+        spl_token::state::Account::pack(source, &mut from.try_borrow_mut_data()?)?;
+        spl_token::state::Account::pack(destination, &mut to.try_borrow_mut_data()?)?;
+        Ok(())
+    }
+
+    /// Install the stubbed runtime and clear this thread's transfer log and
+    /// stub programs.
+    pub(crate) fn install_runtime() {
+        set_syscall_stubs(Box::new(Runtime));
+        TOKEN_TRANSFERS.with(|log| log.borrow_mut().clear());
+        STUB_PROGRAMS.with(|programs| programs.borrow_mut().clear());
+    }
+
+    /// Serve CPIs to `program_id` on this thread with `stub`.
+    pub(crate) fn install_program(program_id: Pubkey, stub: StubProgram) {
+        STUB_PROGRAMS.with(|programs| programs.borrow_mut().push((program_id, stub)));
+    }
+
+    /// `(destination, amount)` of every token transfer on this thread since
+    /// `install_runtime`.
+    pub(crate) fn token_transfers() -> Vec<(Pubkey, u64)> {
+        TOKEN_TRANSFERS.with(|log| log.borrow().clone())
+    }
+
+    /// A writable, funded account living for the rest of the test run.
+    pub(crate) fn account_info(
+        key: Pubkey,
+        owner: Pubkey,
+        data: Vec<u8>,
+        is_signer: bool,
+        executable: bool,
+    ) -> &'static AccountInfo<'static> {
+        Box::leak(Box::new(AccountInfo::new(
+            Box::leak(Box::new(key)),
+            is_signer,
+            true,
+            Box::leak(Box::new(1_000_000_000)),
+            Box::leak(data.into_boxed_slice()),
+            Box::leak(Box::new(owner)),
+            executable,
+            0,
+        )))
+    }
+
+    pub(crate) fn program_account<T: AccountSerialize + AccountDeserialize + Owner + Clone>(
+        account: &T,
+        space: usize,
+    ) -> Account<'static, T> {
+        let mut data = Vec::with_capacity(space);
+        account.try_serialize(&mut data).unwrap();
+        data.resize(space, 0);
+        Account::try_from(account_info(Pubkey::new_unique(), crate::ID, data, false, false)).unwrap()
+    }
+
+    pub(crate) fn token_account(mint: Pubkey, owner: Pubkey, amount: u64) -> Account<'static, TokenAccount> {
+        Account::try_from(token_account_info(mint, owner, amount)).unwrap()
+    }
+
+    /// `token_account` as a raw account, for accounts a CPI checks itself.
+    pub(crate) fn token_account_info(mint: Pubkey, owner: Pubkey, amount: u64) -> &'static AccountInfo<'static> {
+        let mut data = vec![0u8; spl_token::state::Account::LEN];
+        let account = spl_token::state::Account {
+            mint,
+            owner,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
         };
-        let cpi_ctx = CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            cpi_accounts,
-            signer_seeds,
+        spl_token::state::Account::pack(account, &mut data).unwrap();
+        account_info(Pubkey::new_unique(), spl_token::ID, data, false, false)
+    }
+
+    pub(crate) fn token_account_balance(account: &mut Account<TokenAccount>) -> u64 {
+        account.reload().unwrap();
+        account.amount
+    }
+
+    pub(crate) fn tree_account(tree: &MerkleTree) -> Account<'static, MerkleTree> {
+        program_account(tree, MerkleTree::space(tree.depth, MIN_ROOT_HISTORY_SIZE))
+    }
+
+    pub(crate) fn token_program() -> Program<'static, Token> {
+        Program::try_from(account_info(Token::id(), Pubkey::default(), Vec::new(), false, true)).unwrap()
+    }
+
+    /// The not-yet-created nullifier PDA for `nullifier_hash`, and its bump.
+    pub(crate) fn unspent_nullifier(pool: &Pubkey, nullifier_hash: &[u8; 32]) -> (&'static AccountInfo<'static>, u8) {
+        let (address, bump) = SpentNullifier::find_pda(&crate::ID, pool, nullifier_hash);
+        let info = account_info(address, System::id(), vec![0u8; SpentNullifier::LEN], false, false);
+        **info.try_borrow_mut_lamports().unwrap() = 0;
+        (info, bump)
+    }
+
+    /// A withdrawable pool holding one note, with `VAULT_BALANCE` in its
+    /// vault and `relayer` paying for the withdrawal of `nullifier_hash` to
+    /// `recipient`. Proofs are `synthetic_proof(1, ..)`.
+    pub(crate) struct WithdrawFixture {
+        pub withdraw: Withdraw<'static>,
+        pub bumps: WithdrawBumps,
+        pub merkle_root: [u8; 32],
+    }
+
+    impl WithdrawFixture {
+        pub(crate) fn new(relayer: &Pubkey, recipient: &Pubkey, nullifier_hash: &[u8; 32]) -> Self {
+            install_runtime();
+
+            let mut pool = new_pool();
+            pool.set_vk_configured(true);
+            #[cfg(feature = "dev-mode")]
+            pool.set_dev_mode_acknowledged(true);
+            pool.record_deposit(VAULT_BALANCE).unwrap();
+            let mut tree = new_tree(8, TreeHash::Poseidon);
+            tree.insert_leaf_into([7u8; 32], 0, None).unwrap();
+            let merkle_root = tree.current_root;
+
+            let vk = synthetic_vk(1);
+            let vk_space = VerificationKeyAccount::space(VerificationKeyAccount::DEFAULT_MAX_IC_POINTS);
+            let mut vk_account =
+                VerificationKeyAccount::try_deserialize_unchecked(&mut vec![0u8; vk_space].as_slice()).unwrap();
+            vk_account.initialize(Pubkey::new_unique(), 255);
+            vk_account.set_vk(vk.alpha_g1, vk.beta_g2, vk.gamma_g2, vk.delta_g2, vk.ic);
+
+            let pool_config = program_account(&pool, PoolConfig::LEN);
+            let (spent_nullifier, bump) = unspent_nullifier(&pool_config.key(), nullifier_hash);
+            let withdraw = Withdraw {
+                merkle_tree: tree_account(&tree),
+                root_history: None,
+                verification_key: program_account(&vk_account, vk_space),
+                verification_key_next: None,
+                spent_nullifier: UncheckedAccount::try_from(spent_nullifier),
+                vault: token_account(pool.token_mint, pool_config.key(), VAULT_BALANCE),
+                recipient_token_account: token_account(pool.token_mint, *recipient, 0),
+                relayer_token_account: token_account(pool.token_mint, *relayer, 0),
+                recipient_cooldown: None,
+                recipient_denylist: None,
+                payer: Signer::try_from(account_info(*relayer, System::id(), Vec::new(), true, false)).unwrap(),
+                token_program: token_program(),
+                system_program: Program::try_from(account_info(System::id(), Pubkey::default(), Vec::new(), false, true))
+                    .unwrap(),
+                pool_config,
+            };
+
+            Self {
+                withdraw,
+                bumps: WithdrawBumps { spent_nullifier: bump, ..Default::default() },
+                merkle_root,
+            }
+        }
+
+        /// Proof for `public_inputs` as this pool binds them.
+        pub(crate) fn proof(&self, public_inputs: ZkPublicInputs) -> Vec<u8> {
+            let bound = public_inputs
+                .with_recipient_binding(self.withdraw.pool_config.recipient_binding().unwrap())
+                .with_vault(self.withdraw.vault.key());
+            synthetic_proof(1, &bound)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::groth16_verifier::test_fixtures::{synthetic_proof, synthetic_vk};
+    use crate::state::merkle_tree::test_fixtures::new_tree;
+    use crate::state::pool_config::test_fixtures::new_pool;
+
+    #[test]
+    fn test_relayer_fee_within_cap() {
+        assert!(validate_relayer_fee(1_000, 0).is_ok());
+        assert!(validate_relayer_fee(1_000, 100).is_ok());
+    }
+
+    #[test]
+    fn test_relayer_fee_above_cap_rejected() {
+        assert!(validate_relayer_fee(1_000, 101).is_err());
+        assert!(validate_relayer_fee(100, 200).is_err());
+    }
+
+    fn vk_account(vk: &VerificationKey) -> VerificationKeyAccount {
+        let data = vec![0u8; VerificationKeyAccount::space(VerificationKeyAccount::DEFAULT_MAX_IC_POINTS)];
+        let mut account = VerificationKeyAccount::try_deserialize_unchecked(&mut data.as_slice()).unwrap();
+        account.initialize(Pubkey::new_unique(), 255);
+        account.set_vk(vk.alpha_g1, vk.beta_g2, vk.gamma_g2, vk.delta_g2, vk.ic.clone());
+        account
+    }
+
+    fn inputs() -> ZkPublicInputs {
+        ZkPublicInputs::new(
+            [1u8; 32],
+            [2u8; 32],
+            Pubkey::new_from_array([3u8; 32]),
+            1_000,
+            Pubkey::new_from_array([4u8; 32]),
+            10,
+        )
+    }
+
+    #[test]
+    fn test_old_vk_proof_accepted_only_during_window() {
+        let old_vk = synthetic_vk(1);
+        let new_vk = synthetic_vk(2);
+        let proof = synthetic_proof(1, &inputs());
+
+        // After promotion: new VK is primary, old VK sits in verification_key_next
+        let old_account = vk_account(&old_vk);
+        let mut pool = new_pool();
+        pool.set_dual_vk_window_until(1_000);
+
+        let during = next_verification_key(&pool, Some(&old_account), 999).unwrap();
+        assert!(verify_withdrawal_proof(&proof, &new_vk, during.as_ref(), &inputs(), false).unwrap());
+
+        let after = next_verification_key(&pool, Some(&old_account), 1_000).unwrap();
+        assert!(after.is_none());
+        assert!(!verify_withdrawal_proof(&proof, &new_vk, after.as_ref(), &inputs(), false).unwrap());
+    }
+
+    #[test]
+    fn test_unset_vk_rejected_by_proof_guard() {
+        let mut pool = new_pool();
+        let account = vk_account(&synthetic_vk(1));
+        let mut unset = vk_account(&synthetic_vk(1));
+        unset.is_initialized = false;
+
+        // Pool never configured (also what `private_transfer` checks)
+        let err = require_verification_key_set(&pool, &account).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::VerificationKeyNotSet));
+
+        // Pool configured but the account passed holds no VK
+        pool.set_vk_configured(true);
+        let err = require_verification_key_set(&pool, &unset).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::VerificationKeyNotSet));
+
+        assert!(require_verification_key_set(&pool, &account).is_ok());
+    }
+
+    #[test]
+    fn test_inconsistent_ic_len_rejected() {
+        let mut account = vk_account(&synthetic_vk(1));
+        assert!(VerificationKey::try_from(&account).is_ok());
+
+        // Partial write: one IC point short of the declared length
+        account.vk_ic.pop();
+        let err = VerificationKey::try_from(&account).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::VkIcLengthMismatch));
+
+        // Same guard on the secondary VK during a dual-VK window
+        let mut pool = new_pool();
+        pool.set_dual_vk_window_until(1_000);
+        let err = next_verification_key(&pool, Some(&account), 999).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::VkIcLengthMismatch));
+    }
+
+    #[test]
+    fn test_compressed_ic_vk_verifies_identically() {
+        let vk = synthetic_vk(1);
+        let uncompressed = vk_account(&vk);
+        let mut compressed = vk_account(&vk);
+        compressed.set_ic_compression(true).unwrap();
+        assert!(compressed.vk_ic.is_empty());
+        assert_eq!(compressed.vk_ic_compressed.len(), vk.ic.len());
+
+        let loaded = VerificationKey::try_from(&compressed).unwrap();
+        assert_eq!(loaded.ic, VerificationKey::try_from(&uncompressed).unwrap().ic);
+
+        let proof = synthetic_proof(1, &inputs());
+        let other = ZkPublicInputs { amount: 999, ..inputs() };
+        for public_inputs in [inputs(), other] {
+            assert_eq!(
+                verify_withdrawal_proof(&proof, &loaded, None, &public_inputs, false).unwrap(),
+                verify_withdrawal_proof(&proof, &vk, None, &public_inputs, false).unwrap(),
+            );
+        }
+        assert!(verify_withdrawal_proof(&proof, &loaded, None, &inputs(), false).unwrap());
+
+        // A corrupted compressed point fails to load rather than verifying
+        compressed.vk_ic_compressed[0] = [0u8; 32];
+        assert!(VerificationKey::try_from(&compressed).is_err());
+    }
+
+    #[test]
+    fn test_withdraw_event_carries_inputs_hash() {
+        let inputs = inputs();
+        let event = withdraw_event(
+            Pubkey::new_unique(),
+            1,
+            Pubkey::new_unique(),
+            &inputs,
+            &Rent::default(),
+            1_700_000_000,
+        )
+        .unwrap();
+
+        assert_eq!(event.inputs_hash, inputs.hash().unwrap());
+        assert_eq!(event.nullifier_hash, inputs.nullifier_hash);
+        assert_eq!(event.amount, 990);
+        assert_eq!(event.relayer_fee, 10);
+    }
+
+    #[test]
+    fn test_withdraw_event_reports_nullifier_pda() {
+        let (pool, inputs) = (Pubkey::new_unique(), inputs());
+        let (pda, _) = SpentNullifier::find_pda(&crate::ID, &pool, &inputs.nullifier_hash);
+        let event = withdraw_event(pool, 1, pda, &inputs, &Rent::default(), 1_700_000_000).unwrap();
+
+        // Relayers dedupe on this address; it is derivable from the event alone
+        let (derived, _) = SpentNullifier::find_pda(&crate::ID, &event.pool, &event.nullifier_hash);
+        assert_eq!(event.spent_nullifier, derived);
+        let (other, _) = SpentNullifier::find_pda(&crate::ID, &pool, &[9u8; 32]);
+        assert_ne!(event.spent_nullifier, other);
+    }
+
+    #[test]
+    fn test_withdraw_event_reports_nullifier_rent() {
+        let (pool, inputs) = (Pubkey::new_unique(), inputs());
+        let (pda, _) = SpentNullifier::find_pda(&crate::ID, &pool, &inputs.nullifier_hash);
+        let rent = Rent::default();
+        let event = withdraw_event(pool, 1, pda, &inputs, &rent, 1_700_000_000).unwrap();
+
+        assert_eq!(event.rent_paid, rent.minimum_balance(SpentNullifier::LEN));
+        assert!(event.rent_paid > 0);
+        // Tracks the cluster's rent parameters
+        let pricier = Rent { lamports_per_byte_year: rent.lamports_per_byte_year * 2, ..rent };
+        let event = withdraw_event(pool, 1, pda, &inputs, &pricier, 1_700_000_000).unwrap();
+        assert_eq!(event.rent_paid, pricier.minimum_balance(SpentNullifier::LEN));
+    }
+
+    #[test]
+    fn test_nullifier_generation_increments_per_withdrawal() {
+        let mut pool = new_pool();
+        let inputs = inputs();
+
+        for expected in 1..=3u64 {
+            pool.record_withdrawal(inputs.amount).unwrap();
+            assert_eq!(pool.nullifier_generation, expected);
+
+            let event = withdraw_event(
+                Pubkey::new_unique(),
+                pool.nullifier_generation,
+                Pubkey::new_unique(),
+                &inputs,
+                &Rent::default(),
+                1_700_000_000,
+            )
+            .unwrap();
+            assert_eq!(event.nullifier_generation, expected);
+        }
+    }
+
+    #[test]
+    fn test_payouts_split_between_distinct_accounts() {
+        let (recipient, relayer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert_eq!(
+            withdrawal_payouts(recipient, relayer, 1_000, 50).unwrap(),
+            vec![(recipient, 950), (relayer, 50)]
+        );
+        assert_eq!(
+            withdrawal_payouts(recipient, relayer, 1_000, 0).unwrap(),
+            vec![(recipient, 1_000)]
         );
-        token::transfer(cpi_ctx, net_amount)?;
     }
 
-    // Transfer relayer fee
-    if relayer_fee > 0 {
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.vault.to_account_info(),
-            to: ctx.accounts.relayer_token_account.to_account_info(),
-            authority: pool_config.to_account_info(),
-        };
-        let cpi_ctx = CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            cpi_accounts,
-            signer_seeds,
+    #[test]
+    fn test_self_relay_aliased_accounts_single_transfer() {
+        let account = Pubkey::new_unique();
+        let payouts = withdrawal_payouts(account, account, 1_000, 50).unwrap();
+
+        // One combined transfer: the recipient receives the full amount
+        assert_eq!(payouts, vec![(account, 1_000)]);
+    }
+
+    #[test]
+    fn test_merge_payouts_combines_any_aliased_legs() {
+        let (recipient, relayer) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        // Split-fee shape with the second relayer aliasing the recipient
+        let payouts = merge_payouts([(recipient, 900), (relayer, 60), (recipient, 40)]).unwrap();
+        assert_eq!(payouts, vec![(recipient, 940), (relayer, 60)]);
+        assert_eq!(payouts.iter().map(|(_, v)| v).sum::<u64>(), 1_000);
+
+        // Zero legs are dropped even when another leg shares the account
+        assert_eq!(
+            merge_payouts([(recipient, 0), (relayer, 0), (recipient, 0)]).unwrap(),
+            vec![]
         );
-        token::transfer(cpi_ctx, relayer_fee)?;
+        assert!(merge_payouts([(recipient, u64::MAX), (recipient, 1)]).is_err());
     }
 
-    // Update pool stats (gross amount for accounting)
-    pool_config.record_withdrawal(amount)?;
+    #[test]
+    fn test_payee_readiness() {
+        use anchor_lang::solana_program::program_pack::Pack;
+        use anchor_spl::token::spl_token::state::{Account as SplAccount, AccountState};
 
-    // Emit event (net amount to user is usually what consumers care about)
-    emit!(WithdrawEvent {
-        pool: pool_config.key(),
-        nullifier_hash,
-        recipient,
-        amount: net_amount,
-        relayer,
-        relayer_fee,
-        timestamp: clock.unix_timestamp,
-    });
+        let rent = Rent::default();
+        let key = Pubkey::new_unique();
+        let exempt = rent.minimum_balance(SplAccount::LEN);
+        let packed = |state| {
+            let mut data = vec![0u8; SplAccount::LEN];
+            SplAccount::pack(
+                SplAccount {
+                    mint: Pubkey::new_unique(),
+                    owner: Pubkey::new_unique(),
+                    state,
+                    ..SplAccount::default()
+                },
+                &mut data,
+            )
+            .unwrap();
+            data
+        };
 
-    msg!("Withdrawal successful");
-    Ok(())
+        let (mut lamports, mut data) = (exempt, packed(AccountState::Initialized));
+        let ready = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &token::ID, false, 0);
+        assert!(require_payee_ready(&ready, &rent).is_ok());
+
+        // Uninitialized: zeroed data, or not yet allocated at all
+        let (mut lamports, mut data) = (exempt, vec![0u8; SplAccount::LEN]);
+        let uninit = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &token::ID, false, 0);
+        let err = require_payee_ready(&uninit, &rent).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::PayeeAccountNotReady));
+        let (mut lamports, mut data, system) = (0, vec![], system_program::ID);
+        let missing = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &system, false, 0);
+        assert!(require_payee_ready(&missing, &rent).is_err());
+
+        // Frozen, or below the rent-exempt minimum
+        let (mut lamports, mut data) = (exempt, packed(AccountState::Frozen));
+        let frozen = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &token::ID, false, 0);
+        assert!(require_payee_ready(&frozen, &rent).is_err());
+        let (mut lamports, mut data) = (exempt - 1, packed(AccountState::Initialized));
+        let poor = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &token::ID, false, 0);
+        assert!(require_payee_ready(&poor, &rent).is_err());
+    }
+
+    #[test]
+    fn test_wrong_mint_destination_rejected() {
+        use anchor_lang::solana_program::program_pack::Pack;
+        use anchor_spl::token::spl_token::state::{Account as SplAccount, AccountState};
+
+        let token_account = |mint: Pubkey| {
+            let mut data = vec![0u8; SplAccount::LEN];
+            let account = SplAccount { mint, state: AccountState::Initialized, ..SplAccount::default() };
+            SplAccount::pack(account, &mut data).unwrap();
+            TokenAccount::try_deserialize(&mut data.as_slice()).unwrap()
+        };
+
+        let mut pool = new_pool();
+        pool.token_mint = Pubkey::new_unique();
+        let (pool_mint, fee_mint) = (pool.token_mint, Pubkey::new_unique());
+
+        let recipient = token_account(pool_mint);
+        assert!(validate_destination_mint(&pool, &recipient, PayoutMint::Pool).is_ok());
+        let wrong = token_account(Pubkey::new_unique());
+        let err = validate_destination_mint(&pool, &wrong, PayoutMint::Pool).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::InvalidMint));
+
+        // Fee-mint payouts need a configured fee mint, and only accept it
+        let relayer = token_account(fee_mint);
+        let err = validate_destination_mint(&pool, &relayer, PayoutMint::Fee).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::InvalidMint));
+        pool.set_fee_mint(fee_mint, PoolConfig::FEE_RATE_SCALE);
+        assert!(validate_destination_mint(&pool, &relayer, PayoutMint::Fee).is_ok());
+        assert!(validate_destination_mint(&pool, &recipient, PayoutMint::Fee).is_err());
+        assert!(validate_destination_mint(&pool, &relayer, PayoutMint::Pool).is_err());
+    }
+
+    #[test]
+    fn test_cpi_withdraw_rejected_when_disallowed() {
+        assert!(require_top_level_invocation(TRANSACTION_LEVEL_STACK_HEIGHT).is_ok());
+        // Invoked by another program, or re-entered from one of ours
+        for stack_height in [TRANSACTION_LEVEL_STACK_HEIGHT + 1, TRANSACTION_LEVEL_STACK_HEIGHT + 3] {
+            let err = require_top_level_invocation(stack_height).unwrap_err();
+            assert_eq!(err, error!(PrivacyError::CpiWithdrawDisallowed));
+        }
+
+        let mut pool = new_pool();
+        assert!(!pool.disallow_cpi_withdraw);
+        pool.set_disallow_cpi_withdraw(true);
+        assert!(pool.disallow_cpi_withdraw);
+    }
+
+    #[test]
+    fn test_zero_net_withdrawal_gated_by_pool_flag() {
+        let mut pool = new_pool();
+        assert!(!pool.allow_zero_net_withdrawal);
+
+        // Blocked by default, whatever the fee cap would allow
+        let err = pool.require_nonzero_net(1_000, 1_000).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::ZeroNetWithdrawal));
+        assert!(pool.require_nonzero_net(1_000, 999).is_ok());
+        assert!(pool.require_nonzero_net(1_000, 0).is_ok());
+
+        pool.set_allow_zero_net_withdrawal(true);
+        assert!(pool.require_nonzero_net(1_000, 1_000).is_ok());
+        // Explicitly permitted, but the relayer fee cap still applies
+        let err = validate_relayer_fee(1_000, 1_000).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::RelayerFeeExceedsAmount));
+    }
+
+    #[test]
+    fn test_withdraw_from_empty_tree_fails_early() {
+        use crate::crypto::TreeHash;
+
+        let mut tree = new_tree(4, TreeHash::Keccak);
+
+        // The empty root is known, but there is nothing to withdraw
+        assert!(tree.is_known_root(&tree.current_root));
+        let err = require_nonempty_tree(&tree).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::EmptyPool));
+
+        tree.insert_leaf([1u8; 32], 1).unwrap();
+        assert!(require_nonempty_tree(&tree).is_ok());
+    }
+
+    #[test]
+    fn test_replay_fails_with_nullifier_already_spent() {
+        use anchor_lang::Discriminator;
+
+        let key = Pubkey::new_unique();
+        let system_program = system_program::ID;
+        let mut lamports = 0;
+        let mut empty = vec![];
+        let fresh = AccountInfo::new(&key, false, true, &mut lamports, &mut empty, &system_program, false, 0);
+        assert!(require_nullifier_unspent(&fresh).is_ok());
+
+        let mut spent_lamports = 1_000_000;
+        let mut data = vec![0u8; SpentNullifier::LEN];
+        data[..8].copy_from_slice(&SpentNullifier::DISCRIMINATOR);
+        let spent = AccountInfo::new(&key, false, true, &mut spent_lamports, &mut data, &crate::ID, false, 0);
+        let err = require_nullifier_unspent(&spent).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::NullifierAlreadySpent));
+    }
+
+    #[test]
+    fn test_oversized_proof_rejected_before_nullifier_check() {
+        use anchor_lang::Discriminator;
+
+        let key = Pubkey::new_unique();
+        let mut lamports = 1_000_000;
+        let mut data = vec![0u8; SpentNullifier::LEN];
+        data[..8].copy_from_slice(&SpentNullifier::DISCRIMINATOR);
+        let spent = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &crate::ID, false, 0);
+
+        // Length is checked first, even for a replayed nullifier
+        let oversized = vec![0u8; 10 * 1024];
+        let err = precheck_withdrawal(&oversized, 0, &spent).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::InvalidProofFormat));
+
+        let proof = [0u8; crate::crypto::PROOF_DATA_LEN];
+        let err = precheck_withdrawal(&proof, 0, &spent).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::NullifierAlreadySpent));
+    }
+
+    fn cooldown_account() -> RecipientCooldown {
+        let mut cooldown =
+            RecipientCooldown::try_deserialize_unchecked(&mut vec![0u8; RecipientCooldown::LEN].as_slice())
+                .unwrap();
+        cooldown.initialize(Pubkey::new_unique(), Pubkey::new_unique(), 255);
+        cooldown
+    }
+
+    #[test]
+    fn test_recipient_cooldown_disabled_by_default() {
+        let pool = new_pool();
+        assert!(enforce_recipient_cooldown(&pool, None, 10).is_ok());
+        assert!(enforce_recipient_cooldown(&pool, None, 11).is_ok());
+    }
+
+    #[test]
+    fn test_recipient_cooldown_enforced_at_boundary() {
+        let mut pool = new_pool();
+        pool.set_recipient_cooldown_slots(50);
+        let mut cooldown = cooldown_account();
+
+        // Account is mandatory once enabled
+        assert!(enforce_recipient_cooldown(&pool, None, 100).is_err());
+
+        assert!(enforce_recipient_cooldown(&pool, Some(&mut cooldown), 100).is_ok());
+        assert!(enforce_recipient_cooldown(&pool, Some(&mut cooldown), 149).is_err());
+        assert!(enforce_recipient_cooldown(&pool, Some(&mut cooldown), 150).is_ok());
+    }
+
+    #[test]
+    fn test_min_note_age_delays_withdrawal() {
+        use crate::crypto::TreeHash;
+
+        let mut tree = new_tree(4, TreeHash::Keccak);
+        let mut pool = new_pool();
+
+        // Deposit at slot 100; the note is only in roots from then on
+        tree.insert_leaf([1u8; 32], 100).unwrap();
+        let root = tree.current_root;
+
+        // Disabled by default
+        assert!(enforce_min_note_age(&pool, &tree, None, &root, 100).is_ok());
+
+        pool.set_min_note_age_slots(50);
+        let err = enforce_min_note_age(&pool, &tree, None, &root, 100).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::MerkleRootTooRecent));
+        assert!(enforce_min_note_age(&pool, &tree, None, &root, 149).is_err());
+        assert!(enforce_min_note_age(&pool, &tree, None, &root, 150).is_ok());
+
+        // A later deposit does not age the earlier root
+        tree.insert_leaf([2u8; 32], 140).unwrap();
+        assert!(enforce_min_note_age(&pool, &tree, None, &root, 150).is_ok());
+        assert!(enforce_min_note_age(&pool, &tree, None, &tree.current_root, 150).is_err());
+
+        let err = enforce_min_note_age(&pool, &tree, None, &[9u8; 32], 1_000).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::InvalidMerkleRoot));
+    }
+
+    #[test]
+    fn test_recipient_denylist() {
+        let mut pool = new_pool();
+        let denied = Pubkey::new_unique();
+        let allowed = Pubkey::new_unique();
+
+        // Zero root: no denylist, no account needed
+        assert!(enforce_recipient_denylist(&pool, None, &denied).is_ok());
+
+        let mut denylist =
+            RecipientDenylist::try_deserialize_unchecked(&mut vec![0u8; RecipientDenylist::LEN].as_slice())
+                .unwrap();
+        denylist.initialize(Pubkey::new_unique(), 255);
+        denylist.add(denied).unwrap();
+        pool.set_recipient_denylist_root(denylist.root());
+
+        assert!(enforce_recipient_denylist(&pool, Some(&denylist), &allowed).is_ok());
+        let err = enforce_recipient_denylist(&pool, Some(&denylist), &denied).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::RecipientDenied));
+
+        // Account is mandatory once enabled and must match the root
+        assert!(enforce_recipient_denylist(&pool, None, &allowed).is_err());
+        denylist.remove(&denied);
+        let err = enforce_recipient_denylist(&pool, Some(&denylist), &denied).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::RecipientDenylistMismatch));
+    }
+
+    #[test]
+    fn test_primary_vk_proof_unaffected_by_window() {
+        let vk = synthetic_vk(3);
+        let proof = synthetic_proof(3, &inputs());
+        assert!(verify_withdrawal_proof(&proof, &vk, None, &inputs(), false).unwrap());
+        assert!(verify_withdrawal_proof(&proof, &vk, Some(&synthetic_vk(4)), &inputs(), false).unwrap());
+    }
+
+    #[cfg(feature = "dev-mode")]
+    #[test]
+    fn test_dev_mode_withdrawal_requires_acknowledgement() {
+        use super::test_fixtures::{token_account_balance, token_transfers, WithdrawFixture};
+
+        let (relayer, recipient, nullifier_hash) = (Pubkey::new_unique(), Pubkey::new_unique(), [9u8; 32]);
+        let mut pool = WithdrawFixture::new(&relayer, &recipient, &nullifier_hash);
+        let proof = pool.proof(ZkPublicInputs::new(pool.merkle_root, nullifier_hash, recipient, 1_000, relayer, 10));
+        let run = |pool: &mut WithdrawFixture| {
+            let merkle_root = pool.merkle_root;
+            process_withdraw(
+                &mut pool.withdraw,
+                &pool.bumps,
+                proof.clone(),
+                merkle_root,
+                nullifier_hash,
+                recipient,
+                1_000,
+                relayer,
+                10,
+            )
+        };
+
+        // A dev-mode build refuses a pool that never opted in, before
+        // spending the nullifier or moving tokens
+        pool.withdraw.pool_config.set_dev_mode_acknowledged(false);
+        assert_eq!(run(&mut pool).unwrap_err(), error!(PrivacyError::DevModeNotAcknowledged));
+        assert!(!SpentNullifier::exists(&pool.withdraw.spent_nullifier).unwrap());
+        assert!(token_transfers().is_empty());
+
+        pool.withdraw.pool_config.set_dev_mode_acknowledged(true);
+        run(&mut pool).unwrap();
+        assert_eq!(token_account_balance(&mut pool.withdraw.recipient_token_account), 990);
+        assert!(SpentNullifier::exists(&pool.withdraw.spent_nullifier).unwrap());
+    }
 }
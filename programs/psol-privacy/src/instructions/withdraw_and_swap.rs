@@ -0,0 +1,424 @@
+//! Withdraw And Swap Instruction
+//!
+//! Withdrawal whose net amount is swapped into a different SPL token in
+//! the same instruction. The vault pays the recipient's pool-mint account
+//! (the intermediate account), then the recipient's tokens are traded on the
+//! pool's whitelisted swap route into `output_token_account`.
+//!
+//! Only the SPL Token Swap `Swap` interface is supported. The DEX program
+//! and DEX pool must match `PoolConfig.swap_program` / `swap_pool`, and the
+//! recipient signs so the swap can move its intermediate tokens.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke;
+use anchor_spl::token::TokenAccount;
+
+use crate::error::PrivacyError;
+use crate::events::WithdrawSwapped;
+use crate::instructions::withdraw::{self, *};
+
+/// SPL Token Swap `SwapInstruction::Swap` tag.
+pub const TOKEN_SWAP_SWAP_TAG: u8 = 1;
+
+#[derive(Accounts)]
+#[instruction(
+    proof_data: Vec<u8>,
+    merkle_root: [u8; 32],
+    nullifier_hash: [u8; 32],
+    recipient: Pubkey,
+)]
+pub struct WithdrawAndSwap<'info> {
+    /// `recipient_token_account` is the intermediate account fed to the swap
+    pub withdraw: Withdraw<'info>,
+
+    /// Swap user transfer authority over the intermediate account
+    #[account(address = recipient @ PrivacyError::RecipientMismatch)]
+    pub recipient_authority: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = output_token_account.owner == recipient @ PrivacyError::RecipientMismatch,
+        constraint = output_token_account.mint != withdraw.pool_config.token_mint @ PrivacyError::InvalidMint,
+    )]
+    pub output_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: Validated against `PoolConfig.swap_program`
+    #[account(executable)]
+    pub swap_program: UncheckedAccount<'info>,
+
+    /// CHECK: Validated against `PoolConfig.swap_pool` and owned by `swap_program`
+    pub swap_pool: UncheckedAccount<'info>,
+
+    /// CHECK: DEX pool authority, validated by the swap program
+    pub swap_authority: UncheckedAccount<'info>,
+
+    /// CHECK: DEX reserve of the pool mint, validated by the swap program
+    #[account(mut)]
+    pub swap_source: UncheckedAccount<'info>,
+
+    /// CHECK: DEX reserve of the output mint, validated by the swap program
+    #[account(mut)]
+    pub swap_destination: UncheckedAccount<'info>,
+
+    /// CHECK: DEX LP mint, validated by the swap program
+    #[account(mut)]
+    pub swap_lp_mint: UncheckedAccount<'info>,
+
+    /// CHECK: DEX fee account, validated by the swap program
+    #[account(mut)]
+    pub swap_fee_account: UncheckedAccount<'info>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handler(
+    ctx: Context<WithdrawAndSwap>,
+    proof_data: Vec<u8>,
+    merkle_root: [u8; 32],
+    nullifier_hash: [u8; 32],
+    recipient: Pubkey,
+    amount: u64,
+    relayer: Pubkey,
+    relayer_fee: u64,
+    min_amount_out: u64,
+) -> Result<()> {
+    process_withdraw_and_swap(
+        ctx.accounts,
+        &ctx.bumps,
+        proof_data,
+        merkle_root,
+        nullifier_hash,
+        recipient,
+        amount,
+        relayer,
+        relayer_fee,
+        min_amount_out,
+    )
+}
+
+/// Withdraw-and-swap flow: check the swap route, run the withdrawal into
+/// the intermediate account and swap it, measuring what reached
+/// `output_token_account`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn process_withdraw_and_swap<'info>(
+    accounts: &mut WithdrawAndSwap<'info>,
+    bumps: &WithdrawAndSwapBumps,
+    proof_data: Vec<u8>,
+    merkle_root: [u8; 32],
+    nullifier_hash: [u8; 32],
+    recipient: Pubkey,
+    amount: u64,
+    relayer: Pubkey,
+    relayer_fee: u64,
+    min_amount_out: u64,
+) -> Result<()> {
+    accounts.withdraw.pool_config.require_swap_route(
+        &accounts.swap_program.key(),
+        &accounts.swap_pool.key(),
+        accounts.swap_pool.owner,
+    )?;
+
+    let amount_in = amount
+        .checked_sub(relayer_fee)
+        .ok_or(error!(PrivacyError::ArithmeticOverflow))?;
+    require!(amount_in > 0, PrivacyError::InvalidAmount);
+
+    withdraw::process_withdraw(
+        &mut accounts.withdraw,
+        &bumps.withdraw,
+        proof_data,
+        merkle_root,
+        nullifier_hash,
+        recipient,
+        amount,
+        relayer,
+        relayer_fee,
+    )?;
+
+    let balance_before = accounts.output_token_account.amount;
+
+    let ix = token_swap_instruction(
+        &accounts.swap_program.key(),
+        &TokenSwapAccounts {
+            swap_pool: accounts.swap_pool.key(),
+            swap_authority: accounts.swap_authority.key(),
+            user_authority: accounts.recipient_authority.key(),
+            source: accounts.withdraw.recipient_token_account.key(),
+            swap_source: accounts.swap_source.key(),
+            swap_destination: accounts.swap_destination.key(),
+            destination: accounts.output_token_account.key(),
+            lp_mint: accounts.swap_lp_mint.key(),
+            fee_account: accounts.swap_fee_account.key(),
+            token_program: accounts.withdraw.token_program.key(),
+        },
+        amount_in,
+        min_amount_out,
+    );
+    invoke(
+        &ix,
+        &[
+            accounts.swap_pool.to_account_info(),
+            accounts.swap_authority.to_account_info(),
+            accounts.recipient_authority.to_account_info(),
+            accounts.withdraw.recipient_token_account.to_account_info(),
+            accounts.swap_source.to_account_info(),
+            accounts.swap_destination.to_account_info(),
+            accounts.output_token_account.to_account_info(),
+            accounts.swap_lp_mint.to_account_info(),
+            accounts.swap_fee_account.to_account_info(),
+            accounts.withdraw.token_program.to_account_info(),
+            accounts.swap_program.to_account_info(),
+        ],
+    )?;
+
+    // Don't trust the DEX to honour min_amount_out; measure what arrived
+    accounts.output_token_account.reload()?;
+    let amount_out =
+        check_swap_output(balance_before, accounts.output_token_account.amount, min_amount_out)?;
+
+    emit!(WithdrawSwapped {
+        pool: accounts.withdraw.pool_config.key(),
+        nullifier_hash,
+        swap_program: accounts.swap_program.key(),
+        output_mint: accounts.output_token_account.mint,
+        amount_in,
+        amount_out,
+    });
+
+    msg!("Withdraw and swap successful: {} in, {} out", amount_in, amount_out);
+    Ok(())
+}
+
+/// Account keys for an SPL Token Swap `Swap` instruction, in CPI order.
+pub struct TokenSwapAccounts {
+    pub swap_pool: Pubkey,
+    pub swap_authority: Pubkey,
+    pub user_authority: Pubkey,
+    pub source: Pubkey,
+    pub swap_source: Pubkey,
+    pub swap_destination: Pubkey,
+    pub destination: Pubkey,
+    pub lp_mint: Pubkey,
+    pub fee_account: Pubkey,
+    pub token_program: Pubkey,
+}
+
+/// Build an SPL Token Swap `Swap { amount_in, minimum_amount_out }`.
+pub fn token_swap_instruction(
+    swap_program: &Pubkey,
+    accounts: &TokenSwapAccounts,
+    amount_in: u64,
+    minimum_amount_out: u64,
+) -> Instruction {
+    let mut data = Vec::with_capacity(17);
+    data.push(TOKEN_SWAP_SWAP_TAG);
+    data.extend_from_slice(&amount_in.to_le_bytes());
+    data.extend_from_slice(&minimum_amount_out.to_le_bytes());
+
+    Instruction {
+        program_id: *swap_program,
+        accounts: vec![
+            AccountMeta::new_readonly(accounts.swap_pool, false),
+            AccountMeta::new_readonly(accounts.swap_authority, false),
+            AccountMeta::new_readonly(accounts.user_authority, true),
+            AccountMeta::new(accounts.source, false),
+            AccountMeta::new(accounts.swap_source, false),
+            AccountMeta::new(accounts.swap_destination, false),
+            AccountMeta::new(accounts.destination, false),
+            AccountMeta::new(accounts.lp_mint, false),
+            AccountMeta::new(accounts.fee_account, false),
+            AccountMeta::new_readonly(accounts.token_program, false),
+        ],
+        data,
+    }
+}
+
+/// Amount received by the output account, enforcing `>= min_amount_out`.
+pub fn check_swap_output(balance_before: u64, balance_after: u64, min_amount_out: u64) -> Result<u64> {
+    let received = balance_after
+        .checked_sub(balance_before)
+        .ok_or(error!(PrivacyError::SwapSlippageExceeded))?;
+    require!(received >= min_amount_out, PrivacyError::SwapSlippageExceeded);
+    Ok(received)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::ZkPublicInputs;
+    use crate::instructions::withdraw::test_fixtures::*;
+    use crate::state::SpentNullifier;
+    use solana_program::entrypoint::ProgramResult;
+
+    fn swap_accounts() -> TokenSwapAccounts {
+        TokenSwapAccounts {
+            swap_pool: Pubkey::new_unique(),
+            swap_authority: Pubkey::new_unique(),
+            user_authority: Pubkey::new_unique(),
+            source: Pubkey::new_unique(),
+            swap_source: Pubkey::new_unique(),
+            swap_destination: Pubkey::new_unique(),
+            destination: Pubkey::new_unique(),
+            lp_mint: Pubkey::new_unique(),
+            fee_account: Pubkey::new_unique(),
+            token_program: anchor_spl::token::ID,
+        }
+    }
+
+    /// Stand-in for the DEX: a constant-price pool with a fee in bps.
+    fn mock_swap(amount_in: u64, price_num: u64, price_den: u64, fee_bps: u64) -> u64 {
+        let gross = amount_in * price_num / price_den;
+        gross - gross * fee_bps / 10_000
+    }
+
+    #[test]
+    fn test_swap_instruction_layout() {
+        let program = Pubkey::new_unique();
+        let accounts = swap_accounts();
+        let ix = token_swap_instruction(&program, &accounts, 1_000, 950);
+
+        assert_eq!(ix.program_id, program);
+        assert_eq!(ix.data[0], TOKEN_SWAP_SWAP_TAG);
+        assert_eq!(ix.data[1..9], 1_000u64.to_le_bytes());
+        assert_eq!(ix.data[9..17], 950u64.to_le_bytes());
+
+        assert_eq!(ix.accounts.len(), 10);
+        assert_eq!(ix.accounts[2].pubkey, accounts.user_authority);
+        assert!(ix.accounts[2].is_signer);
+        assert_eq!(ix.accounts[3].pubkey, accounts.source);
+        assert_eq!(ix.accounts[6].pubkey, accounts.destination);
+        assert!(ix.accounts[6].is_writable);
+        assert_eq!(ix.accounts.iter().filter(|m| m.is_signer).count(), 1);
+    }
+
+    #[test]
+    fn test_swap_within_slippage_accepted() {
+        let before = 500;
+        let out = mock_swap(1_000, 2, 1, 30);
+        assert_eq!(check_swap_output(before, before + out, 1_990).unwrap(), out);
+    }
+
+    #[test]
+    fn test_swap_below_min_out_rejected() {
+        let before = 500;
+        // Price moved against the user between quote and execution
+        let out = mock_swap(1_000, 19, 10, 30);
+        assert!(check_swap_output(before, before + out, 1_990).is_err());
+    }
+
+    #[test]
+    fn test_swap_balance_decrease_rejected() {
+        assert!(check_swap_output(500, 400, 0).is_err());
+    }
+
+    /// Stand-in DEX program: takes `amount_in` into its source reserve and
+    /// pays out twice that from its destination reserve, ignoring
+    /// `minimum_amount_out` as a malicious DEX could.
+    fn doubling_dex(ix: &Instruction, accounts: &[AccountInfo]) -> ProgramResult {
+        let account = |i: usize| accounts.iter().find(|info| *info.key == ix.accounts[i].pubkey).unwrap();
+        assert!(account(2).is_signer);
+        let amount_in = u64::from_le_bytes(ix.data[1..9].try_into().unwrap());
+        move_tokens(account(3), account(4), amount_in)?;
+        move_tokens(account(5), account(6), 2 * amount_in)
+    }
+
+    /// A withdrawal of 100_000 less a 1_000 fee, swapped on `doubling_dex`.
+    struct WithdrawSwap {
+        accounts: WithdrawAndSwap<'static>,
+        bumps: WithdrawAndSwapBumps,
+        merkle_root: [u8; 32],
+        proof_data: Vec<u8>,
+        recipient: Pubkey,
+        relayer: Pubkey,
+    }
+
+    impl WithdrawSwap {
+        const AMOUNT: u64 = 100_000;
+        const FEE: u64 = 1_000;
+        const AMOUNT_IN: u64 = Self::AMOUNT - Self::FEE;
+        const DEX_RESERVE: u64 = 1_000_000;
+
+        fn new() -> Self {
+            let (recipient, relayer) = (Pubkey::new_unique(), Pubkey::new_unique());
+            let mut pool = WithdrawFixture::new(&relayer, &recipient, &[9u8; 32]);
+            let (dex, dex_pool, dex_authority) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+            pool.withdraw.pool_config.set_swap_route(dex, dex_pool);
+            install_program(dex, doubling_dex);
+
+            let proof_data = pool.proof(ZkPublicInputs::new(
+                pool.merkle_root,
+                [9u8; 32],
+                recipient,
+                Self::AMOUNT,
+                relayer,
+                Self::FEE,
+            ));
+            let (pool_mint, output_mint) = (pool.withdraw.pool_config.token_mint, Pubkey::new_unique());
+            let unchecked = |info| UncheckedAccount::try_from(info);
+            Self {
+                accounts: WithdrawAndSwap {
+                    withdraw: pool.withdraw,
+                    recipient_authority: Signer::try_from(account_info(recipient, System::id(), Vec::new(), true, false))
+                        .unwrap(),
+                    output_token_account: token_account(output_mint, recipient, 0),
+                    swap_program: unchecked(account_info(dex, Pubkey::default(), Vec::new(), false, true)),
+                    swap_pool: unchecked(account_info(dex_pool, dex, Vec::new(), false, false)),
+                    swap_authority: unchecked(account_info(dex_authority, dex, Vec::new(), false, false)),
+                    swap_source: unchecked(token_account_info(pool_mint, dex_authority, 0)),
+                    swap_destination: unchecked(token_account_info(output_mint, dex_authority, Self::DEX_RESERVE)),
+                    swap_lp_mint: unchecked(account_info(Pubkey::new_unique(), dex, Vec::new(), false, false)),
+                    swap_fee_account: unchecked(account_info(Pubkey::new_unique(), dex, Vec::new(), false, false)),
+                },
+                bumps: WithdrawAndSwapBumps { withdraw: pool.bumps },
+                merkle_root: pool.merkle_root,
+                proof_data,
+                recipient,
+                relayer,
+            }
+        }
+
+        fn run(&mut self, min_amount_out: u64) -> Result<()> {
+            process_withdraw_and_swap(
+                &mut self.accounts,
+                &self.bumps,
+                self.proof_data.clone(),
+                self.merkle_root,
+                [9u8; 32],
+                self.recipient,
+                Self::AMOUNT,
+                self.relayer,
+                Self::FEE,
+                min_amount_out,
+            )
+        }
+    }
+
+    #[test]
+    fn test_handler_swaps_proceeds_to_recipient() {
+        let mut swap = WithdrawSwap::new();
+        swap.run(2 * WithdrawSwap::AMOUNT_IN).unwrap();
+
+        // The whole net withdrawal went through the DEX into the output mint
+        let accounts = &mut swap.accounts;
+        assert_eq!(token_account_balance(&mut accounts.output_token_account), 2 * WithdrawSwap::AMOUNT_IN);
+        assert_eq!(token_account_balance(&mut accounts.withdraw.recipient_token_account), 0);
+        assert_eq!(token_account_balance(&mut accounts.withdraw.relayer_token_account), WithdrawSwap::FEE);
+        assert_eq!(token_account_balance(&mut accounts.withdraw.vault), VAULT_BALANCE - WithdrawSwap::AMOUNT);
+        assert!(SpentNullifier::exists(&accounts.withdraw.spent_nullifier).unwrap());
+    }
+
+    #[test]
+    fn test_handler_reverts_short_swap_output() {
+        // The DEX ignores min_amount_out; the handler measures and fails
+        let mut swap = WithdrawSwap::new();
+        let err = swap.run(2 * WithdrawSwap::AMOUNT_IN + 1).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::SwapSlippageExceeded));
+
+        // A route other than the whitelisted one never reaches the withdrawal
+        let mut swap = WithdrawSwap::new();
+        swap.accounts.withdraw.pool_config.set_swap_route(Pubkey::new_unique(), Pubkey::new_unique());
+        assert_eq!(swap.run(0).unwrap_err(), error!(PrivacyError::InvalidSwapRoute));
+        assert!(!SpentNullifier::exists(&swap.accounts.withdraw.spent_nullifier).unwrap());
+        assert!(token_transfers().is_empty());
+    }
+}
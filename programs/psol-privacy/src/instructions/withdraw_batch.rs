@@ -0,0 +1,452 @@
+//! Withdraw Batch Instruction
+//!
+//! Several withdrawals submitted by one relayer in a single instruction.
+//! Each is verified exactly like `withdraw`, but payouts are settled once at
+//! the end: one vault transfer per distinct token account, so the relayer
+//! receives its fees for the whole batch in a single transfer.
+//!
+//! The first withdrawal uses the `withdraw` accounts. Every further entry
+//! passes its nullifier PDA and recipient token account, in that order, as
+//! writable `remaining_accounts`.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+
+use crate::crypto::ZkPublicInputs;
+use crate::error::PrivacyError;
+use crate::instructions::deposit::check_tvl_invariant;
+use crate::instructions::withdraw::*;
+use crate::state::{PayoutMint, SpentNullifier};
+
+/// Withdrawals per batch, the first included. Each proof takes 256 bytes
+/// of the transaction, so more would not fit anyway.
+pub const MAX_WITHDRAWAL_BATCH: usize = 4;
+
+/// A withdrawal after the first in a batch; its fee goes to the batch's
+/// relayer.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BatchedWithdrawal {
+    pub proof_data: Vec<u8>,
+    pub merkle_root: [u8; 32],
+    pub nullifier_hash: [u8; 32],
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub relayer_fee: u64,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawBatch<'info> {
+    /// First withdrawal; `relayer_token_account` receives every fee
+    pub withdraw: Withdraw<'info>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, WithdrawBatch<'info>>,
+    proof_data: Vec<u8>,
+    merkle_root: [u8; 32],
+    nullifier_hash: [u8; 32],
+    recipient: Pubkey,
+    amount: u64,
+    relayer: Pubkey,
+    relayer_fee: u64,
+    batch: Vec<BatchedWithdrawal>,
+) -> Result<()> {
+    let first = BatchedWithdrawal {
+        proof_data,
+        merkle_root,
+        nullifier_hash,
+        recipient,
+        amount,
+        relayer_fee,
+    };
+    process_withdraw_batch(
+        &mut ctx.accounts.withdraw,
+        &ctx.bumps.withdraw,
+        ctx.remaining_accounts,
+        relayer,
+        first,
+        batch,
+    )
+}
+
+/// Verify `first` and every entry of `rest`, marking each nullifier spent,
+/// then pay out the whole batch through `batch_withdrawal_payouts`.
+///
+/// Nothing leaves the vault until every entry has verified.
+///
+/// # Errors
+/// * `LimitExceeded` if the batch exceeds `MAX_WITHDRAWAL_BATCH`
+/// * `InvalidBatchAccounts` if `remaining_accounts` does not hold a
+///   writable nullifier PDA and recipient token account per entry of `rest`
+/// * `RecipientCooldownRequired` for a batch of several withdrawals from a
+///   pool with a recipient cooldown, whose per-recipient cooldown accounts
+///   cannot be passed
+/// * `InsufficientBalance` if the vault cannot cover the batch
+/// * Any error of `verify_withdrawal_inputs` for any entry
+pub(crate) fn process_withdraw_batch<'info>(
+    accounts: &mut Withdraw<'info>,
+    bumps: &WithdrawBumps,
+    remaining_accounts: &'info [AccountInfo<'info>],
+    relayer: Pubkey,
+    first: BatchedWithdrawal,
+    rest: Vec<BatchedWithdrawal>,
+) -> Result<()> {
+    require!(rest.len() < MAX_WITHDRAWAL_BATCH, PrivacyError::LimitExceeded);
+    require!(
+        remaining_accounts.len() == 2 * rest.len(),
+        PrivacyError::InvalidBatchAccounts
+    );
+    require!(
+        rest.is_empty() || accounts.pool_config.recipient_cooldown_slots == 0,
+        PrivacyError::RecipientCooldownRequired
+    );
+
+    let rent = Rent::get()?;
+    let mut withdrawals = Vec::with_capacity(rest.len() + 1);
+    let mut events = Vec::with_capacity(rest.len() + 1);
+    let mut total_amount: u64 = 0;
+    let entry_accounts = std::iter::once(None).chain(remaining_accounts.chunks_exact(2).map(Some));
+
+    for (entry, entry_accounts) in std::iter::once(first).chain(rest).zip(entry_accounts) {
+        let bumps = match entry_accounts {
+            Some(entry_accounts) => WithdrawBumps {
+                spent_nullifier: bind_batch_entry(accounts, &entry, &entry_accounts[0], &entry_accounts[1])?,
+                merkle_tree: bumps.merkle_tree,
+                vault: bumps.vault,
+            },
+            None => WithdrawBumps { ..*bumps },
+        };
+
+        // Each entry is checked against the vault alone; the batch must fit too
+        total_amount = total_amount
+            .checked_add(entry.amount)
+            .ok_or(error!(PrivacyError::ArithmeticOverflow))?;
+        require!(
+            accounts.vault.amount >= total_amount,
+            PrivacyError::InsufficientBalance
+        );
+
+        let public_inputs = ZkPublicInputs::new(
+            entry.merkle_root,
+            entry.nullifier_hash,
+            entry.recipient,
+            entry.amount,
+            relayer,
+            entry.relayer_fee,
+        );
+        let (clock, public_inputs) =
+            verify_withdrawal_inputs(accounts, &bumps, &entry.proof_data, public_inputs, &relayer)?;
+        withdrawals.push((
+            accounts.recipient_token_account.to_account_info(),
+            accounts.relayer_token_account.to_account_info(),
+            entry.amount,
+            entry.relayer_fee,
+        ));
+
+        let pool_config = &mut accounts.pool_config;
+        pool_config.record_withdrawal(entry.amount)?;
+        events.push(withdraw_event(
+            pool_config.key(),
+            pool_config.nullifier_generation,
+            accounts.spent_nullifier.key(),
+            &public_inputs,
+            &rent,
+            clock.unix_timestamp,
+        )?);
+    }
+
+    let payouts = batch_withdrawal_payouts(withdrawals)?;
+    pay_from_vault(accounts, payouts)?;
+
+    accounts.vault.reload()?;
+    check_tvl_invariant(accounts.pool_config.key(), &accounts.pool_config, accounts.vault.amount);
+
+    let count = events.len();
+    for event in events {
+        emit!(event);
+    }
+    if accounts.pool_config.verbose_logging {
+        msg!("Batch of {} withdrawals successful", count);
+    }
+    Ok(())
+}
+
+/// Point `accounts` at a batch entry's nullifier PDA and recipient token
+/// account, after the checks `Withdraw` applies to its own. Returns the
+/// nullifier PDA's bump.
+fn bind_batch_entry<'info>(
+    accounts: &mut Withdraw<'info>,
+    entry: &BatchedWithdrawal,
+    spent_nullifier: &'info AccountInfo<'info>,
+    recipient_token_account: &'info AccountInfo<'info>,
+) -> Result<u8> {
+    let (address, bump) =
+        SpentNullifier::find_pda(&crate::ID, &accounts.pool_config.key(), &entry.nullifier_hash);
+    require!(
+        spent_nullifier.key() == address && spent_nullifier.is_writable,
+        PrivacyError::InvalidBatchAccounts
+    );
+    require!(
+        recipient_token_account.is_writable,
+        PrivacyError::InvalidBatchAccounts
+    );
+
+    let recipient_token_account = Account::<TokenAccount>::try_from(recipient_token_account)?;
+    validate_destination_mint(&accounts.pool_config, &recipient_token_account, PayoutMint::Pool)?;
+    require!(
+        recipient_token_account.owner == entry.recipient,
+        PrivacyError::RecipientMismatch
+    );
+
+    accounts.spent_nullifier = UncheckedAccount::try_from(spent_nullifier);
+    accounts.recipient_token_account = recipient_token_account;
+    Ok(bump)
+}
+
+/// Vault payouts for a batch of `(recipient_token_account,
+/// relayer_token_account, amount, relayer_fee)` withdrawals, as one
+/// transfer per distinct token account in first-seen order.
+///
+/// A relayer named by every entry is paid the sum of its fees once, rather
+/// than once per withdrawal.
+///
+/// # Errors
+/// * `ArithmeticOverflow` if a fee exceeds its amount or a total overflows
+pub fn batch_withdrawal_payouts<K: Key>(
+    withdrawals: impl IntoIterator<Item = (K, K, u64, u64)>,
+) -> Result<Vec<(K, u64)>> {
+    let mut legs = Vec::new();
+    for (recipient_token_account, relayer_token_account, amount, relayer_fee) in withdrawals {
+        legs.extend(withdrawal_payouts(
+            recipient_token_account,
+            relayer_token_account,
+            amount,
+            relayer_fee,
+        )?);
+    }
+    merge_payouts(legs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::withdraw::test_fixtures::*;
+
+    const FEE: u64 = 1_000;
+
+    #[test]
+    fn test_batch_settles_relayer_fees_once() {
+        let relayer = Pubkey::new_unique();
+        let recipients: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let batch: Vec<_> = recipients
+            .iter()
+            .zip([(1_000, 50), (2_000, 0), (500, 25), (7_000, 300)])
+            .map(|(recipient, (amount, fee))| (*recipient, relayer, amount, fee))
+            .collect();
+
+        let payouts = batch_withdrawal_payouts(batch.clone()).unwrap();
+
+        // The relayer's single transfer equals the sum of individual fees
+        let individual: u64 = batch
+            .iter()
+            .flat_map(|w| withdrawal_payouts(w.0, w.1, w.2, w.3).unwrap())
+            .filter(|(account, _)| *account == relayer)
+            .map(|(_, fee)| fee)
+            .sum();
+        let relayer_payouts: Vec<_> = payouts.iter().filter(|(account, _)| *account == relayer).collect();
+        assert_eq!(relayer_payouts, vec![&(relayer, 375)]);
+        assert_eq!(individual, 375);
+
+        // One transfer per destination, and the vault pays out the same total
+        assert_eq!(payouts.len(), 5);
+        assert_eq!(payouts.iter().map(|(_, p)| p).sum::<u64>(), 10_500);
+        assert_eq!(payouts[0], (recipients[0], 950));
+    }
+
+    #[test]
+    fn test_batch_rejects_fee_above_amount() {
+        let (recipient, relayer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let batch = [(recipient, relayer, 1_000, 50), (recipient, relayer, 10, 11)];
+        let err = batch_withdrawal_payouts(batch).err().unwrap();
+        assert_eq!(err, error!(PrivacyError::ArithmeticOverflow));
+        assert!(batch_withdrawal_payouts(Vec::<(Pubkey, Pubkey, u64, u64)>::new()).unwrap().is_empty());
+    }
+
+    /// A pool with one note, relayed by `relayer` for a first withdrawal of
+    /// `amount` to `recipient`.
+    struct Batch {
+        pool: WithdrawFixture,
+        relayer: Pubkey,
+        first: BatchedWithdrawal,
+        rest: Vec<BatchedWithdrawal>,
+        remaining_accounts: Vec<AccountInfo<'static>>,
+    }
+
+    impl Batch {
+        fn new(amount: u64) -> Self {
+            let (relayer, recipient) = (Pubkey::new_unique(), Pubkey::new_unique());
+            let pool = WithdrawFixture::new(&relayer, &recipient, &[9u8; 32]);
+            let mut batch = Self {
+                pool,
+                relayer,
+                first: BatchedWithdrawal {
+                    proof_data: Vec::new(),
+                    merkle_root: [0u8; 32],
+                    nullifier_hash: [9u8; 32],
+                    recipient,
+                    amount,
+                    relayer_fee: FEE,
+                },
+                rest: Vec::new(),
+                remaining_accounts: Vec::new(),
+            };
+            batch.first = batch.entry(batch.first.clone());
+            batch
+        }
+
+        /// `entry` against the pool's root with a valid proof.
+        fn entry(&self, entry: BatchedWithdrawal) -> BatchedWithdrawal {
+            let merkle_root = self.pool.merkle_root;
+            let proof_data = self.pool.proof(ZkPublicInputs::new(
+                merkle_root,
+                entry.nullifier_hash,
+                entry.recipient,
+                entry.amount,
+                self.relayer,
+                entry.relayer_fee,
+            ));
+            BatchedWithdrawal { proof_data, merkle_root, ..entry }
+        }
+
+        /// Queue a withdrawal of `amount` to a fresh recipient, with its
+        /// accounts. Returns the recipient token account.
+        fn push(&mut self, nullifier_hash: [u8; 32], amount: u64) -> AccountInfo<'static> {
+            let recipient = Pubkey::new_unique();
+            let pool = self.pool.withdraw.pool_config.key();
+            let recipient_token_account =
+                token_account(self.pool.withdraw.pool_config.token_mint, recipient, 0).to_account_info();
+            let entry = self.entry(BatchedWithdrawal {
+                proof_data: Vec::new(),
+                merkle_root: [0u8; 32],
+                nullifier_hash,
+                recipient,
+                amount,
+                relayer_fee: FEE,
+            });
+            self.rest.push(entry);
+            self.remaining_accounts.push(unspent_nullifier(&pool, &nullifier_hash).0.clone());
+            self.remaining_accounts.push(recipient_token_account.clone());
+            recipient_token_account
+        }
+
+        fn run(&mut self) -> Result<()> {
+            let remaining_accounts = Box::leak(std::mem::take(&mut self.remaining_accounts).into_boxed_slice());
+            process_withdraw_batch(
+                &mut self.pool.withdraw,
+                &self.pool.bumps,
+                remaining_accounts,
+                self.relayer,
+                self.first.clone(),
+                std::mem::take(&mut self.rest),
+            )
+        }
+    }
+
+    fn balance(token_account: &AccountInfo) -> u64 {
+        TokenAccount::try_deserialize(&mut &token_account.data.borrow()[..]).unwrap().amount
+    }
+
+    #[test]
+    fn test_batch_pays_the_relayer_once() {
+        let mut batch = Batch::new(100_000);
+        let first_recipient = batch.pool.withdraw.recipient_token_account.to_account_info();
+        let relayer_token_account = batch.pool.withdraw.relayer_token_account.key();
+        let second = batch.push([10u8; 32], 200_000);
+        let third = batch.push([11u8; 32], 300_000);
+        let nullifiers = [batch.remaining_accounts[0].clone(), batch.remaining_accounts[2].clone()];
+
+        batch.run().unwrap();
+
+        // One transfer per recipient plus a single one carrying every fee
+        let transfers = token_transfers();
+        assert_eq!(transfers.len(), 4);
+        let fee_transfers: Vec<_> = transfers.iter().filter(|(to, _)| *to == relayer_token_account).collect();
+        assert_eq!(fee_transfers, vec![&(relayer_token_account, 3 * FEE)]);
+
+        let withdraw = &mut batch.pool.withdraw;
+        assert_eq!(token_account_balance(&mut withdraw.relayer_token_account), 3 * FEE);
+        assert_eq!(balance(&first_recipient), 100_000 - FEE);
+        assert_eq!(balance(&second), 200_000 - FEE);
+        assert_eq!(balance(&third), 300_000 - FEE);
+        assert_eq!(token_account_balance(&mut withdraw.vault), VAULT_BALANCE - 600_000);
+
+        // Every nullifier is spent and every withdrawal recorded
+        assert!(SpentNullifier::exists(&withdraw.spent_nullifier).unwrap());
+        assert!(nullifiers.iter().all(|n| SpentNullifier::exists(n).unwrap()));
+        assert_eq!(withdraw.pool_config.total_withdrawals, 3);
+        assert_eq!(withdraw.pool_config.total_value_withdrawn, 600_000);
+    }
+
+    #[test]
+    fn test_batch_pays_nothing_unless_every_entry_verifies() {
+        // The same note twice: the second spend fails after the first
+        // entry already verified
+        let mut batch = Batch::new(100_000);
+        batch.push([9u8; 32], 100_000);
+        batch.remaining_accounts[0] = batch.pool.withdraw.spent_nullifier.to_account_info();
+        assert_eq!(batch.run().err().unwrap(), error!(PrivacyError::NullifierAlreadySpent));
+        assert!(token_transfers().is_empty());
+
+        // A bad proof in the last entry
+        let mut batch = Batch::new(100_000);
+        batch.push([10u8; 32], 100_000);
+        batch.rest[0].amount += 1;
+        assert_eq!(batch.run().err().unwrap(), error!(PrivacyError::InvalidProof));
+        assert!(token_transfers().is_empty());
+
+        // Together the entries exceed the vault
+        let mut batch = Batch::new(VAULT_BALANCE / 2);
+        batch.push([10u8; 32], VAULT_BALANCE / 2 + 1);
+        assert_eq!(batch.run().err().unwrap(), error!(PrivacyError::InsufficientBalance));
+        assert!(token_transfers().is_empty());
+    }
+
+    #[test]
+    fn test_batch_entry_accounts_validated() {
+        // Missing accounts for an entry
+        let mut batch = Batch::new(100_000);
+        batch.push([10u8; 32], 100_000);
+        batch.remaining_accounts.pop();
+        assert_eq!(batch.run().err().unwrap(), error!(PrivacyError::InvalidBatchAccounts));
+
+        // Nullifier PDA of a different note
+        let mut batch = Batch::new(100_000);
+        batch.push([10u8; 32], 100_000);
+        let pool = batch.pool.withdraw.pool_config.key();
+        batch.remaining_accounts[0] = unspent_nullifier(&pool, &[11u8; 32]).0.clone();
+        assert_eq!(batch.run().err().unwrap(), error!(PrivacyError::InvalidBatchAccounts));
+
+        // Recipient token account owned by someone else
+        let mut batch = Batch::new(100_000);
+        batch.push([10u8; 32], 100_000);
+        let mint = batch.pool.withdraw.pool_config.token_mint;
+        batch.remaining_accounts[1] = token_account(mint, Pubkey::new_unique(), 0).to_account_info();
+        assert_eq!(batch.run().err().unwrap(), error!(PrivacyError::RecipientMismatch));
+
+        // Too many entries
+        let mut batch = Batch::new(1_000);
+        for i in 0..MAX_WITHDRAWAL_BATCH {
+            batch.push([20 + i as u8; 32], 1_000);
+        }
+        assert_eq!(batch.run().err().unwrap(), error!(PrivacyError::LimitExceeded));
+    }
+
+    #[test]
+    fn test_batch_requires_no_recipient_cooldown() {
+        let mut batch = Batch::new(100_000);
+        batch.pool.withdraw.pool_config.set_recipient_cooldown_slots(10);
+        batch.push([10u8; 32], 100_000);
+        assert_eq!(batch.run().err().unwrap(), error!(PrivacyError::RecipientCooldownRequired));
+    }
+}
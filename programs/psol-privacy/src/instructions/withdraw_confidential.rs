@@ -0,0 +1,102 @@
+//! Withdraw Confidential Instruction
+//!
+//! Withdrawal against the confidential-amount circuit, whose `amount` public
+//! input is `amount_commitment = Poseidon(amount, blinding)` instead of the
+//! cleartext amount. The circuit range-checks the amount and proves it is
+//! the note value; the program receives `amount` and `blinding` to pay out
+//! and checks they open the commitment, so the vault can only transfer the
+//! committed value.
+//!
+//! The pool's verification key must be for the confidential-amount circuit.
+//! With plain SPL transfers the payout still reveals the amount on-chain;
+//! the commitment keeps it out of the proof's public inputs so a
+//! confidential-transfer payout can later hide it end to end.
+
+use anchor_lang::prelude::*;
+
+use crate::crypto::{hash_amount_commitment, ZkPublicInputs};
+use crate::error::PrivacyError;
+use crate::instructions::withdraw::{self, *};
+
+#[allow(clippy::too_many_arguments)]
+pub fn handler(
+    ctx: Context<Withdraw>,
+    proof_data: Vec<u8>,
+    merkle_root: [u8; 32],
+    nullifier_hash: [u8; 32],
+    recipient: Pubkey,
+    amount: u64,
+    relayer: Pubkey,
+    relayer_fee: u64,
+    amount_blinding: [u8; 32],
+    amount_commitment: [u8; 32],
+) -> Result<()> {
+    check_amount_commitment(amount, &amount_blinding, &amount_commitment)?;
+
+    let public_inputs =
+        ZkPublicInputs::new(merkle_root, nullifier_hash, recipient, amount, relayer, relayer_fee)
+            .with_amount_commitment(amount_commitment);
+    withdraw::process_withdraw_inputs(ctx.accounts, &ctx.bumps, &proof_data, public_inputs, None)
+}
+
+/// Require `amount_commitment == Poseidon(amount, blinding)`.
+///
+/// # Errors
+/// * `AmountCommitmentMismatch` if the transfer amount is not the committed value
+pub fn check_amount_commitment(
+    amount: u64,
+    blinding: &[u8; 32],
+    amount_commitment: &[u8; 32],
+) -> Result<()> {
+    require!(
+        hash_amount_commitment(amount, blinding)? == *amount_commitment,
+        PrivacyError::AmountCommitmentMismatch
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blinding() -> [u8; 32] {
+        let mut blinding = [0x5au8; 32];
+        blinding[0] = 0;
+        blinding
+    }
+
+    #[test]
+    fn test_matching_amount_accepted() {
+        let commitment = hash_amount_commitment(1_000, &blinding()).unwrap();
+        assert!(check_amount_commitment(1_000, &blinding(), &commitment).is_ok());
+    }
+
+    #[test]
+    fn test_mismatched_amount_rejected() {
+        let commitment = hash_amount_commitment(1_000, &blinding()).unwrap();
+
+        let err = check_amount_commitment(1_001, &blinding(), &commitment).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::AmountCommitmentMismatch));
+
+        let mut other_blinding = blinding();
+        other_blinding[31] ^= 1;
+        assert!(check_amount_commitment(1_000, &other_blinding, &commitment).is_err());
+    }
+
+    #[test]
+    fn test_commitment_replaces_amount_input() {
+        let commitment = hash_amount_commitment(1_000, &blinding()).unwrap();
+        let inputs = ZkPublicInputs::new(
+            [1u8; 32],
+            [2u8; 32],
+            Pubkey::new_unique(),
+            1_000,
+            Pubkey::new_unique(),
+            0,
+        );
+        let confidential = inputs.clone().with_amount_commitment(commitment);
+
+        assert_eq!(confidential.to_field_elements().unwrap()[3].to_bytes(), commitment);
+        assert_ne!(confidential.hash(), inputs.hash());
+    }
+}
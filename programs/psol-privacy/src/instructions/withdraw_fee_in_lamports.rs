@@ -0,0 +1,311 @@
+//! Withdraw Fee In Lamports Instruction
+//!
+//! Withdrawal whose relayer is paid in SOL rather than the deposited asset,
+//! so relayers need no token account per pool mint. The recipient receives
+//! the full `amount` from the vault; the relayer receives
+//! `relayer_fee_lamports` from the pool's SOL fee reserve, capped by
+//! `max_relayer_fee_lamports`.
+//!
+//! The token `relayer_fee` public input is zero and the lamport fee is
+//! bound through `lamport_fee_relayer_binding`. Pools with a
+//! `min_relayer_fee` reject these withdrawals unless self-relayed.
+//!
+//! `withdraw.relayer_token_account` is validated but not paid.
+
+use anchor_lang::prelude::*;
+
+use crate::crypto::{lamport_fee_relayer_binding, ZkPublicInputs};
+use crate::error::PrivacyError;
+use crate::events::{RelayerFeePaidInLamports, WithdrawEvent};
+use crate::instructions::deposit::check_tvl_invariant;
+use crate::instructions::withdraw::{self, *};
+use crate::state::{PoolConfig, SolFeeReserve};
+
+#[derive(Accounts)]
+#[instruction(
+    proof_data: Vec<u8>,
+    merkle_root: [u8; 32],
+    nullifier_hash: [u8; 32],
+    recipient: Pubkey,
+    amount: u64,
+    relayer: Pubkey,
+)]
+pub struct WithdrawFeeInLamports<'info> {
+    pub withdraw: Withdraw<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"sol_fee_reserve", withdraw.pool_config.key().as_ref()],
+        bump = sol_fee_reserve.bump,
+        constraint = sol_fee_reserve.pool == withdraw.pool_config.key() @ PrivacyError::Unauthorized,
+    )]
+    pub sol_fee_reserve: Account<'info, SolFeeReserve>,
+
+    /// Relayer wallet credited with the lamport fee
+    #[account(mut, address = relayer @ PrivacyError::Unauthorized)]
+    pub relayer_wallet: SystemAccount<'info>,
+}
+
+/// Payouts of a fee-in-lamports withdrawal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeeInLamportsPayouts {
+    /// Pool mint, vault -> recipient
+    pub recipient_amount: u64,
+    /// Lamports, SOL fee reserve -> relayer
+    pub relayer_lamports: u64,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handler(
+    ctx: Context<WithdrawFeeInLamports>,
+    proof_data: Vec<u8>,
+    merkle_root: [u8; 32],
+    nullifier_hash: [u8; 32],
+    recipient: Pubkey,
+    amount: u64,
+    relayer: Pubkey,
+    relayer_fee_lamports: u64,
+) -> Result<()> {
+    process_withdraw_fee_in_lamports(
+        ctx.accounts,
+        &ctx.bumps,
+        proof_data,
+        merkle_root,
+        nullifier_hash,
+        recipient,
+        amount,
+        relayer,
+        relayer_fee_lamports,
+    )
+}
+
+/// Fee-in-lamports withdrawal flow: verify the proof against the lamport
+/// fee binding, pay the recipient from the vault and the relayer from the
+/// SOL fee reserve.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn process_withdraw_fee_in_lamports<'info>(
+    accounts: &mut WithdrawFeeInLamports<'info>,
+    bumps: &WithdrawFeeInLamportsBumps,
+    proof_data: Vec<u8>,
+    merkle_root: [u8; 32],
+    nullifier_hash: [u8; 32],
+    recipient: Pubkey,
+    amount: u64,
+    relayer: Pubkey,
+    relayer_fee_lamports: u64,
+) -> Result<()> {
+    let payouts =
+        fee_in_lamports_payouts(&accounts.withdraw.pool_config, amount, relayer_fee_lamports)?;
+    let rent = Rent::get()?;
+    let reserve = accounts.sol_fee_reserve.to_account_info();
+    require!(
+        SolFeeReserve::available(&reserve, &rent) >= payouts.relayer_lamports,
+        PrivacyError::InsufficientBalance
+    );
+    let relayer_binding = lamport_fee_relayer_binding(&relayer, relayer_fee_lamports);
+
+    let relayer_wallet = accounts.relayer_wallet.to_account_info();
+    let accounts = &mut accounts.withdraw;
+    let (clock, public_inputs) = withdraw::verify_withdrawal_inputs(
+        accounts,
+        &bumps.withdraw,
+        &proof_data,
+        ZkPublicInputs::new(merkle_root, nullifier_hash, recipient, amount, relayer_binding, 0),
+        &relayer,
+    )?;
+
+    let to = accounts.recipient_token_account.to_account_info();
+    withdraw::transfer_from_vault(accounts, to, payouts.recipient_amount)?;
+    if payouts.relayer_lamports > 0 {
+        SolFeeReserve::pay_out(&reserve, &relayer_wallet, payouts.relayer_lamports, &rent)?;
+    }
+
+    accounts.pool_config.record_withdrawal(amount)?;
+    accounts.vault.reload()?;
+    let pool_config = &accounts.pool_config;
+    check_tvl_invariant(pool_config.key(), pool_config, accounts.vault.amount);
+
+    emit!(WithdrawEvent {
+        relayer,
+        ..withdraw::withdraw_event(
+            pool_config.key(),
+            pool_config.nullifier_generation,
+            accounts.spent_nullifier.key(),
+            &public_inputs,
+            &rent,
+            clock.unix_timestamp,
+        )?
+    });
+    emit!(RelayerFeePaidInLamports {
+        pool: pool_config.key(),
+        nullifier_hash,
+        relayer,
+        fee_lamports: payouts.relayer_lamports,
+    });
+
+    msg!("Fee-in-lamports withdrawal successful");
+    Ok(())
+}
+
+/// Payouts of a withdrawal of `amount` whose relayer is paid
+/// `relayer_fee_lamports`.
+///
+/// # Errors
+/// * `LamportFeeNotEnabled` / `LimitExceeded` per
+///   `PoolConfig::require_lamport_fee_allowed`
+pub fn fee_in_lamports_payouts(
+    pool_config: &PoolConfig,
+    amount: u64,
+    relayer_fee_lamports: u64,
+) -> Result<FeeInLamportsPayouts> {
+    pool_config.require_lamport_fee_allowed(relayer_fee_lamports)?;
+    Ok(FeeInLamportsPayouts {
+        recipient_amount: amount,
+        relayer_lamports: relayer_fee_lamports,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::withdraw::test_fixtures::{
+        account_info, program_account, token_account_balance, token_transfers, WithdrawFixture,
+    };
+    use crate::state::pool_config::test_fixtures::new_pool;
+    use crate::state::SpentNullifier;
+
+    fn capped_pool(max_relayer_fee_lamports: u64) -> PoolConfig {
+        let mut pool = new_pool();
+        pool.set_max_relayer_fee_lamports(max_relayer_fee_lamports);
+        pool
+    }
+
+    /// A reserve account holding `spare` lamports above rent exemption.
+    fn reserve_with(pool: Pubkey, spare: u64) -> Account<'static, SolFeeReserve> {
+        let reserve = program_account(&SolFeeReserve { pool, bump: 255 }, SolFeeReserve::LEN);
+        **reserve.to_account_info().try_borrow_mut_lamports().unwrap() =
+            Rent::default().minimum_balance(SolFeeReserve::LEN) + spare;
+        reserve
+    }
+
+    /// A fee-in-lamports withdrawal of 100_000 paying the relayer 5_000
+    /// lamports from a reserve with 20_000 to spare.
+    struct FeeInLamports {
+        accounts: WithdrawFeeInLamports<'static>,
+        bumps: WithdrawFeeInLamportsBumps,
+        merkle_root: [u8; 32],
+        proof_data: Vec<u8>,
+        recipient: Pubkey,
+        relayer: Pubkey,
+    }
+
+    impl FeeInLamports {
+        const AMOUNT: u64 = 100_000;
+        const FEE_LAMPORTS: u64 = 5_000;
+        const RESERVE_SPARE: u64 = 20_000;
+
+        fn new() -> Self {
+            let (recipient, relayer) = (Pubkey::new_unique(), Pubkey::new_unique());
+            let mut pool = WithdrawFixture::new(&relayer, &recipient, &[9u8; 32]);
+            pool.withdraw.pool_config.set_max_relayer_fee_lamports(10_000);
+
+            let proof_data = pool.proof(ZkPublicInputs::new(
+                pool.merkle_root,
+                [9u8; 32],
+                recipient,
+                Self::AMOUNT,
+                lamport_fee_relayer_binding(&relayer, Self::FEE_LAMPORTS),
+                0,
+            ));
+            let sol_fee_reserve = reserve_with(pool.withdraw.pool_config.key(), Self::RESERVE_SPARE);
+            let relayer_wallet = account_info(relayer, System::id(), Vec::new(), false, false);
+            Self {
+                accounts: WithdrawFeeInLamports {
+                    withdraw: pool.withdraw,
+                    sol_fee_reserve,
+                    relayer_wallet: SystemAccount::try_from(relayer_wallet).unwrap(),
+                },
+                bumps: WithdrawFeeInLamportsBumps { withdraw: pool.bumps },
+                merkle_root: pool.merkle_root,
+                proof_data,
+                recipient,
+                relayer,
+            }
+        }
+
+        fn run(&mut self, relayer_fee_lamports: u64) -> Result<()> {
+            process_withdraw_fee_in_lamports(
+                &mut self.accounts,
+                &self.bumps,
+                self.proof_data.clone(),
+                self.merkle_root,
+                [9u8; 32],
+                self.recipient,
+                Self::AMOUNT,
+                self.relayer,
+                relayer_fee_lamports,
+            )
+        }
+    }
+
+    #[test]
+    fn test_relayer_paid_in_lamports() {
+        let mut withdrawal = FeeInLamports::new();
+        let relayer_before = withdrawal.accounts.relayer_wallet.lamports();
+        let reserve_before = withdrawal.accounts.sol_fee_reserve.to_account_info().lamports();
+
+        withdrawal.run(FeeInLamports::FEE_LAMPORTS).unwrap();
+
+        // The recipient gets the whole amount in tokens, the relayer none
+        let accounts = &mut withdrawal.accounts;
+        assert_eq!(token_transfers().len(), 1);
+        assert_eq!(
+            token_account_balance(&mut accounts.withdraw.recipient_token_account),
+            FeeInLamports::AMOUNT
+        );
+        assert_eq!(token_account_balance(&mut accounts.withdraw.relayer_token_account), 0);
+
+        // ...and the relayer is paid from the reserve in lamports
+        assert_eq!(
+            accounts.relayer_wallet.lamports() - relayer_before,
+            FeeInLamports::FEE_LAMPORTS
+        );
+        assert_eq!(
+            reserve_before - accounts.sol_fee_reserve.to_account_info().lamports(),
+            FeeInLamports::FEE_LAMPORTS
+        );
+        assert!(SpentNullifier::exists(&accounts.withdraw.spent_nullifier).unwrap());
+    }
+
+    #[test]
+    fn test_lamport_fee_bound_by_proof() {
+        // A relayer cannot claim more lamports than the proof committed to
+        let mut withdrawal = FeeInLamports::new();
+        let relayer_before = withdrawal.accounts.relayer_wallet.lamports();
+        let err = withdrawal.run(FeeInLamports::FEE_LAMPORTS + 1).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::InvalidProof));
+        assert_eq!(withdrawal.accounts.relayer_wallet.lamports(), relayer_before);
+        assert!(token_transfers().is_empty());
+    }
+
+    #[test]
+    fn test_lamport_fee_limits() {
+        let err = fee_in_lamports_payouts(&capped_pool(0), 1_000, 1).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::LamportFeeNotEnabled));
+        let err = fee_in_lamports_payouts(&capped_pool(10_000), 1_000, 10_001).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::LimitExceeded));
+        assert!(fee_in_lamports_payouts(&capped_pool(10_000), 1_000, 0).is_ok());
+
+        // The reserve never pays itself below rent exemption
+        let rent = Rent::default();
+        let reserve = reserve_with(Pubkey::new_unique(), 4_000).to_account_info();
+        let reserve_before = reserve.lamports();
+        let relayer = account_info(Pubkey::new_unique(), System::id(), Vec::new(), false, false);
+        let relayer_before = relayer.lamports();
+
+        assert_eq!(SolFeeReserve::available(&reserve, &rent), 4_000);
+        let err = SolFeeReserve::pay_out(&reserve, relayer, 4_001, &rent).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::InsufficientBalance));
+        assert_eq!((reserve.lamports(), relayer.lamports()), (reserve_before, relayer_before));
+    }
+}
@@ -0,0 +1,197 @@
+//! Withdraw Fee In Mint Instruction
+//!
+//! Withdrawal whose relayer is paid in the pool's `fee_mint` (e.g. a
+//! stablecoin) rather than the deposited asset. Fixed-rate reserve model:
+//! the proof-bound `relayer_fee` (pool-mint units) goes from the vault to
+//! the pool's fee recipient, and the relayer receives
+//! `fee_in_fee_mint(relayer_fee)` from the pool's fee reserve. The recipient
+//! is paid `amount - relayer_fee` in the pool mint, as in `withdraw`.
+//!
+//! `withdraw.relayer_token_account` is validated but not paid.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+
+use crate::error::PrivacyError;
+use crate::events::RelayerFeePaidInMint;
+use crate::instructions::deposit::check_tvl_invariant;
+use crate::instructions::withdraw::{self, *};
+use crate::state::{PayoutMint, PoolConfig};
+
+#[derive(Accounts)]
+#[instruction(
+    proof_data: Vec<u8>,
+    merkle_root: [u8; 32],
+    nullifier_hash: [u8; 32],
+    recipient: Pubkey,
+    amount: u64,
+    relayer: Pubkey,
+)]
+pub struct WithdrawFeeInMint<'info> {
+    pub withdraw: Withdraw<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"fee_reserve", withdraw.pool_config.key().as_ref()],
+        bump,
+        constraint = fee_reserve.mint == withdraw.pool_config.fee_mint @ PrivacyError::InvalidMint,
+    )]
+    pub fee_reserve: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = validate_destination_mint(&withdraw.pool_config, &relayer_fee_token_account, PayoutMint::Fee).is_ok() @ PrivacyError::InvalidMint,
+        constraint = relayer_fee_token_account.owner == relayer @ PrivacyError::Unauthorized,
+    )]
+    pub relayer_fee_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = validate_destination_mint(&withdraw.pool_config, &fee_recipient_token_account, PayoutMint::Pool).is_ok() @ PrivacyError::InvalidMint,
+        constraint = fee_recipient_token_account.owner == withdraw.pool_config.fee_recipient @ PrivacyError::InvalidFeeRecipient,
+    )]
+    pub fee_recipient_token_account: Account<'info, TokenAccount>,
+}
+
+/// Token movements of a fee-in-mint withdrawal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeeInMintPayouts {
+    /// Pool mint, vault -> recipient
+    pub recipient_amount: u64,
+    /// Pool mint, vault -> fee recipient
+    pub protocol_fee: u64,
+    /// Fee mint, fee reserve -> relayer
+    pub relayer_fee_mint_amount: u64,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handler(
+    ctx: Context<WithdrawFeeInMint>,
+    proof_data: Vec<u8>,
+    merkle_root: [u8; 32],
+    nullifier_hash: [u8; 32],
+    recipient: Pubkey,
+    amount: u64,
+    relayer: Pubkey,
+    relayer_fee: u64,
+) -> Result<()> {
+    let payouts = fee_in_mint_payouts(&ctx.accounts.withdraw.pool_config, amount, relayer_fee)?;
+    require!(
+        ctx.accounts.fee_reserve.amount >= payouts.relayer_fee_mint_amount,
+        PrivacyError::InsufficientBalance
+    );
+
+    let accounts = &mut ctx.accounts.withdraw;
+    let (clock, public_inputs) = withdraw::verify_withdrawal(
+        accounts,
+        &ctx.bumps.withdraw,
+        &proof_data,
+        merkle_root,
+        nullifier_hash,
+        recipient,
+        amount,
+        relayer,
+        relayer_fee,
+    )?;
+
+    // A recipient withdrawing to the fee recipient's account gets one transfer
+    let vault_payouts = withdraw::merge_payouts([
+        (accounts.recipient_token_account.to_account_info(), payouts.recipient_amount),
+        (ctx.accounts.fee_recipient_token_account.to_account_info(), payouts.protocol_fee),
+    ])?;
+    withdraw::pay_from_vault(&ctx.accounts.withdraw, vault_payouts)?;
+    if payouts.relayer_fee_mint_amount > 0 {
+        withdraw::transfer_signed_by_pool(
+            &ctx.accounts.withdraw,
+            ctx.accounts.fee_reserve.to_account_info(),
+            ctx.accounts.relayer_fee_token_account.to_account_info(),
+            payouts.relayer_fee_mint_amount,
+        )?;
+    }
+
+    let accounts = &mut ctx.accounts.withdraw;
+    accounts.pool_config.record_withdrawal(amount)?;
+    accounts.vault.reload()?;
+    let pool_config = &accounts.pool_config;
+    check_tvl_invariant(pool_config.key(), pool_config, accounts.vault.amount);
+
+    emit!(withdraw::withdraw_event(
+        pool_config.key(),
+        pool_config.nullifier_generation,
+        accounts.spent_nullifier.key(),
+        &public_inputs,
+        &Rent::get()?,
+        clock.unix_timestamp,
+    )?);
+    emit!(RelayerFeePaidInMint {
+        pool: pool_config.key(),
+        nullifier_hash,
+        relayer,
+        relayer_fee,
+        fee_mint: pool_config.fee_mint,
+        fee_amount: payouts.relayer_fee_mint_amount,
+    });
+
+    msg!("Fee-in-mint withdrawal successful");
+    Ok(())
+}
+
+/// Split a withdrawal of `amount` with proof-bound `relayer_fee` into its
+/// pool-mint and fee-mint payouts.
+///
+/// # Errors
+/// * `FeeMintNotConfigured` if the pool has no fee mint
+/// * `ArithmeticOverflow` if `relayer_fee > amount` or the conversion overflows
+pub fn fee_in_mint_payouts(
+    pool_config: &PoolConfig,
+    amount: u64,
+    relayer_fee: u64,
+) -> Result<FeeInMintPayouts> {
+    let recipient_amount = amount
+        .checked_sub(relayer_fee)
+        .ok_or(error!(PrivacyError::ArithmeticOverflow))?;
+    Ok(FeeInMintPayouts {
+        recipient_amount,
+        protocol_fee: relayer_fee,
+        relayer_fee_mint_amount: pool_config.fee_in_fee_mint(relayer_fee)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::pool_config::test_fixtures::new_pool;
+
+    #[test]
+    fn test_relayer_paid_in_fee_mint() {
+        let mut pool = new_pool();
+        // 1 pool unit = 0.5 fee-mint units
+        pool.set_fee_mint(Pubkey::new_unique(), PoolConfig::FEE_RATE_SCALE / 2);
+
+        let payouts = fee_in_mint_payouts(&pool, 1_000, 100).unwrap();
+        assert_eq!(
+            payouts,
+            FeeInMintPayouts {
+                recipient_amount: 900,
+                protocol_fee: 100,
+                relayer_fee_mint_amount: 50,
+            }
+        );
+
+        // Recipient gets the whole amount in the pool mint when there is no fee
+        let payouts = fee_in_mint_payouts(&pool, 1_000, 0).unwrap();
+        assert_eq!(payouts.recipient_amount, 1_000);
+        assert_eq!(payouts.relayer_fee_mint_amount, 0);
+
+        // Vault outflow equals `amount`, so the TVL invariant holds
+        let payouts = fee_in_mint_payouts(&pool, 777, 33).unwrap();
+        assert_eq!(payouts.recipient_amount + payouts.protocol_fee, 777);
+    }
+
+    #[test]
+    fn test_fee_in_mint_requires_configured_mint() {
+        let pool = new_pool();
+        let err = fee_in_mint_payouts(&pool, 1_000, 100).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::FeeMintNotConfigured));
+    }
+}
@@ -0,0 +1,404 @@
+//! Withdraw Split Fee Instruction
+//!
+//! Withdrawal whose relayer fee is shared between two relayers (e.g. a
+//! submitter and a liquidity provider). Both recipients and both shares are
+//! bound by the proof through `split_fee_relayer_binding`.
+//!
+//! The shares are not separate public inputs. That would take a circuit
+//! with two more inputs and a new verification key for every pool. The
+//! deployed circuit already proves over a `relayer` and a `relayer_fee`.
+//! Here `relayer` is a Keccak hash of both relayers and both shares, and
+//! `relayer_fee` is their total. So a relayer cannot shift value between
+//! the shares or redirect one without a hash collision. The circuit never
+//! sees the individual shares. On chain, `split_fee_total` adds them up and
+//! checks the sum against `amount` and the bps cap. The proof is verified
+//! against that sum.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+
+use crate::crypto::{split_fee_relayer_binding, ZkPublicInputs};
+use crate::error::PrivacyError;
+use crate::events::{RelayerFeeSplit, WithdrawEvent};
+use crate::instructions::deposit::check_tvl_invariant;
+use crate::instructions::withdraw::{self, *};
+use crate::state::{PayoutMint, PoolConfig};
+
+#[derive(Accounts)]
+#[instruction(
+    proof_data: Vec<u8>,
+    merkle_root: [u8; 32],
+    nullifier_hash: [u8; 32],
+    recipient: Pubkey,
+    amount: u64,
+    relayer: Pubkey,
+    relayer_fee: u64,
+    second_relayer: Pubkey,
+)]
+pub struct WithdrawSplitFee<'info> {
+    /// `relayer_token_account` receives the first share (`relayer_fee`)
+    pub withdraw: Withdraw<'info>,
+
+    #[account(
+        mut,
+        constraint = validate_destination_mint(&withdraw.pool_config, &second_relayer_token_account, PayoutMint::Pool).is_ok() @ PrivacyError::InvalidMint,
+        constraint = second_relayer_token_account.owner == second_relayer @ PrivacyError::Unauthorized,
+    )]
+    pub second_relayer_token_account: Account<'info, TokenAccount>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handler(
+    ctx: Context<WithdrawSplitFee>,
+    proof_data: Vec<u8>,
+    merkle_root: [u8; 32],
+    nullifier_hash: [u8; 32],
+    recipient: Pubkey,
+    amount: u64,
+    relayer: Pubkey,
+    relayer_fee: u64,
+    second_relayer: Pubkey,
+    second_relayer_fee: u64,
+) -> Result<()> {
+    process_withdraw_split_fee(
+        ctx.accounts,
+        &ctx.bumps,
+        proof_data,
+        merkle_root,
+        nullifier_hash,
+        recipient,
+        amount,
+        relayer,
+        relayer_fee,
+        second_relayer,
+        second_relayer_fee,
+    )
+}
+
+/// Split-fee withdrawal flow: check the second relayer's account, verify
+/// the proof against the split-fee binding and pay all three shares.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn process_withdraw_split_fee<'info>(
+    accounts: &mut WithdrawSplitFee<'info>,
+    bumps: &WithdrawSplitFeeBumps,
+    proof_data: Vec<u8>,
+    merkle_root: [u8; 32],
+    nullifier_hash: [u8; 32],
+    recipient: Pubkey,
+    amount: u64,
+    relayer: Pubkey,
+    relayer_fee: u64,
+    second_relayer: Pubkey,
+    second_relayer_fee: u64,
+) -> Result<()> {
+    // The proof binds the total fee; the per-relayer shares are bound via the
+    // relayer public input.
+    let total_fee = split_fee_total(amount, relayer_fee, second_relayer_fee)?;
+    let relayer_binding =
+        split_fee_relayer_binding(&relayer, relayer_fee, &second_relayer, second_relayer_fee);
+
+    let second_relayer_token_account = accounts.second_relayer_token_account.to_account_info();
+    require_second_relayer_ready(
+        &accounts.withdraw.pool_config,
+        &second_relayer_token_account,
+        &Rent::get()?,
+    )?;
+
+    // Fee policy applies to the first relayer, who submits the withdrawal
+    let accounts = &mut accounts.withdraw;
+    let (clock, public_inputs) = withdraw::verify_withdrawal_inputs(
+        accounts,
+        &bumps.withdraw,
+        &proof_data,
+        ZkPublicInputs::new(
+            merkle_root,
+            nullifier_hash,
+            recipient,
+            amount,
+            relayer_binding,
+            total_fee,
+        ),
+        &relayer,
+    )?;
+
+    let vault_payouts = split_fee_payouts(
+        accounts.recipient_token_account.to_account_info(),
+        accounts.relayer_token_account.to_account_info(),
+        second_relayer_token_account,
+        amount,
+        relayer_fee,
+        second_relayer_fee,
+    )?;
+    withdraw::pay_from_vault(accounts, vault_payouts)?;
+
+    accounts.pool_config.record_withdrawal(amount)?;
+    accounts.vault.reload()?;
+    let pool_config = &accounts.pool_config;
+    check_tvl_invariant(pool_config.key(), pool_config, accounts.vault.amount);
+
+    // inputs_hash covers the binding the proof was verified against;
+    // `relayer` reports the first relayer rather than the binding
+    emit!(WithdrawEvent {
+        relayer,
+        ..withdraw::withdraw_event(
+            pool_config.key(),
+            pool_config.nullifier_generation,
+            accounts.spent_nullifier.key(),
+            &public_inputs,
+            &Rent::get()?,
+            clock.unix_timestamp,
+        )?
+    });
+    emit!(RelayerFeeSplit {
+        pool: pool_config.key(),
+        nullifier_hash,
+        relayer,
+        relayer_fee,
+        second_relayer,
+        second_relayer_fee,
+    });
+
+    msg!("Split-fee withdrawal successful");
+    Ok(())
+}
+
+/// Total of both fee shares, enforcing `total <= amount` and the relayer
+/// fee bps cap on the total.
+pub fn split_fee_total(amount: u64, relayer_fee: u64, second_relayer_fee: u64) -> Result<u64> {
+    let total = relayer_fee
+        .checked_add(second_relayer_fee)
+        .ok_or(error!(PrivacyError::ArithmeticOverflow))?;
+    withdraw::validate_relayer_fee(amount, total)?;
+    Ok(total)
+}
+
+/// Vault payouts of a split-fee withdrawal: `amount - relayer_fee -
+/// second_relayer_fee` to the recipient and each share to its relayer,
+/// merged per `withdraw::merge_payouts` so the transfers sum to `amount`.
+pub fn split_fee_payouts<K: Key>(
+    recipient_token_account: K,
+    relayer_token_account: K,
+    second_relayer_token_account: K,
+    amount: u64,
+    relayer_fee: u64,
+    second_relayer_fee: u64,
+) -> Result<Vec<(K, u64)>> {
+    let total_fee = split_fee_total(amount, relayer_fee, second_relayer_fee)?;
+    let net_amount = amount
+        .checked_sub(total_fee)
+        .ok_or(error!(PrivacyError::ArithmeticOverflow))?;
+    withdraw::merge_payouts([
+        (recipient_token_account, net_amount),
+        (relayer_token_account, relayer_fee),
+        (second_relayer_token_account, second_relayer_fee),
+    ])
+}
+
+/// `require_ready_payees` for the second relayer's token account, which
+/// `verify_withdrawal_inputs` does not know about. Runs before any state
+/// change.
+pub fn require_second_relayer_ready(
+    pool_config: &PoolConfig,
+    second_relayer_token_account: &AccountInfo,
+    rent: &Rent,
+) -> Result<()> {
+    if pool_config.require_ready_payees {
+        withdraw::require_payee_ready(second_relayer_token_account, rent)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::withdraw::test_fixtures::*;
+    use crate::state::pool_config::test_fixtures::new_pool;
+    use crate::state::SpentNullifier;
+
+    #[test]
+    fn test_two_way_split_within_cap() {
+        assert_eq!(split_fee_total(1_000, 60, 40).unwrap(), 100);
+        assert_eq!(split_fee_total(1_000, 0, 50).unwrap(), 50);
+    }
+
+    #[test]
+    fn test_split_sum_above_cap_rejected() {
+        // Each share is under the 10% cap, but the total is not
+        assert!(split_fee_total(1_000, 60, 50).is_err());
+        assert!(split_fee_total(1_000, u64::MAX, 1).is_err());
+    }
+
+    #[test]
+    fn test_vault_pays_out_exactly_amount() {
+        let (recipient, relayer, second) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let amount = 1_000;
+
+        for (relayer_account, second_account) in
+            [(relayer, second), (relayer, recipient), (recipient, recipient)]
+        {
+            let mut pool = new_pool();
+            pool.record_deposit(5_000).unwrap();
+            let mut vault = 5_000u64;
+            let mut balances = std::collections::HashMap::new();
+
+            let payouts =
+                split_fee_payouts(recipient, relayer_account, second_account, amount, 60, 40).unwrap();
+            // One transfer per distinct account
+            let mut accounts: Vec<_> = payouts.iter().map(|(to, _)| *to).collect();
+            accounts.sort();
+            accounts.dedup();
+            assert_eq!(accounts.len(), payouts.len());
+            for (to, payout) in payouts {
+                vault -= payout;
+                *balances.entry(to).or_insert(0u64) += payout;
+            }
+            pool.record_withdrawal(amount).unwrap();
+
+            // net + fee1 + fee2 leaves the vault, and the accounting agrees
+            assert_eq!(vault, 5_000 - amount);
+            assert!(pool.tvl_invariant_holds(vault));
+            let paid = |account: Pubkey| balances.get(&account).copied().unwrap_or(0);
+            assert_eq!(paid(recipient) + paid(relayer) + paid(second), amount);
+            if second_account == second {
+                assert_eq!((paid(recipient), paid(relayer), paid(second)), (900, 60, 40));
+            }
+        }
+
+        // The cap applies to the shares' total, as in split_fee_total
+        assert!(split_fee_payouts(recipient, relayer, second, amount, 60, 50).is_err());
+    }
+
+    #[test]
+    fn test_second_relayer_account_readiness() {
+        use anchor_lang::solana_program::program_pack::Pack;
+        use anchor_spl::token::spl_token::state::{Account as SplAccount, AccountState};
+
+        let rent = Rent::default();
+        let key = Pubkey::new_unique();
+        let token_program = anchor_spl::token::ID;
+        let mut ready_data = vec![0u8; SplAccount::LEN];
+        SplAccount::pack(
+            SplAccount {
+                mint: Pubkey::new_unique(),
+                owner: Pubkey::new_unique(),
+                state: AccountState::Initialized,
+                ..SplAccount::default()
+            },
+            &mut ready_data,
+        )
+        .unwrap();
+        let mut ready_lamports = rent.minimum_balance(SplAccount::LEN);
+        let ready = AccountInfo::new(
+            &key, false, true, &mut ready_lamports, &mut ready_data, &token_program, false, 0,
+        );
+        let (mut lamports, mut data) = (0, vec![0u8; SplAccount::LEN]);
+        let unready =
+            AccountInfo::new(&key, false, true, &mut lamports, &mut data, &token_program, false, 0);
+
+        // Not checked unless the pool asks for it
+        let mut pool = new_pool();
+        assert!(require_second_relayer_ready(&pool, &unready, &rent).is_ok());
+
+        pool.require_ready_payees = true;
+        assert!(require_second_relayer_ready(&pool, &ready, &rent).is_ok());
+        let err = require_second_relayer_ready(&pool, &unready, &rent).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::PayeeAccountNotReady));
+    }
+
+    /// A split-fee withdrawal of 100_000 paying 600 and 400 to two relayers.
+    struct SplitFee {
+        accounts: WithdrawSplitFee<'static>,
+        bumps: WithdrawSplitFeeBumps,
+        merkle_root: [u8; 32],
+        proof_data: Vec<u8>,
+        recipient: Pubkey,
+        relayer: Pubkey,
+        second_relayer: Pubkey,
+    }
+
+    impl SplitFee {
+        const AMOUNT: u64 = 100_000;
+        const FEES: (u64, u64) = (600, 400);
+
+        fn new() -> Self {
+            let (recipient, relayer, second_relayer) =
+                (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+            let mut pool = WithdrawFixture::new(&relayer, &recipient, &[9u8; 32]);
+            pool.withdraw.pool_config.require_ready_payees = true;
+
+            let relayer_binding =
+                split_fee_relayer_binding(&relayer, Self::FEES.0, &second_relayer, Self::FEES.1);
+            let proof_data = pool.proof(ZkPublicInputs::new(
+                pool.merkle_root,
+                [9u8; 32],
+                recipient,
+                Self::AMOUNT,
+                relayer_binding,
+                Self::FEES.0 + Self::FEES.1,
+            ));
+            let mint = pool.withdraw.pool_config.token_mint;
+            Self {
+                accounts: WithdrawSplitFee {
+                    withdraw: pool.withdraw,
+                    second_relayer_token_account: token_account(mint, second_relayer, 0),
+                },
+                bumps: WithdrawSplitFeeBumps { withdraw: pool.bumps },
+                merkle_root: pool.merkle_root,
+                proof_data,
+                recipient,
+                relayer,
+                second_relayer,
+            }
+        }
+
+        fn run(&mut self) -> Result<()> {
+            process_withdraw_split_fee(
+                &mut self.accounts,
+                &self.bumps,
+                self.proof_data.clone(),
+                self.merkle_root,
+                [9u8; 32],
+                self.recipient,
+                Self::AMOUNT,
+                self.relayer,
+                Self::FEES.0,
+                self.second_relayer,
+                Self::FEES.1,
+            )
+        }
+    }
+
+    #[test]
+    fn test_handler_vault_pays_net_plus_both_shares() {
+        let mut split = SplitFee::new();
+        split.run().unwrap();
+
+        let transfers = token_transfers();
+        assert_eq!(transfers.len(), 3);
+        assert_eq!(transfers.iter().map(|(_, amount)| amount).sum::<u64>(), SplitFee::AMOUNT);
+
+        let accounts = &mut split.accounts;
+        assert_eq!(token_account_balance(&mut accounts.withdraw.recipient_token_account), 99_000);
+        assert_eq!(token_account_balance(&mut accounts.withdraw.relayer_token_account), 600);
+        assert_eq!(token_account_balance(&mut accounts.second_relayer_token_account), 400);
+        assert_eq!(
+            token_account_balance(&mut accounts.withdraw.vault),
+            VAULT_BALANCE - SplitFee::AMOUNT
+        );
+        assert!(accounts.withdraw.pool_config.tvl_invariant_holds(accounts.withdraw.vault.amount));
+        assert!(SpentNullifier::exists(&accounts.withdraw.spent_nullifier).unwrap());
+    }
+
+    #[test]
+    fn test_handler_checks_second_relayer_account() {
+        // Below rent exemption, so not a ready payee
+        let mut split = SplitFee::new();
+        **split.accounts.second_relayer_token_account.to_account_info().try_borrow_mut_lamports().unwrap() = 0;
+        assert_eq!(split.run().err().unwrap(), error!(PrivacyError::PayeeAccountNotReady));
+
+        // Rejected before the nullifier is spent or anything is paid
+        assert!(!SpentNullifier::exists(&split.accounts.withdraw.spent_nullifier).unwrap());
+        assert!(token_transfers().is_empty());
+    }
+}
@@ -0,0 +1,55 @@
+//! Withdraw To Escrow Instruction
+//!
+//! Private withdrawal whose recipient is a PDA owned by the pool's
+//! whitelisted escrow program, enabling conditional release by that program.
+
+use anchor_lang::prelude::*;
+
+use crate::error::PrivacyError;
+use crate::instructions::withdraw::{self, *};
+
+#[derive(Accounts)]
+#[instruction(
+    proof_data: Vec<u8>,
+    merkle_root: [u8; 32],
+    nullifier_hash: [u8; 32],
+    recipient: Pubkey,
+)]
+pub struct WithdrawToEscrow<'info> {
+    pub withdraw: Withdraw<'info>,
+
+    /// CHECK: Escrow PDA; owner is validated against the pool whitelist in the handler
+    #[account(constraint = escrow.key() == recipient @ PrivacyError::RecipientMismatch)]
+    pub escrow: UncheckedAccount<'info>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handler(
+    ctx: Context<WithdrawToEscrow>,
+    proof_data: Vec<u8>,
+    merkle_root: [u8; 32],
+    nullifier_hash: [u8; 32],
+    recipient: Pubkey,
+    amount: u64,
+    relayer: Pubkey,
+    relayer_fee: u64,
+) -> Result<()> {
+    ctx.accounts
+        .withdraw
+        .pool_config
+        .require_escrow_recipient(&recipient, ctx.accounts.escrow.owner)?;
+
+    msg!("Withdrawing to escrow: {}", recipient);
+
+    withdraw::process_withdraw(
+        &mut ctx.accounts.withdraw,
+        &ctx.bumps.withdraw,
+        proof_data,
+        merkle_root,
+        nullifier_hash,
+        recipient,
+        amount,
+        relayer,
+        relayer_fee,
+    )
+}
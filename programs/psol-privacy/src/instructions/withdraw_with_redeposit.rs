@@ -0,0 +1,275 @@
+//! Withdraw With Redeposit Instruction
+//!
+//! Withdrawal that keeps part of the note shielded: of the `amount - relayer_fee`
+//! net, `redeposit_amount` stays in the vault as a new `change_commitment`
+//! leaf and only the rest is paid to the recipient. The change note is bound
+//! by the proof through `redeposit_relayer_binding`.
+//!
+//! `Withdraw` only reads the tree, so the tree (and the root history, when
+//! the pool uses the split layout) is passed a second time as a writable
+//! account for the insert.
+
+use anchor_lang::prelude::*;
+
+use crate::crypto::{redeposit_relayer_binding, ZkPublicInputs};
+use crate::error::PrivacyError;
+use crate::events::WithdrawEvent;
+use crate::instructions::deposit::{self, check_tvl_invariant, MAX_DEPOSIT_AMOUNT};
+use crate::instructions::withdraw::{self, *};
+use crate::state::merkle_tree::debug_assert_not_nullifier;
+use crate::state::{DepositNote, MerkleTree, PoolConfig, RootHistory};
+
+#[derive(Accounts)]
+#[instruction(
+    proof_data: Vec<u8>,
+    merkle_root: [u8; 32],
+    nullifier_hash: [u8; 32],
+    recipient: Pubkey,
+    amount: u64,
+    relayer: Pubkey,
+    relayer_fee: u64,
+)]
+pub struct WithdrawWithRedeposit<'info> {
+    pub withdraw: Withdraw<'info>,
+
+    /// `withdraw.merkle_tree`, writable; receives the change commitment
+    #[account(
+        mut,
+        address = withdraw.merkle_tree.key() @ PrivacyError::Unauthorized,
+    )]
+    pub merkle_tree: Box<Account<'info, MerkleTree>>,
+
+    /// `withdraw.root_history`, writable; required iff the pool uses the
+    /// split layout
+    #[account(
+        mut,
+        constraint = withdraw.root_history.as_ref().map(|h| h.key()) == Some(root_history.key())
+            @ PrivacyError::RootHistoryMismatch,
+    )]
+    pub root_history: Option<Box<Account<'info, RootHistory>>>,
+}
+
+/// Token movements of a withdrawal with redeposit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RedepositPayouts {
+    /// Vault -> recipient
+    pub recipient_amount: u64,
+    /// Vault -> relayer
+    pub relayer_fee: u64,
+    /// Stays in the vault behind the change commitment
+    pub redeposit_amount: u64,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handler(
+    ctx: Context<WithdrawWithRedeposit>,
+    proof_data: Vec<u8>,
+    merkle_root: [u8; 32],
+    nullifier_hash: [u8; 32],
+    recipient: Pubkey,
+    amount: u64,
+    relayer: Pubkey,
+    relayer_fee: u64,
+    change_commitment: [u8; 32],
+    redeposit_amount: u64,
+) -> Result<()> {
+    let payouts = redeposit_payouts(amount, relayer_fee, redeposit_amount)?;
+    let relayer_binding = redeposit_relayer_binding(&relayer, &change_commitment, redeposit_amount);
+
+    let accounts = &mut ctx.accounts.withdraw;
+    let (clock, public_inputs) = withdraw::verify_withdrawal_inputs(
+        accounts,
+        &ctx.bumps.withdraw,
+        &proof_data,
+        ZkPublicInputs::new(
+            merkle_root,
+            nullifier_hash,
+            recipient,
+            amount,
+            relayer_binding,
+            relayer_fee,
+        ),
+        &relayer,
+    )?;
+
+    let vault_payouts = withdraw::merge_payouts([
+        (accounts.recipient_token_account.to_account_info(), payouts.recipient_amount),
+        (accounts.relayer_token_account.to_account_info(), payouts.relayer_fee),
+    ])?;
+    withdraw::pay_from_vault(accounts, vault_payouts)?;
+
+    debug_assert_not_nullifier(&change_commitment, &nullifier_hash);
+    let merkle_tree = &mut ctx.accounts.merkle_tree;
+    let leaf_index = apply_redeposit(
+        &mut accounts.pool_config,
+        merkle_tree,
+        ctx.accounts.root_history.as_deref_mut().map(|history| &mut **history),
+        amount,
+        change_commitment,
+        redeposit_amount,
+        clock.slot,
+    )?;
+    msg!("Change commitment inserted at leaf index: {}", leaf_index);
+
+    accounts.vault.reload()?;
+    let pool_config = &accounts.pool_config;
+    check_tvl_invariant(pool_config.key(), pool_config, accounts.vault.amount);
+    emit!(WithdrawEvent {
+        relayer,
+        ..withdraw::withdraw_event(
+            pool_config.key(),
+            pool_config.nullifier_generation,
+            accounts.spent_nullifier.key(),
+            &public_inputs,
+            &Rent::get()?,
+            clock.unix_timestamp,
+        )?
+    });
+
+    let (deposit_event, root_updated) = deposit::deposit_events(
+        pool_config.key(),
+        change_commitment,
+        leaf_index,
+        redeposit_amount,
+        merkle_tree.current_root,
+        &clock,
+    );
+    emit!(deposit_event);
+    emit!(root_updated);
+
+    msg!("Withdrawal with redeposit successful");
+    Ok(())
+}
+
+/// Split a withdrawal of `amount` into recipient payout, relayer fee and
+/// re-deposited change, so that the three always sum to `amount`.
+///
+/// # Errors
+/// * `RelayerFeeExceedsAmount` per `withdraw::validate_relayer_fee`
+/// * `InvalidAmount` if `redeposit_amount` is zero or exceeds
+///   `amount - relayer_fee`
+/// * `LimitExceeded` if `redeposit_amount` is above `MAX_DEPOSIT_AMOUNT`
+pub fn redeposit_payouts(
+    amount: u64,
+    relayer_fee: u64,
+    redeposit_amount: u64,
+) -> Result<RedepositPayouts> {
+    withdraw::validate_relayer_fee(amount, relayer_fee)?;
+    let net_amount = amount
+        .checked_sub(relayer_fee)
+        .ok_or(error!(PrivacyError::ArithmeticOverflow))?;
+
+    require!(redeposit_amount > 0, PrivacyError::InvalidAmount);
+    require!(redeposit_amount <= net_amount, PrivacyError::InvalidAmount);
+    require!(redeposit_amount <= MAX_DEPOSIT_AMOUNT, PrivacyError::LimitExceeded);
+
+    Ok(RedepositPayouts {
+        recipient_amount: net_amount - redeposit_amount,
+        relayer_fee,
+        redeposit_amount,
+    })
+}
+
+/// Insert the change note and record the withdrawal of `amount` plus the
+/// re-deposit of `redeposit_amount`. Returns the change leaf index.
+///
+/// The vault only pays out `amount - redeposit_amount`, so recording both
+/// sides keeps `total_value_deposited - total_value_withdrawn` equal to the
+/// vault balance.
+pub(crate) fn apply_redeposit(
+    pool_config: &mut PoolConfig,
+    merkle_tree: &mut MerkleTree,
+    root_history: Option<&mut RootHistory>,
+    amount: u64,
+    change_commitment: [u8; 32],
+    redeposit_amount: u64,
+    slot: u64,
+) -> Result<u32> {
+    let change_commitment =
+        pool_config.resolve_commitment(DepositNote::Commitment(change_commitment), redeposit_amount)?;
+    deposit::validate_commitment(&change_commitment)?;
+
+    let leaf_index = merkle_tree.insert_leaf_into(change_commitment, slot, root_history)?;
+
+    pool_config.record_withdrawal(amount)?;
+    pool_config.record_deposit(redeposit_amount)?;
+    Ok(leaf_index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::poseidon::TreeHash;
+    use crate::state::merkle_tree::test_fixtures::new_tree;
+    use crate::state::pool_config::test_fixtures::new_pool;
+
+    #[test]
+    fn test_redeposit_balance_equation() {
+        let payouts = redeposit_payouts(1_000, 50, 400).unwrap();
+        assert_eq!(
+            payouts,
+            RedepositPayouts {
+                recipient_amount: 550,
+                relayer_fee: 50,
+                redeposit_amount: 400,
+            }
+        );
+
+        for (amount, fee, change) in [(1_000, 0, 1_000), (1_000, 100, 900), (777, 33, 1)] {
+            let p = redeposit_payouts(amount, fee, change).unwrap();
+            assert_eq!(p.recipient_amount + p.relayer_fee + p.redeposit_amount, amount);
+        }
+    }
+
+    #[test]
+    fn test_redeposit_amount_bounds() {
+        let err = redeposit_payouts(1_000, 50, 0).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::InvalidAmount));
+        // Change cannot eat into the relayer fee
+        let err = redeposit_payouts(1_000, 50, 951).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::InvalidAmount));
+        assert!(redeposit_payouts(1_000, 101, 10).is_err());
+    }
+
+    #[test]
+    fn test_withdraw_and_insert_change() {
+        let mut pool = new_pool();
+        let mut tree = new_tree(4, TreeHash::Keccak);
+        let mut vault = 0u64;
+
+        for _ in 0..2 {
+            pool.record_deposit(1_000).unwrap();
+            vault += 1_000;
+            tree.insert_leaf([1u8; 32], 1).unwrap();
+        }
+        let root_before = tree.current_root;
+
+        let payouts = redeposit_payouts(1_000, 50, 400).unwrap();
+        vault -= payouts.recipient_amount + payouts.relayer_fee;
+        let leaf_index =
+            apply_redeposit(&mut pool, &mut tree, None, 1_000, [9u8; 32], 400, 7).unwrap();
+
+        assert_eq!(leaf_index, 2);
+        assert_eq!(tree.next_leaf_index, 3);
+        assert_ne!(tree.current_root, root_before);
+        assert!(tree.is_known_root(&root_before));
+        assert_eq!(vault, 1_400);
+        assert!(pool.tvl_invariant_holds(vault));
+        assert_eq!((pool.total_deposits, pool.total_withdrawals), (3, 1));
+    }
+
+    #[test]
+    fn test_invalid_change_commitment_rejected() {
+        let mut pool = new_pool();
+        let mut tree = new_tree(4, TreeHash::Keccak);
+        pool.record_deposit(1_000).unwrap();
+
+        let err = apply_redeposit(&mut pool, &mut tree, None, 1_000, [0u8; 32], 400, 7).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::InvalidCommitment));
+        // Not a canonical field element
+        let err = apply_redeposit(&mut pool, &mut tree, None, 1_000, [0xff; 32], 400, 7).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::InvalidCommitment));
+        assert_eq!(tree.next_leaf_index, 0);
+        assert_eq!(pool.total_withdrawals, 0);
+    }
+}
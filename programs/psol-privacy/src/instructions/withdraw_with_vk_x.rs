@@ -0,0 +1,169 @@
+//! Withdraw With vk_x Instruction
+//!
+//! `withdraw` for circuits whose public-input linear combination does not
+//! fit one instruction: vk_x is accumulated beforehand with
+//! `accumulate_vk_x` into the payer's `VkXScratch` and only checked and
+//! consumed here. The scratch must have absorbed exactly this withdrawal's
+//! public inputs against the pool's current VK.
+
+use anchor_lang::prelude::*;
+
+use crate::crypto::ZkPublicInputs;
+use crate::error::PrivacyError;
+use crate::instructions::withdraw::{self, *};
+use crate::state::VkXScratch;
+
+#[derive(Accounts)]
+#[instruction(
+    proof_data: Vec<u8>,
+    merkle_root: [u8; 32],
+    nullifier_hash: [u8; 32],
+    recipient: Pubkey,
+    amount: u64,
+    relayer: Pubkey,
+    relayer_fee: u64,
+)]
+pub struct WithdrawWithVkX<'info> {
+    pub withdraw: Withdraw<'info>,
+
+    /// The payer's finished accumulation; reset once consumed
+    #[account(
+        mut,
+        seeds = [b"vk_x_scratch", withdraw.pool_config.key().as_ref(), withdraw.payer.key().as_ref()],
+        bump = scratch.bump,
+        constraint = scratch.pool == withdraw.pool_config.key() @ PrivacyError::Unauthorized,
+    )]
+    pub scratch: Account<'info, VkXScratch>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handler(
+    ctx: Context<WithdrawWithVkX>,
+    proof_data: Vec<u8>,
+    merkle_root: [u8; 32],
+    nullifier_hash: [u8; 32],
+    recipient: Pubkey,
+    amount: u64,
+    relayer: Pubkey,
+    relayer_fee: u64,
+) -> Result<()> {
+    let public_inputs =
+        ZkPublicInputs::new(merkle_root, nullifier_hash, recipient, amount, relayer, relayer_fee);
+    process_withdraw_with_vk_x(
+        &mut ctx.accounts.withdraw,
+        &ctx.bumps.withdraw,
+        &proof_data,
+        public_inputs,
+        &mut ctx.accounts.scratch,
+    )
+}
+
+/// `process_withdraw_inputs` against the vk_x in `scratch`, which is reset
+/// afterwards so the next accumulation starts over from IC[0].
+pub(crate) fn process_withdraw_with_vk_x<'info>(
+    accounts: &mut Withdraw<'info>,
+    bumps: &WithdrawBumps,
+    proof_data: &[u8],
+    public_inputs: ZkPublicInputs,
+    scratch: &mut VkXScratch,
+) -> Result<()> {
+    withdraw::process_withdraw_inputs(accounts, bumps, proof_data, public_inputs, Some(scratch))?;
+    scratch.reset();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::groth16_verifier::test_fixtures::synthetic_vk;
+    use crate::crypto::Scalar;
+    use crate::instructions::withdraw::test_fixtures::{token_account_balance, WithdrawFixture};
+    use crate::state::VerificationKey;
+
+    const AMOUNT: u64 = 100_000;
+    const FEE: u64 = 1_000;
+
+    struct Scratched {
+        pool: WithdrawFixture,
+        public_inputs: ZkPublicInputs,
+        /// The inputs as the pool binds and encodes them
+        encoded: Vec<Scalar>,
+        scratch: VkXScratch,
+    }
+
+    impl Scratched {
+        fn new() -> Self {
+            let (relayer, recipient, nullifier_hash) = (Pubkey::new_unique(), Pubkey::new_unique(), [9u8; 32]);
+            let pool = WithdrawFixture::new(&relayer, &recipient, &nullifier_hash);
+            let public_inputs =
+                ZkPublicInputs::new(pool.merkle_root, nullifier_hash, recipient, AMOUNT, relayer, FEE);
+            let encoded = public_inputs
+                .clone()
+                .with_recipient_binding(pool.withdraw.pool_config.recipient_binding().unwrap())
+                .with_vault(pool.withdraw.vault.key())
+                .to_field_elements()
+                .unwrap();
+
+            let mut scratch =
+                VkXScratch::try_deserialize_unchecked(&mut vec![0u8; VkXScratch::LEN].as_slice()).unwrap();
+            scratch.initialize(pool.withdraw.pool_config.key(), relayer, 255);
+            Self { pool, public_inputs, encoded, scratch }
+        }
+
+        /// Accumulate `inputs` against `vk` in chunks, as `accumulate_vk_x` would.
+        fn accumulate(&mut self, vk: &VerificationKey, inputs: &[Scalar]) {
+            for chunk in inputs.chunks(VkXScratch::MAX_CHUNK) {
+                self.scratch.absorb(vk, chunk).unwrap();
+            }
+        }
+
+        fn run(&mut self) -> Result<()> {
+            let proof = self.pool.proof(self.public_inputs.clone());
+            process_withdraw_with_vk_x(
+                &mut self.pool.withdraw,
+                &self.pool.bumps,
+                &proof,
+                self.public_inputs.clone(),
+                &mut self.scratch,
+            )
+        }
+    }
+
+    #[test]
+    fn test_withdraw_verifies_against_accumulated_vk_x() {
+        let mut w = Scratched::new();
+        let encoded = w.encoded.clone();
+        w.accumulate(&synthetic_vk(1), &encoded);
+
+        w.run().unwrap();
+
+        let withdraw = &mut w.pool.withdraw;
+        assert_eq!(token_account_balance(&mut withdraw.recipient_token_account), AMOUNT - FEE);
+        assert_eq!(token_account_balance(&mut withdraw.relayer_token_account), FEE);
+        // Consumed: the next accumulation starts from IC[0] again
+        assert_eq!(w.scratch.next_input, 0);
+    }
+
+    #[test]
+    fn test_unfinished_or_stale_scratch_rejected() {
+        // Not every input absorbed
+        let mut w = Scratched::new();
+        let encoded = w.encoded.clone();
+        w.accumulate(&synthetic_vk(1), &encoded[..4]);
+        assert_eq!(w.run().unwrap_err(), error!(PrivacyError::VkXScratchIncomplete));
+
+        // Accumulated against a key the pool no longer uses
+        let mut w = Scratched::new();
+        let encoded = w.encoded.clone();
+        w.accumulate(&synthetic_vk(2), &encoded);
+        assert_eq!(w.run().unwrap_err(), error!(PrivacyError::VkXScratchStale));
+
+        // Accumulated for other public inputs
+        let mut w = Scratched::new();
+        let mut encoded = w.encoded.clone();
+        encoded.swap(0, 1);
+        w.accumulate(&synthetic_vk(1), &encoded);
+        assert_eq!(w.run().unwrap_err(), error!(PrivacyError::InvalidPublicInputs));
+        assert_eq!(token_account_balance(&mut w.pool.withdraw.recipient_token_account), 0);
+    }
+}
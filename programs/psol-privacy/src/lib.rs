@@ -10,121 +10,828 @@ pub mod events;
 pub mod instructions;
 pub mod state;
 
+#[cfg(any(test, feature = "client"))]
+pub mod client;
+
 #[cfg(test)]
 mod tests;
 
+#[cfg(all(feature = "dev-mode", feature = "mainnet"))]
+compile_error!("`dev-mode` must never be enabled in a `mainnet` build");
+
+/// Whether this binary was built with the `dev-mode` feature.
+pub const DEV_MODE: bool = cfg!(feature = "dev-mode");
+
 use instructions::*;
+use state::VerificationKeyData;
 
 declare_id!("2uPHpGmCNoTk6mnzzuP3DGbVyMiDPrQYRxkYBHMxwhBi");
 
-#[program]
-pub mod psol_privacy {
-    use super::*;
+/// Expands the program module with `$dev_instructions` spliced in.
+///
+/// Anchor 0.30's `#[program]` dispatches every `fn` in the module and
+/// ignores `#[cfg]` on them, so dev-only instructions are compiled out by
+/// expanding the module once per build instead.
+macro_rules! psol_privacy_program {
+    ($($dev_instructions:tt)*) => {
+        #[program]
+        pub mod psol_privacy {
+            use super::*;
 
-    pub fn initialize_pool(
-        ctx: Context<InitializePool>,
-        tree_depth: u8,
-        root_history_size: u16,
-    ) -> Result<()> {
-        instructions::initialize_pool::handler(ctx, tree_depth, root_history_size)
-    }
+            $($dev_instructions)*
 
-    pub fn set_verification_key(
-        ctx: Context<SetVerificationKey>,
-        vk_alpha_g1: [u8; 64],
-        vk_beta_g2: [u8; 128],
-        vk_gamma_g2: [u8; 128],
-        vk_delta_g2: [u8; 128],
-        vk_ic: Vec<[u8; 64]>,
-    ) -> Result<()> {
-        instructions::set_verification_key::handler(
-            ctx,
-            vk_alpha_g1,
-            vk_beta_g2,
-            vk_gamma_g2,
-            vk_delta_g2,
-            vk_ic,
-        )
-    }
+            pub fn initialize_pool(
+                ctx: Context<InitializePool>,
+                tree_depth: u8,
+                root_history_size: u16,
+                tree_hash: u8,
+                commitment_mode: u8,
+                zeros: Option<Vec<[u8; 32]>>,
+            ) -> Result<()> {
+                instructions::initialize_pool::handler(
+                    ctx,
+                    tree_depth,
+                    root_history_size,
+                    tree_hash,
+                    commitment_mode,
+                    zeros,
+                )
+            }
 
-    pub fn lock_verification_key(ctx: Context<LockVerificationKey>) -> Result<()> {
-        instructions::set_verification_key::lock_vk_handler(ctx)
-    }
+            pub fn set_verification_key(
+                ctx: Context<SetVerificationKey>,
+                vk_alpha_g1: [u8; 64],
+                vk_beta_g2: [u8; 128],
+                vk_gamma_g2: [u8; 128],
+                vk_delta_g2: [u8; 128],
+                vk_ic: Vec<[u8; 64]>,
+                circuit_id: [u8; 32],
+            ) -> Result<()> {
+                instructions::set_verification_key::handler(
+                    ctx,
+                    vk_alpha_g1,
+                    vk_beta_g2,
+                    vk_gamma_g2,
+                    vk_delta_g2,
+                    vk_ic,
+                    circuit_id,
+                )
+            }
 
-    pub fn deposit(
-        ctx: Context<Deposit>,
-        amount: u64,
-        commitment: [u8; 32],
-    ) -> Result<()> {
-        instructions::deposit::handler(ctx, amount, commitment)
-    }
+            /// Replace only the VK's IC points; same guards as `set_verification_key`.
+            pub fn update_vk_ic(ctx: Context<SetVerificationKey>, vk_ic: Vec<[u8; 64]>) -> Result<()> {
+                instructions::set_verification_key::update_vk_ic_handler(ctx, vk_ic)
+            }
 
-    #[allow(clippy::too_many_arguments)]
-    pub fn withdraw(
-        ctx: Context<Withdraw>,
-        proof_data: Vec<u8>,
-        merkle_root: [u8; 32],
-        nullifier_hash: [u8; 32],
-        recipient: Pubkey,
-        amount: u64,
-        relayer: Pubkey,
-        relayer_fee: u64,
-    ) -> Result<()> {
-        instructions::withdraw::handler(
-            ctx,
-            proof_data,
-            merkle_root,
-            nullifier_hash,
-            recipient,
-            amount,
-            relayer,
-            relayer_fee,
-        )
-    }
+            /// Store the VK's IC points compressed (or not again), resizing the
+            /// account accordingly.
+            pub fn set_vk_ic_compression(ctx: Context<SetVkIcCompression>, compressed_ic: bool) -> Result<()> {
+                instructions::set_verification_key::set_vk_ic_compression_handler(ctx, compressed_ic)
+            }
 
-    #[deprecated(note = "private_transfer is not implemented in pSOL v1 and is a placeholder for a future version.")]
-    pub fn private_transfer(
-        ctx: Context<PrivateTransfer>,
-        _input_nullifiers: Vec<[u8; 32]>,
-        _output_commitments: Vec<[u8; 32]>,
-        _proof_data: Vec<u8>,
-    ) -> Result<()> {
-        // The underlying handler is also deprecated and always returns NotImplemented.
-        #[allow(deprecated)]
-        {
-            instructions::private_transfer::handler(ctx)
-        }
-    }
+            pub fn lock_verification_key(ctx: Context<LockVerificationKey>) -> Result<()> {
+                instructions::set_verification_key::lock_vk_handler(ctx)
+            }
 
-    pub fn pause_pool(ctx: Context<PausePool>) -> Result<()> {
-        instructions::admin::pause::handler(ctx)
-    }
+            /// Recreate a verification key account left missing or unreadable,
+            /// before any key is configured.
+            pub fn reinitialize_verification_key(ctx: Context<ReinitializeVerificationKey>) -> Result<()> {
+                instructions::admin::reinitialize_verification_key::reinitialize_verification_key_handler(ctx)
+            }
 
-    pub fn unpause_pool(ctx: Context<UnpausePool>) -> Result<()> {
-        instructions::admin::unpause::handler(ctx)
-    }
+            /// Rewrite a version 2 pool config in the current layout, growing the
+            /// account; required before a pre-upgrade pool can be used again.
+            pub fn migrate_pool_config(ctx: Context<MigratePoolConfig>) -> Result<()> {
+                instructions::admin::migrate_pool_config::migrate_pool_config_handler(ctx)
+            }
 
-    pub fn initiate_authority_transfer(
-        ctx: Context<InitiateAuthorityTransfer>,
-        new_authority: Pubkey,
-    ) -> Result<()> {
-        instructions::admin::update_authority::initiate_transfer_handler(ctx, new_authority)
-    }
+            /// Rewrite a version 1 Merkle tree in the current layout, growing the
+            /// account; required before a pre-upgrade tree can be used again.
+            pub fn migrate_merkle_tree(ctx: Context<MigrateMerkleTree>) -> Result<()> {
+                instructions::admin::migrate_merkle_tree::migrate_merkle_tree_handler(ctx)
+            }
 
-    pub fn accept_authority_transfer(ctx: Context<AcceptAuthorityTransfer>) -> Result<()> {
-        instructions::admin::update_authority::accept_transfer_handler(ctx)
-    }
+            pub fn init_next_verification_key(ctx: Context<InitNextVerificationKey>) -> Result<()> {
+                instructions::next_verification_key::init_handler(ctx)
+            }
 
-    pub fn cancel_authority_transfer(ctx: Context<CancelAuthorityTransfer>) -> Result<()> {
-        instructions::admin::update_authority::cancel_transfer_handler(ctx)
-    }
+            pub fn set_next_verification_key(
+                ctx: Context<SetNextVerificationKey>,
+                vk_data: VerificationKeyData,
+                dual_vk_window_until: i64,
+            ) -> Result<()> {
+                instructions::next_verification_key::set_handler(ctx, vk_data, dual_vk_window_until)
+            }
+
+            pub fn promote_next_verification_key(ctx: Context<PromoteNextVerificationKey>) -> Result<()> {
+                instructions::next_verification_key::promote_handler(ctx)
+            }
+
+            pub fn init_vk_x_scratch(ctx: Context<InitVkXScratch>) -> Result<()> {
+                instructions::accumulate_vk_x::init_scratch_handler(ctx)
+            }
+
+            pub fn accumulate_vk_x(
+                ctx: Context<AccumulateVkX>,
+                reset: bool,
+                inputs: Vec<[u8; 32]>,
+            ) -> Result<()> {
+                instructions::accumulate_vk_x::accumulate_handler(ctx, reset, inputs)
+            }
+
+            /// Withdraw verified against the vk_x accumulated in the payer's
+            /// scratch by `accumulate_vk_x`; consumes the scratch.
+            #[allow(clippy::too_many_arguments)]
+            pub fn withdraw_with_vk_x(
+                ctx: Context<WithdrawWithVkX>,
+                proof_data: Vec<u8>,
+                merkle_root: [u8; 32],
+                nullifier_hash: [u8; 32],
+                recipient: Pubkey,
+                amount: u64,
+                relayer: Pubkey,
+                relayer_fee: u64,
+            ) -> Result<()> {
+                instructions::withdraw_with_vk_x::handler(
+                    ctx,
+                    proof_data,
+                    merkle_root,
+                    nullifier_hash,
+                    recipient,
+                    amount,
+                    relayer,
+                    relayer_fee,
+                )
+            }
+
+            pub fn deposit(
+                ctx: Context<Deposit>,
+                amount: u64,
+                commitment: [u8; 32],
+                expected_next_leaf_index: Option<u32>,
+                encrypted_memo: Option<Vec<u8>>,
+            ) -> Result<u32> {
+                instructions::deposit::handler(
+                    ctx,
+                    amount,
+                    commitment,
+                    expected_next_leaf_index,
+                    encrypted_memo,
+                )
+            }
+
+            /// Deposit into a `CommitmentMode::ProgramComputed` pool.
+            pub fn deposit_note_hash(
+                ctx: Context<Deposit>,
+                amount: u64,
+                note_hash: [u8; 32],
+            ) -> Result<()> {
+                instructions::deposit::note_hash_handler(ctx, amount, note_hash)
+            }
+
+            /// Deposit into a `CommitmentMode::PositionBound` pool.
+            pub fn deposit_position_bound(
+                ctx: Context<Deposit>,
+                amount: u64,
+                commitment: [u8; 32],
+                leaf_index: u32,
+            ) -> Result<u32> {
+                instructions::deposit::position_bound_handler(ctx, amount, commitment, leaf_index)
+            }
+
+            pub fn deposit_with_referral(
+                ctx: Context<Deposit>,
+                amount: u64,
+                commitment: [u8; 32],
+                referrer: Pubkey,
+            ) -> Result<()> {
+                instructions::deposit::referral_handler(ctx, amount, commitment, referrer)
+            }
+
+            pub fn init_pending_commitments(ctx: Context<InitPendingCommitments>) -> Result<()> {
+                instructions::deferred_deposit::init_pending_handler(ctx)
+            }
+
+            /// Deposit whose commitment is queued for a later `process_pending`.
+            pub fn deposit_deferred(
+                ctx: Context<DepositDeferred>,
+                amount: u64,
+                commitment: [u8; 32],
+            ) -> Result<u64> {
+                instructions::deferred_deposit::deposit_deferred_handler(ctx, amount, commitment)
+            }
+
+            /// Permissionless: insert up to `max_count` queued commitments.
+            pub fn process_pending(ctx: Context<ProcessPending>, max_count: u8) -> Result<()> {
+                instructions::deferred_deposit::process_pending_handler(ctx, max_count)
+            }
+
+            #[allow(clippy::too_many_arguments)]
+            pub fn withdraw(
+                ctx: Context<Withdraw>,
+                proof_data: Vec<u8>,
+                merkle_root: [u8; 32],
+                nullifier_hash: [u8; 32],
+                recipient: Pubkey,
+                amount: u64,
+                relayer: Pubkey,
+                relayer_fee: u64,
+            ) -> Result<()> {
+                instructions::withdraw::handler(
+                    ctx,
+                    proof_data,
+                    merkle_root,
+                    nullifier_hash,
+                    recipient,
+                    amount,
+                    relayer,
+                    relayer_fee,
+                )
+            }
+
+            #[allow(clippy::too_many_arguments)]
+            pub fn withdraw_to_escrow(
+                ctx: Context<WithdrawToEscrow>,
+                proof_data: Vec<u8>,
+                merkle_root: [u8; 32],
+                nullifier_hash: [u8; 32],
+                recipient: Pubkey,
+                amount: u64,
+                relayer: Pubkey,
+                relayer_fee: u64,
+            ) -> Result<()> {
+                instructions::withdraw_to_escrow::handler(
+                    ctx,
+                    proof_data,
+                    merkle_root,
+                    nullifier_hash,
+                    recipient,
+                    amount,
+                    relayer,
+                    relayer_fee,
+                )
+            }
+
+            #[allow(clippy::too_many_arguments)]
+            pub fn withdraw_batch<'info>(
+                ctx: Context<'_, '_, 'info, 'info, WithdrawBatch<'info>>,
+                proof_data: Vec<u8>,
+                merkle_root: [u8; 32],
+                nullifier_hash: [u8; 32],
+                recipient: Pubkey,
+                amount: u64,
+                relayer: Pubkey,
+                relayer_fee: u64,
+                batch: Vec<BatchedWithdrawal>,
+            ) -> Result<()> {
+                instructions::withdraw_batch::handler(
+                    ctx,
+                    proof_data,
+                    merkle_root,
+                    nullifier_hash,
+                    recipient,
+                    amount,
+                    relayer,
+                    relayer_fee,
+                    batch,
+                )
+            }
+
+            #[allow(clippy::too_many_arguments)]
+            pub fn withdraw_split_fee(
+                ctx: Context<WithdrawSplitFee>,
+                proof_data: Vec<u8>,
+                merkle_root: [u8; 32],
+                nullifier_hash: [u8; 32],
+                recipient: Pubkey,
+                amount: u64,
+                relayer: Pubkey,
+                relayer_fee: u64,
+                second_relayer: Pubkey,
+                second_relayer_fee: u64,
+            ) -> Result<()> {
+                instructions::withdraw_split_fee::handler(
+                    ctx,
+                    proof_data,
+                    merkle_root,
+                    nullifier_hash,
+                    recipient,
+                    amount,
+                    relayer,
+                    relayer_fee,
+                    second_relayer,
+                    second_relayer_fee,
+                )
+            }
+
+            /// Withdrawal that re-deposits `redeposit_amount` of the net as
+            /// `change_commitment`, paying the recipient only the rest.
+            #[allow(clippy::too_many_arguments)]
+            pub fn withdraw_with_redeposit(
+                ctx: Context<WithdrawWithRedeposit>,
+                proof_data: Vec<u8>,
+                merkle_root: [u8; 32],
+                nullifier_hash: [u8; 32],
+                recipient: Pubkey,
+                amount: u64,
+                relayer: Pubkey,
+                relayer_fee: u64,
+                change_commitment: [u8; 32],
+                redeposit_amount: u64,
+            ) -> Result<()> {
+                instructions::withdraw_with_redeposit::handler(
+                    ctx,
+                    proof_data,
+                    merkle_root,
+                    nullifier_hash,
+                    recipient,
+                    amount,
+                    relayer,
+                    relayer_fee,
+                    change_commitment,
+                    redeposit_amount,
+                )
+            }
+
+            /// Withdrawal whose relayer fee is paid in the pool's `fee_mint`.
+            #[allow(clippy::too_many_arguments)]
+            pub fn withdraw_fee_in_mint(
+                ctx: Context<WithdrawFeeInMint>,
+                proof_data: Vec<u8>,
+                merkle_root: [u8; 32],
+                nullifier_hash: [u8; 32],
+                recipient: Pubkey,
+                amount: u64,
+                relayer: Pubkey,
+                relayer_fee: u64,
+            ) -> Result<()> {
+                instructions::withdraw_fee_in_mint::handler(
+                    ctx,
+                    proof_data,
+                    merkle_root,
+                    nullifier_hash,
+                    recipient,
+                    amount,
+                    relayer,
+                    relayer_fee,
+                )
+            }
+
+            pub fn init_fee_reserve(ctx: Context<InitFeeReserve>, fee_mint_rate: u64) -> Result<()> {
+                instructions::admin::fee_reserve::init_fee_reserve_handler(ctx, fee_mint_rate)
+            }
+
+            /// Withdrawal whose relayer fee is paid in lamports from the pool's
+            /// SOL fee reserve; the recipient receives the full `amount`.
+            #[allow(clippy::too_many_arguments)]
+            pub fn withdraw_fee_in_lamports(
+                ctx: Context<WithdrawFeeInLamports>,
+                proof_data: Vec<u8>,
+                merkle_root: [u8; 32],
+                nullifier_hash: [u8; 32],
+                recipient: Pubkey,
+                amount: u64,
+                relayer: Pubkey,
+                relayer_fee_lamports: u64,
+            ) -> Result<()> {
+                instructions::withdraw_fee_in_lamports::handler(
+                    ctx,
+                    proof_data,
+                    merkle_root,
+                    nullifier_hash,
+                    recipient,
+                    amount,
+                    relayer,
+                    relayer_fee_lamports,
+                )
+            }
+
+            pub fn init_sol_fee_reserve(
+                ctx: Context<InitSolFeeReserve>,
+                max_relayer_fee_lamports: u64,
+            ) -> Result<()> {
+                instructions::admin::sol_fee_reserve::init_sol_fee_reserve_handler(
+                    ctx,
+                    max_relayer_fee_lamports,
+                )
+            }
+
+            /// Spend a note in `withdraw.pool_config` and re-create it as
+            /// `commitment` in `deposit.pool_config`, with the relayer swapping the
+            /// underlying tokens.
+            #[allow(clippy::too_many_arguments)]
+            pub fn migrate_note(
+                ctx: Context<MigrateNote>,
+                proof_data: Vec<u8>,
+                merkle_root: [u8; 32],
+                nullifier_hash: [u8; 32],
+                recipient: Pubkey,
+                amount: u64,
+                relayer: Pubkey,
+                relayer_fee: u64,
+                commitment: [u8; 32],
+            ) -> Result<u32> {
+                instructions::migrate_note::handler(
+                    ctx,
+                    proof_data,
+                    merkle_root,
+                    nullifier_hash,
+                    recipient,
+                    amount,
+                    relayer,
+                    relayer_fee,
+                    commitment,
+                )
+            }
+
+            #[allow(clippy::too_many_arguments)]
+            pub fn shielded_swap(
+                ctx: Context<ShieldedSwap>,
+                proof_data: Vec<u8>,
+                merkle_root: [u8; 32],
+                nullifier_hash: [u8; 32],
+                recipient: Pubkey,
+                amount: u64,
+                relayer: Pubkey,
+                relayer_fee: u64,
+                deposit_amount: u64,
+                commitment: [u8; 32],
+            ) -> Result<u32> {
+                instructions::shielded_swap::handler(
+                    ctx,
+                    proof_data,
+                    merkle_root,
+                    nullifier_hash,
+                    recipient,
+                    amount,
+                    relayer,
+                    relayer_fee,
+                    deposit_amount,
+                    commitment,
+                )
+            }
+
+            #[allow(clippy::too_many_arguments)]
+            pub fn withdraw_and_swap(
+                ctx: Context<WithdrawAndSwap>,
+                proof_data: Vec<u8>,
+                merkle_root: [u8; 32],
+                nullifier_hash: [u8; 32],
+                recipient: Pubkey,
+                amount: u64,
+                relayer: Pubkey,
+                relayer_fee: u64,
+                min_amount_out: u64,
+            ) -> Result<()> {
+                instructions::withdraw_and_swap::handler(
+                    ctx,
+                    proof_data,
+                    merkle_root,
+                    nullifier_hash,
+                    recipient,
+                    amount,
+                    relayer,
+                    relayer_fee,
+                    min_amount_out,
+                )
+            }
+
+            /// Withdraw against the confidential-amount circuit.
+            #[allow(clippy::too_many_arguments)]
+            pub fn withdraw_confidential(
+                ctx: Context<Withdraw>,
+                proof_data: Vec<u8>,
+                merkle_root: [u8; 32],
+                nullifier_hash: [u8; 32],
+                recipient: Pubkey,
+                amount: u64,
+                relayer: Pubkey,
+                relayer_fee: u64,
+                amount_blinding: [u8; 32],
+                amount_commitment: [u8; 32],
+            ) -> Result<()> {
+                instructions::withdraw_confidential::handler(
+                    ctx,
+                    proof_data,
+                    merkle_root,
+                    nullifier_hash,
+                    recipient,
+                    amount,
+                    relayer,
+                    relayer_fee,
+                    amount_blinding,
+                    amount_commitment,
+                )
+            }
+
+            pub fn init_recipient_cooldown(
+                ctx: Context<InitRecipientCooldown>,
+                recipient: Pubkey,
+            ) -> Result<()> {
+                instructions::init_recipient_cooldown::handler(ctx, recipient)
+            }
+
+            pub fn is_nullifier_spent(
+                ctx: Context<IsNullifierSpent>,
+                nullifier_hash: [u8; 32],
+            ) -> Result<bool> {
+                instructions::is_nullifier_spent::handler(ctx, nullifier_hash)
+            }
+
+            /// Whether `commitment` is in the tree at `leaf_index` under a known
+            /// `root`; lets wallets catch a desynced tree before proving.
+            pub fn verify_commitment_inclusion(
+                ctx: Context<VerifyCommitmentInclusion>,
+                commitment: [u8; 32],
+                leaf_index: u32,
+                path: Vec<[u8; 32]>,
+                root: [u8; 32],
+            ) -> Result<bool> {
+                instructions::verify_commitment_inclusion::verify_commitment_inclusion_handler(
+                    ctx, commitment, leaf_index, path, root,
+                )
+            }
 
-    #[allow(deprecated)]
-    pub fn update_authority(ctx: Context<UpdateAuthority>, new_authority: Pubkey) -> Result<()> {
-        instructions::admin::update_authority::handler(ctx, new_authority)
+            pub fn get_tree_state(ctx: Context<GetTreeState>, start_level: u8) -> Result<TreeState> {
+                instructions::get_tree_state::handler(ctx, start_level)
+            }
+
+            /// Program-wide bounds (tree depth, root history, amounts, fees).
+            pub fn get_limits(ctx: Context<GetLimits>) -> Result<ProgramLimits> {
+                instructions::get_limits::get_limits_handler(ctx)
+            }
+
+            /// Serialized `VerificationKeyData`, `MAX_VK_CHUNK_LEN` bytes per call.
+            pub fn get_verification_key(
+                ctx: Context<GetVerificationKey>,
+                offset: u32,
+            ) -> Result<VerificationKeyChunk> {
+                instructions::get_verification_key::handler(ctx, offset)
+            }
+
+            pub fn preview_withdrawal(
+                ctx: Context<PreviewWithdrawal>,
+                amount: u64,
+                relayer_fee: u64,
+            ) -> Result<WithdrawalPreview> {
+                instructions::preview_withdrawal::handler(ctx, amount, relayer_fee)
+            }
+
+            #[deprecated(note = "private_transfer is not implemented in pSOL v1 and is a placeholder for a future version.")]
+            pub fn private_transfer(
+                ctx: Context<PrivateTransfer>,
+                _input_nullifiers: Vec<[u8; 32]>,
+                _output_commitments: Vec<[u8; 32]>,
+                proof_data: Vec<u8>,
+            ) -> Result<()> {
+                // The underlying handler is also deprecated and always returns NotImplemented.
+                #[allow(deprecated)]
+                {
+                    instructions::private_transfer::handler(ctx, &proof_data)
+                }
+            }
+
+            pub fn pause_pool(
+                ctx: Context<PausePool>,
+                reason: u8,
+                pause_until: Option<i64>,
+            ) -> Result<()> {
+                instructions::admin::pause::handler(ctx, reason, pause_until)
+            }
+
+            pub fn unpause_pool(ctx: Context<UnpausePool>, reason: u8) -> Result<()> {
+                instructions::admin::unpause::handler(ctx, reason)
+            }
+
+            pub fn set_min_anonymity_set(
+                ctx: Context<UpdatePoolSettings>,
+                min_anonymity_set: u32,
+            ) -> Result<()> {
+                instructions::admin::pool_settings::set_min_anonymity_set_handler(ctx, min_anonymity_set)
+            }
+
+            pub fn set_escrow_program(
+                ctx: Context<UpdatePoolSettings>,
+                escrow_program: Pubkey,
+            ) -> Result<()> {
+                instructions::admin::pool_settings::set_escrow_program_handler(ctx, escrow_program)
+            }
+
+            pub fn set_swap_route(
+                ctx: Context<UpdatePoolSettings>,
+                swap_program: Pubkey,
+                swap_pool: Pubkey,
+            ) -> Result<()> {
+                instructions::admin::pool_settings::set_swap_route_handler(ctx, swap_program, swap_pool)
+            }
+
+            pub fn set_recipient_cooldown_slots(
+                ctx: Context<UpdatePoolSettings>,
+                recipient_cooldown_slots: u64,
+            ) -> Result<()> {
+                instructions::admin::pool_settings::set_recipient_cooldown_slots_handler(
+                    ctx,
+                    recipient_cooldown_slots,
+                )
+            }
+
+            pub fn init_recipient_denylist(ctx: Context<InitRecipientDenylist>) -> Result<()> {
+                instructions::admin::recipient_denylist::init_denylist_handler(ctx)
+            }
+
+            /// Deny (`denied = true`) or re-allow withdrawals to `recipient`.
+            pub fn set_recipient_denied(
+                ctx: Context<UpdateRecipientDenylist>,
+                recipient: Pubkey,
+                denied: bool,
+            ) -> Result<()> {
+                instructions::admin::recipient_denylist::set_recipient_denied_handler(
+                    ctx, recipient, denied,
+                )
+            }
+
+            pub fn set_min_relayer_fee(
+                ctx: Context<UpdatePoolSettings>,
+                min_relayer_fee: u64,
+            ) -> Result<()> {
+                instructions::admin::pool_settings::set_min_relayer_fee_handler(ctx, min_relayer_fee)
+            }
+
+            pub fn set_fee_mint_rate(ctx: Context<UpdatePoolSettings>, fee_mint_rate: u64) -> Result<()> {
+                instructions::admin::pool_settings::set_fee_mint_rate_handler(ctx, fee_mint_rate)
+            }
+
+            pub fn set_max_relayer_fee_lamports(
+                ctx: Context<UpdatePoolSettings>,
+                max_relayer_fee_lamports: u64,
+            ) -> Result<()> {
+                instructions::admin::pool_settings::set_max_relayer_fee_lamports_handler(
+                    ctx,
+                    max_relayer_fee_lamports,
+                )
+            }
+
+            pub fn set_recipient_binding(
+                ctx: Context<UpdatePoolSettings>,
+                recipient_binding: u8,
+            ) -> Result<()> {
+                instructions::admin::pool_settings::set_recipient_binding_handler(ctx, recipient_binding)
+            }
+
+            pub fn set_max_root_age_slots(
+                ctx: Context<UpdatePoolSettings>,
+                max_root_age_slots: u64,
+            ) -> Result<()> {
+                instructions::admin::pool_settings::set_max_root_age_slots_handler(ctx, max_root_age_slots)
+            }
+
+            pub fn set_require_ready_payees(
+                ctx: Context<UpdatePoolSettings>,
+                require_ready_payees: bool,
+            ) -> Result<()> {
+                instructions::admin::pool_settings::set_require_ready_payees_handler(ctx, require_ready_payees)
+            }
+
+            pub fn set_disallow_cpi_withdraw(
+                ctx: Context<UpdatePoolSettings>,
+                disallow_cpi_withdraw: bool,
+            ) -> Result<()> {
+                instructions::admin::pool_settings::set_disallow_cpi_withdraw_handler(ctx, disallow_cpi_withdraw)
+            }
+
+            pub fn set_allow_zero_net_withdrawal(
+                ctx: Context<UpdatePoolSettings>,
+                allow_zero_net_withdrawal: bool,
+            ) -> Result<()> {
+                instructions::admin::pool_settings::set_allow_zero_net_withdrawal_handler(
+                    ctx,
+                    allow_zero_net_withdrawal,
+                )
+            }
+
+            pub fn set_proof_version(ctx: Context<UpdatePoolSettings>, proof_version: u8) -> Result<()> {
+                instructions::admin::pool_settings::set_proof_version_handler(ctx, proof_version)
+            }
+
+            pub fn set_min_note_age_slots(
+                ctx: Context<UpdatePoolSettings>,
+                min_note_age_slots: u64,
+            ) -> Result<()> {
+                instructions::admin::pool_settings::set_min_note_age_slots_handler(ctx, min_note_age_slots)
+            }
+
+            pub fn set_verbose_logging(
+                ctx: Context<UpdatePoolSettings>,
+                verbose_logging: bool,
+            ) -> Result<()> {
+                instructions::admin::pool_settings::set_verbose_logging_handler(ctx, verbose_logging)
+            }
+
+            pub fn set_dev_mode_acknowledged(
+                ctx: Context<UpdatePoolSettings>,
+                acknowledged: bool,
+            ) -> Result<()> {
+                instructions::admin::pool_settings::set_dev_mode_acknowledged_handler(ctx, acknowledged)
+            }
+
+            pub fn init_pool_registry(ctx: Context<InitPoolRegistry>) -> Result<()> {
+                instructions::admin::pool_registry::init_registry_handler(ctx)
+            }
+
+            pub fn pause_all<'info>(
+                ctx: Context<'_, '_, 'info, 'info, PauseAll<'info>>,
+                reason: u8,
+            ) -> Result<()> {
+                instructions::admin::pool_registry::pause_all_handler(ctx, reason)
+            }
+
+            pub fn split_root_history(ctx: Context<SplitRootHistory>) -> Result<()> {
+                instructions::admin::split_root_history::handler(ctx)
+            }
+
+            /// Resize the root history and set `max_root_age_slots` together,
+            /// keeping every root still in the history.
+            pub fn configure_root_policy(
+                ctx: Context<ConfigureRootPolicy>,
+                new_size: u16,
+                max_root_age_slots: u64,
+            ) -> Result<()> {
+                instructions::admin::root_policy::configure_root_policy_handler(ctx, new_size, max_root_age_slots)
+            }
+
+            /// Append a migrated legacy subtree of `count = 2^level` leaves by its
+            /// root. Only before the pool's first deposit.
+            pub fn import_subtree(
+                ctx: Context<ImportSubtree>,
+                level: u8,
+                node: [u8; 32],
+                count: u32,
+            ) -> Result<()> {
+                instructions::admin::import_subtree::import_subtree_handler(ctx, level, node, count)
+            }
+
+            pub fn sweep_untracked(ctx: Context<SweepUntracked>) -> Result<()> {
+                instructions::admin::sweep_untracked::handler(ctx)
+            }
+
+            pub fn initiate_authority_transfer(
+                ctx: Context<InitiateAuthorityTransfer>,
+                new_authority: Pubkey,
+            ) -> Result<()> {
+                instructions::admin::update_authority::initiate_transfer_handler(ctx, new_authority)
+            }
+
+            /// Move the authority to an M-of-N multisig.
+            pub fn init_authority_multisig(
+                ctx: Context<InitAuthorityMultisig>,
+                signers: Vec<Pubkey>,
+                threshold: u8,
+            ) -> Result<()> {
+                instructions::admin::authority_multisig::init_multisig_handler(ctx, signers, threshold)
+            }
+
+            pub fn accept_authority_transfer(ctx: Context<AcceptAuthorityTransfer>) -> Result<()> {
+                instructions::admin::update_authority::accept_transfer_handler(ctx)
+            }
+
+            pub fn cancel_authority_transfer(ctx: Context<CancelAuthorityTransfer>) -> Result<()> {
+                instructions::admin::update_authority::cancel_transfer_handler(ctx)
+            }
+
+            pub fn initiate_fee_recipient_change(
+                ctx: Context<InitiateFeeRecipientChange>,
+                new_fee_recipient: Pubkey,
+            ) -> Result<()> {
+                instructions::admin::fee_recipient::initiate_change_handler(ctx, new_fee_recipient)
+            }
+
+            pub fn accept_fee_recipient_change(ctx: Context<AcceptFeeRecipientChange>) -> Result<()> {
+                instructions::admin::fee_recipient::accept_change_handler(ctx)
+            }
+
+            pub fn cancel_fee_recipient_change(ctx: Context<CancelFeeRecipientChange>) -> Result<()> {
+                instructions::admin::fee_recipient::cancel_change_handler(ctx)
+            }
+
+            #[allow(deprecated)]
+            pub fn update_authority(ctx: Context<UpdateAuthority>, new_authority: Pubkey) -> Result<()> {
+                instructions::admin::update_authority::handler(ctx, new_authority)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "dev-mode")]
+psol_privacy_program! {
+    /// Dev-mode only: seed deterministic commitments without deposits.
+    pub fn dev_seed_commitments(ctx: Context<DevSeedCommitments>, count: u32) -> Result<()> {
+        instructions::dev_seed_commitments::handler(ctx, count)
     }
 }
 
+#[cfg(not(feature = "dev-mode"))]
+psol_privacy_program! {}
+
 pub use error::PrivacyError;
 pub use events::*;
 pub use state::{MerkleTree, PoolConfig, SpentNullifier, VerificationKeyAccount};
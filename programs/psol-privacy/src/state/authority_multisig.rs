@@ -0,0 +1,138 @@
+//! Authority Multisig
+//!
+//! Simple on-chain M-of-N threshold account. When a pool enables it,
+//! `PoolConfig::authority` is set to this account's address and the
+//! sensitive admin actions (pause, VK changes, authority transfer) require
+//! `threshold` distinct member signatures in the same transaction.
+
+use anchor_lang::prelude::*;
+
+use crate::error::PrivacyError;
+
+/// Maximum members per multisig (bounds account size and signer scans).
+pub const MAX_MULTISIG_SIGNERS: usize = 11;
+
+/// M-of-N signer set for a pool's authority.
+///
+/// PDA Seeds: `[b"authority_multisig", pool_config.key().as_ref()]`
+#[account]
+pub struct AuthorityMultisig {
+    /// Pool this multisig governs
+    pub pool: Pubkey,
+
+    /// Distinct member signatures required (M)
+    pub threshold: u8,
+
+    /// Members (N), no duplicates
+    pub signers: Vec<Pubkey>,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl AuthorityMultisig {
+    pub const LEN: usize = 8                        // discriminator
+        + 32                                        // pool
+        + 1                                         // threshold
+        + 4 + (32 * MAX_MULTISIG_SIGNERS)           // signers (vec)
+        + 1;                                        // bump
+
+    /// Set the member set and threshold.
+    ///
+    /// # Errors
+    /// * `InvalidMultisigConfig` if there are no members, more than
+    ///   `MAX_MULTISIG_SIGNERS`, a duplicate or zero member, or the
+    ///   threshold is not in `1..=signers.len()`
+    pub fn initialize(
+        &mut self,
+        pool: Pubkey,
+        signers: Vec<Pubkey>,
+        threshold: u8,
+        bump: u8,
+    ) -> Result<()> {
+        require!(
+            !signers.is_empty() && signers.len() <= MAX_MULTISIG_SIGNERS,
+            PrivacyError::InvalidMultisigConfig
+        );
+        require!(
+            threshold >= 1 && threshold as usize <= signers.len(),
+            PrivacyError::InvalidMultisigConfig
+        );
+        for (i, signer) in signers.iter().enumerate() {
+            require!(
+                *signer != Pubkey::default() && !signers[..i].contains(signer),
+                PrivacyError::InvalidMultisigConfig
+            );
+        }
+
+        self.pool = pool;
+        self.threshold = threshold;
+        self.signers = signers;
+        self.bump = bump;
+        Ok(())
+    }
+
+    /// Number of distinct members among `signers`.
+    pub fn count_approvals(&self, signers: &[Pubkey]) -> usize {
+        self.signers
+            .iter()
+            .filter(|member| signers.contains(member))
+            .count()
+    }
+
+    /// Require at least `threshold` distinct members among `signers`.
+    pub fn require_threshold(&self, signers: &[Pubkey]) -> Result<()> {
+        require!(
+            self.count_approvals(signers) >= self.threshold as usize,
+            PrivacyError::MultisigThresholdNotMet
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_multisig(members: &[Pubkey], threshold: u8) -> Result<AuthorityMultisig> {
+        let mut multisig = AuthorityMultisig::try_deserialize_unchecked(
+            &mut vec![0u8; AuthorityMultisig::LEN].as_slice(),
+        )
+        .unwrap();
+        multisig.initialize(Pubkey::new_unique(), members.to_vec(), threshold, 255)?;
+        Ok(multisig)
+    }
+
+    #[test]
+    fn test_threshold_counts_distinct_members() {
+        let members: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let multisig = new_multisig(&members, 2).unwrap();
+
+        assert!(multisig.require_threshold(&members[..1]).is_err());
+        assert!(multisig.require_threshold(&members[..2]).is_ok());
+        assert!(multisig.require_threshold(&members).is_ok());
+
+        // A repeated signer or an outsider does not count towards M
+        assert!(multisig.require_threshold(&[members[0], members[0]]).is_err());
+        assert!(multisig
+            .require_threshold(&[members[0], Pubkey::new_unique()])
+            .is_err());
+    }
+
+    #[test]
+    fn test_invalid_configs_rejected() {
+        let a = Pubkey::new_unique();
+        let b = Pubkey::new_unique();
+
+        assert!(new_multisig(&[], 1).is_err());
+        assert!(new_multisig(&[a, b], 0).is_err());
+        assert!(new_multisig(&[a, b], 3).is_err());
+        assert!(new_multisig(&[a, a], 1).is_err());
+        assert!(new_multisig(&[a, Pubkey::default()], 1).is_err());
+
+        let too_many: Vec<Pubkey> = (0..=MAX_MULTISIG_SIGNERS)
+            .map(|_| Pubkey::new_unique())
+            .collect();
+        assert!(new_multisig(&too_many, 1).is_err());
+    }
+}
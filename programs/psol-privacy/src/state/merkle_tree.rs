@@ -4,13 +4,32 @@
 //! Uses filled_subtrees pattern for O(log n) insertions.
 //!
 //! # Hash Function
-//! Currently uses a placeholder hash (see crypto/poseidon.rs).
-//! MUST be replaced with actual Poseidon before production.
+//! Selected per tree at init via `tree_hash` (see crypto/poseidon.rs):
+//! Poseidon (0) or Keccak256 (1). MUST match the withdrawal circuit.
+//!
+//! # Root History Layout
+//! Roots live in this account by default. Large pools can split them into a
+//! `RootHistory` PDA (see root_history.rs); use `insert_leaf_into` and
+//! `is_known_root_in` so both layouts are handled.
+//!
+//! # Leaves vs Nullifiers
+//! Leaves are only ever commitments (`Poseidon(secret, nullifier_preimage,
+//! amount)` or a variant). A `nullifier_hash` (`Poseidon(nullifier_preimage,
+//! secret)`) is revealed by a withdrawal and only ever seeds a
+//! `SpentNullifier` PDA; it must never be inserted here. Flows that hold
+//! both at once (re-deposits, shielded swaps) check them with
+//! `debug_assert_not_nullifier`.
 
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
 
-use crate::crypto::poseidon;
+use crate::crypto::is_valid_scalar;
+use crate::crypto::poseidon::TreeHash;
 use crate::error::PrivacyError;
+use crate::state::root_history::{
+    distinct_roots_in_history, push_root_to_history, resize_history, root_in_history,
+    root_slot_in_history, RootHistory,
+};
 
 /// Maximum supported tree depth (2^24 = ~16M leaves)
 pub const MAX_TREE_DEPTH: u8 = 24;
@@ -21,6 +40,14 @@ pub const MIN_TREE_DEPTH: u8 = 4;
 /// Minimum root history size
 pub const MIN_ROOT_HISTORY_SIZE: u16 = 200;
 
+/// Debug-build check that a leaf about to be inserted is not the
+/// `nullifier_hash` spent in the same instruction, i.e. that the two were
+/// not swapped. Best-effort: the tree keeps no record of past nullifiers.
+#[inline]
+pub fn debug_assert_not_nullifier(leaf: &[u8; 32], nullifier_hash: &[u8; 32]) {
+    debug_assert_ne!(leaf, nullifier_hash, "nullifier hash inserted as a tree leaf");
+}
+
 /// Incremental Merkle tree state account.
 ///
 /// PDA Seeds: `[b"merkle_tree", pool_config.key().as_ref()]`
@@ -32,6 +59,13 @@ pub struct MerkleTree {
     /// Tree depth (immutable after init)
     pub depth: u8,
 
+    /// Internal node hash function (`TreeHash` discriminant, immutable after init)
+    pub tree_hash: u8,
+
+    /// Root history lives in a separate `RootHistory` account
+    /// (`root_history` / `root_slots` below are then empty)
+    pub external_root_history: bool,
+
     /// Next leaf index to be filled (also = total leaves inserted)
     pub next_leaf_index: u32,
 
@@ -42,6 +76,9 @@ pub struct MerkleTree {
     /// Allows users to prove against recent roots even if tree updated
     pub root_history: Vec<[u8; 32]>,
 
+    /// Slot at which each `root_history` entry was inserted (parallel buffer)
+    pub root_slots: Vec<u64>,
+
     /// Current position in circular root history buffer
     pub root_history_index: u16,
 
@@ -73,9 +110,12 @@ impl MerkleTree {
         8                                       // discriminator
             + 32                                // pool
             + 1                                 // depth
+            + 1                                 // tree_hash
+            + 1                                 // external_root_history
             + 4                                 // next_leaf_index
             + 32                                // current_root
             + 4 + (32 * history_usize)          // root_history (vec)
+            + 4 + (8 * history_usize)           // root_slots (vec)
             + 2                                 // root_history_index
             + 2                                 // root_history_size
             + 4 + (32 * depth_usize)            // filled_subtrees (vec)
@@ -88,6 +128,28 @@ impl MerkleTree {
         pool: Pubkey,
         depth: u8,
         root_history_size: u16,
+        tree_hash: u8,
+    ) -> Result<()> {
+        self.initialize_with_zeros(pool, depth, root_history_size, tree_hash, None)
+    }
+
+    /// Initialize the Merkle tree, optionally with externally specified
+    /// zero values.
+    ///
+    /// Tornado-style circuits fix a list of per-level zeros rather than
+    /// deriving `zeros[i] = hash(zeros[i-1], zeros[i-1])` from a zero leaf.
+    /// Passing that list makes the empty root and every empty sibling match
+    /// the circuit; `None` computes them with `compute_zero_values`.
+    ///
+    /// # Errors
+    /// * `InvalidZeroValues` if `zeros` fails `validate_zero_values`
+    pub fn initialize_with_zeros(
+        &mut self,
+        pool: Pubkey,
+        depth: u8,
+        root_history_size: u16,
+        tree_hash: u8,
+        zeros: Option<Vec<[u8; 32]>>,
     ) -> Result<()> {
         // Validate parameters
         require!(
@@ -98,21 +160,31 @@ impl MerkleTree {
             root_history_size >= MIN_ROOT_HISTORY_SIZE,
             PrivacyError::InvalidRootHistorySize
         );
+        let hash = TreeHash::from_u8(tree_hash).ok_or(error!(PrivacyError::InvalidTreeHash))?;
 
         self.pool = pool;
         self.depth = depth;
+        self.tree_hash = tree_hash;
+        self.external_root_history = false;
         self.next_leaf_index = 0;
         self.root_history_index = 0;
         self.root_history_size = root_history_size;
 
-        // Compute and store zero values for all levels
-        self.zeros = Self::compute_zero_values(depth);
+        // Store supplied zero values, or compute them for all levels
+        self.zeros = match zeros {
+            Some(zeros) => {
+                Self::validate_zero_values(depth, &zeros)?;
+                zeros
+            }
+            None => Self::compute_zero_values(depth, hash)?,
+        };
 
         // Initialize filled subtrees with zeros (will be overwritten on inserts)
         self.filled_subtrees = self.zeros[..depth as usize].to_vec();
 
         // Initialize root history buffer
         self.root_history = vec![[0u8; 32]; root_history_size as usize];
+        self.root_slots = vec![0u64; root_history_size as usize];
 
         // Set initial root (root of empty tree)
         self.current_root = self.zeros[depth as usize];
@@ -130,7 +202,7 @@ impl MerkleTree {
     ///
     /// # Note
     /// These MUST match the circuit's zero values exactly.
-    fn compute_zero_values(depth: u8) -> Vec<[u8; 32]> {
+    pub fn compute_zero_values(depth: u8, hash: TreeHash) -> Result<Vec<[u8; 32]>> {
         let mut zeros = Vec::with_capacity((depth + 1) as usize);
 
         // Level 0: canonical zero leaf
@@ -140,24 +212,69 @@ impl MerkleTree {
         // Compute hash(zero[i-1], zero[i-1]) for each level
         for i in 1..=depth {
             let prev = &zeros[(i - 1) as usize];
-            let zero_at_level = poseidon::hash_two_to_one(prev, prev);
+            let zero_at_level = hash.hash_two_to_one(prev, prev)?;
             zeros.push(zero_at_level);
         }
 
-        zeros
+        Ok(zeros)
     }
 
-    /// Insert a new commitment leaf into the tree.
+    /// Check externally supplied zero values: one per level including the
+    /// root (`depth + 1`), each a canonical BN254 scalar so the circuit
+    /// can take it as a field element.
+    ///
+    /// # Errors
+    /// * `InvalidZeroValues` on a wrong length or non-canonical value
+    pub fn validate_zero_values(depth: u8, zeros: &[[u8; 32]]) -> Result<()> {
+        require!(
+            zeros.len() == depth as usize + 1,
+            PrivacyError::InvalidZeroValues
+        );
+        require!(
+            zeros.iter().all(is_valid_scalar),
+            PrivacyError::InvalidZeroValues
+        );
+        Ok(())
+    }
+
+    /// Decode the stored hash selector.
+    pub fn hash_fn(&self) -> Result<TreeHash> {
+        TreeHash::from_u8(self.tree_hash).ok_or(error!(PrivacyError::InvalidTreeHash))
+    }
+
+    /// Insert a new commitment leaf into a single-account tree.
     ///
     /// # Arguments
     /// * `commitment` - 32-byte commitment hash
+    /// * `slot` - Current slot, recorded alongside the new root
     ///
     /// # Returns
     /// The leaf index where commitment was inserted
     ///
     /// # Errors
     /// * `MerkleTreeFull` if tree has reached capacity
-    pub fn insert_leaf(&mut self, commitment: [u8; 32]) -> Result<u32> {
+    /// * `RootHistoryMismatch` if the tree uses a separate `RootHistory`
+    pub fn insert_leaf(&mut self, commitment: [u8; 32], slot: u64) -> Result<u32> {
+        self.insert_leaf_into(commitment, slot, None)
+    }
+
+    /// Insert a new commitment leaf, recording the new root in `history`
+    /// for split-layout trees or in this account otherwise.
+    ///
+    /// # Errors
+    /// * `MerkleTreeFull` if tree has reached capacity
+    /// * `RootHistoryMismatch` if `history` does not match the tree's layout
+    pub fn insert_leaf_into(
+        &mut self,
+        commitment: [u8; 32],
+        slot: u64,
+        history: Option<&mut RootHistory>,
+    ) -> Result<u32> {
+        require!(
+            self.external_root_history == history.is_some(),
+            PrivacyError::RootHistoryMismatch
+        );
+
         // Check tree capacity
         let max_leaves = 1u32
             .checked_shl(self.depth as u32)
@@ -169,11 +286,90 @@ impl MerkleTree {
         );
 
         let leaf_index = self.next_leaf_index;
-        let mut current_hash = commitment;
-        let mut current_index = leaf_index;
+        self.insert_node(0, commitment, slot, history)?;
+
+        // Increment leaf counter
+        self.next_leaf_index = self
+            .next_leaf_index
+            .checked_add(1)
+            .ok_or(error!(PrivacyError::ArithmeticOverflow))?;
+
+        Ok(leaf_index)
+    }
+
+    /// Import a complete subtree of `count = 2^level` leaves whose root is
+    /// `node`, as if its leaves had been inserted one by one. Used to migrate
+    /// commitments from a legacy pool without re-depositing each leaf.
+    ///
+    /// Invariants (checked here):
+    /// * `next_leaf_index` is a multiple of `count`, so the block occupies
+    ///   exactly one node at `level`. Unaligned prefixes are imported as a
+    ///   sequence of smaller blocks, largest first.
+    /// * The block fits in the remaining capacity.
+    ///
+    /// `filled_subtrees` below `level` are left stale: the next insertion
+    /// starts at an aligned index, so it is a left child at every level
+    /// below `level` and overwrites them before they are read.
+    ///
+    /// Only the resulting root enters the root history; the roots a leaf
+    /// by leaf insertion would have produced in between are never known.
+    ///
+    /// # Returns
+    /// The index of the first imported leaf
+    ///
+    /// # Errors
+    /// * `InvalidSubtreeImport` if `count != 2^level` or the block is unaligned
+    /// * `MerkleTreeFull` if the block does not fit
+    /// * `RootHistoryMismatch` if `history` does not match the tree's layout
+    pub fn import_subtree(
+        &mut self,
+        level: u8,
+        node: [u8; 32],
+        count: u32,
+        slot: u64,
+        history: Option<&mut RootHistory>,
+    ) -> Result<u32> {
+        require!(
+            self.external_root_history == history.is_some(),
+            PrivacyError::RootHistoryMismatch
+        );
+        require!(
+            level <= self.depth && 1u64 << level == count as u64,
+            PrivacyError::InvalidSubtreeImport
+        );
+        require!(
+            self.next_leaf_index % count == 0,
+            PrivacyError::InvalidSubtreeImport
+        );
+
+        let first_leaf = self.next_leaf_index;
+        let end = first_leaf as u64 + count as u64;
+        require!(end <= self.capacity() as u64, PrivacyError::MerkleTreeFull);
+
+        self.insert_node(level, node, slot, history)?;
+        self.next_leaf_index = end as u32;
+
+        Ok(first_leaf)
+    }
+
+    /// Place `node` at `level` in the position of the next leaf and
+    /// recompute the path to the root, recording the new root.
+    ///
+    /// The caller checks capacity and alignment and advances
+    /// `next_leaf_index`.
+    fn insert_node(
+        &mut self,
+        level: u8,
+        node: [u8; 32],
+        slot: u64,
+        history: Option<&mut RootHistory>,
+    ) -> Result<()> {
+        let hash = self.hash_fn()?;
+        let mut current_hash = node;
+        let mut current_index = self.next_leaf_index >> level;
 
         // Walk up the tree, updating hashes
-        for level in 0..self.depth {
+        for level in level..self.depth {
             let level_usize = level as usize;
 
             // Determine if this node is a left (0) or right (1) child
@@ -183,11 +379,11 @@ impl MerkleTree {
             if is_right_child {
                 // Right child: hash with left sibling from filled_subtrees
                 let left_sibling = self.filled_subtrees[level_usize];
-                current_hash = poseidon::hash_two_to_one(&left_sibling, &current_hash);
+                current_hash = hash.hash_two_to_one(&left_sibling, &current_hash)?;
             } else {
                 // Left child: update filled_subtree, hash with zero
                 self.filled_subtrees[level_usize] = current_hash;
-                current_hash = poseidon::hash_two_to_one(&current_hash, &self.zeros[level_usize]);
+                current_hash = hash.hash_two_to_one(&current_hash, &self.zeros[level_usize])?;
             }
         }
 
@@ -195,16 +391,19 @@ impl MerkleTree {
         self.current_root = current_hash;
 
         // Add to root history (circular buffer)
-        self.root_history_index = (self.root_history_index + 1) % self.root_history_size;
-        self.root_history[self.root_history_index as usize] = current_hash;
-
-        // Increment leaf counter
-        self.next_leaf_index = self
-            .next_leaf_index
-            .checked_add(1)
-            .ok_or(error!(PrivacyError::ArithmeticOverflow))?;
+        if let Some(history) = history {
+            history.push(current_hash, slot);
+        } else {
+            push_root_to_history(
+                &mut self.root_history,
+                &mut self.root_slots,
+                &mut self.root_history_index,
+                current_hash,
+                slot,
+            );
+        }
 
-        Ok(leaf_index)
+        Ok(())
     }
 
     /// Check if a root exists in recent history.
@@ -222,6 +421,175 @@ impl MerkleTree {
         self.root_history.iter().any(|r| r == root)
     }
 
+    /// Check if a root exists in recent history and was inserted no more
+    /// than `max_age_slots` before `current_slot`.
+    ///
+    /// A `max_age_slots` of 0 disables the age limit.
+    pub fn is_known_root_within(
+        &self,
+        root: &[u8; 32],
+        current_slot: u64,
+        max_age_slots: u64,
+    ) -> bool {
+        if max_age_slots == 0 {
+            return self.is_known_root(root);
+        }
+
+        root_in_history(
+            &self.root_history,
+            &self.root_slots,
+            root,
+            current_slot,
+            max_age_slots,
+        )
+    }
+
+    /// Slot at which `root` entered the history buffer, if it is still there.
+    pub fn root_slot(&self, root: &[u8; 32]) -> Option<u64> {
+        root_slot_in_history(&self.root_history, &self.root_slots, root)
+    }
+
+    /// Layout-aware `root_slot`.
+    ///
+    /// # Errors
+    /// * `RootHistoryMismatch` if `history` does not match the tree's layout
+    pub fn root_slot_in(&self, history: Option<&RootHistory>, root: &[u8; 32]) -> Result<Option<u64>> {
+        require!(
+            self.external_root_history == history.is_some(),
+            PrivacyError::RootHistoryMismatch
+        );
+        Ok(match history {
+            Some(history) => history.root_slot(root),
+            None => self.root_slot(root),
+        })
+    }
+
+    /// Resize the root history to `new_size` entries, keeping every root
+    /// still in it, in order. Reads and writes `history` for split-layout
+    /// trees and this account otherwise.
+    ///
+    /// Shrinking evicts the oldest roots, so it is only allowed when none
+    /// of them is still valid under `max_age_slots` at `current_slot`
+    /// (with 0, every recorded root is valid).
+    ///
+    /// # Errors
+    /// * `InvalidRootHistorySize` if `new_size` is below `MIN_ROOT_HISTORY_SIZE`
+    /// * `RootHistoryMismatch` if `history` does not match the tree's layout
+    /// * `RootHistoryShrinkDropsRoots` if a valid root would be evicted
+    pub fn resize_root_history_in(
+        &mut self,
+        history: Option<&mut RootHistory>,
+        new_size: u16,
+        current_slot: u64,
+        max_age_slots: u64,
+    ) -> Result<()> {
+        require!(
+            new_size >= MIN_ROOT_HISTORY_SIZE,
+            PrivacyError::InvalidRootHistorySize
+        );
+        require!(
+            self.external_root_history == history.is_some(),
+            PrivacyError::RootHistoryMismatch
+        );
+
+        match history {
+            Some(history) => history.resize(new_size, current_slot, max_age_slots),
+            None => {
+                resize_history(
+                    &mut self.root_history,
+                    &mut self.root_slots,
+                    &mut self.root_history_index,
+                    new_size,
+                    current_slot,
+                    max_age_slots,
+                )?;
+                self.root_history_size = new_size;
+                Ok(())
+            }
+        }
+    }
+
+    /// Number of distinct roots in the history buffer, for diagnostics: the
+    /// effective width of the window proofs can be built against.
+    pub fn distinct_root_count(&self) -> usize {
+        distinct_roots_in_history(&self.root_history)
+    }
+
+    /// Layout-aware `distinct_root_count`.
+    ///
+    /// # Errors
+    /// * `RootHistoryMismatch` if `history` does not match the tree's layout
+    pub fn distinct_root_count_in(&self, history: Option<&RootHistory>) -> Result<usize> {
+        require!(
+            self.external_root_history == history.is_some(),
+            PrivacyError::RootHistoryMismatch
+        );
+        Ok(match history {
+            Some(history) => history.distinct_root_count(),
+            None => self.distinct_root_count(),
+        })
+    }
+
+    /// Layout-aware root check: reads `history` for split-layout trees and
+    /// this account otherwise. Returns `(known, within_max_age)`.
+    ///
+    /// # Errors
+    /// * `RootHistoryMismatch` if `history` does not match the tree's layout
+    pub fn is_known_root_in(
+        &self,
+        history: Option<&RootHistory>,
+        root: &[u8; 32],
+        current_slot: u64,
+        max_age_slots: u64,
+    ) -> Result<(bool, bool)> {
+        require!(
+            self.external_root_history == history.is_some(),
+            PrivacyError::RootHistoryMismatch
+        );
+
+        Ok(match history {
+            Some(history) => (
+                history.is_known_root(root),
+                history.is_known_root_within(root, current_slot, max_age_slots),
+            ),
+            None => (
+                self.is_known_root(root),
+                self.is_known_root_within(root, current_slot, max_age_slots),
+            ),
+        })
+    }
+
+    /// Whether `path` (siblings, leaf level first) takes `leaf` at
+    /// `leaf_index` to `root` under this tree's hash. Left/right at each
+    /// level follows the bits of `leaf_index`.
+    ///
+    /// Returns `false` if `path` is not `depth` siblings long or
+    /// `leaf_index` is outside the tree.
+    pub fn verify_merkle_path(
+        &self,
+        leaf: &[u8; 32],
+        leaf_index: u32,
+        path: &[[u8; 32]],
+        root: &[u8; 32],
+    ) -> Result<bool> {
+        if path.len() != self.depth as usize || leaf_index >= self.capacity() {
+            return Ok(false);
+        }
+
+        let hash = self.hash_fn()?;
+        let mut node = *leaf;
+        let mut index = leaf_index;
+        for sibling in path {
+            node = if index & 1 == 1 {
+                hash.hash_two_to_one(sibling, &node)?
+            } else {
+                hash.hash_two_to_one(&node, sibling)?
+            };
+            index >>= 1;
+        }
+        Ok(node == *root)
+    }
+
     /// Get the current Merkle root.
     pub fn get_current_root(&self) -> [u8; 32] {
         self.current_root
@@ -243,9 +611,116 @@ impl MerkleTree {
     }
 }
 
+/// `MerkleTree` as laid out before the per-tree hash selector, slot-tagged
+/// roots and split root history. Read only by `migrate_merkle_tree`; such
+/// accounts no longer deserialize as a `MerkleTree`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct MerkleTreeV1 {
+    pub pool: Pubkey,
+    pub depth: u8,
+    pub next_leaf_index: u32,
+    pub current_root: [u8; 32],
+    pub root_history: Vec<[u8; 32]>,
+    pub root_history_index: u16,
+    pub root_history_size: u16,
+    pub filled_subtrees: Vec<[u8; 32]>,
+    pub zeros: Vec<[u8; 32]>,
+}
+
+impl MerkleTreeV1 {
+    /// Allocated size of a version 1 tree account.
+    pub fn space(depth: u8, root_history_size: u16) -> usize {
+        let depth_usize = depth as usize;
+        let history_usize = root_history_size as usize;
+
+        8                                       // discriminator
+            + 32                                // pool
+            + 1                                 // depth
+            + 4                                 // next_leaf_index
+            + 32                                // current_root
+            + 4 + (32 * history_usize)          // root_history (vec)
+            + 2                                 // root_history_index
+            + 2                                 // root_history_size
+            + 4 + (32 * depth_usize)            // filled_subtrees (vec)
+            + 4 + (32 * (depth_usize + 1))      // zeros (vec)
+    }
+
+    /// Parse a version 1 tree account, discriminator included.
+    ///
+    /// Version 1 accounts were allocated at exactly `space`, so `data` must
+    /// hold one version 1 tree and nothing else; a current-layout tree
+    /// never does.
+    ///
+    /// # Errors
+    /// * `MerkleTreeNotMigratable` unless `data` is exactly a version 1
+    ///   `MerkleTree` account
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self> {
+        require!(
+            data.len() >= 8 && data[..8] == MerkleTree::DISCRIMINATOR,
+            PrivacyError::MerkleTreeNotMigratable
+        );
+        let old = Self::deserialize(&mut &data[8..])
+            .map_err(|_| error!(PrivacyError::MerkleTreeNotMigratable))?;
+        let depth = old.depth as usize;
+        require!(
+            data.len() == Self::space(old.depth, old.root_history_size)
+                && old.root_history.len() == old.root_history_size as usize
+                && old.filled_subtrees.len() == depth
+                && old.zeros.len() == depth + 1,
+            PrivacyError::MerkleTreeNotMigratable
+        );
+        Ok(old)
+    }
+}
+
+impl MerkleTree {
+    /// Current-layout tree for a version 1 tree. Version 1 trees hashed
+    /// with Keccak256 and kept their roots in this account. Their roots
+    /// carry no insertion slot, so each stored root is tagged with
+    /// `slot`, the migration slot: age limits count from the migration and
+    /// no pre-migration root looks older than it is.
+    pub fn from_v1(old: &MerkleTreeV1, slot: u64) -> Self {
+        let root_slots = old
+            .root_history
+            .iter()
+            .map(|root| if *root == [0u8; 32] { 0 } else { slot })
+            .collect();
+        Self {
+            pool: old.pool,
+            depth: old.depth,
+            tree_hash: TreeHash::Keccak as u8,
+            external_root_history: false,
+            next_leaf_index: old.next_leaf_index,
+            current_root: old.current_root,
+            root_history: old.root_history.clone(),
+            root_slots,
+            root_history_index: old.root_history_index,
+            root_history_size: old.root_history_size,
+            filled_subtrees: old.filled_subtrees.clone(),
+            zeros: old.zeros.clone(),
+        }
+    }
+}
+
+/// Shared tree for unit tests.
+#[cfg(test)]
+pub(crate) mod test_fixtures {
+    use super::*;
+
+    /// An empty tree of `depth` with the minimum root history.
+    pub(crate) fn new_tree(depth: u8, tree_hash: TreeHash) -> MerkleTree {
+        let data = vec![0u8; MerkleTree::space(depth, MIN_ROOT_HISTORY_SIZE)];
+        let mut tree = MerkleTree::try_deserialize_unchecked(&mut data.as_slice()).unwrap();
+        tree.initialize(Pubkey::new_unique(), depth, MIN_ROOT_HISTORY_SIZE, tree_hash as u8)
+            .unwrap();
+        tree
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use super::test_fixtures::new_tree;
 
     #[test]
     fn test_space_calculation() {
@@ -254,10 +729,392 @@ mod tests {
         assert!(space < 10_000_000); // Less than 10MB (Solana limit)
     }
 
+    #[test]
+    fn test_space_matches_serialized_len_at_limits() {
+        use crate::instructions::initialize_pool::{MAX_ROOT_HISTORY, MIN_ROOT_HISTORY};
+        use crate::state::serialized_len;
+
+        for (depth, history) in [
+            (MAX_TREE_DEPTH, MAX_ROOT_HISTORY),
+            (MAX_TREE_DEPTH, MIN_ROOT_HISTORY),
+            (MIN_TREE_DEPTH, MAX_ROOT_HISTORY),
+        ] {
+            let space = MerkleTree::space(depth, history);
+            let data = vec![0u8; space];
+            let mut tree = MerkleTree::try_deserialize_unchecked(&mut data.as_slice()).unwrap();
+            tree.initialize(Pubkey::new_unique(), depth, history, TreeHash::Keccak as u8).unwrap();
+            assert_eq!(serialized_len(&tree), space, "depth {} history {}", depth, history);
+
+            // Inserts rewrite entries in place and never grow the account
+            tree.insert_leaf([7u8; 32], 1).unwrap();
+            assert_eq!(serialized_len(&tree), space);
+        }
+    }
+
+    fn leaf(i: u8) -> [u8; 32] {
+        let mut leaf = [0u8; 32];
+        leaf[31] = i;
+        leaf
+    }
+
+    fn split(tree: &mut MerkleTree) -> RootHistory {
+        let data = vec![0u8; RootHistory::space(tree.root_history_size)];
+        let mut history = RootHistory::try_deserialize_unchecked(&mut data.as_slice()).unwrap();
+        history.take_from(tree, 255).unwrap();
+        history
+    }
+
+    #[test]
+    fn test_split_layout_answers_identically() {
+        let mut single = new_tree(8, TreeHash::Keccak);
+        let mut external = new_tree(8, TreeHash::Keccak);
+        external.insert_leaf(leaf(1), 10).unwrap();
+        single.insert_leaf(leaf(1), 10).unwrap();
+
+        // Split after one insert: pre-split roots must carry over
+        let mut history = split(&mut external);
+        assert!(external.external_root_history);
+        assert!(external.root_history.is_empty());
+
+        let mut roots = vec![single.current_root];
+        for i in 2..=20u8 {
+            single.insert_leaf(leaf(i), 10 + i as u64).unwrap();
+            external
+                .insert_leaf_into(leaf(i), 10 + i as u64, Some(&mut history))
+                .unwrap();
+            roots.push(single.current_root);
+        }
+        assert_eq!(single.current_root, external.current_root);
+
+        roots.push([0xabu8; 32]);
+        for root in &roots {
+            for max_age in [0u64, 5, 100] {
+                assert_eq!(
+                    single.is_known_root_in(None, root, 30, max_age).unwrap(),
+                    external
+                        .is_known_root_in(Some(&history), root, 30, max_age)
+                        .unwrap(),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_split_layout_requires_history_account() {
+        let mut tree = new_tree(8, TreeHash::Keccak);
+        let mut history = split(&mut tree);
+        let root = tree.current_root;
+
+        assert!(tree.insert_leaf(leaf(1), 0).is_err());
+        assert!(tree.is_known_root_in(None, &root, 0, 0).is_err());
+
+        let mut single = new_tree(8, TreeHash::Keccak);
+        assert!(single.insert_leaf_into(leaf(1), 0, Some(&mut history)).is_err());
+        assert!(history.take_from(&mut tree, 255).is_err());
+    }
+
+    #[test]
+    fn test_distinct_root_count() {
+        let mut tree = new_tree(8, TreeHash::Keccak);
+        // Only the empty root; unwritten slots do not count
+        assert_eq!(tree.distinct_root_count(), 1);
+
+        for i in 1..=3u8 {
+            tree.insert_leaf(leaf(i), i as u64).unwrap();
+        }
+        assert_eq!(tree.distinct_root_count(), 4);
+
+        // A zero-value leaf leaves the root unchanged: no new history entry
+        let (root, index) = (tree.current_root, tree.root_history_index);
+        tree.insert_leaf(tree.zeros[0], 9).unwrap();
+        assert_eq!(tree.current_root, root);
+        assert_eq!(tree.root_history_index, index);
+        assert_eq!(tree.root_slot(&root), Some(3));
+        assert_eq!(tree.distinct_root_count(), 4);
+
+        // Saturates at the buffer size once it wraps
+        for i in 10..(10 + MIN_ROOT_HISTORY_SIZE as u8) {
+            tree.insert_leaf(leaf(i), i as u64).unwrap();
+        }
+        assert_eq!(tree.distinct_root_count(), MIN_ROOT_HISTORY_SIZE as usize);
+
+        let history = split(&mut tree);
+        assert_eq!(tree.distinct_root_count_in(Some(&history)).unwrap(), MIN_ROOT_HISTORY_SIZE as usize);
+        assert!(tree.distinct_root_count_in(None).is_err());
+    }
+
+    #[test]
+    fn test_grow_root_history_keeps_roots_and_sets_age_window() {
+        let mut tree = new_tree(8, TreeHash::Keccak);
+        let mut roots = vec![tree.current_root];
+        for i in 1..=20u8 {
+            tree.insert_leaf(leaf(i), 100 + i as u64).unwrap();
+            roots.push(tree.current_root);
+        }
+
+        tree.resize_root_history_in(None, 300, 130, 15).unwrap();
+        assert_eq!(tree.root_history_size, 300);
+        assert_eq!(tree.root_history.len(), 300);
+        assert!(roots.iter().all(|root| tree.is_known_root(root)));
+        assert_eq!(tree.root_slot(&roots[20]), Some(120));
+
+        // The new age window applies to the preserved roots
+        assert!(tree.is_known_root_within(&roots[20], 130, 15));
+        assert!(tree.is_known_root_within(&roots[15], 130, 15));
+        assert!(!tree.is_known_root_within(&roots[14], 130, 15));
+
+        // Inserts continue after the newest root
+        tree.insert_leaf(leaf(21), 131).unwrap();
+        assert!(tree.is_known_root(&roots[0]));
+        assert_eq!(tree.distinct_root_count(), 22);
+    }
+
+    #[test]
+    fn test_shrink_root_history_only_drops_expired_roots() {
+        let mut tree = new_tree(8, TreeHash::Keccak);
+        tree.resize_root_history_in(None, 300, 0, 0).unwrap();
+        let mut roots = vec![tree.current_root];
+        for i in 1..=250u8 {
+            tree.insert_leaf(leaf(i), i as u64).unwrap();
+            roots.push(tree.current_root);
+        }
+
+        // 251 roots, all valid without an age limit
+        let err = tree.resize_root_history_in(None, 200, 250, 0).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::RootHistoryShrinkDropsRoots));
+        // The 51 oldest (slots 0..=50) are still within a 200-slot window
+        assert!(tree.resize_root_history_in(None, 200, 250, 200).is_err());
+        assert!(tree.resize_root_history_in(None, MIN_ROOT_HISTORY_SIZE - 1, 250, 10).is_err());
+
+        tree.resize_root_history_in(None, 200, 250, 199).unwrap();
+        assert!(roots[51..].iter().all(|root| tree.is_known_root(root)));
+        assert!(!tree.is_known_root(&roots[50]));
+    }
+
+    #[test]
+    fn test_resize_split_root_history() {
+        let mut tree = new_tree(8, TreeHash::Keccak);
+        tree.insert_leaf(leaf(1), 1).unwrap();
+        let mut history = split(&mut tree);
+        let root = tree.current_root;
+
+        assert!(tree.resize_root_history_in(None, 300, 2, 0).is_err());
+        tree.resize_root_history_in(Some(&mut history), 300, 2, 0).unwrap();
+        assert_eq!(history.root_history_size, 300);
+        assert!(history.is_known_root(&root));
+        assert_eq!(crate::state::serialized_len(&history), RootHistory::space(300));
+
+        tree.insert_leaf_into(leaf(2), 3, Some(&mut history)).unwrap();
+        assert!(history.is_known_root(&root) && history.is_known_root(&tree.current_root));
+    }
+
+    /// Root of the complete subtree over `leaves` (a power of two).
+    fn subtree_root(tree: &MerkleTree, leaves: &[[u8; 32]]) -> [u8; 32] {
+        let hash = tree.hash_fn().unwrap();
+        let mut level = leaves.to_vec();
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|pair| hash.hash_two_to_one(&pair[0], &pair[1]).unwrap())
+                .collect();
+        }
+        level[0]
+    }
+
+    #[test]
+    fn test_imported_subtrees_match_leaf_insertion() {
+        for tree_hash in [TreeHash::Keccak, TreeHash::Poseidon] {
+            let mut inserted = new_tree(8, tree_hash);
+            let mut imported = new_tree(8, tree_hash);
+            let leaves: Vec<[u8; 32]> = (1..=16u8).map(leaf).collect();
+
+            // 13 legacy leaves as blocks of 8, 4 and 1
+            let mut start = 0usize;
+            for level in [3u8, 2, 0] {
+                let count = 1usize << level;
+                let block = &leaves[start..start + count];
+                for l in block {
+                    inserted.insert_leaf(*l, 1).unwrap();
+                }
+                let root = subtree_root(&imported, block);
+                let first = imported.import_subtree(level, root, count as u32, 1, None).unwrap();
+
+                assert_eq!(first as usize, start);
+                assert_eq!(imported.current_root, inserted.current_root);
+                assert!(imported.is_known_root(&inserted.current_root));
+                start += count;
+            }
+            assert_eq!(imported.next_leaf_index, 13);
+
+            // Regular deposits continue from the imported state
+            for l in &leaves[13..] {
+                assert_eq!(
+                    imported.insert_leaf(*l, 2).unwrap(),
+                    inserted.insert_leaf(*l, 2).unwrap()
+                );
+                assert_eq!(imported.current_root, inserted.current_root);
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_subtree_import_rejected() {
+        let mut tree = new_tree(4, TreeHash::Keccak);
+        let node = [7u8; 32];
+
+        // count must be 2^level
+        assert_eq!(
+            tree.import_subtree(2, node, 3, 0, None).unwrap_err(),
+            error!(PrivacyError::InvalidSubtreeImport)
+        );
+        assert!(tree.import_subtree(5, node, 32, 0, None).is_err());
+
+        // Block must be aligned to its size
+        tree.insert_leaf(leaf(1), 0).unwrap();
+        assert_eq!(
+            tree.import_subtree(1, node, 2, 0, None).unwrap_err(),
+            error!(PrivacyError::InvalidSubtreeImport)
+        );
+
+        // The whole tree at once fits only when empty
+        let mut tree = new_tree(4, TreeHash::Keccak);
+        tree.import_subtree(4, node, 16, 0, None).unwrap();
+        assert!(tree.is_full());
+        assert_eq!(tree.current_root, node);
+        assert!(tree.import_subtree(0, node, 1, 0, None).is_err());
+    }
+
     #[test]
     fn test_zero_values_deterministic() {
-        let zeros1 = MerkleTree::compute_zero_values(10);
-        let zeros2 = MerkleTree::compute_zero_values(10);
-        assert_eq!(zeros1, zeros2);
+        for hash in [TreeHash::Poseidon, TreeHash::Keccak] {
+            let zeros1 = MerkleTree::compute_zero_values(10, hash).unwrap();
+            let zeros2 = MerkleTree::compute_zero_values(10, hash).unwrap();
+            assert_eq!(zeros1, zeros2);
+        }
+    }
+
+    #[test]
+    fn test_empty_roots_distinct_per_hash() {
+        let poseidon = new_tree(10, TreeHash::Poseidon);
+        let keccak = new_tree(10, TreeHash::Keccak);
+        assert_eq!(poseidon.current_root, new_tree(10, TreeHash::Poseidon).current_root);
+        assert_eq!(keccak.current_root, new_tree(10, TreeHash::Keccak).current_root);
+        assert_ne!(poseidon.current_root, keccak.current_root);
+    }
+
+    #[test]
+    fn test_invalid_tree_hash_rejected() {
+        let data = vec![0u8; MerkleTree::space(10, MIN_ROOT_HISTORY_SIZE)];
+        let mut tree = MerkleTree::try_deserialize_unchecked(&mut data.as_slice()).unwrap();
+        assert!(tree
+            .initialize(Pubkey::new_unique(), 10, MIN_ROOT_HISTORY_SIZE, 2)
+            .is_err());
+    }
+
+    #[test]
+    fn test_insertions_roundtrip_per_hash() {
+        for hash in [TreeHash::Poseidon, TreeHash::Keccak] {
+            let mut tree = new_tree(4, hash);
+            let empty_root = tree.current_root;
+
+            assert_eq!(tree.insert_leaf(leaf(1), 0).unwrap(), 0);
+            assert_eq!(tree.insert_leaf(leaf(2), 0).unwrap(), 1);
+            assert_ne!(tree.current_root, empty_root);
+            assert!(tree.is_known_root(&empty_root));
+
+            // Recompute the depth-4 root by hand from the two leaves
+            let mut node = hash.hash_two_to_one(&leaf(1), &leaf(2)).unwrap();
+            for level in 1..4 {
+                node = hash.hash_two_to_one(&node, &tree.zeros[level]).unwrap();
+            }
+            assert_eq!(tree.current_root, node);
+        }
+    }
+
+    #[test]
+    fn test_root_age_limit() {
+        let mut tree = new_tree(4, TreeHash::Keccak);
+        tree.insert_leaf(leaf(1), 100).unwrap();
+        let old_root = tree.current_root;
+        tree.insert_leaf(leaf(2), 500).unwrap();
+        let new_root = tree.current_root;
+
+        // Still present in history
+        assert!(tree.is_known_root(&old_root));
+
+        // No limit configured
+        assert!(tree.is_known_root_within(&old_root, 1_000, 0));
+
+        // Old root too old, new root fresh
+        assert!(!tree.is_known_root_within(&old_root, 1_000, 600));
+        assert!(tree.is_known_root_within(&new_root, 1_000, 600));
+
+        // Exactly at the boundary is accepted
+        assert!(tree.is_known_root_within(&old_root, 700, 600));
+
+        // Unknown root is never accepted
+        assert!(!tree.is_known_root_within(&[7u8; 32], 500, 600));
+    }
+
+    /// Arbitrary per-level zeros unrelated to any hash recursion.
+    fn custom_zeros(depth: u8) -> Vec<[u8; 32]> {
+        (0..=depth).map(|level| leaf(100 + level)).collect()
+    }
+
+    #[test]
+    fn test_supplied_zeros_used_for_root_and_inserts() {
+        for hash in [TreeHash::Keccak, TreeHash::Poseidon] {
+            let zeros = custom_zeros(4);
+            let data = vec![0u8; MerkleTree::space(4, MIN_ROOT_HISTORY_SIZE)];
+            let mut tree = MerkleTree::try_deserialize_unchecked(&mut data.as_slice()).unwrap();
+            tree.initialize_with_zeros(
+                Pubkey::new_unique(),
+                4,
+                MIN_ROOT_HISTORY_SIZE,
+                hash as u8,
+                Some(zeros.clone()),
+            )
+            .unwrap();
+
+            assert_eq!(tree.zeros, zeros);
+            assert_eq!(tree.current_root, zeros[4]);
+            assert_ne!(tree.current_root, new_tree(4, hash).current_root);
+            assert!(tree.is_known_root(&zeros[4]));
+
+            // Empty siblings come from the supplied list
+            tree.insert_leaf(leaf(1), 0).unwrap();
+            let mut node = leaf(1);
+            for zero in &zeros[..4] {
+                node = hash.hash_two_to_one(&node, zero).unwrap();
+            }
+            assert_eq!(tree.current_root, node);
+        }
+    }
+
+    #[test]
+    fn test_invalid_supplied_zeros_rejected() {
+        let data = vec![0u8; MerkleTree::space(4, MIN_ROOT_HISTORY_SIZE)];
+        let mut tree = MerkleTree::try_deserialize_unchecked(&mut data.as_slice()).unwrap();
+        let mut init = |zeros| {
+            tree.initialize_with_zeros(Pubkey::new_unique(), 4, MIN_ROOT_HISTORY_SIZE, 1, Some(zeros))
+        };
+
+        // Wrong length
+        assert_eq!(init(custom_zeros(3)).unwrap_err(), error!(PrivacyError::InvalidZeroValues));
+        assert!(init(custom_zeros(5)).is_err());
+
+        // Non-canonical field element
+        let mut zeros = custom_zeros(4);
+        zeros[2] = [0xff; 32];
+        assert_eq!(init(zeros).unwrap_err(), error!(PrivacyError::InvalidZeroValues));
+
+        assert!(init(custom_zeros(4)).is_ok());
+    }
+
+    #[test]
+    fn test_poseidon_rejects_non_field_leaf() {
+        let mut tree = new_tree(4, TreeHash::Poseidon);
+        assert!(tree.insert_leaf([0xffu8; 32], 0).is_err());
+        assert_eq!(tree.next_leaf_index, 0);
     }
 }
@@ -18,17 +18,92 @@
 //! - Stores Groth16 verification key from trusted setup
 //! - Contains α, β, γ, δ points and IC array
 //!
+//! ## Root History (`RootHistory`, optional)
+//! - PDA Seeds: `["root_history", pool_config]`
+//! - Root history buffer split out of `MerkleTree` for large pools
+//!
+//! ## vk_x Scratch (`VkXScratch`)
+//! - PDA Seeds: `["vk_x_scratch", pool_config, owner]`
+//! - Partial Groth16 vk_x sum for circuits too large for one instruction
+//!
 //! ## Spent Nullifier (`SpentNullifier`)
 //! - PDA Seeds: `["nullifier", pool_config, nullifier_hash]`
 //! - Per-nullifier account for O(1) double-spend detection
 //! - Created during withdrawal, existence = spent
+//! - Records the merkle root the withdrawal proved against
+//!
+//! ## Pending Commitments (`PendingCommitments`, optional)
+//! - PDA Seeds: `["pending_commitments", pool_config]`
+//! - FIFO of deferred deposits awaiting insertion by `process_pending`
+//!
+//! ## Authority Multisig (`AuthorityMultisig`, optional)
+//! - PDA Seeds: `["authority_multisig", pool_config]`
+//! - M-of-N signer set holding the pool authority when
+//!   `authority_is_multisig` is enabled
+//!
+//! ## Pool Registry (`PoolRegistry`, optional)
+//! - PDA Seeds: `["pool_registry", authority]`
+//! - Bounded list of an authority's pools for enumeration and `pause_all`
+//!
+//! ## Recipient Denylist (`RecipientDenylist`, optional)
+//! - PDA Seeds: `["recipient_denylist", pool_config]`
+//! - Recipients withdrawals may not pay, committed to by
+//!   `recipient_denylist_root`
+//!
+//! ## Recipient Cooldown (`RecipientCooldown`, optional)
+//! - PDA Seeds: `["recipient_cooldown", pool_config, recipient]`
+//! - Last withdrawal slot per recipient, enforced when the pool sets
+//!   `recipient_cooldown_slots`
+//!
+//! ## SOL Fee Reserve (`SolFeeReserve`, optional)
+//! - PDA Seeds: `["sol_fee_reserve", pool_config]`
+//! - Lamports paying relayer fees for `withdraw_fee_in_lamports`
 
+pub mod authority_multisig;
 pub mod merkle_tree;
+pub mod pending_commitments;
 pub mod pool_config;
+pub mod pool_registry;
+pub mod recipient_cooldown;
+pub mod recipient_denylist;
+pub mod root_history;
+pub mod sol_fee_reserve;
 pub mod spent_nullifier;
 pub mod verification_key;
+pub mod vk_x_scratch;
 
-pub use merkle_tree::MerkleTree;
-pub use pool_config::PoolConfig;
+pub use authority_multisig::{AuthorityMultisig, MAX_MULTISIG_SIGNERS};
+pub use merkle_tree::{MerkleTree, MerkleTreeV1};
+pub use pending_commitments::{PendingCommitment, PendingCommitments, MAX_PENDING_COMMITMENTS};
+pub use pool_config::{CommitmentMode, DepositNote, PayoutMint, PoolConfig, PoolConfigV2};
+pub use pool_registry::{PoolRegistry, RegistryEntry, MAX_REGISTRY_POOLS};
+pub use recipient_cooldown::RecipientCooldown;
+pub use recipient_denylist::{RecipientDenylist, MAX_DENIED_RECIPIENTS};
+pub use root_history::RootHistory;
+pub use sol_fee_reserve::SolFeeReserve;
 pub use spent_nullifier::SpentNullifier;
-pub use verification_key::{VerificationKey, VerificationKeyAccount};
+pub use verification_key::{VerificationKey, VerificationKeyAccount, VerificationKeyData};
+pub use vk_x_scratch::VkXScratch;
+
+use anchor_lang::prelude::*;
+
+/// Bytes `account` occupies once Anchor writes it back: the 8-byte
+/// discriminator plus its Borsh body.
+pub fn serialized_len<T: AnchorSerialize>(account: &T) -> usize {
+    8 + account.try_to_vec().map_or(usize::MAX - 8, |bytes| bytes.len())
+}
+
+/// Debug-build check that `account` fits its `data_len`-byte allocation.
+///
+/// A `space` helper that undercounts only fails when Anchor serializes the
+/// account on exit, as an opaque "failed to serialize" on whichever
+/// instruction first grows it. Asserting after init and after the largest
+/// writes points at the `space` helper instead.
+pub fn debug_assert_fits<T: AnchorSerialize>(account: &T, data_len: usize) {
+    debug_assert!(
+        serialized_len(account) <= data_len,
+        "account needs {} bytes but only {} are allocated",
+        serialized_len(account),
+        data_len
+    );
+}
@@ -0,0 +1,136 @@
+//! Deferred Commitment Queue
+//!
+//! `deposit_deferred` moves tokens into the vault and appends the commitment
+//! here instead of updating the tree, so the depositor skips the expensive
+//! hashing. A permissionless `process_pending` keeper later inserts queued
+//! commitments into the tree strictly in FIFO order; leaf indices are
+//! assigned (and reported via `DepositEvent`) at processing time.
+
+use anchor_lang::prelude::*;
+
+use crate::error::PrivacyError;
+
+/// Maximum queued commitments (bounds account size).
+pub const MAX_PENDING_COMMITMENTS: usize = 64;
+
+/// One queued deposit.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PendingCommitment {
+    pub commitment: [u8; 32],
+    pub amount: u64,
+    /// Queue sequence number (0-based, across the queue's lifetime)
+    pub sequence: u64,
+}
+
+/// Per-pool FIFO of commitments awaiting tree insertion.
+///
+/// PDA Seeds: `[b"pending_commitments", pool_config.key().as_ref()]`
+#[account]
+pub struct PendingCommitments {
+    /// Reference to parent pool
+    pub pool: Pubkey,
+
+    /// Total commitments ever enqueued (next sequence number)
+    pub total_enqueued: u64,
+
+    /// Queued commitments, oldest first
+    pub queue: Vec<PendingCommitment>,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl PendingCommitments {
+    pub const LEN: usize = 8                            // discriminator
+        + 32                                            // pool
+        + 8                                             // total_enqueued
+        + 4 + (48 * MAX_PENDING_COMMITMENTS)            // queue (vec)
+        + 1;                                            // bump
+
+    pub fn initialize(&mut self, pool: Pubkey, bump: u8) {
+        self.pool = pool;
+        self.total_enqueued = 0;
+        self.queue = Vec::new();
+        self.bump = bump;
+    }
+
+    /// Append a commitment. Returns its sequence number.
+    ///
+    /// # Errors
+    /// * `PendingQueueFull` at `MAX_PENDING_COMMITMENTS`
+    pub fn enqueue(&mut self, commitment: [u8; 32], amount: u64) -> Result<u64> {
+        require!(
+            self.queue.len() < MAX_PENDING_COMMITMENTS,
+            PrivacyError::PendingQueueFull
+        );
+        let sequence = self.total_enqueued;
+        self.queue.push(PendingCommitment {
+            commitment,
+            amount,
+            sequence,
+        });
+        self.total_enqueued = sequence
+            .checked_add(1)
+            .ok_or(error!(PrivacyError::ArithmeticOverflow))?;
+        Ok(sequence)
+    }
+
+    /// Remove and return up to `max_count` of the oldest commitments.
+    pub fn dequeue(&mut self, max_count: usize) -> Vec<PendingCommitment> {
+        let count = max_count.min(self.queue.len());
+        self.queue.drain(..count).collect()
+    }
+
+    /// Number of commitments waiting for insertion.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_queue() -> PendingCommitments {
+        let data = vec![0u8; PendingCommitments::LEN];
+        let mut queue = PendingCommitments::try_deserialize_unchecked(&mut data.as_slice()).unwrap();
+        queue.initialize(Pubkey::new_unique(), 255);
+        queue
+    }
+
+    #[test]
+    fn test_fifo_and_sequence_numbers() {
+        let mut queue = new_queue();
+        for i in 0..5u8 {
+            assert_eq!(queue.enqueue([i; 32], 100).unwrap(), i as u64);
+        }
+
+        let first = queue.dequeue(2);
+        assert_eq!(first.iter().map(|p| p.commitment[0]).collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(queue.len(), 3);
+
+        // Sequence numbers keep counting after a dequeue
+        assert_eq!(queue.enqueue([9; 32], 100).unwrap(), 5);
+        let rest = queue.dequeue(10);
+        assert_eq!(rest.iter().map(|p| p.sequence).collect::<Vec<_>>(), vec![2, 3, 4, 5]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_queue_full() {
+        let mut queue = new_queue();
+        for _ in 0..MAX_PENDING_COMMITMENTS {
+            queue.enqueue([1; 32], 1).unwrap();
+        }
+        let err = queue.enqueue([1; 32], 1).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::PendingQueueFull));
+
+        let mut data = Vec::new();
+        queue.try_serialize(&mut data).unwrap();
+        assert!(data.len() <= PendingCommitments::LEN);
+    }
+}
@@ -2,12 +2,75 @@
 //!
 //! # Security Properties
 //! - Authority changes require 2-step process (pending → accept)
+//! - Fee recipient changes use the same 2-step process
 //! - VK can be locked to prevent post-deployment changes
+//! - Authority may be an M-of-N `AuthorityMultisig`
 //! - All counters use checked arithmetic
 
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
 
+use crate::crypto::{hash_note_commitment, RecipientBinding};
 use crate::error::PrivacyError;
+use crate::state::AuthorityMultisig;
+
+/// How a pool derives the Merkle leaf for a deposit. Fixed at pool init.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum CommitmentMode {
+    /// Depositor supplies the full commitment
+    /// `Poseidon(secret, nullifier_preimage, amount)`. The program cannot
+    /// check that it binds the deposited amount.
+    ClientSupplied = 0,
+    /// Depositor supplies `note_hash = Poseidon(secret, nullifier_preimage)`;
+    /// the program computes `Poseidon(note_hash, amount)`.
+    ProgramComputed = 1,
+    /// Depositor supplies
+    /// `Poseidon(secret, nullifier_preimage, amount, leaf_index)`, for
+    /// circuits that bind a note to its tree position.
+    ///
+    /// The program assigns `leaf_index` at insert time, so the client
+    /// pre-assigns it deterministically: read `next_leaf_index`, compute the
+    /// commitment for it, and deposit it with that index. If another deposit
+    /// lands first the index no longer matches and the deposit fails
+    /// without moving tokens; the client recomputes and retries. Only the
+    /// direct `deposit_position_bound` path accepts these notes: queued,
+    /// swap and change-note inserts cannot know their index up front.
+    PositionBound = 2,
+}
+
+impl CommitmentMode {
+    /// Decode a stored mode byte.
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(CommitmentMode::ClientSupplied),
+            1 => Some(CommitmentMode::ProgramComputed),
+            2 => Some(CommitmentMode::PositionBound),
+            _ => None,
+        }
+    }
+}
+
+/// Deposit argument, in the shape each `CommitmentMode` expects.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DepositNote {
+    /// Full commitment (`CommitmentMode::ClientSupplied`)
+    Commitment([u8; 32]),
+    /// Note hash (`CommitmentMode::ProgramComputed`)
+    NoteHash([u8; 32]),
+    /// Full commitment and the leaf index it binds
+    /// (`CommitmentMode::PositionBound`)
+    Positioned { commitment: [u8; 32], leaf_index: u32 },
+}
+
+/// Mint a withdrawal payout is denominated in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PayoutMint {
+    /// `token_mint`: recipient, relayers and protocol fee recipient
+    Pool,
+    /// `fee_mint`: relayer fee paid by `withdraw_fee_in_mint`
+    Fee,
+}
 
 /// Main pool configuration account.
 #[account]
@@ -36,7 +99,7 @@ pub struct PoolConfig {
     /// PDA bump seed
     pub bump: u8,
 
-    /// Pool paused flag
+    /// Pool paused flag; the pause lasts until `pause_until`
     pub is_paused: bool,
 
     /// Whether verification key has been set
@@ -57,6 +120,112 @@ pub struct PoolConfig {
     /// Total value withdrawn
     pub total_value_withdrawn: u64,
 
+    /// Minimum number of deposits required before withdrawals are allowed
+    /// (0 = no minimum)
+    pub min_anonymity_set: u32,
+
+    /// Program whose PDAs may receive escrow withdrawals
+    /// (zero = escrow withdrawals disabled)
+    pub escrow_program: Pubkey,
+
+    /// Maximum age (in slots) of a root accepted for withdrawal
+    /// (0 = no age limit, only root history size applies)
+    pub max_root_age_slots: u64,
+
+    /// Explicit per-pool opt-in required for value-moving instructions when
+    /// the program is built with `dev-mode`. Ignored in normal builds.
+    pub dev_mode_acknowledged: bool,
+
+    /// Destination for protocol fees (defaults to the initial authority)
+    pub fee_recipient: Pubkey,
+
+    /// Pending fee recipient for 2-step change (zero if none pending)
+    pub pending_fee_recipient: Pubkey,
+
+    /// Unix timestamp until which proofs against the secondary
+    /// (`verification_key_next`) VK are also accepted (0 = no window)
+    pub dual_vk_window_until: i64,
+
+    /// DEX program `withdraw_and_swap` may CPI into
+    /// (zero = swap withdrawals disabled)
+    pub swap_program: Pubkey,
+
+    /// DEX pool (owned by `swap_program`) `withdraw_and_swap` may trade against
+    pub swap_pool: Pubkey,
+
+    /// Minimum slot gap between withdrawals to the same recipient
+    /// (0 = disabled). Publicly links recipients to withdrawals, so off by default.
+    pub recipient_cooldown_slots: u64,
+
+    /// `CommitmentMode` as u8 (immutable after init)
+    pub commitment_mode: u8,
+
+    /// Minimum relayer fee when the relayer is not the recipient
+    /// (0 = no floor). Self-relay is exempt.
+    pub min_relayer_fee: u64,
+
+    /// `RecipientBinding` as u8: how `recipient` is encoded as a public input
+    pub recipient_binding: u8,
+
+    /// Bumped on every withdrawal (i.e. every nullifier spent). Relayers
+    /// compare it between simulation and submission to detect that the
+    /// spent set changed and re-check their nullifier.
+    pub nullifier_generation: u64,
+
+    /// Whether `authority` is an `AuthorityMultisig` account, in which case
+    /// sensitive admin actions need M-of-N member signatures
+    pub authority_is_multisig: bool,
+
+    /// `RecipientDenylist::root()` of the pool's denylist
+    /// (zero = no denylist)
+    pub recipient_denylist_root: [u8; 32],
+
+    /// Mint `withdraw_fee_in_mint` pays relayer fees in, from the pool's
+    /// fee reserve (zero = disabled)
+    pub fee_mint: Pubkey,
+
+    /// `fee_mint` units paid per `FEE_RATE_SCALE` pool-mint units of fee
+    pub fee_mint_rate: u64,
+
+    /// Emit step-by-step `msg!` logs during proof verification and
+    /// withdrawal. Off by default: they cost compute and log space on every
+    /// withdrawal. Error-path logs are always emitted.
+    pub verbose_logging: bool,
+
+    /// Minimum age (in slots) of the root a withdrawal proves against, so
+    /// a note cannot be withdrawn in the slots right after its deposit
+    /// (0 = no mixing delay)
+    pub min_note_age_slots: u64,
+
+    /// Check before any state change that the recipient and relayer token
+    /// accounts are initialized, unfrozen and rent-exempt, so a withdrawal
+    /// to an unusable account fails with a clear error
+    pub require_ready_payees: bool,
+
+    /// Only accept withdrawals invoked directly by the transaction, not via
+    /// CPI from another program
+    pub disallow_cpi_withdraw: bool,
+
+    /// Decimals of `token_mint`, read from the mint at initialization so
+    /// clients can display amounts without fetching it
+    pub token_decimals: u8,
+
+    /// Proof-system version withdrawal proofs must carry; untagged
+    /// 256-byte proofs are version 0
+    pub proof_version: u8,
+
+    /// Largest relayer fee `withdraw_fee_in_lamports` pays from the SOL fee
+    /// reserve per withdrawal (0 = disabled)
+    pub max_relayer_fee_lamports: u64,
+
+    /// Accept withdrawals whose relayer fee takes the whole amount, paying
+    /// the recipient nothing (off by default)
+    pub allow_zero_net_withdrawal: bool,
+
+    /// Unix timestamp a pause expires at, after which the pool operates
+    /// again without an unpause (`i64::MAX` = until unpaused)
+    pub pause_until: i64,
+
     /// Schema version
     pub version: u8,
 
@@ -65,23 +234,28 @@ pub struct PoolConfig {
 }
 
 impl PoolConfig {
-    pub const LEN: usize = 8 + 32 + 32 + 32 + 32 + 32 + 32 + 1 + 1 + 1 + 1 + 1 + 3 + 8 + 8 + 8 + 8 + 1 + 64;
-    pub const VERSION: u8 = 2;
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 32 + 32 + 32 + 1 + 1 + 1 + 1 + 1 + 3 + 8 + 8 + 8 + 8 + 4 + 32 + 8 + 1 + 32 + 32 + 8 + 32 + 32 + 8 + 1 + 8 + 1 + 1 + 8 + 1 + 32 + 32 + 8 + 1 + 8 + 1 + 1 + 1 + 1 + 8 + 1 + 8 + 64;
+    pub const VERSION: u8 = 3;
+    /// Fixed-point scale of `fee_mint_rate` (1e9 = one fee-mint unit per pool-mint unit)
+    pub const FEE_RATE_SCALE: u64 = 1_000_000_000;
 
     #[allow(clippy::too_many_arguments)]
     pub fn initialize(
         &mut self,
         authority: Pubkey,
         token_mint: Pubkey,
+        token_decimals: u8,
         vault: Pubkey,
         merkle_tree: Pubkey,
         verification_key: Pubkey,
         tree_depth: u8,
         bump: u8,
+        commitment_mode: CommitmentMode,
     ) {
         self.authority = authority;
         self.pending_authority = Pubkey::default();
         self.token_mint = token_mint;
+        self.token_decimals = token_decimals;
         self.vault = vault;
         self.merkle_tree = merkle_tree;
         self.verification_key = verification_key;
@@ -94,13 +268,57 @@ impl PoolConfig {
         self.total_withdrawals = 0;
         self.total_value_deposited = 0;
         self.total_value_withdrawn = 0;
+        self.min_anonymity_set = 0;
+        self.escrow_program = Pubkey::default();
+        self.max_root_age_slots = 0;
+        self.dev_mode_acknowledged = false;
+        self.fee_recipient = authority;
+        self.pending_fee_recipient = Pubkey::default();
+        self.dual_vk_window_until = 0;
+        self.swap_program = Pubkey::default();
+        self.swap_pool = Pubkey::default();
+        self.recipient_cooldown_slots = 0;
+        self.commitment_mode = commitment_mode as u8;
+        self.min_relayer_fee = 0;
+        self.recipient_binding = RecipientBinding::Raw as u8;
+        self.nullifier_generation = 0;
+        self.authority_is_multisig = false;
+        self.recipient_denylist_root = [0u8; 32];
+        self.fee_mint = Pubkey::default();
+        self.fee_mint_rate = 0;
+        self.verbose_logging = false;
+        self.min_note_age_slots = 0;
+        self.require_ready_payees = false;
+        self.disallow_cpi_withdraw = false;
+        self.proof_version = 0;
+        self.max_relayer_fee_lamports = 0;
+        self.allow_zero_net_withdrawal = false;
+        self.pause_until = 0;
         self.version = Self::VERSION;
         self._reserved = [0u8; 64];
     }
 
     #[inline]
     pub fn require_not_paused(&self) -> Result<()> {
-        require!(!self.is_paused, PrivacyError::PoolPaused);
+        // Only a timed pause needs the clock
+        let now = if self.is_paused && self.pause_until != i64::MAX {
+            Clock::get()?.unix_timestamp
+        } else {
+            i64::MIN
+        };
+        self.require_not_paused_at(now)
+    }
+
+    /// Whether the pool is paused at `now`: `is_paused` is set and the
+    /// pause has not expired.
+    #[inline]
+    pub fn is_paused_at(&self, now: i64) -> bool {
+        self.is_paused && now < self.pause_until
+    }
+
+    #[inline]
+    pub fn require_not_paused_at(&self, now: i64) -> Result<()> {
+        require!(!self.is_paused_at(now), PrivacyError::PoolPaused);
         Ok(())
     }
 
@@ -110,12 +328,190 @@ impl PoolConfig {
         Ok(())
     }
     
+    /// Fail closed in `dev-mode` builds unless this pool explicitly opted in.
+    /// No-op in normal builds.
+    #[inline]
+    pub fn require_dev_mode_acknowledged(&self) -> Result<()> {
+        if crate::DEV_MODE {
+            msg!("⚠️ DEV MODE BUILD - NOT FOR PRODUCTION FUNDS");
+            require!(self.dev_mode_acknowledged, PrivacyError::DevModeNotAcknowledged);
+        }
+        Ok(())
+    }
+
     #[inline]
     pub fn require_vk_unlocked(&self) -> Result<()> {
         require!(!self.vk_locked, PrivacyError::VerificationKeyLocked);
         Ok(())
     }
 
+    /// Require that the tree holds enough leaves to satisfy the configured
+    /// minimum anonymity set. A threshold of 0 disables the check.
+    #[inline]
+    pub fn require_anonymity_set(&self, leaf_count: u32) -> Result<()> {
+        require!(
+            leaf_count >= self.min_anonymity_set,
+            PrivacyError::AnonymitySetTooSmall
+        );
+        Ok(())
+    }
+
+    /// Require that `recipient` is a PDA (off-curve) held by the whitelisted
+    /// escrow program. Fails if no escrow program is configured.
+    pub fn require_escrow_recipient(&self, recipient: &Pubkey, owner: &Pubkey) -> Result<()> {
+        require!(
+            self.escrow_program != Pubkey::default(),
+            PrivacyError::InvalidEscrowRecipient
+        );
+        require!(!recipient.is_on_curve(), PrivacyError::InvalidEscrowRecipient);
+        require!(*owner == self.escrow_program, PrivacyError::InvalidEscrowRecipient);
+        Ok(())
+    }
+
+    /// Require that `swap_program` / `swap_pool` are the whitelisted swap
+    /// route and that the pool account is owned by the DEX program. Fails if
+    /// no swap route is configured.
+    pub fn require_swap_route(
+        &self,
+        swap_program: &Pubkey,
+        swap_pool: &Pubkey,
+        swap_pool_owner: &Pubkey,
+    ) -> Result<()> {
+        require!(
+            self.swap_program != Pubkey::default(),
+            PrivacyError::InvalidSwapRoute
+        );
+        require!(*swap_program == self.swap_program, PrivacyError::InvalidSwapRoute);
+        require!(*swap_pool == self.swap_pool, PrivacyError::InvalidSwapRoute);
+        require!(*swap_pool_owner == self.swap_program, PrivacyError::InvalidSwapRoute);
+        Ok(())
+    }
+
+    /// Require `relayer_fee >= min_relayer_fee` unless self-relaying
+    /// (`relayer == recipient`).
+    pub fn require_min_relayer_fee(
+        &self,
+        recipient: &Pubkey,
+        relayer: &Pubkey,
+        relayer_fee: u64,
+    ) -> Result<()> {
+        if relayer != recipient {
+            require!(
+                relayer_fee >= self.min_relayer_fee,
+                PrivacyError::RelayerFeeTooLow
+            );
+        }
+        Ok(())
+    }
+
+    /// Decoded `recipient_binding`.
+    pub fn recipient_binding(&self) -> Result<RecipientBinding> {
+        RecipientBinding::from_u8(self.recipient_binding)
+            .ok_or(error!(PrivacyError::InvalidRecipientBinding))
+    }
+
+    /// Merkle leaf for a deposit of `amount`, per this pool's commitment mode.
+    ///
+    /// A `Positioned` note's leaf index is checked against the tree by the
+    /// caller (`deposit::check_assigned_leaf_index`).
+    ///
+    /// # Errors
+    /// * `CommitmentModeMismatch` if `note` has the other mode's shape
+    /// * `InvalidCommitmentMode` if the stored mode is unknown
+    pub fn resolve_commitment(&self, note: DepositNote, amount: u64) -> Result<[u8; 32]> {
+        let mode = CommitmentMode::from_u8(self.commitment_mode)
+            .ok_or(error!(PrivacyError::InvalidCommitmentMode))?;
+        match (mode, note) {
+            (CommitmentMode::ClientSupplied, DepositNote::Commitment(commitment)) => Ok(commitment),
+            (CommitmentMode::ProgramComputed, DepositNote::NoteHash(note_hash)) => {
+                require!(note_hash != [0u8; 32], PrivacyError::InvalidCommitment);
+                hash_note_commitment(&note_hash, amount)
+            }
+            (CommitmentMode::PositionBound, DepositNote::Positioned { commitment, .. }) => {
+                Ok(commitment)
+            }
+            _ => Err(error!(PrivacyError::CommitmentModeMismatch)),
+        }
+    }
+
+    /// Whether the vault balance equals the value the pool has accounted
+    /// for (`total_value_deposited - total_value_withdrawn`).
+    pub fn tvl_invariant_holds(&self, vault_amount: u64) -> bool {
+        self.total_value_deposited
+            .checked_sub(self.total_value_withdrawn)
+            == Some(vault_amount)
+    }
+
+    /// Vault balance not backed by recorded deposits, i.e.
+    /// `vault_amount - (total_value_deposited - total_value_withdrawn)`.
+    ///
+    /// # Errors
+    /// * `NoUntrackedBalance` if the vault holds no more than the tracked value
+    pub fn untracked_excess(&self, vault_amount: u64) -> Result<u64> {
+        let tracked = self.total_value_deposited
+            .checked_sub(self.total_value_withdrawn)
+            .ok_or(error!(PrivacyError::ArithmeticOverflow))?;
+        let excess = vault_amount.saturating_sub(tracked);
+        require!(excess > 0, PrivacyError::NoUntrackedBalance);
+        Ok(excess)
+    }
+
+    /// Mint a `payout` destination must hold; `Pubkey::default()` for
+    /// `PayoutMint::Fee` when no fee mint is configured.
+    pub fn payout_mint(&self, payout: PayoutMint) -> Pubkey {
+        match payout {
+            PayoutMint::Pool => self.token_mint,
+            PayoutMint::Fee => self.fee_mint,
+        }
+    }
+
+    /// `relayer_fee` (pool-mint units) converted to `fee_mint` units at the
+    /// fixed `fee_mint_rate`, rounded down.
+    ///
+    /// # Errors
+    /// * `FeeMintNotConfigured` if the pool has no fee mint
+    pub fn fee_in_fee_mint(&self, relayer_fee: u64) -> Result<u64> {
+        require!(
+            self.fee_mint != Pubkey::default(),
+            PrivacyError::FeeMintNotConfigured
+        );
+        let converted =
+            relayer_fee as u128 * self.fee_mint_rate as u128 / Self::FEE_RATE_SCALE as u128;
+        u64::try_from(converted).map_err(|_| error!(PrivacyError::ArithmeticOverflow))
+    }
+
+    /// Reject a withdrawal whose `relayer_fee` leaves the recipient nothing,
+    /// unless the pool sets `allow_zero_net_withdrawal`. The relayer fee cap
+    /// still applies either way.
+    ///
+    /// # Errors
+    /// * `ZeroNetWithdrawal` if `relayer_fee >= amount` and not allowed
+    pub fn require_nonzero_net(&self, amount: u64, relayer_fee: u64) -> Result<()> {
+        require!(
+            self.allow_zero_net_withdrawal || relayer_fee < amount,
+            PrivacyError::ZeroNetWithdrawal
+        );
+        Ok(())
+    }
+
+    /// Require that the pool pays lamport relayer fees and `fee_lamports`
+    /// is within `max_relayer_fee_lamports`.
+    ///
+    /// # Errors
+    /// * `LamportFeeNotEnabled` if the cap is zero
+    /// * `LimitExceeded` if `fee_lamports` is above the cap
+    pub fn require_lamport_fee_allowed(&self, fee_lamports: u64) -> Result<()> {
+        require!(
+            self.max_relayer_fee_lamports > 0,
+            PrivacyError::LamportFeeNotEnabled
+        );
+        require!(
+            fee_lamports <= self.max_relayer_fee_lamports,
+            PrivacyError::LimitExceeded
+        );
+        Ok(())
+    }
+
     pub fn record_deposit(&mut self, amount: u64) -> Result<()> {
         self.total_deposits = self.total_deposits
             .checked_add(1)
@@ -133,12 +529,24 @@ impl PoolConfig {
         self.total_value_withdrawn = self.total_value_withdrawn
             .checked_add(amount)
             .ok_or(error!(PrivacyError::ArithmeticOverflow))?;
+        self.nullifier_generation = self.nullifier_generation
+            .checked_add(1)
+            .ok_or(error!(PrivacyError::ArithmeticOverflow))?;
         Ok(())
     }
 
+    /// Pause until unpaused, or clear any pause.
     #[inline]
     pub fn set_paused(&mut self, paused: bool) {
         self.is_paused = paused;
+        self.pause_until = if paused { i64::MAX } else { 0 };
+    }
+
+    /// Pause until the unix timestamp `pause_until`.
+    #[inline]
+    pub fn set_paused_until(&mut self, pause_until: i64) {
+        self.is_paused = true;
+        self.pause_until = pause_until;
     }
 
     #[inline]
@@ -151,6 +559,105 @@ impl PoolConfig {
         self.vk_locked = true;
     }
 
+    #[inline]
+    pub fn set_min_anonymity_set(&mut self, min_anonymity_set: u32) {
+        self.min_anonymity_set = min_anonymity_set;
+    }
+
+    #[inline]
+    pub fn set_escrow_program(&mut self, escrow_program: Pubkey) {
+        self.escrow_program = escrow_program;
+    }
+
+    #[inline]
+    pub fn set_swap_route(&mut self, swap_program: Pubkey, swap_pool: Pubkey) {
+        self.swap_program = swap_program;
+        self.swap_pool = swap_pool;
+    }
+
+    #[inline]
+    pub fn set_recipient_cooldown_slots(&mut self, recipient_cooldown_slots: u64) {
+        self.recipient_cooldown_slots = recipient_cooldown_slots;
+    }
+
+    #[inline]
+    pub fn set_min_relayer_fee(&mut self, min_relayer_fee: u64) {
+        self.min_relayer_fee = min_relayer_fee;
+    }
+
+    #[inline]
+    pub fn set_recipient_binding(&mut self, recipient_binding: RecipientBinding) {
+        self.recipient_binding = recipient_binding as u8;
+    }
+
+    #[inline]
+    pub fn set_fee_mint(&mut self, fee_mint: Pubkey, fee_mint_rate: u64) {
+        self.fee_mint = fee_mint;
+        self.fee_mint_rate = fee_mint_rate;
+    }
+
+    pub fn set_fee_mint_rate(&mut self, fee_mint_rate: u64) {
+        self.fee_mint_rate = fee_mint_rate;
+    }
+
+    pub fn set_max_relayer_fee_lamports(&mut self, max_relayer_fee_lamports: u64) {
+        self.max_relayer_fee_lamports = max_relayer_fee_lamports;
+    }
+
+    pub fn set_recipient_denylist_root(&mut self, recipient_denylist_root: [u8; 32]) {
+        self.recipient_denylist_root = recipient_denylist_root;
+    }
+
+    pub fn set_dev_mode_acknowledged(&mut self, acknowledged: bool) {
+        self.dev_mode_acknowledged = acknowledged;
+    }
+
+    #[inline]
+    pub fn set_verbose_logging(&mut self, verbose_logging: bool) {
+        self.verbose_logging = verbose_logging;
+    }
+
+    #[inline]
+    pub fn set_require_ready_payees(&mut self, require_ready_payees: bool) {
+        self.require_ready_payees = require_ready_payees;
+    }
+
+    #[inline]
+    pub fn set_disallow_cpi_withdraw(&mut self, disallow_cpi_withdraw: bool) {
+        self.disallow_cpi_withdraw = disallow_cpi_withdraw;
+    }
+
+    #[inline]
+    pub fn set_allow_zero_net_withdrawal(&mut self, allow_zero_net_withdrawal: bool) {
+        self.allow_zero_net_withdrawal = allow_zero_net_withdrawal;
+    }
+
+    #[inline]
+    pub fn set_proof_version(&mut self, proof_version: u8) {
+        self.proof_version = proof_version;
+    }
+
+    #[inline]
+    pub fn set_min_note_age_slots(&mut self, min_note_age_slots: u64) {
+        self.min_note_age_slots = min_note_age_slots;
+    }
+
+    #[inline]
+    pub fn set_max_root_age_slots(&mut self, max_root_age_slots: u64) {
+        self.max_root_age_slots = max_root_age_slots;
+    }
+
+    #[inline]
+    pub fn set_dual_vk_window_until(&mut self, dual_vk_window_until: i64) {
+        self.dual_vk_window_until = dual_vk_window_until;
+    }
+
+    /// Whether the secondary VK is accepted at `now`.
+    #[inline]
+    pub fn dual_vk_window_open(&self, now: i64) -> bool {
+        now < self.dual_vk_window_until
+    }
+
     pub fn initiate_authority_transfer(&mut self, new_authority: Pubkey) -> Result<()> {
         require!(new_authority != Pubkey::default(), PrivacyError::InvalidAuthority);
         require!(new_authority != self.authority, PrivacyError::InvalidAuthority);
@@ -163,6 +670,8 @@ impl PoolConfig {
         require!(acceptor == self.pending_authority, PrivacyError::Unauthorized);
         self.authority = self.pending_authority;
         self.pending_authority = Pubkey::default();
+        // The acceptor signed, so the new authority is a plain key
+        self.authority_is_multisig = false;
         Ok(())
     }
     
@@ -175,6 +684,64 @@ impl PoolConfig {
         self.pending_authority != Pubkey::default()
     }
 
+    /// Hand the authority to an `AuthorityMultisig` account.
+    pub fn enable_authority_multisig(&mut self, multisig: Pubkey) {
+        self.authority = multisig;
+        self.authority_is_multisig = true;
+        self.pending_authority = Pubkey::default();
+    }
+
+    /// Check that `signers` may act as the pool authority.
+    ///
+    /// `signers[0]` is the instruction's `authority` signer and must equal
+    /// `authority` for a single-key pool. When `authority_is_multisig` is
+    /// set, `multisig` must be the authority account and `signers` must
+    /// include at least its threshold of distinct members.
+    pub fn require_admin(
+        &self,
+        signers: &[Pubkey],
+        multisig: Option<(Pubkey, &AuthorityMultisig)>,
+    ) -> Result<()> {
+        if !self.authority_is_multisig {
+            require!(
+                signers.first() == Some(&self.authority),
+                PrivacyError::Unauthorized
+            );
+            return Ok(());
+        }
+
+        let (multisig_key, multisig) = multisig.ok_or(error!(PrivacyError::Unauthorized))?;
+        require_keys_eq!(multisig_key, self.authority, PrivacyError::Unauthorized);
+        multisig.require_threshold(signers)
+    }
+
+    pub fn initiate_fee_recipient_change(&mut self, new_fee_recipient: Pubkey) -> Result<()> {
+        require!(new_fee_recipient != Pubkey::default(), PrivacyError::InvalidFeeRecipient);
+        require!(new_fee_recipient != self.fee_recipient, PrivacyError::InvalidFeeRecipient);
+        self.pending_fee_recipient = new_fee_recipient;
+        Ok(())
+    }
+
+    pub fn accept_fee_recipient_change(&mut self, acceptor: Pubkey) -> Result<()> {
+        require!(
+            self.pending_fee_recipient != Pubkey::default(),
+            PrivacyError::NoPendingFeeRecipient
+        );
+        require!(acceptor == self.pending_fee_recipient, PrivacyError::Unauthorized);
+        self.fee_recipient = self.pending_fee_recipient;
+        self.pending_fee_recipient = Pubkey::default();
+        Ok(())
+    }
+
+    pub fn cancel_fee_recipient_change(&mut self) {
+        self.pending_fee_recipient = Pubkey::default();
+    }
+
+    #[inline]
+    pub fn has_pending_fee_recipient(&self) -> bool {
+        self.pending_fee_recipient != Pubkey::default()
+    }
+
     #[deprecated(note = "Use record_deposit() for value tracking")]
     pub fn increment_deposits(&mut self) -> Result<()> {
         self.total_deposits = self.total_deposits
@@ -191,3 +758,393 @@ impl PoolConfig {
         Ok(())
     }
 }
+
+/// `PoolConfig` as laid out at schema version 2, before the per-pool
+/// settings of version 3. Read only by `migrate_pool_config`; version 2
+/// accounts are too short to deserialize as a `PoolConfig`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PoolConfigV2 {
+    pub authority: Pubkey,
+    pub pending_authority: Pubkey,
+    pub token_mint: Pubkey,
+    pub vault: Pubkey,
+    pub merkle_tree: Pubkey,
+    pub verification_key: Pubkey,
+    pub tree_depth: u8,
+    pub bump: u8,
+    pub is_paused: bool,
+    pub vk_configured: bool,
+    pub vk_locked: bool,
+    pub total_deposits: u64,
+    pub total_withdrawals: u64,
+    pub total_value_deposited: u64,
+    pub total_value_withdrawn: u64,
+    pub version: u8,
+    pub _reserved: [u8; 64],
+}
+
+impl PoolConfigV2 {
+    /// Allocated size of a version 2 pool account
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 32 + 32 + 32 + 1 + 1 + 1 + 1 + 1 + 3 + 8 + 8 + 8 + 8 + 1 + 64;
+    pub const VERSION: u8 = 2;
+
+    /// Parse a version 2 pool account, discriminator included.
+    ///
+    /// # Errors
+    /// * `PoolConfigNotMigratable` unless `data` is exactly a version 2
+    ///   `PoolConfig` account
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self> {
+        require!(
+            data.len() == Self::LEN && data[..8] == PoolConfig::DISCRIMINATOR,
+            PrivacyError::PoolConfigNotMigratable
+        );
+        let old = Self::deserialize(&mut &data[8..])
+            .map_err(|_| error!(PrivacyError::PoolConfigNotMigratable))?;
+        require!(old.version == Self::VERSION, PrivacyError::PoolConfigNotMigratable);
+        Ok(old)
+    }
+}
+
+impl PoolConfig {
+    /// Current-layout config for a version 2 pool. Every setting added
+    /// since takes its `initialize` default, so the pool behaves as it did:
+    /// client-supplied commitments, raw recipient encoding and an
+    /// indefinite pause if it was paused.
+    pub fn from_v2(old: &PoolConfigV2, token_decimals: u8) -> Result<Self> {
+        let mut pool = Self::try_deserialize_unchecked(&mut vec![0u8; Self::LEN].as_slice())?;
+        pool.initialize(
+            old.authority,
+            old.token_mint,
+            token_decimals,
+            old.vault,
+            old.merkle_tree,
+            old.verification_key,
+            old.tree_depth,
+            old.bump,
+            CommitmentMode::ClientSupplied,
+        );
+        pool.pending_authority = old.pending_authority;
+        pool.set_paused(old.is_paused);
+        pool.vk_configured = old.vk_configured;
+        pool.vk_locked = old.vk_locked;
+        pool.total_deposits = old.total_deposits;
+        pool.total_withdrawals = old.total_withdrawals;
+        pool.total_value_deposited = old.total_value_deposited;
+        pool.total_value_withdrawn = old.total_value_withdrawn;
+        Ok(pool)
+    }
+}
+
+/// Shared pool for unit tests.
+#[cfg(test)]
+pub(crate) mod test_fixtures {
+    use super::*;
+
+    /// A pool initialized with fresh keys, depth 20 and client-supplied
+    /// commitments; every limit and flag at its `initialize` default.
+    pub(crate) fn new_pool() -> PoolConfig {
+        let data = vec![0u8; PoolConfig::LEN];
+        let mut pool = PoolConfig::try_deserialize_unchecked(&mut data.as_slice()).unwrap();
+        pool.initialize(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            9,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            20,
+            255,
+            CommitmentMode::ClientSupplied,
+        );
+        pool
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::test_fixtures::new_pool;
+
+    #[test]
+    fn test_default_anonymity_set_allows_withdrawal() {
+        let pool = new_pool();
+        assert_eq!(pool.min_anonymity_set, 0);
+        assert!(pool.require_anonymity_set(0).is_ok());
+    }
+
+    #[test]
+    fn test_withdrawal_blocked_below_anonymity_set() {
+        let mut pool = new_pool();
+        pool.set_min_anonymity_set(10);
+        assert!(pool.require_anonymity_set(0).is_err());
+        assert!(pool.require_anonymity_set(9).is_err());
+    }
+
+    #[test]
+    fn test_withdrawal_allowed_at_or_above_anonymity_set() {
+        let mut pool = new_pool();
+        pool.set_min_anonymity_set(10);
+        assert!(pool.require_anonymity_set(10).is_ok());
+        assert!(pool.require_anonymity_set(11).is_ok());
+    }
+
+    #[cfg(not(feature = "dev-mode"))]
+    #[test]
+    fn test_dev_mode_guard_noop_in_normal_build() {
+        let pool = new_pool();
+        assert!(pool.require_dev_mode_acknowledged().is_ok());
+    }
+
+    #[cfg(feature = "dev-mode")]
+    #[test]
+    fn test_dev_mode_requires_acknowledgement() {
+        let mut pool = new_pool();
+        assert!(pool.require_dev_mode_acknowledged().is_err());
+
+        pool.set_dev_mode_acknowledged(true);
+        assert!(pool.require_dev_mode_acknowledged().is_ok());
+    }
+
+    #[test]
+    fn test_escrow_whitelisted_pda_accepted() {
+        let mut pool = new_pool();
+        let escrow_program = Pubkey::new_unique();
+        pool.set_escrow_program(escrow_program);
+
+        let (escrow, _) = Pubkey::find_program_address(&[b"escrow"], &escrow_program);
+        assert!(pool.require_escrow_recipient(&escrow, &escrow_program).is_ok());
+    }
+
+    #[test]
+    fn test_escrow_non_whitelisted_owner_rejected() {
+        let mut pool = new_pool();
+        let escrow_program = Pubkey::new_unique();
+        let other_program = Pubkey::new_unique();
+        pool.set_escrow_program(escrow_program);
+
+        let (escrow, _) = Pubkey::find_program_address(&[b"escrow"], &other_program);
+        assert!(pool.require_escrow_recipient(&escrow, &other_program).is_err());
+    }
+
+    #[test]
+    fn test_escrow_rejects_wallet_and_disabled_pool() {
+        let mut pool = new_pool();
+        let escrow_program = Pubkey::new_unique();
+        let (escrow, _) = Pubkey::find_program_address(&[b"escrow"], &escrow_program);
+
+        // Not configured
+        assert!(pool.require_escrow_recipient(&escrow, &escrow_program).is_err());
+
+        // On-curve wallet address (ed25519 base point) is not a PDA
+        pool.set_escrow_program(escrow_program);
+        let mut base_point = [0x66u8; 32];
+        base_point[0] = 0x58;
+        let wallet = Pubkey::new_from_array(base_point);
+        assert!(wallet.is_on_curve());
+        assert!(pool.require_escrow_recipient(&wallet, &escrow_program).is_err());
+    }
+
+    #[test]
+    fn test_swap_route_whitelist() {
+        let mut pool = new_pool();
+        let dex = Pubkey::new_unique();
+        let dex_pool = Pubkey::new_unique();
+
+        // Not configured
+        assert!(pool.require_swap_route(&dex, &dex_pool, &dex).is_err());
+
+        pool.set_swap_route(dex, dex_pool);
+        assert!(pool.require_swap_route(&dex, &dex_pool, &dex).is_ok());
+
+        let other = Pubkey::new_unique();
+        assert!(pool.require_swap_route(&other, &dex_pool, &dex).is_err());
+        assert!(pool.require_swap_route(&dex, &other, &dex).is_err());
+        assert!(pool.require_swap_route(&dex, &dex_pool, &other).is_err());
+    }
+
+    #[test]
+    fn test_untracked_excess_after_direct_transfer() {
+        let mut pool = new_pool();
+        pool.record_deposit(1_000).unwrap();
+        pool.record_deposit(500).unwrap();
+        pool.record_withdrawal(300).unwrap();
+
+        // 1_200 tracked, 77 sent directly to the vault
+        let vault = 1_200 + 77;
+        let excess = pool.untracked_excess(vault).unwrap();
+        assert_eq!(excess, 77);
+
+        // After the sweep the vault holds exactly the note-backed value
+        assert!(pool.tvl_invariant_holds(vault - excess));
+    }
+
+    #[test]
+    fn test_untracked_excess_requires_surplus() {
+        let mut pool = new_pool();
+        pool.record_deposit(1_000).unwrap();
+
+        assert!(pool.untracked_excess(1_000).is_err());
+        // A vault short of tracked value must not be "swept" further
+        assert!(pool.untracked_excess(900).is_err());
+        assert!(pool.untracked_excess(0).is_err());
+    }
+
+    #[test]
+    fn test_client_supplied_mode_accepts_commitment_only() {
+        let pool = new_pool();
+        let commitment = [7u8; 32];
+        assert_eq!(
+            pool.resolve_commitment(DepositNote::Commitment(commitment), 1_000).unwrap(),
+            commitment
+        );
+        assert!(pool.resolve_commitment(DepositNote::NoteHash([7u8; 32]), 1_000).is_err());
+    }
+
+    #[test]
+    fn test_program_computed_mode_binds_amount() {
+        let mut pool = new_pool();
+        pool.commitment_mode = CommitmentMode::ProgramComputed as u8;
+        let mut note_hash = [7u8; 32];
+        note_hash[0] = 0;
+
+        let leaf = pool.resolve_commitment(DepositNote::NoteHash(note_hash), 1_000).unwrap();
+        assert_eq!(leaf, hash_note_commitment(&note_hash, 1_000).unwrap());
+        assert_ne!(
+            leaf,
+            pool.resolve_commitment(DepositNote::NoteHash(note_hash), 1).unwrap()
+        );
+
+        assert!(pool.resolve_commitment(DepositNote::Commitment(leaf), 1_000).is_err());
+        assert!(pool.resolve_commitment(DepositNote::NoteHash([0u8; 32]), 1_000).is_err());
+    }
+
+    #[test]
+    fn test_min_relayer_fee_floor() {
+        let mut pool = new_pool();
+        let (recipient, relayer) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        // No floor by default
+        assert!(pool.require_min_relayer_fee(&recipient, &relayer, 0).is_ok());
+
+        pool.set_min_relayer_fee(500);
+        assert!(pool.require_min_relayer_fee(&recipient, &relayer, 0).is_err());
+        assert!(pool.require_min_relayer_fee(&recipient, &relayer, 499).is_err());
+        assert!(pool.require_min_relayer_fee(&recipient, &relayer, 500).is_ok());
+
+        // Self-relay is exempt
+        assert!(pool.require_min_relayer_fee(&recipient, &recipient, 0).is_ok());
+    }
+
+    #[test]
+    fn test_fee_recipient_defaults_to_authority() {
+        let pool = new_pool();
+        assert_eq!(pool.fee_recipient, pool.authority);
+        assert!(!pool.has_pending_fee_recipient());
+    }
+
+    #[test]
+    fn test_fee_recipient_initiate_and_accept() {
+        let mut pool = new_pool();
+        let new_recipient = Pubkey::new_unique();
+
+        pool.initiate_fee_recipient_change(new_recipient).unwrap();
+        assert!(pool.has_pending_fee_recipient());
+        assert_ne!(pool.fee_recipient, new_recipient);
+
+        pool.accept_fee_recipient_change(new_recipient).unwrap();
+        assert_eq!(pool.fee_recipient, new_recipient);
+        assert!(!pool.has_pending_fee_recipient());
+    }
+
+    #[test]
+    fn test_fee_recipient_cancel() {
+        let mut pool = new_pool();
+        let original = pool.fee_recipient;
+        let new_recipient = Pubkey::new_unique();
+
+        pool.initiate_fee_recipient_change(new_recipient).unwrap();
+        pool.cancel_fee_recipient_change();
+        assert!(!pool.has_pending_fee_recipient());
+        assert!(pool.accept_fee_recipient_change(new_recipient).is_err());
+        assert_eq!(pool.fee_recipient, original);
+    }
+
+    #[test]
+    fn test_fee_recipient_rejects_non_pending_acceptor() {
+        let mut pool = new_pool();
+        let new_recipient = Pubkey::new_unique();
+
+        assert!(pool.initiate_fee_recipient_change(Pubkey::default()).is_err());
+        pool.initiate_fee_recipient_change(new_recipient).unwrap();
+
+        assert!(pool.accept_fee_recipient_change(Pubkey::new_unique()).is_err());
+        assert!(pool.accept_fee_recipient_change(pool.authority).is_err());
+        assert!(pool.has_pending_fee_recipient());
+    }
+
+    #[test]
+    fn test_dual_vk_window() {
+        let mut pool = new_pool();
+        assert!(!pool.dual_vk_window_open(0));
+
+        pool.set_dual_vk_window_until(1_000);
+        assert!(pool.dual_vk_window_open(999));
+        assert!(!pool.dual_vk_window_open(1_000));
+    }
+
+    #[test]
+    fn test_multisig_authority_requires_threshold() {
+        let mut pool = new_pool();
+        let single = pool.authority;
+        assert!(pool.require_admin(&[single], None).is_ok());
+        assert!(pool.require_admin(&[Pubkey::new_unique()], None).is_err());
+
+        let members: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let mut multisig = AuthorityMultisig::try_deserialize_unchecked(
+            &mut vec![0u8; AuthorityMultisig::LEN].as_slice(),
+        )
+        .unwrap();
+        multisig.initialize(Pubkey::new_unique(), members.clone(), 2, 255).unwrap();
+        let multisig_key = Pubkey::new_unique();
+        pool.enable_authority_multisig(multisig_key);
+
+        // The old single key no longer works on its own
+        assert!(pool.require_admin(&[single], None).is_err());
+        assert!(pool
+            .require_admin(&[single], Some((multisig_key, &multisig)))
+            .is_err());
+
+        // Fewer than M members fails, exactly M succeeds
+        let ms = Some((multisig_key, &multisig));
+        assert!(pool.require_admin(&members[..1], ms).is_err());
+        assert!(pool.require_admin(&members[..2], ms).is_ok());
+
+        // The multisig account must be the one holding the authority
+        assert!(pool
+            .require_admin(&members[..2], Some((Pubkey::new_unique(), &multisig)))
+            .is_err());
+        assert!(pool.require_admin(&members[..2], None).is_err());
+
+        // Transferring to a key turns the multisig path off again
+        let new_authority = Pubkey::new_unique();
+        pool.initiate_authority_transfer(new_authority).unwrap();
+        pool.accept_authority_transfer(new_authority).unwrap();
+        assert!(!pool.authority_is_multisig);
+        assert!(pool.require_admin(&[new_authority], None).is_ok());
+    }
+
+    #[test]
+    fn test_fee_in_fee_mint_fixed_rate() {
+        let mut pool = new_pool();
+        assert!(pool.fee_in_fee_mint(100).is_err());
+
+        // 1 pool unit = 2.5 fee-mint units
+        pool.set_fee_mint(Pubkey::new_unique(), 2_500_000_000);
+        assert_eq!(pool.fee_in_fee_mint(100).unwrap(), 250);
+        assert_eq!(pool.fee_in_fee_mint(1).unwrap(), 2);
+        assert_eq!(pool.fee_in_fee_mint(0).unwrap(), 0);
+
+        pool.set_fee_mint_rate(u64::MAX);
+        assert!(pool.fee_in_fee_mint(u64::MAX).is_err());
+    }
+}
@@ -0,0 +1,127 @@
+//! Pool Registry
+//!
+//! Lets one authority enumerate the pools it runs (one per mint) and act on
+//! all of them at once, e.g. `pause_all` during an incident. Pools created
+//! with the registry passed to `initialize_pool` are registered
+//! automatically.
+
+use anchor_lang::prelude::*;
+
+use crate::error::PrivacyError;
+
+/// Maximum pools per registry (bounds account size and `pause_all` compute).
+pub const MAX_REGISTRY_POOLS: usize = 32;
+
+/// One registered pool.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegistryEntry {
+    pub token_mint: Pubkey,
+    pub pool_config: Pubkey,
+}
+
+/// Per-authority list of pools.
+///
+/// PDA Seeds: `[b"pool_registry", authority.key().as_ref()]`
+#[account]
+pub struct PoolRegistry {
+    /// Authority that owns the registry and its pools
+    pub authority: Pubkey,
+
+    /// Registered pools, in registration order
+    pub entries: Vec<RegistryEntry>,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl PoolRegistry {
+    pub const LEN: usize = 8                        // discriminator
+        + 32                                        // authority
+        + 4 + (64 * MAX_REGISTRY_POOLS)             // entries (vec)
+        + 1;                                        // bump
+
+    pub fn initialize(&mut self, authority: Pubkey, bump: u8) {
+        self.authority = authority;
+        self.entries = Vec::new();
+        self.bump = bump;
+    }
+
+    /// Add a pool.
+    ///
+    /// # Errors
+    /// * `PoolAlreadyRegistered` if the pool or its mint is already listed
+    /// * `PoolRegistryFull` at `MAX_REGISTRY_POOLS`
+    pub fn register_pool(&mut self, token_mint: Pubkey, pool_config: Pubkey) -> Result<()> {
+        require!(
+            !self
+                .entries
+                .iter()
+                .any(|e| e.pool_config == pool_config || e.token_mint == token_mint),
+            PrivacyError::PoolAlreadyRegistered
+        );
+        require!(
+            self.entries.len() < MAX_REGISTRY_POOLS,
+            PrivacyError::PoolRegistryFull
+        );
+        self.entries.push(RegistryEntry {
+            token_mint,
+            pool_config,
+        });
+        Ok(())
+    }
+
+    /// Whether `pool_config` is registered.
+    pub fn contains(&self, pool_config: &Pubkey) -> bool {
+        self.entries.iter().any(|e| e.pool_config == *pool_config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_registry() -> PoolRegistry {
+        let mut registry =
+            PoolRegistry::try_deserialize_unchecked(&mut vec![0u8; PoolRegistry::LEN].as_slice())
+                .unwrap();
+        registry.initialize(Pubkey::new_unique(), 255);
+        registry
+    }
+
+    #[test]
+    fn test_register_two_pools() {
+        let mut registry = new_registry();
+        let (mint_a, pool_a) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (mint_b, pool_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        registry.register_pool(mint_a, pool_a).unwrap();
+        registry.register_pool(mint_b, pool_b).unwrap();
+
+        assert_eq!(registry.entries.len(), 2);
+        assert!(registry.contains(&pool_a));
+        assert!(registry.contains(&pool_b));
+        assert!(!registry.contains(&Pubkey::new_unique()));
+
+        // Fits in the allocated account
+        assert!(registry.try_to_vec().unwrap().len() + 8 <= PoolRegistry::LEN);
+    }
+
+    #[test]
+    fn test_duplicate_and_overflow_rejected() {
+        let mut registry = new_registry();
+        let (mint, pool) = (Pubkey::new_unique(), Pubkey::new_unique());
+        registry.register_pool(mint, pool).unwrap();
+        assert!(registry.register_pool(mint, Pubkey::new_unique()).is_err());
+        assert!(registry.register_pool(Pubkey::new_unique(), pool).is_err());
+
+        for _ in 1..MAX_REGISTRY_POOLS {
+            registry
+                .register_pool(Pubkey::new_unique(), Pubkey::new_unique())
+                .unwrap();
+        }
+        assert!(registry
+            .register_pool(Pubkey::new_unique(), Pubkey::new_unique())
+            .is_err());
+        assert!(registry.try_to_vec().unwrap().len() + 8 <= PoolRegistry::LEN);
+    }
+}
@@ -0,0 +1,95 @@
+//! Per-recipient withdrawal cooldown
+//!
+//! When `PoolConfig.recipient_cooldown_slots > 0`, consecutive withdrawals
+//! to the same recipient must be at least that many slots apart. This
+//! limits dusting/spam against a single address.
+//!
+//! # Privacy Trade-off
+//! The PDA is keyed by recipient, so it publicly records that an address
+//! has received withdrawals and when the last one was. Pools opt in; the
+//! default is off.
+
+use anchor_lang::prelude::*;
+
+use crate::error::PrivacyError;
+
+/// Last withdrawal slot for one recipient of one pool.
+///
+/// PDA Seeds: `[b"recipient_cooldown", pool.key().as_ref(), recipient.as_ref()]`
+#[account]
+pub struct RecipientCooldown {
+    /// Reference to parent pool
+    pub pool: Pubkey,
+
+    /// Recipient this cooldown applies to
+    pub recipient: Pubkey,
+
+    /// Slot of the last withdrawal to `recipient` (0 = none yet)
+    pub last_withdraw_slot: u64,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl RecipientCooldown {
+    pub const LEN: usize = 8  // discriminator
+        + 32                  // pool
+        + 32                  // recipient
+        + 8                   // last_withdraw_slot
+        + 1;                  // bump
+
+    pub fn initialize(&mut self, pool: Pubkey, recipient: Pubkey, bump: u8) {
+        self.pool = pool;
+        self.recipient = recipient;
+        self.last_withdraw_slot = 0;
+        self.bump = bump;
+    }
+
+    /// Require `current_slot >= last_withdraw_slot + cooldown_slots`, then
+    /// record `current_slot` as the last withdrawal.
+    ///
+    /// # Errors
+    /// * `RecipientCooldownActive` if the cooldown has not elapsed
+    pub fn check_and_record(&mut self, current_slot: u64, cooldown_slots: u64) -> Result<()> {
+        if self.last_withdraw_slot != 0 {
+            let ready_at = self.last_withdraw_slot.saturating_add(cooldown_slots);
+            require!(current_slot >= ready_at, PrivacyError::RecipientCooldownActive);
+        }
+        self.last_withdraw_slot = current_slot;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_cooldown() -> RecipientCooldown {
+        let mut cooldown =
+            RecipientCooldown::try_deserialize_unchecked(&mut vec![0u8; RecipientCooldown::LEN].as_slice())
+                .unwrap();
+        cooldown.initialize(Pubkey::new_unique(), Pubkey::new_unique(), 255);
+        cooldown
+    }
+
+    #[test]
+    fn test_first_withdrawal_allowed() {
+        let mut cooldown = new_cooldown();
+        assert!(cooldown.check_and_record(1_000, 100).is_ok());
+        assert_eq!(cooldown.last_withdraw_slot, 1_000);
+    }
+
+    #[test]
+    fn test_cooldown_boundary() {
+        let mut cooldown = new_cooldown();
+        cooldown.check_and_record(1_000, 100).unwrap();
+
+        // One slot early is blocked and does not move the window
+        assert!(cooldown.check_and_record(1_099, 100).is_err());
+        assert_eq!(cooldown.last_withdraw_slot, 1_000);
+
+        // Exactly last + cooldown is allowed
+        assert!(cooldown.check_and_record(1_100, 100).is_ok());
+        assert_eq!(cooldown.last_withdraw_slot, 1_100);
+    }
+}
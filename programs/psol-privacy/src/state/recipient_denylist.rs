@@ -0,0 +1,122 @@
+//! Recipient denylist
+//!
+//! Small on-chain list of recipients a pool refuses to pay, for operators
+//! with sanctions obligations. `PoolConfig.recipient_denylist_root` commits
+//! to the list contents; a zero root means no denylist and withdrawals do
+//! not need to pass the account.
+
+use anchor_lang::prelude::*;
+
+use crate::error::PrivacyError;
+
+/// Maximum denied recipients (bounds account size and the withdraw scan).
+pub const MAX_DENIED_RECIPIENTS: usize = 64;
+
+/// Denied recipients of one pool.
+///
+/// PDA Seeds: `[b"recipient_denylist", pool.key().as_ref()]`
+#[account]
+pub struct RecipientDenylist {
+    /// Reference to parent pool
+    pub pool: Pubkey,
+
+    /// Denied recipient addresses, in insertion order
+    pub entries: Vec<Pubkey>,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl RecipientDenylist {
+    pub const LEN: usize = 8                        // discriminator
+        + 32                                        // pool
+        + 4 + (32 * MAX_DENIED_RECIPIENTS)          // entries (vec)
+        + 1;                                        // bump
+
+    pub fn initialize(&mut self, pool: Pubkey, bump: u8) {
+        self.pool = pool;
+        self.entries = Vec::new();
+        self.bump = bump;
+    }
+
+    /// Commitment stored in `PoolConfig.recipient_denylist_root`.
+    ///
+    /// Zero for an empty list, otherwise
+    /// `Keccak256("psol-denylist" || entries...)`.
+    pub fn root(&self) -> [u8; 32] {
+        if self.entries.is_empty() {
+            return [0u8; 32];
+        }
+        let mut parts: Vec<&[u8]> = Vec::with_capacity(self.entries.len() + 1);
+        parts.push(b"psol-denylist");
+        parts.extend(self.entries.iter().map(|e| e.as_ref()));
+        solana_program::keccak::hashv(&parts).to_bytes()
+    }
+
+    /// Whether `recipient` is denied.
+    pub fn contains(&self, recipient: &Pubkey) -> bool {
+        self.entries.contains(recipient)
+    }
+
+    /// Deny `recipient`. Adding an already denied recipient is a no-op.
+    ///
+    /// # Errors
+    /// * `LimitExceeded` at `MAX_DENIED_RECIPIENTS`
+    pub fn add(&mut self, recipient: Pubkey) -> Result<()> {
+        if self.contains(&recipient) {
+            return Ok(());
+        }
+        require!(
+            self.entries.len() < MAX_DENIED_RECIPIENTS,
+            PrivacyError::LimitExceeded
+        );
+        self.entries.push(recipient);
+        Ok(())
+    }
+
+    /// Allow `recipient` again. Removing an absent recipient is a no-op.
+    pub fn remove(&mut self, recipient: &Pubkey) {
+        self.entries.retain(|e| e != recipient);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_denylist() -> RecipientDenylist {
+        let mut denylist = RecipientDenylist::try_deserialize_unchecked(
+            &mut vec![0u8; RecipientDenylist::LEN].as_slice(),
+        )
+        .unwrap();
+        denylist.initialize(Pubkey::new_unique(), 255);
+        denylist
+    }
+
+    #[test]
+    fn test_root_tracks_entries() {
+        let mut denylist = new_denylist();
+        assert_eq!(denylist.root(), [0u8; 32]);
+
+        let denied = Pubkey::new_unique();
+        denylist.add(denied).unwrap();
+        let root = denylist.root();
+        assert_ne!(root, [0u8; 32]);
+
+        // Re-adding does not change the list
+        denylist.add(denied).unwrap();
+        assert_eq!(denylist.root(), root);
+
+        denylist.remove(&denied);
+        assert_eq!(denylist.root(), [0u8; 32]);
+    }
+
+    #[test]
+    fn test_denylist_capacity() {
+        let mut denylist = new_denylist();
+        for _ in 0..MAX_DENIED_RECIPIENTS {
+            denylist.add(Pubkey::new_unique()).unwrap();
+        }
+        assert!(denylist.add(Pubkey::new_unique()).is_err());
+    }
+}
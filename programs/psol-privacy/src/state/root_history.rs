@@ -0,0 +1,464 @@
+//! Root History account for split-layout Merkle trees
+//!
+//! Large pools can move the root history circular buffer out of the
+//! `MerkleTree` account into this PDA, keeping the tree account small as
+//! more per-root data is added. Small pools keep the single-account layout.
+//!
+//! In split layout `MerkleTree.external_root_history` is set, the tree's own
+//! `root_history` / `root_slots` are empty, and every instruction that
+//! inserts leaves or checks roots must pass this account.
+//!
+//! # Root Index
+//! Split histories are the large ones, so lookups here go through
+//! `root_index`, an open-addressing table (linear probing) keyed by the
+//! first 8 bytes of each root, instead of scanning the whole ring. Entries
+//! are ring positions + 1 (0 = empty); the table has at least twice as many
+//! entries as the ring, so probe runs stay short. An empty `root_index`
+//! falls back to the linear scan.
+
+use anchor_lang::prelude::*;
+
+use crate::error::PrivacyError;
+use crate::state::MerkleTree;
+
+/// Root history circular buffer split out of a `MerkleTree`.
+///
+/// PDA Seeds: `[b"root_history", pool_config.key().as_ref()]`
+#[account]
+pub struct RootHistory {
+    /// Reference to parent pool
+    pub pool: Pubkey,
+
+    /// Recent roots (circular buffer)
+    pub root_history: Vec<[u8; 32]>,
+
+    /// Slot at which each `root_history` entry was inserted (parallel buffer)
+    pub root_slots: Vec<u64>,
+
+    /// Current position in the circular buffer
+    pub root_history_index: u16,
+
+    /// Buffer size (taken from the tree when split)
+    pub root_history_size: u16,
+
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// Open-addressing index over `root_history` (see module docs)
+    pub root_index: Vec<u16>,
+}
+
+impl RootHistory {
+    /// Calculate space needed for `root_history_size` roots.
+    pub fn space(root_history_size: u16) -> usize {
+        let history_usize = root_history_size as usize;
+
+        8                                       // discriminator
+            + 32                                // pool
+            + 4 + (32 * history_usize)          // root_history (vec)
+            + 4 + (8 * history_usize)           // root_slots (vec)
+            + 2                                 // root_history_index
+            + 2                                 // root_history_size
+            + 1                                 // bump
+            + 4 + (2 * root_index_capacity(root_history_size)) // root_index (vec)
+    }
+
+    /// Move `tree`'s root history into this account and switch the tree to
+    /// split layout. Existing roots stay valid.
+    ///
+    /// # Errors
+    /// * `RootHistoryMismatch` if the tree is already split
+    pub fn take_from(&mut self, tree: &mut MerkleTree, bump: u8) -> Result<()> {
+        require!(
+            !tree.external_root_history,
+            PrivacyError::RootHistoryMismatch
+        );
+
+        self.pool = tree.pool;
+        self.root_history = std::mem::take(&mut tree.root_history);
+        self.root_slots = std::mem::take(&mut tree.root_slots);
+        self.root_history_index = tree.root_history_index;
+        self.root_history_size = tree.root_history_size;
+        self.bump = bump;
+        self.rebuild_index();
+
+        tree.root_history_index = 0;
+        tree.root_history_size = 0;
+        tree.external_root_history = true;
+
+        Ok(())
+    }
+
+    /// Record a new root. See `push_root_to_history`.
+    pub fn push(&mut self, root: [u8; 32], slot: u64) {
+        let index = self.root_history_index as usize;
+        if self.root_history[index] == root {
+            return;
+        }
+        let evicted = (index + 1) % self.root_history.len();
+        if self.is_indexed() {
+            index_remove(&mut self.root_index, &self.root_history, evicted);
+        }
+        push_root_to_history(
+            &mut self.root_history,
+            &mut self.root_slots,
+            &mut self.root_history_index,
+            root,
+            slot,
+        );
+        if self.is_indexed() {
+            index_insert(&mut self.root_index, &self.root_history, evicted);
+        }
+    }
+
+    /// Whether lookups go through `root_index`.
+    pub fn is_indexed(&self) -> bool {
+        !self.root_index.is_empty()
+    }
+
+    /// Size `root_index` for the current buffer and index every written
+    /// root.
+    pub fn rebuild_index(&mut self) {
+        self.root_index = vec![0u16; root_index_capacity(self.root_history_size)];
+        for position in 0..self.root_history.len() {
+            index_insert(&mut self.root_index, &self.root_history, position);
+        }
+    }
+
+    /// Ring positions holding `root`, or `None` when the lookup must scan
+    /// (unindexed account, or the zero root of never-written entries).
+    fn indexed_positions<'a>(&'a self, root: &'a [u8; 32]) -> Option<impl Iterator<Item = usize> + 'a> {
+        (self.is_indexed() && *root != [0u8; 32])
+            .then(|| index_lookup(&self.root_index, &self.root_history, root))
+    }
+
+    /// See `MerkleTree::resize_root_history_in`.
+    pub fn resize(&mut self, new_size: u16, current_slot: u64, max_age_slots: u64) -> Result<()> {
+        resize_history(
+            &mut self.root_history,
+            &mut self.root_slots,
+            &mut self.root_history_index,
+            new_size,
+            current_slot,
+            max_age_slots,
+        )?;
+        self.root_history_size = new_size;
+        self.rebuild_index();
+        Ok(())
+    }
+
+    /// See `MerkleTree::distinct_root_count`.
+    pub fn distinct_root_count(&self) -> usize {
+        distinct_roots_in_history(&self.root_history)
+    }
+
+    /// Check if a root exists in recent history.
+    pub fn is_known_root(&self, root: &[u8; 32]) -> bool {
+        match self.indexed_positions(root) {
+            Some(mut positions) => positions.next().is_some(),
+            None => self.root_history.iter().any(|r| r == root),
+        }
+    }
+
+    /// See `MerkleTree::root_slot`.
+    pub fn root_slot(&self, root: &[u8; 32]) -> Option<u64> {
+        match self.indexed_positions(root) {
+            Some(positions) => positions.map(|i| self.root_slots[i]).max(),
+            None => root_slot_in_history(&self.root_history, &self.root_slots, root),
+        }
+    }
+
+    /// See `MerkleTree::is_known_root_within`.
+    pub fn is_known_root_within(
+        &self,
+        root: &[u8; 32],
+        current_slot: u64,
+        max_age_slots: u64,
+    ) -> bool {
+        match self.indexed_positions(root) {
+            Some(mut positions) if max_age_slots > 0 => positions
+                .any(|i| current_slot.saturating_sub(self.root_slots[i]) <= max_age_slots),
+            Some(mut positions) => positions.next().is_some(),
+            None => root_in_history(
+                &self.root_history,
+                &self.root_slots,
+                root,
+                current_slot,
+                max_age_slots,
+            ),
+        }
+    }
+}
+
+/// `root_index` entries for a `history_size` ring: a power of two at least
+/// twice the ring size, so the table is at most half full.
+pub fn root_index_capacity(history_size: u16) -> usize {
+    (2 * history_size as usize).next_power_of_two()
+}
+
+/// Home bucket of `root`: its 8-byte prefix, big-endian so the low bits
+/// come from bytes that are uniform for field-element roots too.
+fn index_home(root: &[u8; 32], mask: usize) -> usize {
+    let mut prefix = [0u8; 8];
+    prefix.copy_from_slice(&root[..8]);
+    u64::from_be_bytes(prefix) as usize & mask
+}
+
+/// Index ring `position`, unless it was never written.
+fn index_insert(index: &mut [u16], roots: &[[u8; 32]], position: usize) {
+    if roots[position] == [0u8; 32] {
+        return;
+    }
+    let mask = index.len() - 1;
+    let mut bucket = index_home(&roots[position], mask);
+    while index[bucket] != 0 {
+        bucket = (bucket + 1) & mask;
+    }
+    index[bucket] = (position + 1) as u16;
+}
+
+/// Drop ring `position` from the index before it is overwritten, shifting
+/// later entries of the probe run back so lookups never stop early.
+fn index_remove(index: &mut [u16], roots: &[[u8; 32]], position: usize) {
+    if roots[position] == [0u8; 32] {
+        return;
+    }
+    let mask = index.len() - 1;
+    let entry = (position + 1) as u16;
+    let mut hole = index_home(&roots[position], mask);
+    while index[hole] != entry {
+        hole = (hole + 1) & mask;
+    }
+
+    let mut next = hole;
+    loop {
+        next = (next + 1) & mask;
+        if index[next] == 0 {
+            break;
+        }
+        let home = index_home(&roots[index[next] as usize - 1], mask);
+        // Move the entry into the hole unless its home lies cyclically in
+        // (hole, next], where it would no longer be reachable from home
+        let reachable = if hole <= next {
+            hole < home && home <= next
+        } else {
+            hole < home || home <= next
+        };
+        if !reachable {
+            index[hole] = index[next];
+            hole = next;
+        }
+    }
+    index[hole] = 0;
+}
+
+/// Ring positions holding `root`, from its probe run in `index`.
+fn index_lookup<'a>(
+    index: &'a [u16],
+    roots: &'a [[u8; 32]],
+    root: &'a [u8; 32],
+) -> impl Iterator<Item = usize> + 'a {
+    let mask = index.len() - 1;
+    let home = index_home(root, mask);
+    (0..index.len())
+        .map(move |step| index[(home + step) & mask])
+        .take_while(|&entry| entry != 0)
+        .map(|entry| entry as usize - 1)
+        .filter(move |&position| roots[position] == *root)
+}
+
+/// Shared root lookup over a `(roots, slots)` circular buffer.
+///
+/// A `max_age_slots` of 0 disables the age limit.
+pub(crate) fn root_in_history(
+    roots: &[[u8; 32]],
+    slots: &[u64],
+    root: &[u8; 32],
+    current_slot: u64,
+    max_age_slots: u64,
+) -> bool {
+    if max_age_slots == 0 {
+        return roots.iter().any(|r| r == root);
+    }
+
+    roots
+        .iter()
+        .zip(slots.iter())
+        .any(|(r, &slot)| r == root && current_slot.saturating_sub(slot) <= max_age_slots)
+}
+
+/// Advance a `(roots, slots)` circular buffer and record `root` at `slot`.
+///
+/// A `root` equal to the newest entry is not recorded again: it would
+/// evict the oldest root without adding a new one, and the newest entry
+/// keeps the slot at which the root first appeared.
+pub(crate) fn push_root_to_history(
+    roots: &mut [[u8; 32]],
+    slots: &mut [u64],
+    index: &mut u16,
+    root: [u8; 32],
+    slot: u64,
+) {
+    if roots[*index as usize] == root {
+        return;
+    }
+    *index = (*index + 1) % roots.len() as u16;
+    roots[*index as usize] = root;
+    slots[*index as usize] = slot;
+}
+
+/// Re-lay a `(roots, slots)` circular buffer out at `new_size` entries,
+/// oldest first, with `index` on the newest.
+///
+/// A root is still valid if withdrawals may prove against it: any written
+/// root when `max_age_slots` is 0, otherwise one inserted within
+/// `max_age_slots` of `current_slot`.
+///
+/// # Errors
+/// * `RootHistoryShrinkDropsRoots` if shrinking would evict a valid root
+pub(crate) fn resize_history(
+    roots: &mut Vec<[u8; 32]>,
+    slots: &mut Vec<u64>,
+    index: &mut u16,
+    new_size: u16,
+    current_slot: u64,
+    max_age_slots: u64,
+) -> Result<()> {
+    let size = roots.len();
+    let mut entries: Vec<([u8; 32], u64)> = (1..=size)
+        .map(|offset| (*index as usize + offset) % size)
+        .filter(|&i| roots[i] != [0u8; 32])
+        .map(|i| (roots[i], slots[i]))
+        .collect();
+
+    let dropped = entries.len().saturating_sub(new_size as usize);
+    let still_valid = |(_, slot): &([u8; 32], u64)| {
+        max_age_slots == 0 || current_slot.saturating_sub(*slot) <= max_age_slots
+    };
+    require!(
+        !entries[..dropped].iter().any(still_valid),
+        PrivacyError::RootHistoryShrinkDropsRoots
+    );
+    entries.drain(..dropped);
+
+    *roots = vec![[0u8; 32]; new_size as usize];
+    *slots = vec![0u64; new_size as usize];
+    for (i, (root, slot)) in entries.iter().enumerate() {
+        roots[i] = *root;
+        slots[i] = *slot;
+    }
+    *index = entries.len().saturating_sub(1) as u16;
+    Ok(())
+}
+
+/// Number of distinct roots in a buffer, ignoring never-written (zero)
+/// entries.
+pub(crate) fn distinct_roots_in_history(roots: &[[u8; 32]]) -> usize {
+    let mut distinct: Vec<&[u8; 32]> = roots.iter().filter(|r| **r != [0u8; 32]).collect();
+    distinct.sort_unstable();
+    distinct.dedup();
+    distinct.len()
+}
+
+/// Slot at which `root` was inserted into a `(roots, slots)` buffer; the
+/// latest one should the same root appear twice.
+pub(crate) fn root_slot_in_history(roots: &[[u8; 32]], slots: &[u64], root: &[u8; 32]) -> Option<u64> {
+    roots
+        .iter()
+        .zip(slots.iter())
+        .filter(|(r, _)| *r == root)
+        .map(|(_, &slot)| slot)
+        .max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use proptest::test_runner::RngSeed;
+
+    fn new_history(size: u16, indexed: bool) -> RootHistory {
+        let data = vec![0u8; RootHistory::space(size)];
+        let mut history = RootHistory::try_deserialize_unchecked(&mut data.as_slice()).unwrap();
+        history.root_history = vec![[0u8; 32]; size as usize];
+        history.root_slots = vec![0u64; size as usize];
+        history.root_history_size = size;
+        if indexed {
+            history.rebuild_index();
+        } else {
+            history.root_index.clear();
+        }
+        history
+    }
+
+    /// One of 1024 roots, with the first 8 bytes drawn from 4 values so that
+    /// probe runs collide and wrap around the table.
+    fn root(n: u16) -> [u8; 32] {
+        let mut root = [0u8; 32];
+        root[7] = (n % 4) as u8;
+        root[30..].copy_from_slice(&(n + 1).to_be_bytes());
+        root
+    }
+
+    fn assert_lookups_agree(indexed: &RootHistory, linear: &RootHistory, slot: u64) {
+        assert!(indexed.is_indexed() && !linear.is_indexed());
+        for n in 0..1024 {
+            let root = root(n);
+            assert_eq!(indexed.is_known_root(&root), linear.is_known_root(&root));
+            assert_eq!(indexed.root_slot(&root), linear.root_slot(&root));
+            for max_age in [0, 1, 50, 300] {
+                assert_eq!(
+                    indexed.is_known_root_within(&root, slot, max_age),
+                    linear.is_known_root_within(&root, slot, max_age),
+                );
+            }
+        }
+        let zero = [0u8; 32];
+        assert_eq!(indexed.is_known_root(&zero), linear.is_known_root(&zero));
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig {
+            cases: 16,
+            rng_seed: RngSeed::Fixed(0x7073_6f6c),
+            failure_persistence: None,
+            ..ProptestConfig::default()
+        })]
+
+        #[test]
+        fn indexed_lookup_matches_linear_scan(
+            pushes in prop::collection::vec(0u16..1024, 1..700),
+            resize_at in prop::option::of((0usize..700, 200u16..400)),
+        ) {
+            let (mut indexed, mut linear) = (new_history(200, true), new_history(200, false));
+            for (slot, (i, &n)) in (1u64..).zip(pushes.iter().enumerate()) {
+                if let Some((_, size)) = resize_at.filter(|&(at, _)| at == i) {
+                    let indexed_ok = indexed.resize(size, slot, 1).is_ok();
+                    let linear_ok = linear.resize(size, slot, 1).is_ok();
+                    prop_assert_eq!(indexed_ok, linear_ok);
+                    // resize always rebuilds the index; keep the reference linear
+                    linear.root_index.clear();
+                }
+                indexed.push(root(n), slot);
+                linear.push(root(n), slot);
+                prop_assert_eq!(&indexed.root_history, &linear.root_history);
+            }
+            assert_lookups_agree(&indexed, &linear, pushes.len() as u64);
+        }
+    }
+
+    #[test]
+    fn test_index_survives_wraparound_evictions() {
+        let (mut indexed, mut linear) = (new_history(200, true), new_history(200, false));
+        // Every root shares one home bucket; five laps evict each many times
+        for (slot, n) in (1u64..).zip((0..1000).map(|i| (i % 250) * 4)) {
+            indexed.push(root(n), slot);
+            linear.push(root(n), slot);
+        }
+        assert_eq!(indexed.root_index.len(), root_index_capacity(200));
+        assert_eq!(indexed.root_index.iter().filter(|&&e| e != 0).count(), 200);
+        assert_lookups_agree(&indexed, &linear, 1000);
+        assert!(!indexed.is_known_root(&root(0)));
+        assert!(indexed.is_known_root(&root(996)));
+    }
+}
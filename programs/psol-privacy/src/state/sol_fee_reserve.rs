@@ -0,0 +1,60 @@
+//! SOL fee reserve
+//!
+//! Program-owned account whose lamports above its rent-exempt minimum pay
+//! `withdraw_fee_in_lamports` relayer fees. The protocol funds it with plain
+//! system transfers (e.g. from collected deposit rent); only the program can
+//! debit it.
+
+use anchor_lang::prelude::*;
+
+use crate::error::PrivacyError;
+
+/// Lamport reserve for one pool's relayer fees.
+///
+/// PDA Seeds: `[b"sol_fee_reserve", pool.key().as_ref()]`
+#[account]
+pub struct SolFeeReserve {
+    /// Reference to parent pool
+    pub pool: Pubkey,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl SolFeeReserve {
+    pub const LEN: usize = 8  // discriminator
+        + 32                  // pool
+        + 1;                  // bump
+
+    pub fn initialize(&mut self, pool: Pubkey, bump: u8) {
+        self.pool = pool;
+        self.bump = bump;
+    }
+
+    /// Lamports `reserve` can pay out while staying rent-exempt.
+    pub fn available(reserve: &AccountInfo, rent: &Rent) -> u64 {
+        reserve
+            .lamports()
+            .saturating_sub(rent.minimum_balance(reserve.data_len()))
+    }
+
+    /// Move `lamports` from `reserve` to `to`.
+    ///
+    /// # Errors
+    /// * `InsufficientBalance` if that would leave `reserve` below its
+    ///   rent-exempt minimum
+    pub fn pay_out(reserve: &AccountInfo, to: &AccountInfo, lamports: u64, rent: &Rent) -> Result<()> {
+        require!(
+            lamports <= Self::available(reserve, rent),
+            PrivacyError::InsufficientBalance
+        );
+        let credited = to
+            .lamports()
+            .checked_add(lamports)
+            .ok_or(error!(PrivacyError::ArithmeticOverflow))?;
+
+        **reserve.try_borrow_mut_lamports()? -= lamports;
+        **to.try_borrow_mut_lamports()? = credited;
+        Ok(())
+    }
+}
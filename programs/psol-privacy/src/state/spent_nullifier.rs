@@ -16,6 +16,7 @@
 //! - Each nullifier uses ~100 bytes (rent-exempt minimum)
 
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
 
 /// Spent nullifier marker account.
 ///
@@ -38,6 +39,9 @@ pub struct SpentNullifier {
     /// This is hash(nullifier_preimage, ...) NOT the raw preimage
     pub nullifier_hash: [u8; 32],
 
+    /// Merkle root the withdrawal proved against (audit trail)
+    pub root_used: [u8; 32],
+
     /// Unix timestamp when nullifier was spent
     pub spent_at: i64,
 
@@ -53,6 +57,7 @@ impl SpentNullifier {
     pub const LEN: usize = 8  // discriminator
         + 32                  // pool
         + 32                  // nullifier_hash  
+        + 32                  // root_used
         + 8                   // spent_at
         + 8                   // spent_slot
         + 1;                  // bump
@@ -62,12 +67,14 @@ impl SpentNullifier {
         &mut self,
         pool: Pubkey,
         nullifier_hash: [u8; 32],
+        root_used: [u8; 32],
         spent_at: i64,
         spent_slot: u64,
         bump: u8,
     ) {
         self.pool = pool;
         self.nullifier_hash = nullifier_hash;
+        self.root_used = root_used;
         self.spent_at = spent_at;
         self.spent_slot = spent_slot;
         self.bump = bump;
@@ -108,24 +115,137 @@ impl SpentNullifier {
 
     /// Seed prefix for PDA derivation
     pub const SEED_PREFIX: &'static [u8] = b"nullifier";
+
+    /// Whether `account` holds a live spent-nullifier record, without
+    /// deserializing it.
+    ///
+    /// Callers must still check that `account.key` is the expected PDA
+    /// (see `find_pda`). Returns `false` for never-created accounts
+    /// (system-owned, empty) and closed accounts (no lamports or data).
+    ///
+    /// # Errors
+    /// * `AccountBorrowFailed` if the account data is mutably borrowed;
+    ///   its contents are unknown, so it is never reported unspent
+    pub fn exists(account: &AccountInfo) -> Result<bool> {
+        if account.owner != &crate::ID || account.lamports() == 0 {
+            return Ok(false);
+        }
+        let data = account.try_borrow_data()?;
+        Ok(data.starts_with(&SpentNullifier::DISCRIMINATOR))
+    }
 }
 
-// ============================================================================
-// DEPRECATED: Old NullifierSet pattern (kept for reference, DO NOT USE)
-// ============================================================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::PrivacyError;
+    use crate::instructions::withdraw::require_nullifier_unspent;
 
-/// DEPRECATED: Legacy nullifier set using vector storage.
-/// DO NOT USE - kept only for migration reference.
-///
-/// Problems with this approach:
-/// 1. O(n) lookup - doesn't scale
-/// 2. Limited capacity - must be sized at init
-/// 3. Account size grows unbounded
-#[account]
-#[deprecated(note = "Use SpentNullifier PDA pattern instead")]
-pub struct LegacyNullifierSet {
-    pub pool: Pubkey,
-    pub max_capacity: u32,
-    pub count: u64,
-    pub nullifiers: Vec<[u8; 32]>,
+    #[test]
+    fn test_initialize_records_root_used() {
+        let mut spent =
+            SpentNullifier::try_deserialize_unchecked(&mut vec![0u8; SpentNullifier::LEN].as_slice())
+                .unwrap();
+        let merkle_root = [7u8; 32];
+        spent.initialize(Pubkey::new_unique(), [9u8; 32], merkle_root, 1_700_000_000, 42, 255);
+
+        assert_eq!(spent.root_used, merkle_root);
+        assert_eq!(spent.try_to_vec().unwrap().len() + 8, SpentNullifier::LEN);
+    }
+
+    fn account_info<'a>(
+        key: &'a Pubkey,
+        owner: &'a Pubkey,
+        lamports: &'a mut u64,
+        data: &'a mut [u8],
+    ) -> AccountInfo<'a> {
+        AccountInfo::new(key, false, false, lamports, data, owner, false, 0)
+    }
+
+    #[test]
+    fn test_exists_for_spent_nullifier() {
+        let key = Pubkey::new_unique();
+        let mut lamports = 1_000_000;
+        let mut data = vec![0u8; SpentNullifier::LEN];
+        data[..8].copy_from_slice(&SpentNullifier::DISCRIMINATOR);
+
+        let info = account_info(&key, &crate::ID, &mut lamports, &mut data);
+        assert!(SpentNullifier::exists(&info).unwrap());
+    }
+
+    #[test]
+    fn test_double_spend_rejected_once_record_written() {
+        let pool = Pubkey::new_unique();
+        let nullifier_hash = [9u8; 32];
+        let (key, bump) = SpentNullifier::find_pda(&crate::ID, &pool, &nullifier_hash);
+        // Every other nullifier, or the same one in another pool, has its own PDA
+        assert_ne!(key, SpentNullifier::find_pda(&crate::ID, &pool, &[8u8; 32]).0);
+        assert_ne!(key, SpentNullifier::find_pda(&crate::ID, &Pubkey::new_unique(), &nullifier_hash).0);
+
+        let mut record =
+            SpentNullifier::try_deserialize_unchecked(&mut vec![0u8; SpentNullifier::LEN].as_slice())
+                .unwrap();
+        record.initialize(pool, nullifier_hash, [7u8; 32], 1_700_000_000, 42, bump);
+        let mut lamports = 1_000_000;
+        let mut data = vec![0u8; SpentNullifier::LEN];
+        record.try_serialize(&mut data.as_mut_slice()).unwrap();
+
+        // The first withdrawal wrote the record; the second is rejected
+        let info = account_info(&key, &crate::ID, &mut lamports, &mut data);
+        let err = require_nullifier_unspent(&info).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::NullifierAlreadySpent));
+        let stored = SpentNullifier::try_deserialize(&mut &info.data.borrow()[..]).unwrap();
+        assert_eq!((stored.pool, stored.nullifier_hash), (pool, nullifier_hash));
+    }
+
+    #[test]
+    fn test_exists_false_when_never_created() {
+        let key = Pubkey::new_unique();
+        let system_program = anchor_lang::system_program::ID;
+        let mut lamports = 0;
+        let mut data = vec![];
+
+        let info = account_info(&key, &system_program, &mut lamports, &mut data);
+        assert!(!SpentNullifier::exists(&info).unwrap());
+    }
+
+    #[test]
+    fn test_exists_false_when_closed_or_foreign() {
+        let key = Pubkey::new_unique();
+
+        // Closed in this transaction: still program-owned, lamports drained
+        let mut lamports = 0;
+        let mut data = vec![0u8; SpentNullifier::LEN];
+        data[..8].copy_from_slice(&SpentNullifier::DISCRIMINATOR);
+        let info = account_info(&key, &crate::ID, &mut lamports, &mut data);
+        assert!(!SpentNullifier::exists(&info).unwrap());
+
+        // Closed and wiped
+        let mut lamports = 1;
+        let mut data = vec![0u8; SpentNullifier::LEN];
+        let info = account_info(&key, &crate::ID, &mut lamports, &mut data);
+        assert!(!SpentNullifier::exists(&info).unwrap());
+
+        // Right discriminator, wrong owner
+        let other = Pubkey::new_unique();
+        let mut lamports = 1_000_000;
+        let mut data = vec![0u8; SpentNullifier::LEN];
+        data[..8].copy_from_slice(&SpentNullifier::DISCRIMINATOR);
+        let info = account_info(&key, &other, &mut lamports, &mut data);
+        assert!(!SpentNullifier::exists(&info).unwrap());
+    }
+
+    #[test]
+    fn test_exists_fails_closed_while_borrowed() {
+        let key = Pubkey::new_unique();
+        let mut lamports = 1_000_000;
+        let mut data = vec![0u8; SpentNullifier::LEN];
+        data[..8].copy_from_slice(&SpentNullifier::DISCRIMINATOR);
+        let info = account_info(&key, &crate::ID, &mut lamports, &mut data);
+
+        // Contents unreadable: an error, never "unspent"
+        let _borrow = info.try_borrow_mut_data().unwrap();
+        assert!(SpentNullifier::exists(&info).is_err());
+        assert!(require_nullifier_unspent(&info).is_err());
+    }
 }
@@ -10,15 +10,27 @@
 
 use anchor_lang::prelude::*;
 
+use crate::crypto::{
+    compress_g1, decompress_g1, is_g1_identity, is_g2_identity, validate_g1_point,
+    validate_g2_point, CompressedG1Point, Curve, ZkPublicInputs,
+};
+use crate::error::PrivacyError;
+use crate::instructions::set_verification_key::{MAX_IC_POINTS, MIN_IC_POINTS};
+
 /// Groth16 Verification Key account.
 ///
 /// Stores the VK in a format compatible with BN254/alt_bn128 curves.
 ///
 /// PDA Seeds: `[b"verification_key", pool_config.key().as_ref()]`
 ///
+/// An optional secondary VK for circuit migrations lives at
+/// `[b"verification_key_next", pool_config.key().as_ref()]`.
+///
 /// # Point Encodings
 /// - G1 points: 64 bytes (32 bytes x, 32 bytes y) - uncompressed
 /// - G2 points: 128 bytes (64 bytes x, 64 bytes y) - uncompressed
+/// - IC points when `compressed_ic`: 32 bytes (see `compress_g1`),
+///   decompressed on every load at the cost of one Fp square root each
 ///
 /// # Note
 /// For BN254, G1 and G2 points use different field representations.
@@ -56,6 +68,22 @@ pub struct VerificationKeyAccount {
 
     /// PDA bump seed
     pub bump: u8,
+
+    /// `Curve` as u8 (0 = BN254). Trails the account so pre-existing VK
+    /// accounts, which have zeroed slack here, read as BN254.
+    pub curve: u8,
+
+    /// Opaque identifier of the circuit this key belongs to (e.g. a hash of
+    /// the proving key), so wallets can fetch a matching prover. Not
+    /// interpreted by the program; zero = unknown.
+    pub circuit_id: [u8; 32],
+
+    /// IC points live in `vk_ic_compressed` and `vk_ic` is empty. Trails
+    /// the account like `curve`, so existing accounts read as uncompressed.
+    pub compressed_ic: bool,
+
+    /// Compressed IC points when `compressed_ic`
+    pub vk_ic_compressed: Vec<CompressedG1Point>,
 }
 
 impl VerificationKeyAccount {
@@ -68,6 +96,13 @@ impl VerificationKeyAccount {
     /// For a circuit with N public inputs, you need N+1 IC points.
     /// Typical withdrawal circuit has ~6 public inputs → 7 IC points.
     pub fn space(max_ic_points: u8) -> usize {
+        Self::space_with(max_ic_points, false)
+    }
+
+    /// `space` for IC points stored compressed (32 bytes each) or not.
+    pub fn space_with(max_ic_points: u8, compressed_ic: bool) -> usize {
+        let ic_bytes = Self::ic_point_size(compressed_ic) * max_ic_points as usize;
+
         8                                   // discriminator
             + 32                            // pool
             + 64                            // vk_alpha_g1
@@ -75,14 +110,49 @@ impl VerificationKeyAccount {
             + 128                           // vk_gamma_g2
             + 128                           // vk_delta_g2
             + 1                             // vk_ic_len
-            + 4 + (64 * max_ic_points as usize) // vk_ic (vec)
+            + 4                             // vk_ic (vec)
             + 1                             // is_initialized
             + 1                             // bump
+            + 1                             // curve
+            + 32                            // circuit_id
+            + 1                             // compressed_ic
+            + 4                             // vk_ic_compressed (vec)
+            + ic_bytes                      // IC points in either vec
     }
 
-    /// Default max IC points for withdrawal circuit
-    /// 6 public inputs + 1 = 7
-    pub const DEFAULT_MAX_IC_POINTS: u8 = 10;
+    /// Bytes per stored IC point.
+    pub fn ic_point_size(compressed_ic: bool) -> usize {
+        if compressed_ic {
+            32
+        } else {
+            64
+        }
+    }
+
+    /// IC capacity allocated for new VK accounts.
+    ///
+    /// `MAX_PUBLIC_INPUTS + 1`, i.e. `set_verification_key::MAX_IC_POINTS`,
+    /// so any VK that passes validation fits.
+    pub const DEFAULT_MAX_IC_POINTS: u8 = MAX_IC_POINTS as u8;
+
+    /// Number of IC points an account of `data_len` bytes can hold.
+    pub fn ic_capacity(data_len: usize, compressed_ic: bool) -> usize {
+        data_len.saturating_sub(Self::space(0)) / Self::ic_point_size(compressed_ic)
+    }
+
+    /// Reject IC vectors that would not fit in an account of `data_len`
+    /// bytes (e.g. pools created when the allocation was smaller).
+    ///
+    /// # Errors
+    /// * `InputTooLarge` if `ic_len` exceeds the account's capacity
+    pub fn require_ic_fits(ic_len: usize, data_len: usize, compressed_ic: bool) -> Result<()> {
+        let capacity = Self::ic_capacity(data_len, compressed_ic);
+        if ic_len > capacity {
+            msg!("VK IC length {} exceeds account capacity {}", ic_len, capacity);
+            return Err(error!(PrivacyError::InputTooLarge));
+        }
+        Ok(())
+    }
 
     /// Initialize the VK account (empty, not yet configured)
     pub fn initialize(&mut self, pool: Pubkey, bump: u8) {
@@ -95,6 +165,10 @@ impl VerificationKeyAccount {
         self.vk_ic = Vec::new();
         self.is_initialized = false;
         self.bump = bump;
+        self.curve = Curve::Bn254 as u8;
+        self.circuit_id = [0u8; 32];
+        self.compressed_ic = false;
+        self.vk_ic_compressed = Vec::new();
     }
 
     /// Set the verification key data.
@@ -113,16 +187,102 @@ impl VerificationKeyAccount {
         self.vk_beta_g2 = beta_g2;
         self.vk_gamma_g2 = gamma_g2;
         self.vk_delta_g2 = delta_g2;
-        self.vk_ic_len = ic.len() as u8;
-        self.vk_ic = ic;
+        self.set_ic(ic);
         self.is_initialized = true;
     }
 
+    pub fn set_circuit_id(&mut self, circuit_id: [u8; 32]) {
+        self.circuit_id = circuit_id;
+    }
+
+    /// Replace only the IC points, keeping α, β, γ and δ. Stored
+    /// compressed when `compressed_ic` is set.
+    pub fn set_ic(&mut self, ic: Vec<[u8; 64]>) {
+        self.vk_ic_len = ic.len() as u8;
+        if self.compressed_ic {
+            self.vk_ic_compressed = ic.iter().map(compress_g1).collect();
+            self.vk_ic = Vec::new();
+        } else {
+            self.vk_ic = ic;
+            self.vk_ic_compressed = Vec::new();
+        }
+    }
+
+    /// The IC points, decompressed if stored compressed.
+    ///
+    /// # Errors
+    /// * `InvalidProof` if a compressed point does not decompress
+    pub fn ic_points(&self) -> Result<Vec<[u8; 64]>> {
+        if self.compressed_ic {
+            self.vk_ic_compressed.iter().map(decompress_g1).collect()
+        } else {
+            Ok(self.vk_ic.clone())
+        }
+    }
+
+    /// Re-encode the stored IC points compressed or uncompressed. The key
+    /// itself is unchanged: compression must round-trip every point.
+    ///
+    /// # Errors
+    /// * `InvalidProof` if a stored point does not survive the round trip
+    pub fn set_ic_compression(&mut self, compressed_ic: bool) -> Result<()> {
+        let ic = self.ic_points()?;
+        for point in &ic {
+            require!(
+                decompress_g1(&compress_g1(point))? == *point,
+                PrivacyError::InvalidProof
+            );
+        }
+        self.compressed_ic = compressed_ic;
+        self.set_ic(ic);
+        Ok(())
+    }
+
+    /// Swap VK contents with `other`, keeping each account's pool, bump and
+    /// IC storage mode (the accounts may be sized for different modes).
+    ///
+    /// Used to promote the secondary VK during a circuit migration.
+    ///
+    /// # Errors
+    /// * `InvalidProof` if a compressed IC point does not decompress
+    pub fn swap_keys(&mut self, other: &mut Self) -> Result<()> {
+        let (ic, other_ic) = (self.ic_points()?, other.ic_points()?);
+        std::mem::swap(&mut self.vk_alpha_g1, &mut other.vk_alpha_g1);
+        std::mem::swap(&mut self.vk_beta_g2, &mut other.vk_beta_g2);
+        std::mem::swap(&mut self.vk_gamma_g2, &mut other.vk_gamma_g2);
+        std::mem::swap(&mut self.vk_delta_g2, &mut other.vk_delta_g2);
+        std::mem::swap(&mut self.is_initialized, &mut other.is_initialized);
+        std::mem::swap(&mut self.curve, &mut other.curve);
+        std::mem::swap(&mut self.circuit_id, &mut other.circuit_id);
+        self.set_ic(other_ic);
+        other.set_ic(ic);
+        Ok(())
+    }
+
     /// Check if VK is properly initialized
     pub fn is_valid(&self) -> bool {
         self.is_initialized && self.vk_ic_len > 0
     }
 
+    /// Require the stored IC vector (`vk_ic`, or `vk_ic_compressed` when
+    /// `compressed_ic`) to hold exactly `vk_ic_len` points. A mismatch (e.g.
+    /// from a partial write) leaves verification undefined.
+    ///
+    /// # Errors
+    /// * `VkIcLengthMismatch` if they disagree
+    pub fn require_ic_len_consistent(&self) -> Result<()> {
+        let stored = if self.compressed_ic {
+            self.vk_ic_compressed.len()
+        } else {
+            self.vk_ic.len()
+        };
+        require!(
+            stored == self.vk_ic_len as usize,
+            PrivacyError::VkIcLengthMismatch
+        );
+        Ok(())
+    }
+
     /// Get expected number of public inputs based on IC length
     pub fn expected_public_inputs(&self) -> u8 {
         if self.vk_ic_len > 0 {
@@ -137,6 +297,8 @@ impl VerificationKeyAccount {
 /// This is a helper struct for verification logic.
 #[derive(Clone, Debug)]
 pub struct VerificationKey {
+    /// `Curve` as u8; selects the verifier
+    pub curve: u8,
     pub alpha_g1: [u8; 64],
     pub beta_g2: [u8; 128],
     pub gamma_g2: [u8; 128],
@@ -144,14 +306,280 @@ pub struct VerificationKey {
     pub ic: Vec<[u8; 64]>,
 }
 
-impl From<&VerificationKeyAccount> for VerificationKey {
-    fn from(account: &VerificationKeyAccount) -> Self {
-        VerificationKey {
+impl TryFrom<&VerificationKeyAccount> for VerificationKey {
+    type Error = anchor_lang::error::Error;
+
+    /// # Errors
+    /// * `VkIcLengthMismatch` if the stored IC and `vk_ic_len` disagree
+    /// * `InvalidProof` if a compressed IC point does not decompress
+    fn try_from(account: &VerificationKeyAccount) -> Result<Self> {
+        account.require_ic_len_consistent()?;
+        Ok(VerificationKey {
+            curve: account.curve,
             alpha_g1: account.vk_alpha_g1,
             beta_g2: account.vk_beta_g2,
             gamma_g2: account.vk_gamma_g2,
             delta_g2: account.vk_delta_g2,
-            ic: account.vk_ic.clone(),
+            ic: account.ic_points()?,
+        })
+    }
+}
+
+/// Canonical VK payload for `set_verification_key`.
+///
+/// Off-chain tooling builds this once, checks it with `validate()` and
+/// converts it into the instruction arguments. The handler runs the same
+/// `validate()` on-chain, so both sides agree on what a valid VK is.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct VerificationKeyData {
+    pub alpha_g1: [u8; 64],
+    pub beta_g2: [u8; 128],
+    pub gamma_g2: [u8; 128],
+    pub delta_g2: [u8; 128],
+    pub ic: Vec<[u8; 64]>,
+    /// Stored as `VerificationKeyAccount::circuit_id`
+    pub circuit_id: [u8; 32],
+}
+
+impl TryFrom<&VerificationKeyAccount> for VerificationKeyData {
+    type Error = anchor_lang::error::Error;
+
+    /// # Errors
+    /// * `InvalidProof` if a compressed IC point does not decompress
+    fn try_from(account: &VerificationKeyAccount) -> Result<Self> {
+        Ok(VerificationKeyData {
+            alpha_g1: account.vk_alpha_g1,
+            beta_g2: account.vk_beta_g2,
+            gamma_g2: account.vk_gamma_g2,
+            delta_g2: account.vk_delta_g2,
+            ic: account.ic_points()?,
+            circuit_id: account.circuit_id,
+        })
+    }
+}
+
+impl VerificationKeyData {
+    /// Structural validation of the VK points.
+    ///
+    /// Checks IC length against the withdrawal circuit, that α, β, γ, δ are
+    /// not the identity, and that every point is well-formed.
+    pub fn validate(&self) -> Result<()> {
+        let ic_len = self.ic.len();
+        require!(ic_len >= MIN_IC_POINTS, PrivacyError::InvalidPublicInputs);
+        require!(ic_len <= MAX_IC_POINTS, PrivacyError::InputTooLarge);
+        require!(
+            ic_len == ZkPublicInputs::COUNT + 1,
+            PrivacyError::InvalidPublicInputs
+        );
+
+        require!(
+            !is_g1_identity(&self.alpha_g1),
+            PrivacyError::VerificationKeyNotSet
+        );
+        validate_g1_point(&self.alpha_g1).map_err(|_| error!(PrivacyError::InvalidProof))?;
+
+        for g2 in [&self.beta_g2, &self.gamma_g2, &self.delta_g2] {
+            require!(!is_g2_identity(g2), PrivacyError::VerificationKeyNotSet);
+            validate_g2_point(g2).map_err(|_| error!(PrivacyError::InvalidProof))?;
+        }
+
+        for (i, ic_point) in self.ic.iter().enumerate() {
+            validate_g1_point(ic_point).map_err(|_| {
+                msg!("IC[{}] failed validation", i);
+                error!(PrivacyError::InvalidProof)
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<VerificationKeyData> for crate::instruction::SetVerificationKey {
+    fn from(data: VerificationKeyData) -> Self {
+        crate::instruction::SetVerificationKey {
+            vk_alpha_g1: data.alpha_g1,
+            vk_beta_g2: data.beta_g2,
+            vk_gamma_g2: data.gamma_g2,
+            vk_delta_g2: data.delta_g2,
+            vk_ic: data.ic,
+            circuit_id: data.circuit_id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::G1_GENERATOR;
+
+    fn sample_vk() -> VerificationKeyData {
+        VerificationKeyData {
+            alpha_g1: G1_GENERATOR,
+            beta_g2: [1u8; 128],
+            gamma_g2: [2u8; 128],
+            delta_g2: [3u8; 128],
+            ic: vec![G1_GENERATOR; ZkPublicInputs::COUNT + 1],
+            circuit_id: [4u8; 32],
+        }
+    }
+
+    #[test]
+    fn test_vk_data_borsh_roundtrip() {
+        let vk = sample_vk();
+        let bytes = vk.try_to_vec().unwrap();
+        let decoded = VerificationKeyData::try_from_slice(&bytes).unwrap();
+        assert_eq!(vk, decoded);
+    }
+
+    #[test]
+    fn test_vk_data_validate_accepts_well_formed() {
+        assert!(sample_vk().validate().is_ok());
+    }
+
+    #[test]
+    fn test_vk_data_validate_rejects_off_curve_ic() {
+        let mut vk = sample_vk();
+        // (1, 3) is not on y² = x³ + 3
+        let mut off_curve = G1_GENERATOR;
+        off_curve[63] = 3;
+        vk.ic[2] = off_curve;
+        assert!(vk.validate().is_err());
+    }
+
+    #[test]
+    fn test_vk_data_validate_rejects_wrong_ic_len() {
+        let mut vk = sample_vk();
+        vk.ic.pop();
+        assert!(vk.validate().is_err());
+    }
+
+    #[test]
+    fn test_vk_data_into_instruction_args() {
+        let vk = sample_vk();
+        let ix: crate::instruction::SetVerificationKey = vk.clone().into();
+        assert_eq!(ix.vk_alpha_g1, vk.alpha_g1);
+        assert_eq!(ix.vk_ic, vk.ic);
+        assert_eq!(ix.circuit_id, vk.circuit_id);
+    }
+
+    #[test]
+    fn test_default_capacity_matches_validation_cap() {
+        let data_len = VerificationKeyAccount::space(VerificationKeyAccount::DEFAULT_MAX_IC_POINTS);
+        assert_eq!(VerificationKeyAccount::ic_capacity(data_len, false), MAX_IC_POINTS);
+    }
+
+    #[test]
+    fn test_public_input_limits_consistent() {
+        use crate::crypto::{MAX_PUBLIC_INPUTS, MIN_PUBLIC_INPUTS};
+
+        assert_eq!(MAX_IC_POINTS, MAX_PUBLIC_INPUTS + 1);
+        assert_eq!(MIN_IC_POINTS, MIN_PUBLIC_INPUTS + 1);
+        assert_eq!(usize::from(VerificationKeyAccount::DEFAULT_MAX_IC_POINTS), MAX_IC_POINTS);
+        assert!((MIN_PUBLIC_INPUTS..=MAX_PUBLIC_INPUTS).contains(&ZkPublicInputs::COUNT));
+
+        // The withdrawal key passes validation and fits a default account
+        let data_len = VerificationKeyAccount::space(VerificationKeyAccount::DEFAULT_MAX_IC_POINTS);
+        assert!(VerificationKeyAccount::require_ic_fits(ZkPublicInputs::COUNT + 1, data_len, false).is_ok());
+        assert!(VerificationKeyAccount::require_ic_fits(MAX_IC_POINTS, data_len, false).is_ok());
+        assert!(VerificationKeyAccount::require_ic_fits(MAX_IC_POINTS + 1, data_len, false).is_err());
+        assert!(sample_vk().validate().is_ok());
+    }
+
+    #[test]
+    fn test_space_matches_serialized_len_with_full_ic() {
+        use crate::state::serialized_len;
+
+        let space = VerificationKeyAccount::space(VerificationKeyAccount::DEFAULT_MAX_IC_POINTS);
+        let mut vk = VerificationKeyAccount::try_deserialize_unchecked(&mut vec![0u8; space].as_slice())
+            .unwrap();
+        vk.initialize(Pubkey::new_unique(), 255);
+        assert!(serialized_len(&vk) <= space);
+
+        vk.set_vk(G1_GENERATOR, [1u8; 128], [2u8; 128], [3u8; 128], vec![G1_GENERATOR; MAX_IC_POINTS]);
+        assert_eq!(serialized_len(&vk), space);
+
+        let mut vk = VerificationKeyAccount::try_deserialize_unchecked(&mut vec![0u8; space].as_slice())
+            .unwrap();
+        vk.initialize(Pubkey::new_unique(), 255);
+        vk.set_ic(vec![G1_GENERATOR; MAX_IC_POINTS]);
+        assert_eq!(serialized_len(&vk), space);
+    }
+
+    #[test]
+    fn test_compressed_ic_halves_storage() {
+        use crate::crypto::{g1_scalar_mul, Scalar};
+        use crate::state::serialized_len;
+
+        let max = VerificationKeyAccount::DEFAULT_MAX_IC_POINTS;
+        let (full, compressed) = (
+            VerificationKeyAccount::space_with(max, false),
+            VerificationKeyAccount::space_with(max, true),
+        );
+        assert_eq!(full - compressed, 32 * max as usize);
+        assert_eq!(VerificationKeyAccount::ic_capacity(compressed, true), MAX_IC_POINTS);
+        assert!(VerificationKeyAccount::require_ic_fits(MAX_IC_POINTS, compressed, false).is_err());
+
+        let ic: Vec<[u8; 64]> = (1..=MAX_IC_POINTS as u64)
+            .map(|k| g1_scalar_mul(&G1_GENERATOR, &Scalar::from_u64(k)).unwrap())
+            .collect();
+        let mut vk = VerificationKeyAccount::try_deserialize_unchecked(&mut vec![0u8; full].as_slice())
+            .unwrap();
+        vk.initialize(Pubkey::new_unique(), 255);
+        vk.set_vk(G1_GENERATOR, [1u8; 128], [2u8; 128], [3u8; 128], ic.clone());
+        assert_eq!(serialized_len(&vk), full);
+
+        vk.set_ic_compression(true).unwrap();
+        assert_eq!(serialized_len(&vk), compressed);
+        assert_eq!(vk.ic_points().unwrap(), ic);
+        assert!(vk.require_ic_len_consistent().is_ok());
+
+        // New IC writes keep the mode; switching back restores the bytes
+        vk.set_ic(ic[..2].to_vec());
+        assert_eq!((vk.vk_ic.len(), vk.vk_ic_compressed.len()), (0, 2));
+        vk.set_ic(ic.clone());
+        vk.set_ic_compression(false).unwrap();
+        assert_eq!((&vk.vk_ic, vk.vk_ic_compressed.len()), (&ic, 0));
+        assert_eq!(serialized_len(&vk), full);
+    }
+
+    #[test]
+    fn test_swap_keys_keeps_storage_modes() {
+        let space = VerificationKeyAccount::space(VerificationKeyAccount::DEFAULT_MAX_IC_POINTS);
+        let new_vk = || {
+            let mut vk = VerificationKeyAccount::try_deserialize_unchecked(&mut vec![0u8; space].as_slice())
+                .unwrap();
+            vk.initialize(Pubkey::new_unique(), 255);
+            vk
+        };
+        let (mut primary, mut next) = (new_vk(), new_vk());
+        primary.set_vk(G1_GENERATOR, [1u8; 128], [2u8; 128], [3u8; 128], vec![G1_GENERATOR; 3]);
+        primary.set_ic_compression(true).unwrap();
+        let ic = vec![crate::crypto::negate_g1(&G1_GENERATOR).unwrap(); 7];
+        next.set_vk(G1_GENERATOR, [4u8; 128], [5u8; 128], [6u8; 128], ic.clone());
+
+        primary.swap_keys(&mut next).unwrap();
+        assert!(primary.compressed_ic && !next.compressed_ic);
+        assert_eq!((primary.vk_beta_g2, primary.ic_points().unwrap()), ([4u8; 128], ic));
+        assert_eq!(primary.vk_ic_compressed.len(), 7);
+        assert_eq!(next.vk_ic, vec![G1_GENERATOR; 3]);
+        assert_eq!(next.vk_ic_len, 3);
+    }
+
+    #[test]
+    fn test_ic_at_capacity_accepted() {
+        let data_len = VerificationKeyAccount::space(10);
+        assert!(VerificationKeyAccount::require_ic_fits(10, data_len, false).is_ok());
+        assert!(VerificationKeyAccount::require_ic_fits(ZkPublicInputs::COUNT + 1, data_len, false).is_ok());
+    }
+
+    #[test]
+    fn test_ic_above_capacity_rejected() {
+        let data_len = VerificationKeyAccount::space(10);
+        match VerificationKeyAccount::require_ic_fits(11, data_len, false) {
+            Err(anchor_lang::error::Error::AnchorError(e)) => {
+                assert_eq!(e.error_code_number, 6027); // InputTooLarge
+            }
+            other => panic!("expected InputTooLarge, got {:?}", other),
         }
     }
 }
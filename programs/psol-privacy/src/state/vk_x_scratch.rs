@@ -0,0 +1,239 @@
+//! Scratch account for multi-instruction vk_x accumulation
+//!
+//! `compute_vk_x` performs one scalar multiplication and one addition per
+//! public input. Circuits with many public inputs can exceed the compute
+//! budget of a single instruction, so the linear combination can instead be
+//! accumulated here chunk by chunk and consumed by `withdraw_with_vk_x`.
+//!
+//! An accumulation always starts from IC[0] of the VK it is run against and
+//! records that VK's digest, so a VK replaced mid-way (`set_vk`,
+//! `update_vk_ic`) makes the scratch stale rather than silently mixing two
+//! keys. The withdrawal circuit (6 inputs) stays single-pass with `withdraw`.
+
+use anchor_lang::prelude::*;
+use solana_program::keccak;
+
+use crate::crypto::{accumulate_vk_x, Scalar, MIN_PUBLIC_INPUTS};
+use crate::error::PrivacyError;
+use crate::state::VerificationKey;
+
+/// Partial vk_x sum owned by a single submitter.
+///
+/// PDA Seeds: `[b"vk_x_scratch", pool_config.key().as_ref(), owner.key().as_ref()]`
+#[account]
+pub struct VkXScratch {
+    /// Reference to parent pool
+    pub pool: Pubkey,
+
+    /// Account allowed to feed chunks into this scratch
+    pub owner: Pubkey,
+
+    /// Partial sum IC[0] + Σ input[i] * IC[i+1] for inputs absorbed so far
+    pub acc: [u8; 64],
+
+    /// Number of public inputs absorbed so far
+    pub next_input: u8,
+
+    /// Running Keccak256 digest of absorbed inputs, so a consumer can bind
+    /// the finished vk_x to the exact public inputs it expects
+    pub inputs_digest: [u8; 32],
+
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// `vk_digest` of the verification key the accumulation started from
+    pub vk_hash: [u8; 32],
+}
+
+impl VkXScratch {
+    pub const LEN: usize = 8  // discriminator
+        + 32                  // pool
+        + 32                  // owner
+        + 64                  // acc
+        + 1                   // next_input
+        + 32                  // inputs_digest
+        + 1                   // bump
+        + 32;                 // vk_hash
+
+    /// Maximum public inputs absorbed per instruction
+    pub const MAX_CHUNK: usize = 4;
+
+    pub fn initialize(&mut self, pool: Pubkey, owner: Pubkey, bump: u8) {
+        self.pool = pool;
+        self.owner = owner;
+        self.bump = bump;
+        self.reset();
+    }
+
+    /// Discard any partial sum; the next `absorb` starts from IC[0].
+    pub fn reset(&mut self) {
+        self.acc = [0u8; 64];
+        self.next_input = 0;
+        self.inputs_digest = [0u8; 32];
+        self.vk_hash = [0u8; 32];
+    }
+
+    /// Digest binding an accumulation to every point of `vk`.
+    pub fn vk_digest(vk: &VerificationKey) -> [u8; 32] {
+        let mut parts: Vec<&[u8]> = vec![
+            std::slice::from_ref(&vk.curve),
+            &vk.alpha_g1,
+            &vk.beta_g2,
+            &vk.gamma_g2,
+            &vk.delta_g2,
+        ];
+        parts.extend(vk.ic.iter().map(|point| &point[..]));
+        keccak::hashv(&parts).to_bytes()
+    }
+
+    /// Absorb the next chunk of public inputs for `vk`. The first chunk
+    /// after `initialize`/`reset` seeds the sum with IC[0] and binds the
+    /// scratch to `vk`.
+    ///
+    /// # Errors
+    /// * `LimitExceeded` if the chunk is larger than `MAX_CHUNK`
+    /// * `InvalidPublicInputs` if the chunk runs past the end of `vk.ic`
+    /// * `VkXScratchStale` if `vk` is not the key the accumulation started from
+    pub fn absorb(&mut self, vk: &VerificationKey, inputs: &[Scalar]) -> Result<()> {
+        require!(inputs.len() <= Self::MAX_CHUNK, PrivacyError::LimitExceeded);
+        let ic = &vk.ic;
+
+        let start = self.next_input as usize;
+        let end = start + inputs.len();
+        require!(end < ic.len(), PrivacyError::InvalidPublicInputs);
+
+        let vk_hash = Self::vk_digest(vk);
+        let base = if start == 0 {
+            ic[0]
+        } else {
+            require!(self.vk_hash == vk_hash, PrivacyError::VkXScratchStale);
+            self.acc
+        };
+        self.acc = accumulate_vk_x(&base, &ic[start + 1..end + 1], inputs)?;
+        self.vk_hash = vk_hash;
+
+        if start == 0 {
+            self.inputs_digest = [0u8; 32];
+        }
+        self.inputs_digest = Self::digest_inputs(self.inputs_digest, inputs);
+        self.next_input = end as u8;
+        Ok(())
+    }
+
+    /// Whether every public input for an IC vector of `ic_len` has been
+    /// absorbed. Never true for an IC vector binding no inputs.
+    pub fn is_complete(&self, ic_len: usize) -> bool {
+        ic_len > MIN_PUBLIC_INPUTS && self.next_input as usize == ic_len - 1
+    }
+
+    /// The finished vk_x for `vk` and exactly `inputs`, in order.
+    ///
+    /// # Errors
+    /// * `VkXScratchStale` if the accumulation ran against another key
+    /// * `VkXScratchIncomplete` unless every input of `vk` was absorbed
+    /// * `InvalidPublicInputs` if the absorbed inputs are not `inputs`
+    pub fn finished_vk_x(&self, vk: &VerificationKey, inputs: &[Scalar]) -> Result<[u8; 64]> {
+        require!(self.vk_hash == Self::vk_digest(vk), PrivacyError::VkXScratchStale);
+        require!(self.is_complete(vk.ic.len()), PrivacyError::VkXScratchIncomplete);
+        require!(
+            self.inputs_digest == Self::digest_inputs([0u8; 32], inputs),
+            PrivacyError::InvalidPublicInputs
+        );
+        Ok(self.acc)
+    }
+
+    /// Running Keccak256 digest of `inputs`, continuing from `digest`.
+    fn digest_inputs(mut digest: [u8; 32], inputs: &[Scalar]) -> [u8; 32] {
+        for input in inputs {
+            digest = keccak::hashv(&[&digest, input.as_bytes()]).to_bytes();
+        }
+        digest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::groth16_verifier::test_fixtures::synthetic_vk;
+    use crate::crypto::{compute_vk_x, g1_scalar_mul, G1_GENERATOR};
+
+    fn new_scratch() -> VkXScratch {
+        let data = vec![0u8; VkXScratch::LEN];
+        let mut scratch = VkXScratch::try_deserialize_unchecked(&mut data.as_slice()).unwrap();
+        scratch.initialize(Pubkey::new_unique(), Pubkey::new_unique(), 255);
+        scratch
+    }
+
+    fn vk_with_ic(ic: Vec<[u8; 64]>) -> VerificationKey {
+        VerificationKey { ic, ..synthetic_vk(1) }
+    }
+
+    #[test]
+    fn test_scratch_chunks_match_single_pass() {
+        let vk = vk_with_ic(
+            (1..=7u64)
+                .map(|k| g1_scalar_mul(&G1_GENERATOR, &Scalar::from_u64(k)).unwrap())
+                .collect(),
+        );
+        let inputs: Vec<Scalar> = (20..26u64).map(Scalar::from_u64).collect();
+
+        // No reset needed: the first chunk seeds the sum with IC[0]
+        let mut scratch = new_scratch();
+        scratch.absorb(&vk, &inputs[..4]).unwrap();
+        assert!(!scratch.is_complete(vk.ic.len()));
+        scratch.absorb(&vk, &inputs[4..]).unwrap();
+        assert!(scratch.is_complete(vk.ic.len()));
+
+        // A fresh scratch is never complete for an IC binding no inputs
+        let empty = new_scratch();
+        assert!(!empty.is_complete(1));
+
+        let single_pass = compute_vk_x(&vk.ic, &inputs).unwrap();
+        assert_eq!(scratch.acc, single_pass);
+        assert_eq!(scratch.finished_vk_x(&vk, &inputs).unwrap(), single_pass);
+
+        // Restarting after a reset gives the same sum again
+        scratch.reset();
+        scratch.absorb(&vk, &inputs[..2]).unwrap();
+        scratch.absorb(&vk, &inputs[2..]).unwrap();
+        assert_eq!(scratch.finished_vk_x(&vk, &inputs).unwrap(), single_pass);
+    }
+
+    #[test]
+    fn test_scratch_rejects_overrun_and_large_chunk() {
+        let vk = vk_with_ic(vec![G1_GENERATOR; 3]);
+        let mut scratch = new_scratch();
+
+        let too_many = vec![Scalar::from_u64(1); VkXScratch::MAX_CHUNK + 1];
+        assert!(scratch.absorb(&vk_with_ic(vec![G1_GENERATOR; 8]), &too_many).is_err());
+
+        scratch.absorb(&vk, &[Scalar::from_u64(1), Scalar::from_u64(2)]).unwrap();
+        assert!(scratch.absorb(&vk, &[Scalar::from_u64(3)]).is_err());
+    }
+
+    #[test]
+    fn test_scratch_bound_to_vk_and_inputs() {
+        let vk = synthetic_vk(1);
+        let inputs: Vec<Scalar> = (1..=vk.ic.len() as u64 - 1).map(Scalar::from_u64).collect();
+        let mut scratch = new_scratch();
+        scratch.absorb(&vk, &inputs[..4]).unwrap();
+
+        // The key changes mid-way (e.g. update_vk_ic): no further chunks
+        let updated = synthetic_vk(2);
+        let err = scratch.absorb(&updated, &inputs[4..]).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::VkXScratchStale));
+
+        let err = scratch.finished_vk_x(&vk, &inputs).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::VkXScratchIncomplete));
+        scratch.absorb(&vk, &inputs[4..]).unwrap();
+
+        // Finished, but only for this key and these inputs
+        assert!(scratch.finished_vk_x(&vk, &inputs).is_ok());
+        let err = scratch.finished_vk_x(&updated, &inputs).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::VkXScratchStale));
+        let mut other_inputs = inputs.clone();
+        other_inputs.swap(0, 1);
+        let err = scratch.finished_vk_x(&vk, &other_inputs).unwrap_err();
+        assert_eq!(err, error!(PrivacyError::InvalidPublicInputs));
+    }
+}
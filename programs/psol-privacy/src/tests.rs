@@ -101,7 +101,7 @@ mod public_inputs_tests {
             test_pubkey(),
             100,
         );
-        assert_eq!(inputs.to_field_elements().len(), ZkPublicInputs::COUNT);
+        assert_eq!(inputs.to_field_elements().unwrap().len(), ZkPublicInputs::COUNT);
     }
 
     #[test]
@@ -129,3 +129,66 @@ mod state_tests {
         assert!(space < 10_000_000); // < 10MB
     }
 }
+
+#[cfg(test)]
+mod error_tests {
+    use anchor_lang::prelude::*;
+    use crate::error::PrivacyError;
+
+    fn code_of(err: anchor_lang::error::Error) -> u32 {
+        match err {
+            anchor_lang::error::Error::AnchorError(e) => e.error_code_number,
+            _ => panic!("expected AnchorError"),
+        }
+    }
+
+    #[test]
+    fn test_phase4_error_codes_stable() {
+        assert_eq!(code_of(error!(PrivacyError::VerificationKeyLocked)), 6023);
+        assert_eq!(code_of(error!(PrivacyError::InvalidAuthority)), 6024);
+        assert_eq!(code_of(error!(PrivacyError::NoPendingAuthority)), 6025);
+        assert_eq!(code_of(error!(PrivacyError::InputTooLarge)), 6027);
+        assert_eq!(code_of(error!(PrivacyError::LimitExceeded)), 6031);
+    }
+}
+
+#[cfg(test)]
+mod pause_tests {
+    use anchor_lang::prelude::*;
+    use crate::error::PrivacyError;
+    use crate::instructions::deposit::require_deposits_open;
+    use crate::instructions::private_transfer::check_private_transfer;
+    use crate::instructions::withdraw::require_withdrawals_open;
+    use crate::state::pool_config::test_fixtures::new_pool;
+    use crate::state::{PoolConfig, VerificationKeyAccount};
+
+    fn configured_pool() -> (PoolConfig, VerificationKeyAccount) {
+        let mut pool = new_pool();
+        pool.set_vk_configured(true);
+        pool.set_dev_mode_acknowledged(true);
+        let space = VerificationKeyAccount::space(VerificationKeyAccount::DEFAULT_MAX_IC_POINTS);
+        let mut vk = VerificationKeyAccount::try_deserialize_unchecked(&mut vec![0u8; space].as_slice()).unwrap();
+        vk.is_initialized = true;
+        (pool, vk)
+    }
+
+    #[test]
+    fn test_value_moving_paths_fail_when_paused() {
+        let (mut pool, vk) = configured_pool();
+        assert!(require_deposits_open(&pool).is_ok());
+        assert!(require_withdrawals_open(&pool, &vk).is_ok());
+        assert_eq!(
+            check_private_transfer(&pool, &[0u8; 256]).unwrap_err(),
+            error!(PrivacyError::NotImplemented)
+        );
+
+        pool.set_paused(true);
+        let paused = error!(PrivacyError::PoolPaused);
+        assert_eq!(require_deposits_open(&pool).unwrap_err(), paused);
+        assert_eq!(require_withdrawals_open(&pool, &vk).unwrap_err(), paused);
+        assert_eq!(check_private_transfer(&pool, &[0u8; 256]).unwrap_err(), paused);
+
+        // Pause is reported ahead of malformed input
+        assert_eq!(check_private_transfer(&pool, &[]).unwrap_err(), paused);
+    }
+}
@@ -290,6 +290,7 @@ export class PsolClient {
       discriminator,
       amount.toBuffer('le', 8),
       depositNote.commitment,
+      Buffer.from([0]), // expected_next_leaf_index: None
     ]);
 
     const ix = new TransactionInstruction({
@@ -384,10 +385,22 @@ export class PsolClient {
 
   /**
    * Pause pool (admin only)
+   * @param reason PauseReason: 0 = Manual, 1 = RateBreaker, 2 = Upgrade
    */
-  async pausePool(tokenMint: PublicKey): Promise<string> {
+  async pausePool(
+    tokenMint: PublicKey,
+    reason: number = 0,
+    pauseUntil: number | null = null
+  ): Promise<string> {
     const [poolConfig] = this.pda.poolConfig(tokenMint);
     const discriminator = this.getInstructionDiscriminator('pause_pool');
+    // Option<i64>: 0 = pause until unpaused, 1 + timestamp = auto-resume
+    const until = Buffer.alloc(pauseUntil === null ? 1 : 9);
+    if (pauseUntil !== null) {
+      until.writeUInt8(1, 0);
+      until.writeBigInt64LE(BigInt(pauseUntil), 1);
+    }
+    const data = Buffer.concat([discriminator, Buffer.from([reason]), until]);
 
     const ix = new TransactionInstruction({
       keys: [
@@ -395,7 +408,7 @@ export class PsolClient {
         { pubkey: poolConfig, isSigner: false, isWritable: true },
       ],
       programId: this.programId,
-      data: discriminator,
+      data,
     });
 
     const tx = new Transaction().add(ix);
@@ -404,10 +417,12 @@ export class PsolClient {
 
   /**
    * Unpause pool (admin only)
+   * @param reason PauseReason of the pause being lifted
    */
-  async unpausePool(tokenMint: PublicKey): Promise<string> {
+  async unpausePool(tokenMint: PublicKey, reason: number = 0): Promise<string> {
     const [poolConfig] = this.pda.poolConfig(tokenMint);
     const discriminator = this.getInstructionDiscriminator('unpause_pool');
+    const data = Buffer.concat([discriminator, Buffer.from([reason])]);
 
     const ix = new TransactionInstruction({
       keys: [
@@ -415,7 +430,7 @@ export class PsolClient {
         { pubkey: poolConfig, isSigner: false, isWritable: true },
       ],
       programId: this.programId,
-      data: discriminator,
+      data,
     });
 
     const tx = new Transaction().add(ix);
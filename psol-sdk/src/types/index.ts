@@ -218,6 +218,18 @@ export interface DepositEvent {
   leafIndex: number;
   amount: BN;
   timestamp: BN;
+  rootSlot: BN;
+  root: Uint8Array;
+}
+
+/**
+ * Root update event emitted with every inserted leaf
+ */
+export interface RootUpdatedEvent {
+  pool: PublicKey;
+  newRoot: Uint8Array;
+  leafIndex: number;
+  slot: BN;
 }
 
 /**
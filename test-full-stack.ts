@@ -106,6 +106,7 @@ async function main() {
   for (let i = 0; i < 32; i++) {
     commitment[i] = Math.floor(Math.random() * 256);
   }
+  commitment[0] &= 0x1f; // keep below the BN254 scalar modulus
   
   console.log("  Commitment:", Buffer.from(commitment).toString("hex"));
   
@@ -114,7 +115,7 @@ async function main() {
   
   try {
     const tx = await program.methods
-      .deposit(amount, Array.from(commitment))
+      .deposit(amount, Array.from(commitment), null)
       .accounts({
         poolConfig: POOL_CONFIG,
         merkleTree: merkleTree,
@@ -35,6 +35,8 @@ describe("pSOL Privacy Pool", () => {
   // Constants
   const TREE_DEPTH = 20;
   const ROOT_HISTORY_SIZE = 100;
+  const TREE_HASH_KECCAK = 1;
+  const COMMITMENT_MODE_CLIENT = 0;
   const DEPOSIT_AMOUNT = 1_000_000_000; // 1 token (9 decimals)
 
   before(async () => {
@@ -106,7 +108,7 @@ describe("pSOL Privacy Pool", () => {
   describe("Pool Initialization", () => {
     it("Initializes pool successfully", async () => {
       const tx = await program.methods
-        .initializePool(TREE_DEPTH, ROOT_HISTORY_SIZE)
+        .initializePool(TREE_DEPTH, ROOT_HISTORY_SIZE, TREE_HASH_KECCAK, COMMITMENT_MODE_CLIENT, null)
         .accounts({
           authority: authority.publicKey,
           tokenMint,
@@ -134,7 +136,7 @@ describe("pSOL Privacy Pool", () => {
     it("Rejects duplicate initialization", async () => {
       try {
         await program.methods
-          .initializePool(TREE_DEPTH, ROOT_HISTORY_SIZE)
+          .initializePool(TREE_DEPTH, ROOT_HISTORY_SIZE, TREE_HASH_KECCAK, COMMITMENT_MODE_CLIENT, null)
           .accounts({
             authority: authority.publicKey,
             tokenMint,
@@ -246,11 +248,12 @@ describe("pSOL Privacy Pool", () => {
       for (let i = 0; i < 32; i++) {
         commitment[i] = Math.floor(Math.random() * 256);
       }
+      commitment[0] &= 0x1f; // keep below the BN254 scalar modulus
 
       const balanceBefore = await getAccount(provider.connection, depositorTokenAccount);
 
       const tx = await program.methods
-        .deposit(new anchor.BN(DEPOSIT_AMOUNT), Array.from(commitment))
+        .deposit(new anchor.BN(DEPOSIT_AMOUNT), Array.from(commitment), null)
         .accounts({
           poolConfig,
           merkleTree,
@@ -285,7 +288,7 @@ describe("pSOL Privacy Pool", () => {
 
       try {
         await program.methods
-          .deposit(new anchor.BN(DEPOSIT_AMOUNT), zeroCommitment)
+          .deposit(new anchor.BN(DEPOSIT_AMOUNT), zeroCommitment, null)
           .accounts({
             poolConfig,
             merkleTree,
@@ -308,7 +311,7 @@ describe("pSOL Privacy Pool", () => {
 
       try {
         await program.methods
-          .deposit(new anchor.BN(0), Array.from(commitment))
+          .deposit(new anchor.BN(0), Array.from(commitment), null)
           .accounts({
             poolConfig,
             merkleTree,
@@ -330,7 +333,7 @@ describe("pSOL Privacy Pool", () => {
   describe("Admin Controls", () => {
     it("Pauses pool", async () => {
       const tx = await program.methods
-        .pausePool()
+        .pausePool(0, null)
         .accounts({
           authority: authority.publicKey,
           poolConfig,
@@ -349,7 +352,7 @@ describe("pSOL Privacy Pool", () => {
 
       try {
         await program.methods
-          .deposit(new anchor.BN(DEPOSIT_AMOUNT), Array.from(commitment))
+          .deposit(new anchor.BN(DEPOSIT_AMOUNT), Array.from(commitment), null)
           .accounts({
             poolConfig,
             merkleTree,
@@ -369,7 +372,7 @@ describe("pSOL Privacy Pool", () => {
 
     it("Unpauses pool", async () => {
       const tx = await program.methods
-        .unpausePool()
+        .unpausePool(0)
         .accounts({
           authority: authority.publicKey,
           poolConfig,